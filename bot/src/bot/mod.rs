@@ -35,6 +35,10 @@ enum Command {
     Backup,
     #[command(description = "Restore passwords file from backup")]
     Restore,
+    #[command(description = "Report reused, weak and old passwords")]
+    Audit,
+    #[command(description = "Export passwords as CSV, Bitwarden, KeePass or encrypted file")]
+    Export,
 }
 
 pub struct BotContext<F: RepositoriesFactory<R>, R: RecordsRepository> {
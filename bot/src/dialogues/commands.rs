@@ -4,6 +4,8 @@ pub const RESET_COMMAND: &str = "/reset";
 pub const CANCEL_COMMAND: &str = "/cancel";
 pub const BACKUP_COMMAND: &str = "/backup";
 pub const RESTORE_COMMAND: &str = "/restore";
+pub const AUDIT_COMMAND: &str = "/audit";
+pub const EXPORT_COMMAND: &str = "/export";
 
 pub fn default_commands_handler(command: Message) -> Vec<CtxResult> {
     let remove_msg = CtxResult::RemoveMessages(vec![command.id.clone()]);
@@ -18,6 +20,14 @@ pub fn default_commands_handler(command: Message) -> Vec<CtxResult> {
             CtxResult::Messages(vec!["The backup command is only supported in the repository view dialog".into()]),
             remove_msg,
         ],
+        Some(EXPORT_COMMAND) => vec![
+            CtxResult::Messages(vec!["The export command is only supported in the repository view dialog".into()]),
+            remove_msg,
+        ],
+        Some(AUDIT_COMMAND) => vec![
+            CtxResult::Messages(vec!["The audit command is only supported in the repository view dialog".into()]),
+            remove_msg,
+        ],
         Some(RESTORE_COMMAND) => vec![
             CtxResult::Messages(vec!["The restore from backup command is only supported in the open/create repository dialog".into()]),
             remove_msg,
@@ -0,0 +1,129 @@
+use crate::dialogues::commands::default_commands_handler;
+use std::collections::HashSet;
+
+use sec_store::repository::{ChangePasswordError, RecordsRepository};
+use sec_store::secret::SecretString;
+
+use super::view_repo::ViewRepoDialog;
+use crate::dialogues::commands::CANCEL_COMMAND;
+use anyhow::Result;
+use async_trait::async_trait;
+use stated_dialogues::dialogues::{CtxResult, DialContext, Message, MessageId, Select};
+
+#[derive(Clone)]
+enum ChangePasswordState {
+    Old,
+    New(SecretString),
+    Repeat(SecretString, SecretString),
+}
+
+pub struct ChangePasswordDialog<T> {
+    repo: T,
+    state: ChangePasswordState,
+    sent_msg_ids: HashSet<MessageId>,
+}
+
+impl<T> ChangePasswordDialog<T> {
+    pub fn new(repo: T) -> Self {
+        ChangePasswordDialog {
+            repo,
+            state: ChangePasswordState::Old,
+            sent_msg_ids: HashSet::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T> DialContext for ChangePasswordDialog<T>
+where
+    T: RecordsRepository,
+{
+    async fn init(&mut self) -> Result<Vec<CtxResult>> {
+        Ok(vec![CtxResult::Messages(vec![
+            "Enter the current password".into(),
+        ])])
+    }
+
+    async fn shutdown(&mut self) -> Result<Vec<CtxResult>> {
+        Ok(vec![CtxResult::RemoveMessages(
+            self.sent_msg_ids.drain().collect(),
+        )])
+    }
+
+    async fn handle_select(&mut self, select: Select) -> Result<Vec<CtxResult>> {
+        Ok(vec![select
+            .msg_id
+            .map(|msg_id| CtxResult::RemoveMessages(vec![msg_id]))
+            .unwrap_or(CtxResult::Nothing)])
+    }
+
+    async fn handle_message(&mut self, message: Message) -> Result<Vec<CtxResult>> {
+        let result: Vec<CtxResult> = match message.text {
+            Some(text) => match self.state.clone() {
+                ChangePasswordState::Old => {
+                    self.state = ChangePasswordState::New(text.into());
+                    vec![CtxResult::Messages(vec!["Enter a new password".into()])]
+                }
+                ChangePasswordState::New(_) if text.is_empty() => {
+                    vec![CtxResult::Messages(vec![
+                        "You didn't enter anything!".into()
+                    ])]
+                }
+                ChangePasswordState::New(old) => {
+                    self.state = ChangePasswordState::Repeat(old, text.into());
+                    vec![CtxResult::Messages(vec!["Repeat the new password".into()])]
+                }
+                ChangePasswordState::Repeat(_, new) if new.as_str() != text => {
+                    vec![CtxResult::Messages(vec![
+                        "Passwords don't match. Try again".into(),
+                    ])]
+                }
+                ChangePasswordState::Repeat(old, new) => {
+                    self.state = ChangePasswordState::Old;
+                    match self.repo.change_password(old, new).await {
+                        Ok(()) => vec![
+                            CtxResult::Messages(vec!["Master password changed 🔑".into()]),
+                            CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+                        ],
+                        Err(ChangePasswordError::WrongPassword) => {
+                            vec![CtxResult::Messages(vec![
+                                "Wrong password 🤨. Enter the current password".into(),
+                            ])]
+                        }
+                        Err(ChangePasswordError::UnexpectedError(err)) => {
+                            log::error!(
+                                "Failed master password change for {:?}: {err}",
+                                message.user_id
+                            );
+                            return Err(err);
+                        }
+                    }
+                }
+            },
+            None => vec![CtxResult::Nothing],
+        };
+
+        let mut results = vec![CtxResult::RemoveMessages(vec![message.id])];
+        results.extend(result);
+        Ok(results)
+    }
+
+    async fn handle_command(&mut self, command: Message) -> Result<Vec<CtxResult>> {
+        match command.text() {
+            Some(CANCEL_COMMAND) => Ok(vec![
+                CtxResult::RemoveMessages(vec![command.id]),
+                CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+            ]),
+            _ => Ok(default_commands_handler(command)),
+        }
+    }
+
+    fn remember_sent_messages(&mut self, msg_ids: Vec<MessageId>) {
+        msg_ids.into_iter().for_each(|msg_id| {
+            self.sent_msg_ids.insert(msg_id);
+        });
+    }
+    fn file_expected(&self) -> bool {
+        false
+    }
+}
@@ -2,6 +2,7 @@ use crate::dialogues::commands::default_commands_handler;
 use std::{collections::HashSet, marker::PhantomData};
 
 use sec_store::repository::RecordsRepository;
+use sec_store::secret::SecretString;
 
 use crate::{dialogues::commands::CANCEL_COMMAND, user_repo_factory::RepositoriesFactory};
 use anyhow::{Context, Result};
@@ -14,7 +15,7 @@ use stated_dialogues::dialogues::{CtxResult, DialContext, Message, MessageId, Se
 enum CreationState {
     Disabled,
     WaitForPassword,
-    WaitPasswordRepeat(String),
+    WaitPasswordRepeat(SecretString),
 }
 
 pub struct CreateRepoDialogue<F, R> {
@@ -72,14 +73,14 @@ where
                         CtxResult::Messages(vec!["You didn't enter anything!".into()]),
                     ]);
                 }
-                self.creation_state = CreationState::WaitPasswordRepeat(input.to_string());
+                self.creation_state = CreationState::WaitPasswordRepeat(input.into());
                 Ok(vec![
                     CtxResult::RemoveMessages(vec![message.id]),
                     CtxResult::Messages(vec!["Repeat the password".into()]),
                 ])
             }
             (user_id, Some(input), CreationState::WaitPasswordRepeat(passwd)) => {
-                if passwd.ne(&input) {
+                if passwd.as_str() != input {
                     return Ok(vec![
                         CtxResult::RemoveMessages(vec![message.id]),
                         CtxResult::Messages(vec!["Passwords don't match. Try again".into()]),
@@ -0,0 +1,194 @@
+use crate::dialogues::commands::default_commands_handler;
+use std::collections::HashSet;
+
+use sec_store::export::{export_records, ExportFormat};
+use sec_store::repository::RecordsRepository;
+use sec_store::secret::SecretString;
+
+use super::view_repo::ViewRepoDialog;
+use crate::dialogues::commands::CANCEL_COMMAND;
+use anyhow::Result;
+use async_trait::async_trait;
+use stated_dialogues::dialogues::{
+    ButtonPayload, CtxResult, DialContext, Message, MessageId, OutgoingDocument, Select,
+};
+
+const SELECT_FORMAT: &str = "FORMAT:";
+const CONFIRM_PLAIN_TEXT: &str = "CONFIRM_PLAIN_TEXT";
+const CANCEL: &str = "CANCEL";
+
+enum ExportState {
+    Choose,
+    Confirm(ExportFormat),
+    Password,
+    Repeat(SecretString),
+}
+
+pub struct ExportDialog<T> {
+    repo: T,
+    state: ExportState,
+    sent_msg_ids: HashSet<MessageId>,
+}
+
+impl<T> ExportDialog<T> {
+    pub fn new(repo: T) -> Self {
+        ExportDialog {
+            repo,
+            state: ExportState::Choose,
+            sent_msg_ids: HashSet::new(),
+        }
+    }
+}
+
+impl<T> ExportDialog<T>
+where
+    T: RecordsRepository,
+{
+    async fn export(
+        &mut self,
+        format: ExportFormat,
+        password: Option<SecretString>,
+    ) -> Result<Vec<CtxResult>> {
+        let records = self.repo.get_records().await?;
+        let data = export_records(&records, format, password)?;
+        Ok(vec![
+            CtxResult::Document(OutgoingDocument::new(
+                data,
+                format!("passwords.{}", format.file_extension()),
+            )),
+            CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+        ])
+    }
+}
+
+#[async_trait]
+impl<T> DialContext for ExportDialog<T>
+where
+    T: RecordsRepository,
+{
+    async fn init(&mut self) -> Result<Vec<CtxResult>> {
+        let mut buttons: Vec<Vec<(ButtonPayload, String)>> = ExportFormat::ALL
+            .iter()
+            .enumerate()
+            .map(|(idx, format)| {
+                let label = if format.is_plain_text() {
+                    format!("⚠️ {format} (unencrypted)")
+                } else {
+                    format!("🔒 {format} (new password)")
+                };
+                vec![(format!("{SELECT_FORMAT}{idx}").into(), label)]
+            })
+            .collect();
+        buttons.push(vec![(CANCEL.into(), "Cancel".into())]);
+        Ok(vec![CtxResult::Buttons(
+            "Choose the export format".into(),
+            buttons,
+        )])
+    }
+
+    async fn shutdown(&mut self) -> Result<Vec<CtxResult>> {
+        Ok(vec![CtxResult::RemoveMessages(
+            self.sent_msg_ids.drain().collect(),
+        )])
+    }
+
+    async fn handle_select(&mut self, select: Select) -> Result<Vec<CtxResult>> {
+        Ok(match (select.data(), &self.state) {
+            (Some(CANCEL), _) => vec![CtxResult::NewCtx(Box::new(ViewRepoDialog::new(
+                self.repo.clone(),
+            )))],
+            (Some(CONFIRM_PLAIN_TEXT), ExportState::Confirm(format)) => {
+                let format = *format;
+                self.export(format, None).await?
+            }
+            (Some(data), _) if data.starts_with(SELECT_FORMAT) => {
+                let idx = data[SELECT_FORMAT.len()..].parse::<usize>()?;
+                match ExportFormat::ALL.get(idx) {
+                    Some(format) if format.is_plain_text() => {
+                        self.state = ExportState::Confirm(*format);
+                        vec![CtxResult::Buttons(
+                            format!(
+                                "⚠️⚠️⚠️ The {format} file will hold EVERY password in PLAIN TEXT. \
+                                 Anyone who gets the file or this chat history can read them. \
+                                 Delete the message as soon as the file has been imported. \
+                                 Export anyway?"
+                            )
+                            .into(),
+                            vec![
+                                vec![(CONFIRM_PLAIN_TEXT.into(), "Yes, export unencrypted".into())],
+                                vec![(CANCEL.into(), "Cancel".into())],
+                            ],
+                        )]
+                    }
+                    Some(_) => {
+                        self.state = ExportState::Password;
+                        vec![CtxResult::Messages(vec![
+                            "Enter a password for the exported file".into(),
+                        ])]
+                    }
+                    None => vec![CtxResult::Nothing],
+                }
+            }
+            _ => vec![select
+                .msg_id
+                .map(|msg_id| CtxResult::RemoveMessages(vec![msg_id]))
+                .unwrap_or(CtxResult::Nothing)],
+        })
+    }
+
+    async fn handle_message(&mut self, message: Message) -> Result<Vec<CtxResult>> {
+        let mut results = vec![CtxResult::RemoveMessages(vec![message.id.clone()])];
+        let Some(text) = message.text() else {
+            return Ok(results);
+        };
+        match std::mem::replace(&mut self.state, ExportState::Choose) {
+            ExportState::Password if text.is_empty() => {
+                self.state = ExportState::Password;
+                results.push(CtxResult::Messages(vec![
+                    "You didn't enter anything!".into()
+                ]));
+            }
+            ExportState::Password if self.repo.is_password(text) => {
+                self.state = ExportState::Password;
+                results.push(CtxResult::Messages(vec![
+                    "The export password must differ from the repository password. Enter another one"
+                        .into(),
+                ]));
+            }
+            ExportState::Password => {
+                self.state = ExportState::Repeat(text.into());
+                results.push(CtxResult::Messages(vec!["Repeat the password".into()]));
+            }
+            ExportState::Repeat(password) if password.as_str() != text => {
+                self.state = ExportState::Password;
+                results.push(CtxResult::Messages(vec![
+                    "Passwords don't match. Enter a password for the exported file".into(),
+                ]));
+            }
+            ExportState::Repeat(password) => {
+                results.extend(self.export(ExportFormat::Encrypted, Some(password)).await?);
+            }
+            state => self.state = state,
+        }
+        Ok(results)
+    }
+
+    async fn handle_command(&mut self, command: Message) -> Result<Vec<CtxResult>> {
+        match command.text() {
+            Some(CANCEL_COMMAND) => Ok(vec![
+                CtxResult::RemoveMessages(vec![command.id]),
+                CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+            ]),
+            _ => Ok(default_commands_handler(command)),
+        }
+    }
+
+    fn remember_sent_messages(&mut self, msg_ids: Vec<MessageId>) {
+        msg_ids.into_iter().for_each(|msg_id| {
+            self.sent_msg_ids.insert(msg_id);
+        });
+    }
+    fn file_expected(&self) -> bool {
+        false
+    }
+}
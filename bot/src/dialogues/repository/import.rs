@@ -0,0 +1,140 @@
+use crate::dialogues::commands::default_commands_handler;
+use std::collections::HashSet;
+
+use sec_store::import::{
+    bitwarden::import_bitwarden, csv::import_csv, import_records, kdbx::import_kdbx, CsvMapping,
+    ImportError, ImportFormat, ImportResult,
+};
+use sec_store::record::Record;
+use sec_store::repository::RecordsRepository;
+
+use super::view_repo::ViewRepoDialog;
+use crate::dialogues::commands::CANCEL_COMMAND;
+use anyhow::Result;
+use async_trait::async_trait;
+use stated_dialogues::dialogues::{CtxResult, DialContext, Message, MessageId, Select};
+
+pub struct ImportDialog<T> {
+    repo: T,
+    // Uploaded KeePass database waiting for its password.
+    kdbx: Option<Vec<u8>>,
+    sent_msg_ids: HashSet<MessageId>,
+}
+
+impl<T> ImportDialog<T> {
+    pub fn new(repo: T) -> Self {
+        ImportDialog {
+            repo,
+            kdbx: None,
+            sent_msg_ids: HashSet::new(),
+        }
+    }
+}
+
+impl<T> ImportDialog<T>
+where
+    T: RecordsRepository,
+{
+    async fn import(&mut self, records: ImportResult<Vec<Record>>) -> Result<Vec<CtxResult>> {
+        let records = match records {
+            Ok(records) => records,
+            Err(ImportError::WrongPassword) => {
+                return Ok(vec![CtxResult::Messages(vec![
+                    "Wrong password, try again".into()
+                ])])
+            }
+            Err(err) => {
+                self.kdbx = None;
+                return Ok(vec![CtxResult::Messages(vec![format!(
+                    "Import failed: {err}"
+                )
+                .into()])]);
+            }
+        };
+        self.kdbx = None;
+        let count = import_records(&mut self.repo, records).await?;
+        self.repo.save().await?;
+        Ok(vec![
+            CtxResult::Messages(vec![format!("Imported {count} records 📥").into()]),
+            CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+        ])
+    }
+}
+
+#[async_trait]
+impl<T> DialContext for ImportDialog<T>
+where
+    T: RecordsRepository,
+{
+    async fn init(&mut self) -> Result<Vec<CtxResult>> {
+        Ok(vec![CtxResult::Messages(vec![
+            "Send a KeePass database, an unencrypted Bitwarden JSON export or a CSV file".into(),
+        ])])
+    }
+
+    async fn shutdown(&mut self) -> Result<Vec<CtxResult>> {
+        Ok(vec![CtxResult::RemoveMessages(
+            self.sent_msg_ids.drain().collect(),
+        )])
+    }
+
+    async fn handle_select(&mut self, select: Select) -> Result<Vec<CtxResult>> {
+        Ok(vec![select
+            .msg_id
+            .map(|msg_id| CtxResult::RemoveMessages(vec![msg_id]))
+            .unwrap_or(CtxResult::Nothing)])
+    }
+
+    async fn handle_message(&mut self, message: Message) -> Result<Vec<CtxResult>> {
+        let mut results = vec![CtxResult::RemoveMessages(vec![message.id.clone()])];
+
+        if let Some(document_file) = message.document_file() {
+            let data = std::fs::read(document_file)?;
+            let format = ImportFormat::detect(&data);
+            results.extend(match format {
+                ImportFormat::Kdbx => {
+                    self.kdbx = Some(data);
+                    vec![CtxResult::Messages(vec![
+                        "Enter the KeePass database password".into(),
+                    ])]
+                }
+                ImportFormat::Bitwarden => self.import(import_bitwarden(&data)).await?,
+                ImportFormat::Csv => {
+                    self.import(import_csv(&data, &CsvMapping::default()))
+                        .await?
+                }
+            });
+        } else if let (Some(data), Some(password)) = (self.kdbx.take(), message.text()) {
+            // The key derivation takes seconds, keep it off the runtime workers.
+            let password = password.to_string();
+            let (data, records) = tokio::task::spawn_blocking(move || {
+                let records = import_kdbx(&data, &password);
+                (data, records)
+            })
+            .await?;
+            self.kdbx = Some(data);
+            results.extend(self.import(records).await?);
+        }
+
+        Ok(results)
+    }
+
+    async fn handle_command(&mut self, command: Message) -> Result<Vec<CtxResult>> {
+        match command.text() {
+            Some(CANCEL_COMMAND) => Ok(vec![
+                CtxResult::RemoveMessages(vec![command.id]),
+                CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+            ]),
+            _ => Ok(default_commands_handler(command)),
+        }
+    }
+
+    fn remember_sent_messages(&mut self, msg_ids: Vec<MessageId>) {
+        msg_ids.into_iter().for_each(|msg_id| {
+            self.sent_msg_ids.insert(msg_id);
+        });
+    }
+    fn file_expected(&self) -> bool {
+        true
+    }
+}
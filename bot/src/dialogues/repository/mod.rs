@@ -1,4 +1,7 @@
+pub mod change_password;
 pub mod create_repo;
+pub mod export;
+pub mod import;
 pub mod open_repo;
 pub mod view_repo;
 
@@ -52,43 +52,54 @@ where
     }
 
     async fn handle_message(&mut self, message: Message) -> Result<Vec<CtxResult>> {
-        Ok(vec![
-            CtxResult::RemoveMessages(
-                self.sent_msg_ids
-                    .drain()
-                    .chain(vec![message.id.clone()])
-                    .collect(),
-            ),
-            match (
-                message
-                    .user_id
-                    .with_context(|| format!("Message without user_id msg_id={}", message.id,))?,
-                message.text,
-            ) {
-                (user_id, Some(passwd)) => {
-                    match self
-                        .factory
-                        .get_user_repository(&user_id.clone().into(), passwd)
-                        .await
-                    {
-                        Ok(repo) => Ok(CtxResult::NewCtx(Box::new(ViewRepoDialog::new(repo)))),
-                        Err(RepositoryOpenError::WrongPassword) => Ok(CtxResult::Messages(vec![
-                            "Wrong password 🤨. Try again".into(),
-                        ])),
-                        Err(RepositoryOpenError::DoesntExist) => Ok(CtxResult::CloseCtx),
-                        Err(RepositoryOpenError::InvalidRepositoryName(_)) => {
-                            Ok(CtxResult::Messages(vec![
-                                "Repository configuration is invalid".into(),
-                            ]))
+        let mut result = vec![CtxResult::RemoveMessages(
+            self.sent_msg_ids
+                .drain()
+                .chain(vec![message.id.clone()])
+                .collect(),
+        )];
+        match (
+            message
+                .user_id
+                .with_context(|| format!("Message without user_id msg_id={}", message.id,))?,
+            message.text,
+        ) {
+            (user_id, Some(passwd)) => {
+                match self
+                    .factory
+                    .get_user_repository(&user_id.clone().into(), passwd.into())
+                    .await
+                {
+                    Ok(repo) => {
+                        let damaged = repo.damaged_records();
+                        if !damaged.is_empty() {
+                            result.push(CtxResult::Messages(vec![format!(
+                                "⚠️ {} damaged records couldn't be read and were skipped",
+                                damaged.len()
+                            )
+                            .into()]));
                         }
-                        Err(error) => Err(error),
+                        result.push(CtxResult::NewCtx(Box::new(ViewRepoDialog::new(repo))));
                     }
+                    Err(RepositoryOpenError::WrongPassword) => {
+                        result.push(CtxResult::Messages(vec![
+                            "Wrong password 🤨. Try again".into()
+                        ]))
+                    }
+                    Err(RepositoryOpenError::DoesntExist) => result.push(CtxResult::CloseCtx),
+                    Err(RepositoryOpenError::InvalidRepositoryName(_)) => {
+                        result.push(CtxResult::Messages(vec![
+                            "Repository configuration is invalid".into(),
+                        ]))
+                    }
+                    Err(error) => return Err(error.into()),
                 }
-                _ => Ok(CtxResult::Messages(vec![
-                    "That's not a password 🤨. Try again".into(),
-                ])),
-            }?,
-        ])
+            }
+            _ => result.push(CtxResult::Messages(vec![
+                "That's not a password 🤨. Try again".into(),
+            ])),
+        }
+        Ok(result)
     }
 
     async fn handle_command(&mut self, command: Message) -> Result<Vec<CtxResult>> {
@@ -1,14 +1,16 @@
 use crate::dialogues::commands::default_commands_handler;
 use std::collections::HashSet;
 
-use sec_store::record::Record;
+use sec_store::record::{
+    Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+};
 use sec_store::repository::RecordsRepository;
 
-use super::fields::{
-    RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
-};
 use crate::dialogues::commands::CANCEL_COMMAND;
 use crate::dialogues::repository::view_repo::ViewRepoDialog;
+
+use super::fields::generated_password_message;
+use super::generate::{generate, generator_buttons};
 use anyhow::Result;
 use async_trait::async_trait;
 use stated_dialogues::dialogues::DialContext;
@@ -80,7 +82,10 @@ where
     T: RecordsRepository,
 {
     async fn init(&mut self) -> Result<Vec<CtxResult>> {
-        Ok(vec![CtxResult::Messages(vec!["Enter password".into()])])
+        Ok(vec![CtxResult::Buttons(
+            "Enter password or generate one".into(),
+            generator_buttons(),
+        )])
     }
 
     async fn shutdown(&mut self) -> Result<Vec<CtxResult>> {
@@ -90,6 +95,17 @@ where
     }
 
     async fn handle_select(&mut self, select: Select) -> Result<Vec<CtxResult>> {
+        if let (AddRecordState::Value, Some(generated)) =
+            (&self.state, select.data().and_then(generate))
+        {
+            let generated = generated?;
+            self.state = AddRecordState::Name(generated.value.to_string());
+            return Ok(vec![CtxResult::Messages(vec![
+                generated_password_message(&generated),
+                "Enter name".into(),
+            ])]);
+        }
+
         Ok(vec![select
             .msg_id
             .map(|msg_id| CtxResult::RemoveMessages(vec![msg_id]))
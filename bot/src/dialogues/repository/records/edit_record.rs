@@ -2,15 +2,16 @@ use crate::dialogues::commands::default_commands_handler;
 use std::collections::HashSet;
 
 use sec_store::{
-    record::{Record, RecordId},
+    record::{
+        Record, RecordId, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD,
+        RECORD_PASSWD_FIELD, RECORD_TOTP_FIELD,
+    },
     repository::RecordsRepository,
 };
 
 use super::{
-    fields::{
-        record_as_message, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD,
-        RECORD_PASSWD_FIELD,
-    },
+    fields::{generated_password_message, record_as_message},
+    generate::{generate, generator_buttons},
     view_record::ViewRecordDialog,
 };
 use crate::dialogues::{commands::CANCEL_COMMAND, repository::view_repo::ViewRepoDialog};
@@ -47,6 +48,10 @@ impl<T> EditRecordDialog<T> {
 
 const _CANCEL_EDIT: &str = "CANCEL_EDIT";
 const _SAVE_RESULT: &str = "SAVE_RESULT";
+const EDIT_FOLDER: &str = "EDIT_FOLDER";
+const EDIT_TAGS: &str = "EDIT_TAGS";
+// Telegram doesn't deliver empty messages, so this clears the folder or tags.
+const CLEAR_VALUE: &str = "-";
 
 #[async_trait]
 impl<T> DialContext for EditRecordDialog<T>
@@ -75,6 +80,25 @@ where
     }
 
     async fn handle_select(&mut self, select: Select) -> Result<Vec<CtxResult>> {
+        if let DialogState::FieldEdit(field) = self.state.clone() {
+            if let Some(generated) = select.data().and_then(generate) {
+                let generated = generated?;
+                let mut record = self.repo.get(&self.record_id).await?.with_context(|| {
+                    format!("Missed record {} in FieldEdit state", self.record_id)
+                })?;
+                record.update_field(field, generated.value.clone())?;
+                let edit_buttons = get_edit_record_buttons(&record);
+
+                self.repo.update(record).await?;
+                self.state = DialogState::WaitForSelect;
+                return Ok(vec![
+                    CtxResult::RemoveMessages(self.sent_msg_ids.drain().collect()),
+                    CtxResult::Messages(vec![generated_password_message(&generated)]),
+                    edit_buttons,
+                ]);
+            }
+        }
+
         match select
             .data
             .with_context(|| {
@@ -104,6 +128,9 @@ where
                     RECORD_LOGIN_FIELD => Ok("login"),
                     RECORD_NAME_FIELD => Ok("name"),
                     RECORD_PASSWD_FIELD => Ok("password"),
+                    EDIT_FOLDER => Ok("folder"),
+                    EDIT_TAGS => Ok("tags"),
+                    RECORD_TOTP_FIELD => Ok("2FA secret"),
                     unexpected_field => Err(anyhow!(
                         "Selected unexpected field '{}' by user {}",
                         unexpected_field,
@@ -116,11 +143,30 @@ where
                     CtxResult::RemoveMessages(
                         self.sent_msg_ids.drain().collect::<Vec<MessageId>>(),
                     ),
-                    CtxResult::Messages(vec![format!(
-                        "Enter new value for field '{}'",
-                        field_name
-                    )
-                    .into()]),
+                    match select_payload {
+                        EDIT_FOLDER => CtxResult::Messages(vec![format!(
+                            "Enter folder path like 'work/servers', '{CLEAR_VALUE}' for none"
+                        )
+                        .into()]),
+                        EDIT_TAGS => CtxResult::Messages(vec![format!(
+                            "Enter tags separated by commas, '{CLEAR_VALUE}' for none"
+                        )
+                        .into()]),
+                        RECORD_TOTP_FIELD => {
+                            CtxResult::Messages(
+                                vec!["Enter otpauth:// URI or base32 secret".into()],
+                            )
+                        }
+                        RECORD_PASSWD_FIELD => CtxResult::Buttons(
+                            "Enter new password or generate one".into(),
+                            generator_buttons(),
+                        ),
+                        _ => CtxResult::Messages(vec![format!(
+                            "Enter new value for field '{}'",
+                            field_name
+                        )
+                        .into()]),
+                    },
                 ])
             }
         }
@@ -133,7 +179,27 @@ where
                     format!("Missed record {} in FieldEdit state", self.record_id)
                 })?;
 
-                record.update_field(field, msg_text)?;
+                let value = if msg_text.trim() == CLEAR_VALUE {
+                    ""
+                } else {
+                    msg_text.as_str()
+                };
+                match field.as_str() {
+                    EDIT_FOLDER => record.set_folder(value),
+                    EDIT_TAGS => record.set_tags(value.split(',')),
+                    RECORD_TOTP_FIELD => {
+                        if let Err(err) = record.set_totp(&msg_text) {
+                            return Ok(vec![
+                                CtxResult::RemoveMessages(vec![message.id]),
+                                CtxResult::Messages(vec![format!(
+                                    "Invalid 2FA secret: {err}. Try again"
+                                )
+                                .into()]),
+                            ]);
+                        }
+                    }
+                    _ => record.update_field(field, msg_text)?,
+                }
                 let edit_buttons = get_edit_record_buttons(&record);
 
                 self.repo.update(record).await?;
@@ -195,6 +261,11 @@ fn get_edit_record_buttons(record: &Record) -> CtxResult {
     }
 
     button_rows.extend(vec![
+        vec![
+            (EDIT_FOLDER.into(), "📁 Folder".into()),
+            (EDIT_TAGS.into(), "🏷 Tags".into()),
+        ],
+        vec![(RECORD_TOTP_FIELD.into(), "🔢 2FA secret".into())],
         vec![(_CANCEL_EDIT.into(), "❌ Cancel".into())],
         vec![(_SAVE_RESULT.into(), "💾 Save".into())],
     ]);
@@ -1,20 +1,29 @@
 use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
 
-use sec_store::record::Record;
+use sec_store::generator::GeneratedPassword;
+use sec_store::record::{
+    Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+    RECORD_TOTP_FIELD,
+};
 
 use stated_dialogues::dialogues::{MessageFormat, OutgoingMessage};
 
-pub const RECORD_NAME_FIELD: &str = "RECORD_NAME";
-pub const RECORD_PASSWD_FIELD: &str = "RECORD_PASSWD";
-pub const RECORD_DESCR_FIELD: &str = "RECORD_DESCR";
-pub const RECORD_LOGIN_FIELD: &str = "RECORD_LOGIN";
+// The TOTP secret is never printed, its codes are sent on request.
+const SHOWN_FIELDS: [&str; 5] = [
+    RECORD_NAME_FIELD,
+    RECORD_LOGIN_FIELD,
+    RECORD_DESCR_FIELD,
+    RECORD_PASSWD_FIELD,
+    RECORD_TOTP_FIELD,
+];
 
 pub fn record_as_message(record: &Record) -> OutgoingMessage {
-    let fields: HashMap<String, String> = HashMap::from_iter(
+    let fields: HashMap<&str, &str> = HashMap::from_iter(
         record
             .get_fields()
             .into_iter()
-            .map(|(name, value)| (name.clone(), value.clone())),
+            .map(|field| (field.name.as_str(), field.value.as_str())),
     );
 
     let mut lines: Vec<String> = vec![format!("Name: <code>{}</code>", fields[RECORD_NAME_FIELD])];
@@ -29,6 +38,83 @@ pub fn record_as_message(record: &Record) -> OutgoingMessage {
         "Password: <code>{}</code>",
         fields[RECORD_PASSWD_FIELD]
     ));
+    for field in record
+        .get_fields()
+        .into_iter()
+        .filter(|field| !SHOWN_FIELDS.contains(&field.name.as_str()))
+    {
+        if field.kind.is_concealed() {
+            lines.push(format!(
+                "{}: <tg-spoiler><code>{}</code></tg-spoiler>",
+                field.name,
+                field.value.as_str()
+            ));
+        } else {
+            lines.push(format!(
+                "{}: <code>{}</code>",
+                field.name,
+                field.value.as_str()
+            ));
+        }
+    }
+
+    if let Some(folder) = record.folder() {
+        lines.push(format!("📁 {folder}"));
+    }
+    if !record.tags().is_empty() {
+        lines.push(format!("🏷 {}", record.tags().join(", ")));
+    }
+    lines.push(format!(
+        "Modified: {}",
+        format_timestamp(record.modified_at())
+    ));
+
+    OutgoingMessage::new(lines.join("\n"), MessageFormat::Html)
+}
+
+pub fn totp_code_message(record: &Record) -> OutgoingMessage {
+    let text = match record.totp() {
+        Some(Ok(totp)) => format!(
+            "2FA code: <code>{}</code>\nValid for {} seconds",
+            totp.code(),
+            totp.seconds_remaining()
+        ),
+        Some(Err(err)) => format!("Can't generate a 2FA code: {err}"),
+        None => "The record has no 2FA secret".to_string(),
+    };
+    OutgoingMessage::new(text, MessageFormat::Html)
+}
+
+pub fn generated_password_message(generated: &GeneratedPassword) -> OutgoingMessage {
+    OutgoingMessage::new(
+        format!(
+            "Generated password: <tg-spoiler><code>{}</code></tg-spoiler>\nEntropy: {:.0} bits",
+            generated.value.as_str(),
+            generated.entropy_bits
+        ),
+        MessageFormat::Html,
+    )
+}
+
+pub fn password_history_message(record: &Record) -> OutgoingMessage {
+    let mut lines = vec!["Password history:".to_string()];
+    if let Some(field) = record.get_field(RECORD_PASSWD_FIELD) {
+        lines.extend(field.history.iter().enumerate().map(|(idx, revision)| {
+            format!(
+                "{}. <tg-spoiler><code>{}</code></tg-spoiler> replaced {}",
+                idx + 1,
+                revision.value.as_str(),
+                format_timestamp(revision.replaced_at)
+            )
+        }));
+    }
 
     OutgoingMessage::new(lines.join("\n"), MessageFormat::Html)
 }
+
+fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "unknown".to_string();
+    }
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(timestamp)).to_string()
+}
@@ -0,0 +1,24 @@
+use sec_store::generator::{
+    generate_passphrase, generate_password, GenerateResult, GeneratedPassword, PassphrasePolicy,
+    PasswordPolicy,
+};
+use stated_dialogues::dialogues::ButtonPayload;
+
+pub const GENERATE_PASSWORD: &str = "GENERATE_PASSWORD";
+pub const GENERATE_PASSPHRASE: &str = "GENERATE_PASSPHRASE";
+
+pub fn generator_buttons() -> Vec<Vec<(ButtonPayload, String)>> {
+    vec![
+        vec![(GENERATE_PASSWORD.into(), "🎲 Generate password".into())],
+        vec![(GENERATE_PASSPHRASE.into(), "🎲 Generate passphrase".into())],
+    ]
+}
+
+/// Generates a password for a generator button payload, `None` for other payloads.
+pub fn generate(payload: &str) -> Option<GenerateResult<GeneratedPassword>> {
+    match payload {
+        GENERATE_PASSWORD => Some(generate_password(&PasswordPolicy::default())),
+        GENERATE_PASSPHRASE => Some(generate_passphrase(&PassphrasePolicy::default())),
+        _ => None,
+    }
+}
@@ -1,4 +1,5 @@
 pub mod fields;
+pub mod generate;
 
 pub mod add_record;
 pub mod edit_record;
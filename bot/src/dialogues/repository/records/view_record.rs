@@ -1,11 +1,17 @@
 use crate::dialogues::commands::default_commands_handler;
 use std::collections::HashSet;
 
-use sec_store::{record::RecordId, repository::RecordsRepository};
+use sec_store::{
+    record::{RecordId, RECORD_PASSWD_FIELD},
+    repository::RecordsRepository,
+};
 
-use super::{edit_record::EditRecordDialog, fields::record_as_message};
+use super::{
+    edit_record::EditRecordDialog,
+    fields::{password_history_message, record_as_message, totp_code_message},
+};
 use crate::dialogues::repository::view_repo::ViewRepoDialog;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use stated_dialogues::dialogues::{CtxResult, DialContext, Message, MessageId, Select};
 
@@ -28,6 +34,9 @@ impl<T> ViewRecordDialog<T> {
 const EDIT_RECORD: &str = "EDIT_RECORD";
 const REMOVE_RECORD: &str = "REMOVE_RECORD";
 const CLOSE_VIEW: &str = "CLOSE_VIEW";
+const SHOW_HISTORY: &str = "SHOW_HISTORY";
+const GET_CODE: &str = "GET_CODE";
+const RESTORE_PASSWORD: &str = "RESTORE_PASSWORD:";
 
 #[async_trait]
 impl<T> DialContext for ViewRecordDialog<T>
@@ -36,14 +45,23 @@ where
 {
     async fn init(&mut self) -> Result<Vec<CtxResult>> {
         let result: CtxResult = match self.repo.get(&self.record_id).await? {
-            Some(record) => CtxResult::Buttons(
-                record_as_message(&record),
-                vec![
+            Some(record) => {
+                let mut buttons = vec![
                     vec![(EDIT_RECORD.into(), "✏️".into())],
                     vec![(REMOVE_RECORD.into(), "❌".into())],
-                    vec![(CLOSE_VIEW.into(), "⬅️ Close".into())],
-                ],
-            ),
+                ];
+                if record.totp().is_some() {
+                    buttons.push(vec![(GET_CODE.into(), "🔢 Get 2FA code".into())]);
+                }
+                if record
+                    .get_field(RECORD_PASSWD_FIELD)
+                    .is_some_and(|field| !field.history.is_empty())
+                {
+                    buttons.push(vec![(SHOW_HISTORY.into(), "🕘 Password history".into())]);
+                }
+                buttons.push(vec![(CLOSE_VIEW.into(), "⬅️ Close".into())]);
+                CtxResult::Buttons(record_as_message(&record), buttons)
+            }
             None => CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
         };
 
@@ -68,6 +86,50 @@ where
                 CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone())))
             }
             Some(CLOSE_VIEW) => CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone()))),
+            Some(GET_CODE) => {
+                let record = self
+                    .repo
+                    .get(&self.record_id)
+                    .await?
+                    .context("Record doesn't exist")?;
+                CtxResult::Messages(vec![totp_code_message(&record)])
+            }
+            Some(SHOW_HISTORY) => {
+                let record = self
+                    .repo
+                    .get(&self.record_id)
+                    .await?
+                    .context("Record doesn't exist")?;
+                let revisions = record
+                    .get_field(RECORD_PASSWD_FIELD)
+                    .map_or(0, |field| field.history.len());
+                CtxResult::Buttons(
+                    password_history_message(&record),
+                    (0..revisions)
+                        .map(|idx| {
+                            vec![(
+                                format!("{RESTORE_PASSWORD}{idx}").into(),
+                                format!("Restore {}", idx + 1),
+                            )]
+                        })
+                        .collect(),
+                )
+            }
+            Some(data) if data.starts_with(RESTORE_PASSWORD) => {
+                let revision = data[RESTORE_PASSWORD.len()..].parse::<usize>()?;
+                let mut record = self
+                    .repo
+                    .get(&self.record_id)
+                    .await?
+                    .context("Record doesn't exist")?;
+                record.restore_field(RECORD_PASSWD_FIELD, revision)?;
+                self.repo.update(record).await?;
+                self.repo.save().await?;
+                CtxResult::NewCtx(Box::new(ViewRecordDialog::new(
+                    self.repo.clone(),
+                    self.record_id.clone(),
+                )))
+            }
             other => {
                 log::warn!("Unexpected select called {:?}", other);
                 CtxResult::CloseCtx
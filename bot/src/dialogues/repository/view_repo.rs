@@ -2,33 +2,103 @@ use async_trait::async_trait;
 use std::collections::HashSet;
 
 use anyhow::Result;
+use sec_store::audit::{audit_repository, AuditPolicy, AuditReport};
+use sec_store::record::{RecordsFilter, RECORD_NAME_FIELD};
 use sec_store::repository::RecordsRepository;
 use stated_dialogues::dialogues::{
     ButtonPayload, CtxResult, DialContext, Message, MessageId, OutgoingDocument, Select,
 };
 
-use super::super::commands::BACKUP_COMMAND;
+use super::super::commands::{AUDIT_COMMAND, BACKUP_COMMAND, EXPORT_COMMAND};
 use crate::dialogues::commands::default_commands_handler;
 
-use super::records::{
-    add_record::AddRecordDialog, fields::RECORD_NAME_FIELD, view_record::ViewRecordDialog,
-};
+use super::change_password::ChangePasswordDialog;
+use super::export::ExportDialog;
+use super::import::ImportDialog;
+use super::records::{add_record::AddRecordDialog, view_record::ViewRecordDialog};
 
 const CLOSE_REPO: &str = "CLOSE_REPO";
 const ADD_RECORD: &str = "ADD_RECORD";
+const CHANGE_PASSWORD: &str = "CHANGE_PASSWORD";
+const IMPORT: &str = "IMPORT";
+const SHOW_FOLDERS: &str = "SHOW_FOLDERS";
+const SHOW_TAGS: &str = "SHOW_TAGS";
+const CLEAR_FILTER: &str = "CLEAR_FILTER";
+// Telegram refuses messages longer than 4096 characters.
+const MAX_MESSAGE_LEN: usize = 4000;
+// Folders and tags are referenced by position, paths may exceed the callback data limit.
+const SELECT_FOLDER: &str = "FOLDER:";
+const SELECT_TAG: &str = "TAG:";
 
 pub struct ViewRepoDialog<T> {
     repo: T,
+    filter: RecordsFilter,
     sent_msg_ids: HashSet<MessageId>,
 }
 
 impl<T> ViewRepoDialog<T> {
     pub fn new(repo: T) -> Self {
+        ViewRepoDialog::with_filter(repo, RecordsFilter::default())
+    }
+
+    pub fn with_filter(repo: T, filter: RecordsFilter) -> Self {
         ViewRepoDialog {
             repo,
+            filter,
             sent_msg_ids: HashSet::new(),
         }
     }
+
+    fn filter_description(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(folder) = &self.filter.folder {
+            lines.push(format!("📁 {folder}"));
+        }
+        if let Some(tag) = &self.filter.tag {
+            lines.push(format!("🏷 {tag}"));
+        }
+        lines
+    }
+}
+
+fn audit_report_message(report: &AuditReport) -> String {
+    if report.is_clean() {
+        return format!(
+            "✅ Checked {} records, no issues found",
+            report.checked_records
+        );
+    }
+    let mut message = format!(
+        "⚠️ Checked {} records, {} issues in {} records",
+        report.checked_records,
+        report.issues_count(),
+        report.records.len()
+    );
+    for (idx, record) in report.records.iter().enumerate() {
+        let mut entry = format!("\n\n{}", record.name);
+        for issue in &record.issues {
+            entry.push_str(&format!("\n• {issue}"));
+        }
+        if message.len() + entry.len() > MAX_MESSAGE_LEN {
+            message.push_str(&format!(
+                "\n\n…and {} more records",
+                report.records.len() - idx
+            ));
+            break;
+        }
+        message.push_str(&entry);
+    }
+    message
+}
+
+fn choices_buttons(prefix: &str, choices: &[String]) -> Vec<Vec<(ButtonPayload, String)>> {
+    let mut buttons: Vec<Vec<(ButtonPayload, String)>> = choices
+        .iter()
+        .enumerate()
+        .map(|(idx, choice)| vec![(format!("{prefix}{idx}").into(), choice.clone())])
+        .collect();
+    buttons.push(vec![(CLEAR_FILTER.into(), "All records".into())]);
+    buttons
 }
 
 #[async_trait]
@@ -39,7 +109,7 @@ where
     async fn init(&mut self) -> Result<Vec<CtxResult>> {
         let mut records_buttons = self
             .repo
-            .get_records()
+            .find_records(&self.filter)
             .await?
             .into_iter()
             .map(|record| {
@@ -47,6 +117,7 @@ where
                     record.id.clone(),
                     record
                         .get_field_value(RECORD_NAME_FIELD)
+                        .map(|name| name.to_string())
                         .unwrap_or("-".to_string()),
                 )
             })
@@ -62,14 +133,28 @@ where
 
         let records_count = records_buttons.len();
         let mut buttons = records_buttons;
+        let mut filter_buttons = Vec::new();
+        if !self.repo.get_folders().await?.is_empty() {
+            filter_buttons.push((SHOW_FOLDERS.into(), "📁 Folders".into()));
+        }
+        if !self.repo.get_tags().await?.is_empty() {
+            filter_buttons.push((SHOW_TAGS.into(), "🏷 Tags".into()));
+        }
+        if !filter_buttons.is_empty() {
+            buttons.push(filter_buttons);
+        }
+        if !self.filter.is_empty() {
+            buttons.push(vec![(CLEAR_FILTER.into(), "✖️ Show all records".into())]);
+        }
         buttons.extend(vec![
             vec![(ADD_RECORD.into(), "Add record 🗒".into())],
+            vec![(IMPORT.into(), "Import records 📥".into())],
+            vec![(CHANGE_PASSWORD.into(), "Change master password 🔑".into())],
             vec![(CLOSE_REPO.into(), "Close repository 🚪".into())],
         ]);
-        Ok(vec![CtxResult::Buttons(
-            format!("Number of records: {}", records_count).into(),
-            buttons,
-        )])
+        let mut lines = vec![format!("Number of records: {}", records_count)];
+        lines.extend(self.filter_description());
+        Ok(vec![CtxResult::Buttons(lines.join("\n").into(), buttons)])
     }
 
     async fn shutdown(&mut self) -> Result<Vec<CtxResult>> {
@@ -84,6 +169,47 @@ where
             Some(ADD_RECORD) => {
                 CtxResult::NewCtx(Box::new(AddRecordDialog::new(self.repo.clone())))
             }
+            Some(IMPORT) => CtxResult::NewCtx(Box::new(ImportDialog::new(self.repo.clone()))),
+            Some(CHANGE_PASSWORD) => {
+                CtxResult::NewCtx(Box::new(ChangePasswordDialog::new(self.repo.clone())))
+            }
+            Some(SHOW_FOLDERS) => CtxResult::Buttons(
+                "Choose a folder".into(),
+                choices_buttons(SELECT_FOLDER, &self.repo.get_folders().await?),
+            ),
+            Some(SHOW_TAGS) => CtxResult::Buttons(
+                "Choose a tag".into(),
+                choices_buttons(SELECT_TAG, &self.repo.get_tags().await?),
+            ),
+            Some(CLEAR_FILTER) => {
+                CtxResult::NewCtx(Box::new(ViewRepoDialog::new(self.repo.clone())))
+            }
+            Some(data) if data.starts_with(SELECT_FOLDER) => {
+                let idx = data[SELECT_FOLDER.len()..].parse::<usize>()?;
+                match self.repo.get_folders().await?.into_iter().nth(idx) {
+                    Some(folder) => CtxResult::NewCtx(Box::new(ViewRepoDialog::with_filter(
+                        self.repo.clone(),
+                        RecordsFilter {
+                            folder: Some(folder),
+                            tag: self.filter.tag.clone(),
+                        },
+                    ))),
+                    None => CtxResult::Nothing,
+                }
+            }
+            Some(data) if data.starts_with(SELECT_TAG) => {
+                let idx = data[SELECT_TAG.len()..].parse::<usize>()?;
+                match self.repo.get_tags().await?.into_iter().nth(idx) {
+                    Some(tag) => CtxResult::NewCtx(Box::new(ViewRepoDialog::with_filter(
+                        self.repo.clone(),
+                        RecordsFilter {
+                            folder: self.filter.folder.clone(),
+                            tag: Some(tag),
+                        },
+                    ))),
+                    None => CtxResult::Nothing,
+                }
+            }
             Some(record_id) => match self.repo.get(&record_id.to_string()).await? {
                 Some(_) => CtxResult::NewCtx(Box::new(ViewRecordDialog::new(
                     self.repo.clone(),
@@ -112,6 +238,17 @@ where
                     CtxResult::RemoveMessages(vec![command.id]),
                 ]
             }
+            (Some(AUDIT_COMMAND), _) => {
+                let report = audit_repository(&self.repo, &AuditPolicy::default()).await?;
+                vec![
+                    CtxResult::Messages(vec![audit_report_message(&report).into()]),
+                    CtxResult::RemoveMessages(vec![command.id]),
+                ]
+            }
+            (Some(EXPORT_COMMAND), _) => vec![
+                CtxResult::RemoveMessages(vec![command.id]),
+                CtxResult::NewCtx(Box::new(ExportDialog::new(self.repo.clone()))),
+            ],
             _ => default_commands_handler(command),
         })
     }
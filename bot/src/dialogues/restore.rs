@@ -97,7 +97,7 @@ where
                 result.push(CtxResult::RemoveMessages(vec![input.id.clone()]));
                 match self
                     .factory
-                    .load_user_repository(&self.user_id.clone().into(), msg.to_string(), file)
+                    .load_user_repository(&self.user_id.clone().into(), msg.into(), file)
                     .await
                 {
                     Ok(repo) => {
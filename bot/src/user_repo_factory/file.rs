@@ -5,8 +5,11 @@ use crate::user_repo_factory::{
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
 use sec_store::{
-    repository::file::{OpenRecordsFileRepository, RecordsFileRepository},
+    repository::file::{
+        OpenRecordsFileRepository, RecordsFileRepository, RecoverRecordsFileRepository,
+    },
     repository::{OpenRepository, OpenResult, RepositoryOpenError},
+    secret::SecretString,
 };
 use std::fs::rename;
 use std::path::{Path, PathBuf};
@@ -28,16 +31,16 @@ impl RepositoriesFactory<RecordsFileRepository> for FileRepositoriesFactory {
     async fn get_user_repository(
         &self,
         user_id: &UserId,
-        passwd: String,
+        passwd: SecretString,
     ) -> OpenResult<RecordsFileRepository> {
-        OpenRecordsFileRepository(self.get_repository_path(user_id))
+        RecoverRecordsFileRepository(self.get_repository_path(user_id))
             .open(passwd)
             .await
     }
     async fn load_user_repository<P: AsRef<Path> + Send>(
         &self,
         user_id: &UserId,
-        passwd: String,
+        passwd: SecretString,
         file: P,
     ) -> LoadResult<RecordsFileRepository> {
         match OpenRecordsFileRepository(file.as_ref().to_path_buf())
@@ -69,13 +72,16 @@ impl RepositoriesFactory<RecordsFileRepository> for FileRepositoriesFactory {
                     ))
                 }
                 RepositoryOpenError::WrongPassword => RepositoryLoadError::WrongPassword,
+                RepositoryOpenError::DamagedRecords(records) => RepositoryLoadError::OpenError(
+                    anyhow!("Repository has {} damaged records", records.len()),
+                ),
             }),
         }
     }
     async fn initialize_user_repository(
         &self,
         user_id: &UserId,
-        passwd: String,
+        passwd: SecretString,
     ) -> InitRepoResult<RecordsFileRepository> {
         match self.user_has_repository(user_id) {
             true => Err(RepositoryAlreadyExist),
@@ -90,6 +96,7 @@ impl RepositoriesFactory<RecordsFileRepository> for FileRepositoriesFactory {
 #[cfg(test)]
 mod tests {
     use sec_store::repository::{RecordsRepository, RepositoryOpenError};
+    use sec_store::secret::SecretString;
     use tempfile::TempDir;
 
     use crate::user_repo_factory::{file::FileRepositoriesFactory, RepositoriesFactory};
@@ -99,7 +106,7 @@ mod tests {
         let tmp_dir = TempDir::new().unwrap();
 
         let user_id = "user_id".to_string();
-        let passwd = "123".to_string();
+        let passwd = SecretString::from("123");
 
         let factory = FileRepositoriesFactory(tmp_dir.keep());
 
@@ -112,7 +119,7 @@ mod tests {
         let tmp_dir = TempDir::new().unwrap();
 
         let user_id = "user_id".to_string();
-        let passwd = "123".to_string();
+        let passwd = SecretString::from("123");
 
         let factory = FileRepositoriesFactory(tmp_dir.keep());
 
@@ -138,14 +145,14 @@ mod tests {
         let factory = FileRepositoriesFactory(tmp_dir.keep());
 
         factory
-            .initialize_user_repository(&user_id, passwd.to_string())
+            .initialize_user_repository(&user_id, passwd.into())
             .await
             .unwrap()
             .save()
             .await
             .unwrap();
         let result = factory
-            .get_user_repository(&user_id, "312".to_string())
+            .get_user_repository(&user_id, "312".into())
             .await
             .unwrap_err();
 
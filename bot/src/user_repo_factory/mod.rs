@@ -3,6 +3,7 @@ pub mod file;
 use anyhow::Result;
 use async_trait::async_trait;
 use sec_store::repository::OpenResult;
+use sec_store::secret::SecretString;
 use std::fmt::Display;
 use std::path::Path;
 use thiserror::Error;
@@ -43,16 +44,16 @@ pub type UserId = String;
 #[async_trait]
 pub trait RepositoriesFactory<T>: Clone + Sync + Send + 'static {
     fn user_has_repository(&self, user_id: &UserId) -> bool;
-    async fn get_user_repository(&self, user_id: &UserId, passwd: String) -> OpenResult<T>;
+    async fn get_user_repository(&self, user_id: &UserId, passwd: SecretString) -> OpenResult<T>;
     async fn load_user_repository<P: AsRef<Path> + Send>(
         &self,
         user_id: &UserId,
-        passwd: String,
+        passwd: SecretString,
         file: P,
     ) -> LoadResult<T>;
     async fn initialize_user_repository(
         &self,
         user_id: &UserId,
-        passwd: String,
+        passwd: SecretString,
     ) -> InitRepoResult<T>;
 }
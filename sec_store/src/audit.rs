@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use anyhow::Result;
+
+use crate::breach::BreachedRecord;
+use crate::record::{unix_now, Record, RecordId, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+use crate::repository::RecordsRepository;
+
+const SECONDS_IN_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditPolicy {
+    pub min_length: usize,
+    pub min_entropy_bits: f64,
+    /// Passwords unchanged for longer are reported, `None` disables the check.
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        AuditPolicy {
+            min_length: 12,
+            min_entropy_bits: 60.0,
+            max_age_days: Some(365),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditIssue {
+    /// Names of the other records with the same password.
+    ReusedPassword(Vec<String>),
+    ShortPassword(usize),
+    WeakPassword(f64),
+    StalePassword(u64),
+    MissingField(String),
+    /// Times the password was seen in known breaches.
+    BreachedPassword(u64),
+}
+
+impl Display for AuditIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditIssue::ReusedPassword(others) => {
+                write!(f, "Password is also used by {}", others.join(", "))
+            }
+            AuditIssue::ShortPassword(length) => {
+                write!(f, "Password has only {length} characters")
+            }
+            AuditIssue::WeakPassword(bits) => {
+                write!(f, "Password has about {bits:.0} bits of entropy")
+            }
+            AuditIssue::StalePassword(days) => {
+                write!(f, "Password hasn't been changed for {days} days")
+            }
+            AuditIssue::MissingField(field) => {
+                let label = match field.as_str() {
+                    RECORD_NAME_FIELD => "name",
+                    RECORD_PASSWD_FIELD => "password",
+                    other => other,
+                };
+                write!(f, "The {label} is empty")
+            }
+            AuditIssue::BreachedPassword(occurrences) => {
+                write!(f, "Password appears in {occurrences} known breaches")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordAudit {
+    pub record_id: RecordId,
+    pub name: String,
+    pub issues: Vec<AuditIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AuditReport {
+    pub checked_records: usize,
+    /// Records with at least one issue, sorted by name.
+    pub records: Vec<RecordAudit>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn issues_count(&self) -> usize {
+        self.records.iter().map(|record| record.issues.len()).sum()
+    }
+
+    /// Adds the result of a breach check, records missing from the report are added.
+    pub fn add_breaches(&mut self, breached: &[BreachedRecord]) {
+        for breach in breached {
+            let issue = AuditIssue::BreachedPassword(breach.occurrences);
+            match self
+                .records
+                .iter_mut()
+                .find(|record| record.record_id == breach.record_id)
+            {
+                Some(record) => {
+                    record
+                        .issues
+                        .retain(|issue| !matches!(issue, AuditIssue::BreachedPassword(_)));
+                    record.issues.insert(0, issue);
+                }
+                None => self.records.push(RecordAudit {
+                    record_id: breach.record_id.clone(),
+                    name: breach.name.clone(),
+                    issues: vec![issue],
+                }),
+            }
+        }
+        self.records.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+}
+
+pub async fn audit_repository<R: RecordsRepository>(
+    repo: &R,
+    policy: &AuditPolicy,
+) -> Result<AuditReport> {
+    Ok(audit_records(
+        &repo.get_records().await?,
+        policy,
+        unix_now(),
+    ))
+}
+
+pub fn audit_records(records: &[Record], policy: &AuditPolicy, now: u64) -> AuditReport {
+    let name_of = |record: &Record| {
+        record
+            .get_field_value(RECORD_NAME_FIELD)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let mut by_password: HashMap<&str, Vec<&Record>> = HashMap::new();
+    for record in records {
+        if let Some(field) = record.get_field(RECORD_PASSWD_FIELD) {
+            if !field.value.is_empty() {
+                by_password.entry(&field.value).or_default().push(record);
+            }
+        }
+    }
+
+    let mut audited: Vec<RecordAudit> = records
+        .iter()
+        .map(|record| {
+            let mut issues = Vec::new();
+            for required in [RECORD_NAME_FIELD, RECORD_PASSWD_FIELD] {
+                if record
+                    .get_field(required)
+                    .is_none_or(|field| field.value.trim().is_empty())
+                {
+                    issues.push(AuditIssue::MissingField(required.to_string()));
+                }
+            }
+
+            if let Some(field) = record
+                .get_field(RECORD_PASSWD_FIELD)
+                .filter(|field| !field.value.is_empty())
+            {
+                let others: Vec<String> = by_password[field.value.as_str()]
+                    .iter()
+                    .filter(|other| other.id != record.id)
+                    .map(|other| name_of(other))
+                    .collect();
+                if !others.is_empty() {
+                    issues.push(AuditIssue::ReusedPassword(others));
+                }
+
+                let length = field.value.chars().count();
+                if length < policy.min_length {
+                    issues.push(AuditIssue::ShortPassword(length));
+                }
+                let entropy = estimate_entropy(&field.value);
+                if entropy < policy.min_entropy_bits {
+                    issues.push(AuditIssue::WeakPassword(entropy));
+                }
+
+                // The newest history entry is the moment the current value was set.
+                let changed_at = field
+                    .history
+                    .first()
+                    .map_or(record.created_at(), |revision| revision.replaced_at);
+                if let Some(max_age_days) = policy.max_age_days {
+                    let age_days = now.saturating_sub(changed_at) / SECONDS_IN_DAY;
+                    if changed_at != 0 && age_days > max_age_days {
+                        issues.push(AuditIssue::StalePassword(age_days));
+                    }
+                }
+            }
+
+            RecordAudit {
+                record_id: record.id.clone(),
+                name: name_of(record),
+                issues,
+            }
+        })
+        .filter(|audit| !audit.issues.is_empty())
+        .collect();
+    audited.sort_by(|a, b| a.name.cmp(&b.name));
+
+    AuditReport {
+        checked_records: records.len(),
+        records: audited,
+    }
+}
+
+/// A rough estimate: length times log2 of the size of the character classes in use,
+/// a run of the same character counts as one character.
+pub fn estimate_entropy(password: &str) -> f64 {
+    let (mut lower, mut upper, mut digits, mut symbols, mut other) =
+        (false, false, false, false, false);
+    for c in password.chars() {
+        match c {
+            'a'..='z' => lower = true,
+            'A'..='Z' => upper = true,
+            '0'..='9' => digits = true,
+            c if c.is_ascii() => symbols = true,
+            _ => other = true,
+        }
+    }
+    let pool = [
+        (lower, 26),
+        (upper, 26),
+        (digits, 10),
+        (symbols, 33),
+        (other, 100),
+    ]
+    .into_iter()
+    .filter(|(used, _)| *used)
+    .map(|(_, size)| size)
+    .sum::<u32>();
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let mut previous = None;
+    let effective_length = password
+        .chars()
+        .filter(|c| previous.replace(*c) != Some(*c))
+        .count();
+    effective_length as f64 * (pool as f64).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{audit_records, estimate_entropy, AuditIssue, AuditPolicy};
+    use crate::breach::BreachedRecord;
+    use crate::record::{Record, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn record(name: &str, password: &str) -> Record {
+        Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), name),
+            (RECORD_PASSWD_FIELD.to_string(), password),
+        ])
+    }
+
+    #[test]
+    fn test_estimate_entropy() {
+        assert_eq!(estimate_entropy(""), 0.0);
+        assert!((estimate_entropy("abcd") - 4.0 * 26f64.log2()).abs() < 1e-9);
+        assert!(estimate_entropy("aaaaaaaa") < estimate_entropy("abcdefgh"));
+        assert!(estimate_entropy("Tr0ub4dor&3xyzQ") > 90.0);
+    }
+
+    #[test]
+    fn test_reused_and_weak_passwords() {
+        let strong = "u7#Kp2$wQz9!mR4x";
+        let records = vec![
+            record("Mail", strong),
+            record("Bank", strong),
+            record("Forum", "12345"),
+            record("Git", "Xk8$vL2@pQ7#nM5w"),
+        ];
+        let report = audit_records(&records, &AuditPolicy::default(), records[0].created_at());
+
+        assert_eq!(report.checked_records, 4);
+        let names: Vec<&str> = report.records.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Bank", "Forum", "Mail"]);
+        assert_eq!(
+            report.records[0].issues,
+            vec![AuditIssue::ReusedPassword(vec!["Mail".to_string()])]
+        );
+        assert_eq!(report.records[1].issues[0], AuditIssue::ShortPassword(5));
+        assert!(matches!(
+            report.records[1].issues[1],
+            AuditIssue::WeakPassword(_)
+        ));
+        assert_eq!(report.issues_count(), 4);
+    }
+
+    #[test]
+    fn test_stale_password_and_missing_fields() {
+        let old = record("Old", "u7#Kp2$wQz9!mR4x");
+        let mut changed = record("Changed", "Xk8$vL2@pQ7#nM5w");
+        changed
+            .update_field(RECORD_PASSWD_FIELD.to_string(), "Zq3!rT8@wE5#yU1$")
+            .unwrap();
+        let no_password = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "Empty")]);
+        let now = old.created_at() + 400 * DAY;
+
+        let mut policy = AuditPolicy::default();
+        let report = audit_records(&[old.clone(), no_password], &policy, now);
+        assert_eq!(
+            report.records[0].issues,
+            vec![AuditIssue::MissingField(RECORD_PASSWD_FIELD.to_string())]
+        );
+        assert_eq!(
+            report.records[1].issues,
+            vec![AuditIssue::StalePassword(400)]
+        );
+
+        // The history tells when the current password was set.
+        assert!(audit_records(&[changed], &policy, now - 100 * DAY).is_clean());
+
+        policy.max_age_days = None;
+        assert!(audit_records(&[old], &policy, now).is_clean());
+    }
+
+    #[test]
+    fn test_add_breaches() {
+        let records = vec![record("Mail", "12345"), record("Bank", "u7#Kp2$wQz9!mR4x")];
+        let mut report = audit_records(&records, &AuditPolicy::default(), records[0].created_at());
+        let breached: Vec<BreachedRecord> = records
+            .iter()
+            .zip(["Mail", "Bank"])
+            .map(|(record, name)| BreachedRecord {
+                record_id: record.id.clone(),
+                name: name.to_string(),
+                occurrences: 3,
+            })
+            .collect();
+
+        report.add_breaches(&breached);
+        report.add_breaches(&breached);
+        let names: Vec<&str> = report.records.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Bank", "Mail"]);
+        assert_eq!(
+            report.records[0].issues,
+            vec![AuditIssue::BreachedPassword(3)]
+        );
+        assert_eq!(report.records[1].issues[0], AuditIssue::BreachedPassword(3));
+        assert_eq!(report.issues_count(), 4);
+    }
+}
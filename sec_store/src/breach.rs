@@ -0,0 +1,231 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use ring::digest;
+use serde::{Deserialize, Serialize};
+
+use crate::record::{Record, RecordId, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+
+const SHA1_HEX_LEN: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreachedRecord {
+    pub record_id: RecordId,
+    pub name: String,
+    /// How many times the password was seen in breaches.
+    pub occurrences: u64,
+}
+
+/// A "Pwned Passwords" SHA-1 list sorted by hash, one `HASH:COUNT` per line.
+///
+/// The file isn't loaded into memory, lookups are a binary search over its bytes.
+#[derive(Debug)]
+pub struct PwnedHashes {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl PwnedHashes {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<PwnedHashes> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut hashes = PwnedHashes {
+            reader: BufReader::new(file),
+            len,
+        };
+
+        match hashes.line_from(0)? {
+            Some((_, line)) if parse_line(&line).is_some() => Ok(hashes),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a SHA-1 hash list, expected HASH:COUNT lines",
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The hash list is empty",
+            )),
+        }
+    }
+
+    /// How many times the hex encoded SHA-1 hash was seen, `None` if it's not in the list.
+    pub fn occurrences(&mut self, sha1_hex: &str) -> io::Result<Option<u64>> {
+        let target = sha1_hex.to_ascii_uppercase();
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let Some((next, line)) = self.line_from(mid)? else {
+                hi = mid;
+                continue;
+            };
+            let (hash, count) = parse_line(&line).unwrap_or_default();
+            match hash.to_ascii_uppercase().cmp(&target) {
+                Ordering::Equal => return Ok(Some(count)),
+                Ordering::Less => lo = next,
+                Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn check_password(&mut self, password: &str) -> io::Result<Option<u64>> {
+        self.occurrences(&sha1_hex(password))
+    }
+
+    /// Records whose password is in the list, in the order of `records`.
+    pub fn check_records(&mut self, records: &[Record]) -> io::Result<Vec<BreachedRecord>> {
+        let mut breached = Vec::new();
+        for record in records {
+            let Some(password) = record
+                .get_field_value(RECORD_PASSWD_FIELD)
+                .filter(|password| !password.is_empty())
+            else {
+                continue;
+            };
+            if let Some(occurrences) = self.check_password(&password)? {
+                breached.push(BreachedRecord {
+                    record_id: record.id.clone(),
+                    name: record
+                        .get_field_value(RECORD_NAME_FIELD)
+                        .map(|name| name.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    occurrences,
+                });
+            }
+        }
+        Ok(breached)
+    }
+
+    /// The first line starting at or after `pos` and the offset right after it.
+    fn line_from(&mut self, pos: u64) -> io::Result<Option<(u64, String)>> {
+        let mut start = pos;
+        if pos > 0 {
+            // Starting one byte earlier keeps a line that begins exactly at `pos`.
+            self.reader.seek(SeekFrom::Start(pos - 1))?;
+            let mut skipped = Vec::new();
+            start = pos - 1 + self.reader.read_until(b'\n', &mut skipped)? as u64;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start + read as u64, line)))
+    }
+}
+
+/// Uppercase hex SHA-1, the format used by the lists.
+pub fn sha1_hex(value: &str) -> String {
+    digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, value.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<(&str, u64)> {
+    let line = line.trim_end();
+    let (hash, count) = match line.split_once(':') {
+        Some((hash, count)) => (hash, count.trim().parse().ok()?),
+        None => (line, 1),
+    };
+    (hash.len() == SHA1_HEX_LEN && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .then_some((hash, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::{sha1_hex, BreachedRecord, PwnedHashes};
+    use crate::record::{Record, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+
+    fn hashes_file(passwords: &[(&str, u64)], line_end: &str) -> NamedTempFile {
+        let mut lines: Vec<String> = passwords
+            .iter()
+            .map(|(password, count)| format!("{}:{count}", sha1_hex(password)))
+            .collect();
+        lines.sort();
+        let mut file = NamedTempFile::new().unwrap();
+        for line in lines {
+            write!(file, "{line}{line_end}").unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn test_sha1_hex() {
+        assert_eq!(
+            sha1_hex("password"),
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        );
+    }
+
+    #[test]
+    fn test_lookup_every_line() {
+        let passwords: Vec<(String, u64)> = (0..200)
+            .map(|n| (format!("password{n}"), n as u64 + 1))
+            .collect();
+        let passwords: Vec<(&str, u64)> = passwords
+            .iter()
+            .map(|(password, count)| (password.as_str(), *count))
+            .collect();
+
+        for line_end in ["\n", "\r\n"] {
+            let file = hashes_file(&passwords, line_end);
+            let mut hashes = PwnedHashes::open(file.path()).unwrap();
+            for (password, count) in &passwords {
+                assert_eq!(hashes.check_password(password).unwrap(), Some(*count));
+            }
+            assert_eq!(hashes.check_password("not-in-the-list").unwrap(), None);
+            assert_eq!(
+                hashes
+                    .occurrences(&sha1_hex("password7").to_lowercase())
+                    .unwrap(),
+                Some(8)
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_records() {
+        let file = hashes_file(&[("password", 42), ("123456", 7)], "\n");
+        let mut hashes = PwnedHashes::open(file.path()).unwrap();
+        let records = vec![
+            Record::new(vec![
+                (RECORD_NAME_FIELD.to_string(), "Mail"),
+                (RECORD_PASSWD_FIELD.to_string(), "password"),
+            ]),
+            Record::new(vec![
+                (RECORD_NAME_FIELD.to_string(), "Bank"),
+                (RECORD_PASSWD_FIELD.to_string(), "u7#Kp2$wQz9!mR4x"),
+            ]),
+            Record::new(vec![(RECORD_NAME_FIELD.to_string(), "Empty")]),
+        ];
+
+        assert_eq!(
+            hashes.check_records(&records).unwrap(),
+            vec![BreachedRecord {
+                record_id: records[0].id.clone(),
+                name: "Mail".to_string(),
+                occurrences: 42,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_other_files() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "this is not a hash list").unwrap();
+        assert!(PwnedHashes::open(file.path()).is_err());
+
+        let empty = NamedTempFile::new().unwrap();
+        assert!(PwnedHashes::open(empty.path()).is_err());
+    }
+}
@@ -1,68 +1,282 @@
 use aes::cipher::{KeyIvInit, StreamCipher};
-use anyhow::Result;
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
 use rand::Rng;
 use ring::digest;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
 type _Aes128Ctr64LE = ctr::Ctr64LE<aes::Aes256>;
 
 pub type DecryptResult<T> = Result<T, DecryptionError>;
 
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+
+const GCM_NONCE_LEN: usize = 12;
+const KEY_CHECK_LEN: usize = 8;
+const KEY_CHECK_CONTEXT: &[u8] = b"PasswordsKeeper key check v2";
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DecryptionError {
     WrongPassword,
+    // The key is right but the ciphertext or its tag was modified.
+    Tampered,
     EncodingError(std::string::FromUtf8Error),
+    // The stored value isn't a ciphertext or doesn't decrypt to the expected document.
+    Malformed(String),
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct EncryptedData {
+#[serde(untagged)]
+pub enum EncryptedData {
+    V2(EncryptedDataV2),
+    V1(EncryptedDataV1),
+}
+
+/// AES-256-GCM ciphertext. `key_check` is a short fingerprint of the key,
+/// it tells a wrong password apart from a modified ciphertext.
+///
+/// A failed tag alone can't make that distinction, so the tag only reports
+/// `Tampered` once the key is known to be right. The fingerprint gives an
+/// offline guesser nothing the tag wouldn't, both need a full key derivation
+/// per guess. The repository header is not passed as associated data: its
+/// KDF parameters already decide the key, and the body layout decides how
+/// authenticated data is read, so changing either fails decryption. Only the
+/// header timestamps can be edited unnoticed.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedDataV2 {
+    data: Vec<u8>,
+    nonce: [u8; GCM_NONCE_LEN],
+    key_check: [u8; KEY_CHECK_LEN],
+}
+
+/// AES-CTR ciphertext with an MD5 of the plaintext, only read for
+/// compatibility with data written before authenticated encryption.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedDataV1 {
     data: Vec<u8>,
     nonce: [u8; 16],
     hash: Vec<u8>,
 }
 
-pub fn encrypt_string(passwd: &str, string: &str) -> EncryptedData {
-    let key = digest::digest(&digest::SHA256, passwd.as_bytes());
-    let mut encrypted_data = EncryptedData {
-        data: string.as_bytes().to_vec(),
-        nonce: rand::rng().random(),
-        hash: md5::compute(string).to_vec(),
+#[derive(Clone, PartialEq, Eq)]
+pub struct CipherKey([u8; KEY_LEN]);
+
+impl CipherKey {
+    fn check_value(&self) -> [u8; KEY_CHECK_LEN] {
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(KEY_CHECK_CONTEXT);
+        ctx.update(&self.0);
+        let mut check = [0u8; KEY_CHECK_LEN];
+        check.copy_from_slice(&ctx.finish().as_ref()[..KEY_CHECK_LEN]);
+        check
+    }
+}
+
+impl Drop for CipherKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::fmt::Debug for CipherKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CipherKey(<redacted>)")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Cost {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Argon2Cost {
+    /// The highest cost accepted from a repository header, anything above could exhaust memory.
+    pub const MAX: Argon2Cost = Argon2Cost {
+        memory_kib: 1024 * 1024,
+        iterations: 100,
+        parallelism: 16,
     };
+}
 
-    let mut cipher = _Aes128Ctr64LE::new(key.as_ref().into(), &encrypted_data.nonce.into());
-    cipher.apply_keystream(&mut encrypted_data.data);
+impl Default for Argon2Cost {
+    // OWASP recommended minimum for Argon2id.
+    fn default() -> Self {
+        Argon2Cost {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// How the repository key is derived from the master password.
+///
+/// Stored next to the encrypted data so that the cost can be raised later
+/// without breaking repositories that were written with older settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+pub enum KdfParams {
+    /// Unsalted SHA-256 of the password. Only used to read repositories
+    /// written before Argon2id support, they are re-keyed on the next save.
+    LegacySha256,
+    Argon2id {
+        salt: Vec<u8>,
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl KdfParams {
+    pub fn argon2id(cost: Argon2Cost) -> KdfParams {
+        KdfParams::Argon2id {
+            salt: rand::rng().random::<[u8; SALT_LEN]>().to_vec(),
+            memory_kib: cost.memory_kib,
+            iterations: cost.iterations,
+            parallelism: cost.parallelism,
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, KdfParams::LegacySha256)
+    }
+
+    /// Refuses costs above [`Argon2Cost::MAX`], headers come from untrusted files.
+    pub fn check_cost(&self) -> Result<()> {
+        if let KdfParams::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+            ..
+        } = self
+        {
+            let max = Argon2Cost::MAX;
+            if *memory_kib > max.memory_kib
+                || *iterations > max.iterations
+                || *parallelism > max.parallelism
+            {
+                bail!(
+                    "Argon2id cost of {memory_kib} KiB, {iterations} iterations and \
+                     {parallelism} lanes exceeds the supported maximum"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn derive_key(&self, passwd: &str) -> Result<CipherKey> {
+        self.check_cost()?;
+        let mut key = [0u8; KEY_LEN];
+        match self {
+            KdfParams::LegacySha256 => {
+                key.copy_from_slice(digest::digest(&digest::SHA256, passwd.as_bytes()).as_ref());
+            }
+            KdfParams::Argon2id {
+                salt,
+                memory_kib,
+                iterations,
+                parallelism,
+            } => {
+                let params = Params::new(*memory_kib, *iterations, *parallelism, Some(KEY_LEN))
+                    .map_err(|err| anyhow!("Invalid Argon2id parameters: {err}"))?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(passwd.as_bytes(), salt, &mut key)
+                    .map_err(|err| anyhow!("Argon2id key derivation failed: {err}"))?;
+            }
+        }
+        Ok(CipherKey(key))
+    }
+}
+
+pub fn encrypt_string(key: &CipherKey, string: &str) -> EncryptedData {
+    encrypt_bytes(key, string.as_bytes())
+}
+
+pub fn encrypt_bytes(key: &CipherKey, bytes: &[u8]) -> EncryptedData {
+    let nonce: [u8; GCM_NONCE_LEN] = rand::rng().random();
+    let data = Aes256Gcm::new(key.0.as_ref().into())
+        .encrypt(Nonce::from_slice(&nonce), bytes)
+        .expect("AES-GCM encryption of in-memory data must not fail");
+
+    EncryptedData::V2(EncryptedDataV2 {
+        data,
+        nonce,
+        key_check: key.check_value(),
+    })
+}
+
+pub fn decrypt_string(key: &CipherKey, encrypted_data: EncryptedData) -> DecryptResult<String> {
+    String::from_utf8(decrypt_bytes(key, encrypted_data)?).map_err(DecryptionError::EncodingError)
+}
+
+pub fn decrypt_bytes(key: &CipherKey, encrypted_data: EncryptedData) -> DecryptResult<Vec<u8>> {
+    match encrypted_data {
+        EncryptedData::V2(encrypted_data) => decrypt_v2(key, encrypted_data),
+        EncryptedData::V1(encrypted_data) => decrypt_v1(key, encrypted_data),
+    }
+}
+
+fn decrypt_v2(key: &CipherKey, encrypted_data: EncryptedDataV2) -> DecryptResult<Vec<u8>> {
+    if key.check_value() != encrypted_data.key_check {
+        return Err(DecryptionError::WrongPassword);
+    }
 
-    encrypted_data
+    Aes256Gcm::new(key.0.as_ref().into())
+        .decrypt(
+            Nonce::from_slice(&encrypted_data.nonce),
+            encrypted_data.data.as_ref(),
+        )
+        .map_err(|_| DecryptionError::Tampered)
 }
 
-pub fn decrypt_string(passwd: &str, mut encrypted_data: EncryptedData) -> DecryptResult<String> {
-    let key = digest::digest(&digest::SHA256, passwd.as_bytes());
-    let mut cipher = _Aes128Ctr64LE::new(key.as_ref().into(), &encrypted_data.nonce.into());
+fn decrypt_v1(key: &CipherKey, mut encrypted_data: EncryptedDataV1) -> DecryptResult<Vec<u8>> {
+    let mut cipher = _Aes128Ctr64LE::new(key.0.as_ref().into(), &encrypted_data.nonce.into());
 
     cipher.apply_keystream(&mut encrypted_data.data);
     if md5::compute(encrypted_data.data.clone()).to_vec() != encrypted_data.hash {
         return Err(DecryptionError::WrongPassword);
     }
 
-    String::from_utf8(encrypted_data.data).map_err(DecryptionError::EncodingError)
+    Ok(encrypted_data.data)
+}
+
+#[cfg(test)]
+pub(crate) fn test_key(passwd: &str) -> CipherKey {
+    KdfParams::argon2id(Argon2Cost {
+        memory_kib: 8,
+        iterations: 1,
+        parallelism: 1,
+    })
+    .derive_key(passwd)
+    .unwrap()
 }
 
 #[test]
 fn test_encryption() {
-    let passwd = "some password!";
+    let key = test_key("some password!");
     let plaintext = "Hellow rodl!";
 
-    let decryted_string = decrypt_string(passwd, encrypt_string(passwd, plaintext));
+    let decryted_string = decrypt_string(&key, encrypt_string(&key, plaintext));
 
     assert_eq!(decryted_string.unwrap(), plaintext);
 }
 
 #[test]
 fn test_encryption_data() {
-    let passwd = "some password!";
+    let key = test_key("some password!");
     let plaintext = "Hellow rodl!";
 
-    let encrypted_data = encrypt_string(passwd, plaintext);
+    let EncryptedData::V2(encrypted_data) = encrypt_string(&key, plaintext) else {
+        panic!("new data must be encrypted with AES-GCM");
+    };
 
     let result = String::from_utf8(encrypted_data.data);
     if let Ok(result_str) = result {
@@ -70,13 +284,130 @@ fn test_encryption_data() {
     }
 }
 
+#[test]
+fn test_encryption_detects_tampering() {
+    let key = test_key("some password!");
+    let EncryptedData::V2(mut encrypted_data) = encrypt_string(&key, "Some string") else {
+        panic!("new data must be encrypted with AES-GCM");
+    };
+    encrypted_data.data[0] ^= 1;
+
+    let result = decrypt_string(&key, EncryptedData::V2(encrypted_data));
+
+    assert_eq!(result, Err(DecryptionError::Tampered));
+}
+
+#[test]
+fn test_encrypted_data_v1_is_readable() {
+    let key = test_key("some password!");
+    let plaintext = "Hellow rodl!";
+    let mut v1 = EncryptedDataV1 {
+        data: plaintext.as_bytes().to_vec(),
+        nonce: rand::rng().random(),
+        hash: md5::compute(plaintext).to_vec(),
+    };
+    _Aes128Ctr64LE::new(key.0.as_ref().into(), &v1.nonce.into()).apply_keystream(&mut v1.data);
+
+    let json = serde_json::to_string(&EncryptedData::V1(v1)).unwrap();
+    let parsed = serde_json::from_str::<EncryptedData>(&json).unwrap();
+    assert!(matches!(parsed, EncryptedData::V1(_)));
+
+    assert_eq!(decrypt_string(&key, parsed).unwrap(), plaintext);
+    assert_eq!(
+        decrypt_string(
+            &test_key("Second password!"),
+            serde_json::from_str::<EncryptedData>(&json).unwrap()
+        ),
+        Err(DecryptionError::WrongPassword)
+    );
+}
+
+#[test]
+fn test_encrypted_data_v2_roundtrips_through_json() {
+    let key = test_key("some password!");
+    let json = serde_json::to_string(&encrypt_string(&key, "Some string")).unwrap();
+
+    let parsed = serde_json::from_str::<EncryptedData>(&json).unwrap();
+
+    assert!(matches!(parsed, EncryptedData::V2(_)));
+    assert_eq!(decrypt_string(&key, parsed).unwrap(), "Some string");
+}
+
+#[test]
+fn test_encryption_of_binary_data() {
+    let key = test_key("some password!");
+    let bytes = vec![0u8, 159, 146, 150, 255];
+
+    assert_eq!(
+        decrypt_bytes(&key, encrypt_bytes(&key, &bytes)).unwrap(),
+        bytes
+    );
+}
+
 #[test]
 fn test_encryption_acces_with_wrong_passwd() {
     let result = decrypt_string(
-        "Second password!",
-        encrypt_string("First password!", "Some string"),
+        &test_key("Second password!"),
+        encrypt_string(&test_key("First password!"), "Some string"),
     );
     let expected = DecryptionError::WrongPassword;
 
     assert_eq!(result, Err(expected));
 }
+
+#[test]
+fn test_argon2id_uses_random_salt() {
+    let cost = Argon2Cost {
+        memory_kib: 8,
+        iterations: 1,
+        parallelism: 1,
+    };
+    let first = KdfParams::argon2id(cost);
+    let second = KdfParams::argon2id(cost);
+
+    assert_ne!(first, second);
+    assert_ne!(
+        first.derive_key("password").unwrap(),
+        second.derive_key("password").unwrap()
+    );
+    assert_eq!(
+        first.derive_key("password").unwrap(),
+        first.derive_key("password").unwrap()
+    );
+}
+
+#[test]
+fn test_legacy_kdf_matches_sha256() {
+    let key = KdfParams::LegacySha256.derive_key("password").unwrap();
+
+    assert_eq!(
+        key.0.as_ref(),
+        digest::digest(&digest::SHA256, b"password").as_ref()
+    );
+}
+
+#[test]
+fn test_excessive_argon2id_cost_is_refused() {
+    let kdf = KdfParams::Argon2id {
+        salt: vec![0; SALT_LEN],
+        memory_kib: u32::MAX,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    assert!(kdf.check_cost().is_err());
+    assert!(kdf.derive_key("password").is_err());
+    assert!(KdfParams::argon2id(Argon2Cost::MAX).check_cost().is_ok());
+}
+
+#[test]
+fn test_invalid_argon2id_params_fail() {
+    let kdf = KdfParams::Argon2id {
+        salt: vec![0; SALT_LEN],
+        memory_kib: 0,
+        iterations: 0,
+        parallelism: 0,
+    };
+
+    assert!(kdf.derive_key("password").is_err());
+}
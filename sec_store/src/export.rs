@@ -0,0 +1,520 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::record::{
+    FieldKind, Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD,
+    RECORD_PASSWD_FIELD, RECORD_TOTP_FIELD, RECORD_URL_FIELD,
+};
+use crate::repository::file::RecordsFileRepository;
+use crate::secret::SecretString;
+
+const WELL_KNOWN_FIELDS: [&str; 6] = [
+    RECORD_NAME_FIELD,
+    RECORD_LOGIN_FIELD,
+    RECORD_PASSWD_FIELD,
+    RECORD_URL_FIELD,
+    RECORD_DESCR_FIELD,
+    RECORD_TOTP_FIELD,
+];
+// Headers understood by the default CSV import mapping.
+const CSV_COLUMNS: [(&str, &str); 6] = [
+    ("name", RECORD_NAME_FIELD),
+    ("login", RECORD_LOGIN_FIELD),
+    ("password", RECORD_PASSWD_FIELD),
+    ("url", RECORD_URL_FIELD),
+    ("notes", RECORD_DESCR_FIELD),
+    ("totp", RECORD_TOTP_FIELD),
+];
+// KeePassXC keeps the otpauth URI in `otp`.
+const KEEPASS_STRINGS: [(&str, &str); 6] = [
+    ("Title", RECORD_NAME_FIELD),
+    ("UserName", RECORD_LOGIN_FIELD),
+    ("Password", RECORD_PASSWD_FIELD),
+    ("URL", RECORD_URL_FIELD),
+    ("Notes", RECORD_DESCR_FIELD),
+    ("otp", RECORD_TOTP_FIELD),
+];
+const BITWARDEN_LOGIN_TYPE: u8 = 1;
+const BITWARDEN_TEXT_FIELD: u8 = 0;
+const BITWARDEN_HIDDEN_FIELD: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Bitwarden,
+    KeepassXml,
+    Encrypted,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Csv,
+        ExportFormat::Bitwarden,
+        ExportFormat::KeepassXml,
+        ExportFormat::Encrypted,
+    ];
+
+    /// Whether anyone holding the file can read the passwords.
+    pub fn is_plain_text(&self) -> bool {
+        *self != ExportFormat::Encrypted
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Bitwarden | ExportFormat::Encrypted => "json",
+            ExportFormat::KeepassXml => "xml",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Bitwarden | ExportFormat::Encrypted => "application/json",
+            ExportFormat::KeepassXml => "application/xml",
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Bitwarden => "Bitwarden JSON",
+            ExportFormat::KeepassXml => "KeePass XML",
+            ExportFormat::Encrypted => "Encrypted repository",
+        })
+    }
+}
+
+/// Writes `records` in `format`, the `Encrypted` export is protected by `password`.
+pub fn export_records(
+    records: &[Record],
+    format: ExportFormat,
+    password: Option<SecretString>,
+) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Csv => export_csv(records),
+        ExportFormat::Bitwarden => export_bitwarden(records),
+        ExportFormat::KeepassXml => Ok(export_keepass_xml(records)),
+        ExportFormat::Encrypted => {
+            let password = password
+                .filter(|password| !password.is_empty())
+                .ok_or_else(|| anyhow!("The encrypted export needs a password"))?;
+            RecordsFileRepository::encrypted_dump(records, password)
+        }
+    }
+}
+
+/// One row per record, custom fields get a column of their own.
+///
+/// Cells a spreadsheet would run as a formula are prefixed with `'`, the prefix stays in
+/// the value when the file is imported again.
+pub fn export_csv(records: &[Record]) -> Result<Vec<u8>> {
+    let records = sorted(records);
+    let mut custom_fields: Vec<&str> = Vec::new();
+    for field in records.iter().flat_map(|record| record.get_fields()) {
+        if !WELL_KNOWN_FIELDS.contains(&field.name.as_str())
+            && !custom_fields.contains(&field.name.as_str())
+        {
+            custom_fields.push(&field.name);
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(
+        CSV_COLUMNS
+            .iter()
+            .map(|(header, _)| *header)
+            .chain(["folder", "tags"])
+            .chain(custom_fields.iter().copied())
+            .map(csv_cell),
+    )?;
+    for record in records {
+        let value = |name: &str| {
+            record
+                .get_field_value(name)
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        };
+        let mut row: Vec<String> = CSV_COLUMNS.iter().map(|(_, name)| value(name)).collect();
+        row.push(record.folder().unwrap_or_default().to_string());
+        row.push(record.tags().join(","));
+        row.extend(custom_fields.iter().map(|name| value(name)));
+        writer.write_record(row.iter().map(|cell| csv_cell(cell)))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| anyhow!("Failed CSV export: {}", err.error()))
+}
+
+fn csv_cell(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// An unencrypted Bitwarden JSON export, Bitwarden has no tags so they are left out.
+pub fn export_bitwarden(records: &[Record]) -> Result<Vec<u8>> {
+    let mut folders: Vec<(String, &str)> = Vec::new();
+    let mut items = Vec::new();
+    for record in sorted(records) {
+        let folder_id =
+            record.folder().map(
+                |folder| match folders.iter().find(|(_, name)| *name == folder) {
+                    Some((id, _)) => id.clone(),
+                    None => {
+                        let id = Uuid::new_v4().to_string();
+                        folders.push((id.clone(), folder));
+                        id
+                    }
+                },
+            );
+        items.push(bitwarden_item(record, folder_id));
+    }
+    let folders: Vec<Value> = folders
+        .into_iter()
+        .map(|(id, name)| json!({ "id": id, "name": name }))
+        .collect();
+    Ok(serde_json::to_vec_pretty(&json!({
+        "encrypted": false,
+        "folders": folders,
+        "items": items,
+    }))?)
+}
+
+fn bitwarden_item(record: &Record, folder_id: Option<String>) -> Value {
+    let value = |name: &str| record.get_field_value(name).map(|value| value.to_string());
+    let mut uris: Vec<Value> = value(RECORD_URL_FIELD)
+        .into_iter()
+        .map(|uri| json!({ "match": null, "uri": uri }))
+        .collect();
+    let mut fields = Vec::new();
+    for field in record.get_fields() {
+        if WELL_KNOWN_FIELDS.contains(&field.name.as_str()) {
+            continue;
+        }
+        if field.kind == FieldKind::Url {
+            uris.push(json!({ "match": null, "uri": field.value.as_str() }));
+            continue;
+        }
+        let field_type = if field.kind.is_concealed() {
+            BITWARDEN_HIDDEN_FIELD
+        } else {
+            BITWARDEN_TEXT_FIELD
+        };
+        fields.push(json!({
+            "name": field.name,
+            "value": field.value.as_str(),
+            "type": field_type,
+            "linkedId": null,
+        }));
+    }
+    json!({
+        "id": record.id,
+        "organizationId": null,
+        "folderId": folder_id,
+        "type": BITWARDEN_LOGIN_TYPE,
+        "reprompt": 0,
+        "name": value(RECORD_NAME_FIELD).unwrap_or_default(),
+        "notes": value(RECORD_DESCR_FIELD),
+        "favorite": false,
+        "fields": fields,
+        "login": {
+            "uris": uris,
+            "username": value(RECORD_LOGIN_FIELD),
+            "password": value(RECORD_PASSWD_FIELD),
+            "totp": value(RECORD_TOTP_FIELD),
+        },
+        "collectionIds": null,
+    })
+}
+
+#[derive(Default)]
+struct KeepassGroup<'a> {
+    groups: BTreeMap<&'a str, KeepassGroup<'a>>,
+    entries: Vec<&'a Record>,
+}
+
+/// A KeePass 2 XML file, folders become nested groups under the root group.
+pub fn export_keepass_xml(records: &[Record]) -> Vec<u8> {
+    let mut root = KeepassGroup::default();
+    for record in sorted(records) {
+        let mut group = &mut root;
+        for name in record.folder().into_iter().flat_map(|path| path.split('/')) {
+            group = group.groups.entry(name).or_default();
+        }
+        group.entries.push(record);
+    }
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile>\n\
+         <Meta><Generator>PasswordsKeeper</Generator></Meta>\n<Root>\n",
+    );
+    write_keepass_group(&mut xml, "Root", &root);
+    xml.push_str("</Root>\n</KeePassFile>\n");
+    xml.into_bytes()
+}
+
+fn write_keepass_group(xml: &mut String, name: &str, group: &KeepassGroup) {
+    xml.push_str(&format!(
+        "<Group><UUID>{}</UUID><Name>{}</Name>\n",
+        keepass_uuid(None),
+        xml_escape(name)
+    ));
+    for record in &group.entries {
+        write_keepass_entry(xml, record);
+    }
+    for (name, group) in &group.groups {
+        write_keepass_group(xml, name, group);
+    }
+    xml.push_str("</Group>\n");
+}
+
+fn write_keepass_entry(xml: &mut String, record: &Record) {
+    xml.push_str(&format!(
+        "<Entry><UUID>{}</UUID>",
+        keepass_uuid(Some(&record.id))
+    ));
+    if !record.tags().is_empty() {
+        xml.push_str(&format!(
+            "<Tags>{}</Tags>",
+            xml_escape(&record.tags().join(";"))
+        ));
+    }
+    for field in record.get_fields() {
+        let key = KEEPASS_STRINGS
+            .iter()
+            .find(|(_, name)| *name == field.name)
+            .map_or(field.name.as_str(), |(key, _)| key);
+        let protect = if field.kind.is_concealed() {
+            " ProtectInMemory=\"True\""
+        } else {
+            ""
+        };
+        xml.push_str(&format!(
+            "<String><Key>{}</Key><Value{protect}>{}</Value></String>",
+            xml_escape(key),
+            xml_escape(field.value.as_str())
+        ));
+    }
+    xml.push_str("</Entry>\n");
+}
+
+fn keepass_uuid(record_id: Option<&str>) -> String {
+    let uuid = record_id
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .unwrap_or_else(Uuid::new_v4);
+    BASE64_STANDARD.encode(uuid.as_bytes())
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters aren't allowed in XML 1.0.
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            ch if ch.is_control() => {}
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn sorted(records: &[Record]) -> Vec<&Record> {
+    let mut records: Vec<&Record> = records.iter().collect();
+    records.sort_by_cached_key(|record| {
+        record
+            .get_field_value(RECORD_NAME_FIELD)
+            .map(|name| name.to_lowercase())
+    });
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_bitwarden, export_csv, export_keepass_xml, export_records, ExportFormat};
+    use crate::import::{bitwarden::import_bitwarden, csv::import_csv, kdbx, CsvMapping};
+    use crate::record::{
+        FieldKind, Record, RecordField, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+        RECORD_TOTP_FIELD, RECORD_URL_FIELD,
+    };
+    use crate::repository::file::OpenRecordsFileRepository;
+    use crate::repository::{OpenRepository, RecordsRepository};
+
+    fn records() -> Vec<Record> {
+        let mut git = Record::with_fields(vec![
+            RecordField::new(RECORD_NAME_FIELD.into(), FieldKind::Text, "Git <work>"),
+            RecordField::new(RECORD_LOGIN_FIELD.into(), FieldKind::Login, "alice"),
+            RecordField::new(RECORD_PASSWD_FIELD.into(), FieldKind::Password, "p\"a,ss"),
+            RecordField::new(
+                RECORD_URL_FIELD.into(),
+                FieldKind::Url,
+                "https://git.example.com",
+            ),
+            RecordField::new(
+                RECORD_TOTP_FIELD.into(),
+                FieldKind::Totp,
+                "GEZDGNBVGY3TQOJQ",
+            ),
+            RecordField::new("PIN".into(), FieldKind::Hidden, "1234"),
+        ]);
+        git.set_folder("Work/Servers");
+        git.set_tags(["ops", "dev"]);
+        let mail = Record::with_fields(vec![
+            RecordField::new(RECORD_NAME_FIELD.into(), FieldKind::Text, "Mail"),
+            RecordField::new(RECORD_PASSWD_FIELD.into(), FieldKind::Password, "s3cret"),
+            RecordField::new("Recovery".into(), FieldKind::Text, "line 1\nline 2"),
+        ]);
+        vec![mail, git]
+    }
+
+    fn assert_git_record(record: &Record) {
+        assert_eq!(
+            record.get_field_value(RECORD_NAME_FIELD).unwrap(),
+            "Git <work>"
+        );
+        assert_eq!(record.get_field_value(RECORD_LOGIN_FIELD).unwrap(), "alice");
+        assert_eq!(
+            record.get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "p\"a,ss"
+        );
+        assert_eq!(
+            record.get_field_value(RECORD_URL_FIELD).unwrap(),
+            "https://git.example.com"
+        );
+        assert!(record.totp().unwrap().is_ok());
+        assert_eq!(record.get_field_value("PIN").unwrap(), "1234");
+        assert_eq!(record.folder(), Some("Work/Servers"));
+    }
+
+    #[test]
+    fn test_csv_export_is_read_back() {
+        let data = export_csv(&records()).unwrap();
+        let header = String::from_utf8_lossy(&data)
+            .lines()
+            .next()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            header,
+            "name,login,password,url,notes,totp,folder,tags,PIN,Recovery"
+        );
+
+        let imported = import_csv(&data, &CsvMapping::default()).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_git_record(&imported[0]);
+        assert_eq!(imported[0].tags(), ["ops".to_string(), "dev".to_string()]);
+        assert_eq!(
+            imported[1].get_field_value("Recovery").unwrap(),
+            "line 1\nline 2"
+        );
+        assert!(imported[1].get_field("PIN").is_none());
+    }
+
+    #[test]
+    fn test_csv_export_escapes_formulas() {
+        let records = vec![Record::with_fields(vec![
+            RecordField::new(
+                RECORD_NAME_FIELD.into(),
+                FieldKind::Text,
+                "=HYPERLINK(\"x\")",
+            ),
+            RecordField::new(RECORD_LOGIN_FIELD.into(), FieldKind::Login, "@bob"),
+            RecordField::new(RECORD_PASSWD_FIELD.into(), FieldKind::Password, "-1+2"),
+            RecordField::new(RECORD_URL_FIELD.into(), FieldKind::Url, "a=b"),
+            RecordField::new("+Extra".into(), FieldKind::Text, "+1"),
+        ])];
+
+        let imported = import_csv(&export_csv(&records).unwrap(), &CsvMapping::default()).unwrap();
+
+        let value = |name: &str| imported[0].get_field_value(name).unwrap().to_string();
+        assert_eq!(value(RECORD_NAME_FIELD), "'=HYPERLINK(\"x\")");
+        assert_eq!(value(RECORD_LOGIN_FIELD), "'@bob");
+        assert_eq!(value(RECORD_PASSWD_FIELD), "'-1+2");
+        assert_eq!(value(RECORD_URL_FIELD), "a=b");
+        assert_eq!(value("'+Extra"), "'+1");
+    }
+
+    #[test]
+    fn test_bitwarden_export_is_read_back() {
+        let imported = import_bitwarden(&export_bitwarden(&records()).unwrap()).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_git_record(&imported[0]);
+        assert_eq!(
+            imported[0].get_field("PIN").unwrap().kind,
+            FieldKind::Hidden
+        );
+        assert_eq!(
+            imported[1].get_field("Recovery").unwrap().kind,
+            FieldKind::Text
+        );
+    }
+
+    #[test]
+    fn test_keepass_xml_export_is_read_back() {
+        let xml = String::from_utf8(export_keepass_xml(&records())).unwrap();
+        assert!(xml.contains("<Value ProtectInMemory=\"True\">p&quot;a,ss</Value>"));
+
+        let mut imported = kdbx::parse_plain_xml(&xml).unwrap();
+        imported.sort_by_key(|record| {
+            record
+                .get_field_value(RECORD_NAME_FIELD)
+                .map(|name| name.to_string())
+        });
+        assert_eq!(imported.len(), 2);
+        assert_git_record(&imported[0]);
+        assert_eq!(imported[0].tags(), ["ops".to_string(), "dev".to_string()]);
+        assert_eq!(imported[1].folder(), None);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_export_uses_its_own_password() {
+        let mut records = records();
+        records[0]
+            .update_field(RECORD_PASSWD_FIELD.into(), "n3w s3cret")
+            .unwrap();
+        assert!(export_records(&records, ExportFormat::Encrypted, None).is_err());
+        let data = export_records(
+            &records[..1],
+            ExportFormat::Encrypted,
+            Some("colleague".into()),
+        )
+        .unwrap();
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("export.json");
+        std::fs::write(&path, data).unwrap();
+        assert!(OpenRecordsFileRepository(path.clone())
+            .open("other".into())
+            .await
+            .is_err());
+        let repo = OpenRecordsFileRepository(path)
+            .open("colleague".into())
+            .await
+            .unwrap();
+        let exported = repo.get_records().await.unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(
+            exported[0].get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "n3w s3cret"
+        );
+        assert!(exported[0]
+            .get_fields()
+            .iter()
+            .all(|field| field.history.is_empty()));
+    }
+}
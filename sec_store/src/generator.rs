@@ -0,0 +1,229 @@
+use rand::seq::{IndexedRandom, SliceRandom};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use crate::secret::SecretString;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!#$%&*+-=?@^_~.,:;()[]{}";
+const AMBIGUOUS: &str = "Il1O0o";
+
+// The BIP-39 english wordlist, 2048 words give 11 bits per word.
+const WORDLIST: &str = include_str!("generator/wordlist.txt");
+
+pub type GenerateResult<T> = Result<T, GeneratorError>;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum GeneratorError {
+    #[error("At least one character class must be enabled")]
+    NoCharacterClasses,
+    #[error("Length {0} is too short for {1} character classes")]
+    TooShort(usize, usize),
+    #[error("A passphrase needs at least one word")]
+    NoWords,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    /// Leaves out characters that are easy to confuse, such as `l`, `1` and `O`.
+    pub exclude_ambiguous: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassphrasePolicy {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+}
+
+impl Default for PassphrasePolicy {
+    fn default() -> Self {
+        PassphrasePolicy {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedPassword {
+    pub value: SecretString,
+    /// Bits of entropy of the generator output, not of this particular value.
+    pub entropy_bits: f64,
+}
+
+pub fn generate_password(policy: &PasswordPolicy) -> GenerateResult<GeneratedPassword> {
+    let classes: Vec<Vec<u8>> = [
+        (policy.lowercase, LOWERCASE),
+        (policy.uppercase, UPPERCASE),
+        (policy.digits, DIGITS),
+        (policy.symbols, SYMBOLS),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, class)| {
+        class
+            .bytes()
+            .filter(|c| !policy.exclude_ambiguous || !AMBIGUOUS.as_bytes().contains(c))
+            .collect()
+    })
+    .collect();
+    if classes.is_empty() {
+        return Err(GeneratorError::NoCharacterClasses);
+    }
+    if policy.length < classes.len() {
+        return Err(GeneratorError::TooShort(policy.length, classes.len()));
+    }
+
+    let alphabet: Vec<u8> = classes.concat();
+    let mut rng = rand::rng();
+    let mut password = Zeroizing::new(Vec::with_capacity(policy.length));
+    // One character of every enabled class, the rest from the whole alphabet.
+    for class in &classes {
+        password.push(*class.choose(&mut rng).unwrap());
+    }
+    while password.len() < policy.length {
+        password.push(*alphabet.choose(&mut rng).unwrap());
+    }
+    password.shuffle(&mut rng);
+
+    Ok(GeneratedPassword {
+        value: SecretString::new(String::from_utf8(password.to_vec()).unwrap()),
+        entropy_bits: policy.length as f64 * (alphabet.len() as f64).log2(),
+    })
+}
+
+pub fn generate_passphrase(policy: &PassphrasePolicy) -> GenerateResult<GeneratedPassword> {
+    if policy.words == 0 {
+        return Err(GeneratorError::NoWords);
+    }
+    let words: Vec<&str> = WORDLIST.lines().collect();
+    let mut rng = rand::rng();
+    let passphrase = (0..policy.words)
+        .map(|_| {
+            let word = *words.choose(&mut rng).unwrap();
+            if policy.capitalize {
+                word[..1].to_uppercase() + &word[1..]
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(&policy.separator);
+
+    Ok(GeneratedPassword {
+        value: SecretString::new(passphrase),
+        entropy_bits: policy.words as f64 * (words.len() as f64).log2(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        generate_passphrase, generate_password, GeneratorError, PassphrasePolicy, PasswordPolicy,
+        AMBIGUOUS, DIGITS, LOWERCASE, SYMBOLS, UPPERCASE, WORDLIST,
+    };
+
+    #[test]
+    fn test_password_has_every_enabled_class() {
+        let policy = PasswordPolicy {
+            length: 4,
+            ..PasswordPolicy::default()
+        };
+        for _ in 0..50 {
+            let password = generate_password(&policy).unwrap().value;
+            assert_eq!(password.len(), 4);
+            for class in [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS] {
+                assert!(password.chars().any(|c| class.contains(c)));
+            }
+            assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_password_uses_only_enabled_classes() {
+        let policy = PasswordPolicy {
+            length: 32,
+            lowercase: false,
+            uppercase: false,
+            symbols: false,
+            exclude_ambiguous: false,
+            ..PasswordPolicy::default()
+        };
+        let generated = generate_password(&policy).unwrap();
+
+        assert!(generated.value.chars().all(|c| c.is_ascii_digit()));
+        assert!((generated.entropy_bits - 32.0 * 10f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_password_policy_errors() {
+        let no_classes = PasswordPolicy {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(
+            generate_password(&no_classes).unwrap_err(),
+            GeneratorError::NoCharacterClasses
+        );
+
+        let too_short = PasswordPolicy {
+            length: 3,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(
+            generate_password(&too_short).unwrap_err(),
+            GeneratorError::TooShort(3, 4)
+        );
+    }
+
+    #[test]
+    fn test_passphrase() {
+        let policy = PassphrasePolicy {
+            words: 5,
+            separator: " ".to_string(),
+            capitalize: true,
+        };
+        let generated = generate_passphrase(&policy).unwrap();
+        let words: Vec<&str> = generated.value.split(' ').collect();
+
+        assert_eq!(words.len(), 5);
+        for word in words {
+            assert!(word.starts_with(|c: char| c.is_ascii_uppercase()));
+            assert!(WORDLIST.lines().any(|known| known == word.to_lowercase()));
+        }
+        assert!((generated.entropy_bits - 55.0).abs() < 1e-9);
+        assert_eq!(
+            generate_passphrase(&PassphrasePolicy {
+                words: 0,
+                ..PassphrasePolicy::default()
+            })
+            .unwrap_err(),
+            GeneratorError::NoWords
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::{ImportError, ImportResult, RecordBuilder};
+use crate::record::{
+    FieldKind, Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD,
+    RECORD_PASSWD_FIELD, RECORD_URL_FIELD,
+};
+
+// Values of cards, identities and SSH keys that are masked like passwords.
+const HIDDEN_DETAILS: [&str; 6] = [
+    "number",
+    "code",
+    "ssn",
+    "passportNumber",
+    "licenseNumber",
+    "privateKey",
+];
+const HIDDEN_FIELD_TYPE: u8 = 1;
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    name: Option<String>,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    #[serde(default)]
+    fields: Vec<CustomField>,
+    login: Option<Login>,
+    card: Option<Map<String, Value>>,
+    identity: Option<Map<String, Value>>,
+    ssh_key: Option<Map<String, Value>>,
+}
+
+#[derive(Deserialize)]
+struct CustomField {
+    name: Option<String>,
+    value: Option<String>,
+    #[serde(rename = "type", default)]
+    kind: u8,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    #[serde(default)]
+    uris: Vec<LoginUri>,
+}
+
+#[derive(Deserialize)]
+struct LoginUri {
+    uri: Option<String>,
+}
+
+/// Reads an unencrypted Bitwarden JSON export.
+pub fn import_bitwarden(data: &[u8]) -> ImportResult<Vec<Record>> {
+    let export: Export = serde_json::from_slice(data)?;
+    if export.encrypted {
+        return Err(ImportError::Unsupported(
+            "encrypted Bitwarden exports can't be read, export as unencrypted JSON".into(),
+        ));
+    }
+    let folders: HashMap<String, String> = export
+        .folders
+        .into_iter()
+        .map(|folder| (folder.id, folder.name))
+        .collect();
+
+    Ok(export
+        .items
+        .into_iter()
+        .map(|item| item_record(item, &folders))
+        .collect())
+}
+
+fn item_record(item: Item, folders: &HashMap<String, String>) -> Record {
+    let mut builder = RecordBuilder::default();
+    builder.known_field(RECORD_NAME_FIELD, item.name.as_deref().unwrap_or_default());
+
+    if let Some(login) = item.login {
+        builder.known_field(
+            RECORD_LOGIN_FIELD,
+            login.username.as_deref().unwrap_or_default(),
+        );
+        builder.known_field(
+            RECORD_PASSWD_FIELD,
+            login.password.as_deref().unwrap_or_default(),
+        );
+        let mut uris = login.uris.into_iter().filter_map(|uri| uri.uri);
+        if let Some(uri) = uris.next() {
+            builder.known_field(RECORD_URL_FIELD, &uri);
+        }
+        for uri in uris {
+            builder.field("URL", FieldKind::Url, &uri);
+        }
+        if let Some(totp) = login.totp {
+            builder.totp(&totp);
+        }
+    }
+    for details in [item.card, item.identity, item.ssh_key]
+        .into_iter()
+        .flatten()
+    {
+        for (name, value) in details {
+            if let Value::String(value) = value {
+                let kind = if HIDDEN_DETAILS.contains(&name.as_str()) {
+                    FieldKind::Hidden
+                } else {
+                    FieldKind::Text
+                };
+                builder.field(&name, kind, &value);
+            }
+        }
+    }
+    builder.known_field(
+        RECORD_DESCR_FIELD,
+        item.notes.as_deref().unwrap_or_default(),
+    );
+    for field in item.fields {
+        let kind = if field.kind == HIDDEN_FIELD_TYPE {
+            FieldKind::Hidden
+        } else {
+            FieldKind::Text
+        };
+        builder.field(
+            field.name.as_deref().unwrap_or("Field"),
+            kind,
+            field.value.as_deref().unwrap_or_default(),
+        );
+    }
+    if let Some(folder) = item.folder_id.and_then(|id| folders.get(&id)) {
+        builder.folder(folder);
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::import_bitwarden;
+    use crate::import::ImportError;
+    use crate::record::{
+        FieldKind, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+        RECORD_URL_FIELD,
+    };
+
+    const EXPORT: &str = r#"{
+      "encrypted": false,
+      "folders": [{"id": "f1", "name": "Work/Servers"}],
+      "items": [
+        {
+          "id": "i1", "type": 1, "folderId": "f1", "name": "Git", "notes": "deploy key",
+          "favorite": false,
+          "fields": [
+            {"name": "PIN", "value": "1234", "type": 1},
+            {"name": "Team", "value": "ops", "type": 0}
+          ],
+          "login": {
+            "username": "alice", "password": "hunter2",
+            "totp": "otpauth://totp/Git:alice?secret=GEZDGNBVGY3TQOJQ",
+            "uris": [{"match": null, "uri": "https://git.example.com"}, {"uri": "https://example.com"}]
+          }
+        },
+        {
+          "id": "i2", "type": 3, "folderId": null, "name": "Visa",
+          "card": {"cardholderName": "Alice", "number": "4111111111111111", "code": "123", "expYear": null}
+        },
+        {"id": "i3", "type": 2, "name": "Wifi", "notes": "password is on the router", "secureNote": {"type": 0}}
+      ]
+    }"#;
+
+    #[test]
+    fn test_import_items() {
+        let records = import_bitwarden(EXPORT.as_bytes()).unwrap();
+        assert_eq!(records.len(), 3);
+
+        let git = &records[0];
+        assert_eq!(git.get_field_value(RECORD_NAME_FIELD).unwrap(), "Git");
+        assert_eq!(git.get_field_value(RECORD_LOGIN_FIELD).unwrap(), "alice");
+        assert_eq!(git.get_field_value(RECORD_PASSWD_FIELD).unwrap(), "hunter2");
+        assert_eq!(
+            git.get_field_value(RECORD_URL_FIELD).unwrap(),
+            "https://git.example.com"
+        );
+        assert_eq!(git.get_field_value("URL").unwrap(), "https://example.com");
+        assert_eq!(
+            git.get_field_value(RECORD_DESCR_FIELD).unwrap(),
+            "deploy key"
+        );
+        assert_eq!(git.get_field("PIN").unwrap().kind, FieldKind::Hidden);
+        assert_eq!(git.get_field("Team").unwrap().kind, FieldKind::Text);
+        assert_eq!(
+            git.totp().unwrap().unwrap().account.as_deref(),
+            Some("alice")
+        );
+        assert_eq!(git.folder(), Some("Work/Servers"));
+
+        let card = &records[1];
+        assert_eq!(card.get_field("number").unwrap().kind, FieldKind::Hidden);
+        assert_eq!(card.get_field_value("cardholderName").unwrap(), "Alice");
+        assert!(card.get_field("expYear").is_none());
+        assert_eq!(card.folder(), None);
+
+        assert_eq!(
+            records[2].get_field_value(RECORD_DESCR_FIELD).unwrap(),
+            "password is on the router"
+        );
+    }
+
+    #[test]
+    fn test_encrypted_export_is_refused() {
+        let result = import_bitwarden(br#"{"encrypted": true, "passwordProtected": true}"#);
+        assert!(matches!(result, Err(ImportError::Unsupported(_))));
+        assert!(matches!(
+            import_bitwarden(b"{not json"),
+            Err(ImportError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use ::csv::{ReaderBuilder, Trim};
+
+use super::{ImportError, ImportResult, RecordBuilder};
+use crate::record::{
+    FieldName, Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD,
+    RECORD_PASSWD_FIELD, RECORD_TOTP_FIELD, RECORD_URL_FIELD,
+};
+
+const DELIMITERS: [u8; 3] = [b',', b';', b'\t'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvColumn {
+    Field(FieldName),
+    Folder,
+    Tags,
+    Ignore,
+}
+
+impl CsvColumn {
+    /// `name`, `login`, `password`, `url`, `notes`, `totp`, `folder`, `tags`, `ignore`
+    /// or the name of a custom field.
+    pub fn parse(target: &str) -> CsvColumn {
+        match target.trim().to_lowercase().as_str() {
+            "name" => CsvColumn::Field(RECORD_NAME_FIELD.into()),
+            "login" => CsvColumn::Field(RECORD_LOGIN_FIELD.into()),
+            "password" => CsvColumn::Field(RECORD_PASSWD_FIELD.into()),
+            "url" => CsvColumn::Field(RECORD_URL_FIELD.into()),
+            "notes" => CsvColumn::Field(RECORD_DESCR_FIELD.into()),
+            "totp" => CsvColumn::Field(RECORD_TOTP_FIELD.into()),
+            "folder" => CsvColumn::Folder,
+            "tags" => CsvColumn::Tags,
+            "ignore" => CsvColumn::Ignore,
+            _ => CsvColumn::Field(target.trim().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvMapping {
+    /// `None` picks the most frequent of `,`, `;` and tab in the header line.
+    pub delimiter: Option<u8>,
+    /// Column headers, compared case-insensitively, and where their values go.
+    pub columns: Vec<(String, CsvColumn)>,
+    /// Columns missing from `columns` become custom text fields.
+    pub keep_unmapped: bool,
+}
+
+impl Default for CsvMapping {
+    /// Understands the headers of Bitwarden, KeePassXC, LastPass and browser exports.
+    fn default() -> Self {
+        let aliases: [(&[&str], &str); 9] = [
+            (&["name", "title", "account"], "name"),
+            (&["login", "username", "user", "login_username"], "login"),
+            (&["password", "login_password", "pass"], "password"),
+            (&["url", "uri", "login_uri", "website"], "url"),
+            (
+                &["notes", "note", "description", "extra", "comments"],
+                "notes",
+            ),
+            (&["totp", "otp", "login_totp", "otpauth"], "totp"),
+            (&["folder", "group", "grouping", "path"], "folder"),
+            (&["tags", "tag", "labels"], "tags"),
+            (
+                &[
+                    "type",
+                    "favorite",
+                    "fav",
+                    "reprompt",
+                    "last modified",
+                    "created",
+                ],
+                "ignore",
+            ),
+        ];
+        CsvMapping {
+            delimiter: None,
+            columns: aliases
+                .into_iter()
+                .flat_map(|(headers, target)| {
+                    headers
+                        .iter()
+                        .map(move |header| (header.to_string(), CsvColumn::parse(target)))
+                })
+                .collect(),
+            keep_unmapped: true,
+        }
+    }
+}
+
+impl CsvMapping {
+    /// The default mapping extended with comma separated `header=target` pairs,
+    /// e.g. `Site=name,Secret=password,Misc=ignore`.
+    pub fn parse(spec: &str) -> ImportResult<CsvMapping> {
+        let mut mapping = CsvMapping::default();
+        for pair in spec.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (header, target) = pair
+                .split_once('=')
+                .filter(|(header, target)| !header.trim().is_empty() && !target.trim().is_empty())
+                .ok_or_else(|| ImportError::InvalidMapping(pair.trim().to_string()))?;
+            let header = header.trim().to_lowercase();
+            mapping.columns.retain(|(known, _)| *known != header);
+            mapping.columns.push((header, CsvColumn::parse(target)));
+        }
+        Ok(mapping)
+    }
+
+    fn column_for(&self, header: &str) -> CsvColumn {
+        self.columns
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(header))
+            .map(|(_, column)| column.clone())
+            .unwrap_or_else(|| {
+                if self.keep_unmapped && !header.is_empty() {
+                    CsvColumn::Field(header.to_string())
+                } else {
+                    CsvColumn::Ignore
+                }
+            })
+    }
+}
+
+/// Reads a CSV file with a header line, one record per row.
+pub fn import_csv(data: &[u8], mapping: &CsvMapping) -> ImportResult<Vec<Record>> {
+    let delimiter = mapping.delimiter.unwrap_or_else(|| detect_delimiter(data));
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(Trim::Headers)
+        .from_reader(data);
+    let columns: Vec<CsvColumn> = reader
+        .headers()?
+        .iter()
+        .map(|header| mapping.column_for(header))
+        .collect();
+    if !columns
+        .iter()
+        .any(|column| matches!(column, CsvColumn::Field(_)))
+    {
+        return Err(ImportError::Malformed(
+            "no column is mapped to a record field".into(),
+        ));
+    }
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        if row.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let mut builder = RecordBuilder::default();
+        for (column, value) in columns.iter().zip(row.iter()) {
+            match column {
+                CsvColumn::Field(name) if name == RECORD_TOTP_FIELD => {
+                    if !value.trim().is_empty() {
+                        builder.totp(value);
+                    }
+                }
+                CsvColumn::Field(name) => builder.known_field(name, value),
+                CsvColumn::Folder => builder.folder(value),
+                CsvColumn::Tags => builder.tags(value.split([',', ';'])),
+                CsvColumn::Ignore => {}
+            }
+        }
+        records.push(builder.build());
+    }
+    Ok(records)
+}
+
+fn detect_delimiter(data: &[u8]) -> u8 {
+    let header = data.split(|byte| *byte == b'\n').next().unwrap_or_default();
+    DELIMITERS
+        .into_iter()
+        .max_by_key(|delimiter| header.iter().filter(|byte| *byte == delimiter).count())
+        .filter(|delimiter| header.contains(delimiter))
+        .unwrap_or(b',')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_csv, CsvColumn, CsvMapping};
+    use crate::import::ImportError;
+    use crate::record::{
+        FieldKind, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+        RECORD_URL_FIELD,
+    };
+
+    #[test]
+    fn test_default_mapping() {
+        let data = "\u{feff}folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+            Work,,login,Git,\"multi\nline\",,0,https://git.example.com,alice,hunter2,\n\
+            ,,,,,,,,,,\n\
+            ,1,login,Mail,,Recovery: 42,0,,bob,s3cret,GEZDGNBVGY3TQOJQ\n";
+        let records = import_csv(data.as_bytes(), &CsvMapping::default()).unwrap();
+        assert_eq!(records.len(), 2);
+
+        let git = &records[0];
+        assert_eq!(git.get_field_value(RECORD_NAME_FIELD).unwrap(), "Git");
+        assert_eq!(git.get_field_value(RECORD_LOGIN_FIELD).unwrap(), "alice");
+        assert_eq!(git.get_field_value(RECORD_PASSWD_FIELD).unwrap(), "hunter2");
+        assert_eq!(
+            git.get_field_value(RECORD_URL_FIELD).unwrap(),
+            "https://git.example.com"
+        );
+        assert_eq!(
+            git.get_field_value(RECORD_DESCR_FIELD).unwrap(),
+            "multi\nline"
+        );
+        assert_eq!(git.folder(), Some("Work"));
+        assert!(git.get_field("favorite").is_none());
+
+        let mail = &records[1];
+        assert!(mail.totp().unwrap().is_ok());
+        assert_eq!(mail.get_field_value("fields").unwrap(), "Recovery: 42");
+        assert_eq!(mail.get_field("fields").unwrap().kind, FieldKind::Text);
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let mapping =
+            CsvMapping::parse("Site=name, Secret=password, Labels=tags, Misc=ignore").unwrap();
+        let data = "Site;Secret;Labels;Misc;Owner\nBank;p@ss;money, personal;x;alice\n";
+        let records = import_csv(data.as_bytes(), &mapping).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].get_field_value(RECORD_NAME_FIELD).unwrap(),
+            "Bank"
+        );
+        assert_eq!(
+            records[0].get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "p@ss"
+        );
+        assert_eq!(
+            records[0].tags(),
+            ["money".to_string(), "personal".to_string()]
+        );
+        assert!(records[0].get_field("Misc").is_none());
+        assert_eq!(records[0].get_field_value("Owner").unwrap(), "alice");
+
+        let strict = CsvMapping {
+            keep_unmapped: false,
+            ..mapping
+        };
+        let records = import_csv(data.as_bytes(), &strict).unwrap();
+        assert!(records[0].get_field("Owner").is_none());
+    }
+
+    #[test]
+    fn test_mapping_errors() {
+        assert!(matches!(
+            CsvMapping::parse("Site"),
+            Err(ImportError::InvalidMapping(_))
+        ));
+        assert_eq!(CsvColumn::parse(" Folder "), CsvColumn::Folder);
+        let ignore_all = CsvMapping {
+            delimiter: Some(b','),
+            columns: vec![("a".into(), CsvColumn::Ignore)],
+            keep_unmapped: false,
+        };
+        assert!(matches!(
+            import_csv(b"a\n1\n", &ignore_all),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+}
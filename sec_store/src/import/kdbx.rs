@@ -0,0 +1,887 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use aes::cipher::{
+    block_padding::Pkcs7, BlockDecryptMut, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher,
+};
+use aes::Aes256;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chacha20::ChaCha20;
+use flate2::read::GzDecoder;
+use ring::{digest, hmac};
+use roxmltree::{Document, Node};
+use zeroize::Zeroizing;
+
+use super::{ImportError, ImportResult, RecordBuilder};
+use crate::record::{
+    FieldKind, Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD,
+    RECORD_PASSWD_FIELD, RECORD_URL_FIELD,
+};
+
+const SIGNATURE: [u8; 8] = [0x03, 0xd9, 0xa2, 0x9a, 0x67, 0xfb, 0x4b, 0xb5];
+const SUPPORTED_MAJOR_VERSION: u16 = 4;
+
+const CIPHER_AES256: [u8; 16] = uuid(0x31c1f2e6bf714350be5805216afc5aff);
+const CIPHER_CHACHA20: [u8; 16] = uuid(0xd6038a2b8b6f4cb5a524339a31dbb59a);
+const KDF_AES: [u8; 16] = uuid(0x7c02bb8279a74ac0927d114a00648238);
+const KDF_AES_KDBX3: [u8; 16] = uuid(0xc9d9f39a628a4460bf740d08c18a4fea);
+const KDF_ARGON2D: [u8; 16] = uuid(0xef636ddf8c29444b91f7a9a403e30a0c);
+const KDF_ARGON2ID: [u8; 16] = uuid(0x9e298b1956db4773b23dfc3ec6f0a1e6);
+
+// Caps on the key derivation costs a database can ask for, uploads are untrusted.
+const MAX_AES_KDF_ROUNDS: u64 = 100_000_000;
+const MAX_ARGON2_MEMORY: u64 = 1024 * 1024 * 1024;
+const MAX_ARGON2_ITERATIONS: u64 = 100;
+const MAX_ARGON2_PARALLELISM: u64 = 16;
+
+const INNER_STREAM_NONE: u32 = 0;
+const INNER_STREAM_CHACHA20: u32 = 3;
+
+// Entry strings that map to well-known record fields.
+const KNOWN_STRINGS: [(&str, &str); 5] = [
+    ("Title", RECORD_NAME_FIELD),
+    ("UserName", RECORD_LOGIN_FIELD),
+    ("Password", RECORD_PASSWD_FIELD),
+    ("URL", RECORD_URL_FIELD),
+    ("Notes", RECORD_DESCR_FIELD),
+];
+// KeePassXC keeps an otpauth URI in `otp`, KeePass 2 uses the `TimeOtp-*` strings.
+const OTP_STRING: &str = "otp";
+const TIME_OTP_PREFIX: &str = "TimeOtp-";
+
+const fn uuid(value: u128) -> [u8; 16] {
+    value.to_be_bytes()
+}
+
+pub fn is_kdbx(data: &[u8]) -> bool {
+    data.starts_with(&SIGNATURE)
+}
+
+/// Reads a password protected KeePass KDBX 4 database. Key files aren't supported.
+pub fn import_kdbx(data: &[u8], password: &str) -> ImportResult<Vec<Record>> {
+    let mut reader = ByteReader::new(data);
+    if reader.take(SIGNATURE.len())? != SIGNATURE {
+        return Err(ImportError::Unsupported("not a KeePass database".into()));
+    }
+    let _minor = reader.u16()?;
+    let major = reader.u16()?;
+    if major != SUPPORTED_MAJOR_VERSION {
+        return Err(ImportError::Unsupported(format!(
+            "KDBX {major} databases can't be read, save the database as KDBX 4"
+        )));
+    }
+
+    let header = OuterHeader::read(&mut reader)?;
+    let header_bytes = &data[..reader.pos];
+    let header_hash = reader.take(32)?;
+    let header_hmac = reader.take(32)?;
+    if digest::digest(&digest::SHA256, header_bytes).as_ref() != header_hash {
+        return Err(ImportError::Malformed("header checksum mismatch".into()));
+    }
+
+    let transformed = transform_key(&composite_key(password), &header.kdf)?;
+    let mut seeded = Zeroizing::new(header.master_seed.clone());
+    seeded.extend_from_slice(transformed.as_ref());
+    let cipher_key = Zeroizing::new(digest::digest(&digest::SHA256, &seeded).as_ref().to_vec());
+    seeded.push(1);
+    let hmac_key = Zeroizing::new(digest::digest(&digest::SHA512, &seeded).as_ref().to_vec());
+
+    hmac::verify(
+        &block_hmac_key(&hmac_key, u64::MAX),
+        header_bytes,
+        header_hmac,
+    )
+    .map_err(|_| ImportError::WrongPassword)?;
+
+    let mut payload = Zeroizing::new(read_blocks(&mut reader, &hmac_key)?);
+    let payload = Zeroizing::new(decrypt_payload(
+        &header.cipher_id,
+        &cipher_key,
+        &header.encryption_iv,
+        &mut payload,
+    )?);
+    let payload = if header.compressed {
+        let mut decompressed = Zeroizing::new(Vec::new());
+        GzDecoder::new(payload.as_slice())
+            .read_to_end(&mut decompressed)
+            .map_err(|err| ImportError::Malformed(format!("invalid gzip payload: {err}")))?;
+        decompressed
+    } else {
+        payload
+    };
+
+    let mut inner = ByteReader::new(&payload);
+    let mut inner_stream = InnerStream::read(&mut inner)?;
+    let xml = std::str::from_utf8(&payload[inner.pos..])
+        .map_err(|_| ImportError::Malformed("the XML isn't valid UTF-8".into()))?;
+    parse_xml(xml, &mut inner_stream)
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ByteReader { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> ImportResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| ImportError::Malformed("unexpected end of file".into()))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> ImportResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> ImportResult<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> ImportResult<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// A `(type, length, value)` header entry.
+    fn header_field(&mut self) -> ImportResult<(u8, &'a [u8])> {
+        let id = self.u8()?;
+        let len = self.u32()? as usize;
+        Ok((id, self.take(len)?))
+    }
+}
+
+struct OuterHeader {
+    cipher_id: Vec<u8>,
+    compressed: bool,
+    master_seed: Vec<u8>,
+    encryption_iv: Vec<u8>,
+    kdf: HashMap<String, Vec<u8>>,
+}
+
+impl OuterHeader {
+    fn read(reader: &mut ByteReader) -> ImportResult<OuterHeader> {
+        let (mut cipher_id, mut compressed, mut master_seed, mut encryption_iv, mut kdf) =
+            (None, false, None, None, None);
+        loop {
+            match reader.header_field()? {
+                (0, _) => break,
+                (2, value) => cipher_id = Some(value.to_vec()),
+                (3, value) => compressed = value.first().is_some_and(|flag| *flag != 0),
+                (4, value) => master_seed = Some(value.to_vec()),
+                (7, value) => encryption_iv = Some(value.to_vec()),
+                (11, value) => kdf = Some(read_variant_dictionary(value)?),
+                _ => {}
+            }
+        }
+        let missing = |name: &str| ImportError::Malformed(format!("the header has no {name}"));
+        Ok(OuterHeader {
+            cipher_id: cipher_id.ok_or_else(|| missing("cipher"))?,
+            compressed,
+            master_seed: master_seed.ok_or_else(|| missing("master seed"))?,
+            encryption_iv: encryption_iv.ok_or_else(|| missing("encryption IV"))?,
+            kdf: kdf.ok_or_else(|| missing("key derivation parameters"))?,
+        })
+    }
+}
+
+/// Key derivation parameters are stored as a typed key-value list, values are kept raw.
+fn read_variant_dictionary(data: &[u8]) -> ImportResult<HashMap<String, Vec<u8>>> {
+    let mut reader = ByteReader::new(data);
+    if reader.u16()? >> 8 != 1 {
+        return Err(ImportError::Unsupported(
+            "unknown key derivation parameters version".into(),
+        ));
+    }
+    let mut values = HashMap::new();
+    loop {
+        let kind = reader.u8()?;
+        if kind == 0 {
+            return Ok(values);
+        }
+        let name_len = reader.u32()? as usize;
+        let name = String::from_utf8_lossy(reader.take(name_len)?).into_owned();
+        let value_len = reader.u32()? as usize;
+        values.insert(name, reader.take(value_len)?.to_vec());
+    }
+}
+
+fn kdf_u64(params: &HashMap<String, Vec<u8>>, name: &str) -> ImportResult<u64> {
+    match params.get(name).map(Vec::as_slice) {
+        Some(bytes) if bytes.len() == 8 => Ok(u64::from_le_bytes(bytes.try_into().unwrap())),
+        Some(bytes) if bytes.len() == 4 => Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as u64),
+        _ => Err(ImportError::Malformed(format!(
+            "invalid key derivation parameter {name}"
+        ))),
+    }
+}
+
+fn kdf_cost(params: &HashMap<String, Vec<u8>>, name: &str, max: u64) -> ImportResult<u64> {
+    let value = kdf_u64(params, name)?;
+    if value > max {
+        return Err(ImportError::Unsupported(format!(
+            "key derivation parameter {name} is {value}, at most {max} is supported"
+        )));
+    }
+    Ok(value)
+}
+
+fn kdf_cost_u32(value: u64, name: &str) -> ImportResult<u32> {
+    u32::try_from(value).map_err(|_| {
+        ImportError::Unsupported(format!("key derivation parameter {name} is too large"))
+    })
+}
+
+fn kdf_bytes<'a>(params: &'a HashMap<String, Vec<u8>>, name: &str) -> ImportResult<&'a [u8]> {
+    params
+        .get(name)
+        .map(Vec::as_slice)
+        .ok_or_else(|| ImportError::Malformed(format!("missing key derivation parameter {name}")))
+}
+
+fn composite_key(password: &str) -> Zeroizing<Vec<u8>> {
+    let password_hash = digest::digest(&digest::SHA256, password.as_bytes());
+    Zeroizing::new(
+        digest::digest(&digest::SHA256, password_hash.as_ref())
+            .as_ref()
+            .to_vec(),
+    )
+}
+
+fn transform_key(
+    composite: &[u8],
+    params: &HashMap<String, Vec<u8>>,
+) -> ImportResult<Zeroizing<[u8; 32]>> {
+    let mut transformed = Zeroizing::new([0u8; 32]);
+    let kdf_id = kdf_bytes(params, "$UUID")?;
+    if kdf_id == KDF_ARGON2D || kdf_id == KDF_ARGON2ID {
+        let algorithm = if kdf_id == KDF_ARGON2D {
+            Algorithm::Argon2d
+        } else {
+            Algorithm::Argon2id
+        };
+        let version = match kdf_u64(params, "V")? {
+            0x10 => Version::V0x10,
+            _ => Version::V0x13,
+        };
+        let invalid = |err: argon2::Error| {
+            ImportError::Malformed(format!("invalid Argon2 parameters: {err}"))
+        };
+        let memory_kib = kdf_cost(params, "M", MAX_ARGON2_MEMORY)? / 1024;
+        let iterations = kdf_cost(params, "I", MAX_ARGON2_ITERATIONS)?;
+        let parallelism = kdf_cost(params, "P", MAX_ARGON2_PARALLELISM)?;
+        let argon2_params = Params::new(
+            kdf_cost_u32(memory_kib, "M")?,
+            kdf_cost_u32(iterations, "I")?,
+            kdf_cost_u32(parallelism, "P")?,
+            Some(transformed.len()),
+        )
+        .map_err(invalid)?;
+        Argon2::new(algorithm, version, argon2_params)
+            .hash_password_into(composite, kdf_bytes(params, "S")?, transformed.as_mut())
+            .map_err(invalid)?;
+    } else if kdf_id == KDF_AES || kdf_id == KDF_AES_KDBX3 {
+        let cipher = Aes256::new_from_slice(kdf_bytes(params, "S")?)
+            .map_err(|_| ImportError::Malformed("invalid AES-KDF seed".into()))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(composite);
+        for _ in 0..kdf_cost(params, "R", MAX_AES_KDF_ROUNDS)? {
+            for block in key.chunks_exact_mut(16) {
+                cipher.encrypt_block(block.into());
+            }
+        }
+        transformed.copy_from_slice(digest::digest(&digest::SHA256, key.as_ref()).as_ref());
+    } else {
+        return Err(ImportError::Unsupported(
+            "unknown key derivation function".into(),
+        ));
+    }
+    Ok(transformed)
+}
+
+fn block_hmac_key(hmac_key: &[u8], index: u64) -> hmac::Key {
+    let mut ctx = digest::Context::new(&digest::SHA512);
+    ctx.update(&index.to_le_bytes());
+    ctx.update(hmac_key);
+    hmac::Key::new(hmac::HMAC_SHA256, ctx.finish().as_ref())
+}
+
+/// Joins the HMAC protected blocks that follow the header.
+fn read_blocks(reader: &mut ByteReader, hmac_key: &[u8]) -> ImportResult<Vec<u8>> {
+    let mut payload = Vec::new();
+    for index in 0u64.. {
+        let expected = reader.take(32)?;
+        let len = reader.u32()?;
+        let block = reader.take(len as usize)?;
+
+        let mut signed = Vec::with_capacity(12 + block.len());
+        signed.extend_from_slice(&index.to_le_bytes());
+        signed.extend_from_slice(&len.to_le_bytes());
+        signed.extend_from_slice(block);
+        hmac::verify(&block_hmac_key(hmac_key, index), &signed, expected)
+            .map_err(|_| ImportError::Malformed(format!("block {index} is corrupted")))?;
+        if len == 0 {
+            break;
+        }
+        payload.extend_from_slice(block);
+    }
+    Ok(payload)
+}
+
+fn decrypt_payload(
+    cipher_id: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    payload: &mut [u8],
+) -> ImportResult<Vec<u8>> {
+    let invalid_iv = |_| ImportError::Malformed("invalid encryption IV".into());
+    if cipher_id == CIPHER_AES256 {
+        let decrypted = cbc::Decryptor::<Aes256>::new_from_slices(key, iv)
+            .map_err(invalid_iv)?
+            .decrypt_padded_mut::<Pkcs7>(payload)
+            .map_err(|_| ImportError::Malformed("invalid payload padding".into()))?;
+        Ok(decrypted.to_vec())
+    } else if cipher_id == CIPHER_CHACHA20 {
+        ChaCha20::new_from_slices(key, iv)
+            .map_err(invalid_iv)?
+            .apply_keystream(payload);
+        Ok(payload.to_vec())
+    } else {
+        Err(ImportError::Unsupported(
+            "only AES-256 and ChaCha20 encrypted databases are supported".into(),
+        ))
+    }
+}
+
+/// Decrypts `Protected="True"` values, the keystream runs over them in document order.
+struct InnerStream(Option<ChaCha20>);
+
+impl InnerStream {
+    fn read(reader: &mut ByteReader) -> ImportResult<InnerStream> {
+        let (mut stream_id, mut stream_key) = (INNER_STREAM_NONE, None);
+        loop {
+            match reader.header_field()? {
+                (0, _) => break,
+                (1, value) => {
+                    stream_id =
+                        u32::from_le_bytes(value.try_into().map_err(|_| {
+                            ImportError::Malformed("invalid inner stream id".into())
+                        })?)
+                }
+                (2, value) => stream_key = Some(value),
+                _ => {}
+            }
+        }
+        match (stream_id, stream_key) {
+            (INNER_STREAM_NONE, _) => Ok(InnerStream(None)),
+            (INNER_STREAM_CHACHA20, Some(key)) => {
+                let hash = digest::digest(&digest::SHA512, key);
+                let cipher =
+                    ChaCha20::new_from_slices(&hash.as_ref()[..32], &hash.as_ref()[32..44])
+                        .expect("SHA-512 output is long enough for a key and nonce");
+                Ok(InnerStream(Some(cipher)))
+            }
+            _ => Err(ImportError::Unsupported(
+                "only ChaCha20 protected values are supported".into(),
+            )),
+        }
+    }
+
+    fn decrypt(&mut self, value: &str) -> ImportResult<Zeroizing<String>> {
+        let mut bytes = Zeroizing::new(
+            BASE64_STANDARD
+                .decode(value.trim())
+                .map_err(|_| ImportError::Malformed("invalid protected value".into()))?,
+        );
+        if let Some(cipher) = &mut self.0 {
+            cipher.apply_keystream(&mut bytes);
+        }
+        String::from_utf8(bytes.to_vec())
+            .map(Zeroizing::new)
+            .map_err(|_| ImportError::Malformed("invalid protected value".into()))
+    }
+}
+
+fn parse_xml(xml: &str, inner_stream: &mut InnerStream) -> ImportResult<Vec<Record>> {
+    let doc = Document::parse(xml)
+        .map_err(|err| ImportError::Malformed(format!("invalid XML: {err}")))?;
+
+    let mut protected = HashMap::new();
+    for node in doc.descendants().filter(|node| {
+        node.has_tag_name("Value")
+            && node
+                .attribute("Protected")
+                .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+    }) {
+        protected.insert(
+            node.id(),
+            inner_stream.decrypt(node.text().unwrap_or_default())?,
+        );
+    }
+
+    let root = doc.root_element();
+    let recycle_bin = child(root, "Meta")
+        .and_then(|meta| child(meta, "RecycleBinUUID"))
+        .and_then(|uuid| uuid.text());
+    let root_group = child(root, "Root")
+        .and_then(|root| child(root, "Group"))
+        .ok_or_else(|| ImportError::Malformed("the database has no root group".into()))?;
+
+    let mut records = Vec::new();
+    // The root group name is the database name, it isn't part of the folders.
+    let mut groups = vec![(root_group, None::<String>)];
+    while let Some((group, folder)) = groups.pop() {
+        for node in group.children().filter(Node::is_element) {
+            if node.has_tag_name("Entry") {
+                records.push(entry_record(node, folder.as_deref(), &protected));
+            } else if node.has_tag_name("Group") {
+                let uuid = child(node, "UUID").and_then(|uuid| uuid.text());
+                if uuid.is_some() && uuid == recycle_bin {
+                    continue;
+                }
+                let name = child(node, "Name")
+                    .and_then(|name| name.text())
+                    .unwrap_or_default()
+                    .replace('/', "-");
+                let path = match &folder {
+                    Some(parent) => format!("{parent}/{name}"),
+                    None => name,
+                };
+                groups.push((node, Some(path)));
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Reads a KeePass XML export, its values are never protected.
+#[cfg(test)]
+pub(crate) fn parse_plain_xml(xml: &str) -> ImportResult<Vec<Record>> {
+    parse_xml(xml, &mut InnerStream(None))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn entry_record(
+    entry: Node,
+    folder: Option<&str>,
+    protected: &HashMap<roxmltree::NodeId, Zeroizing<String>>,
+) -> Record {
+    let mut builder = RecordBuilder::default();
+    let mut time_otp = Vec::new();
+    for string in entry.children().filter(|node| node.has_tag_name("String")) {
+        let Some(key) = child(string, "Key").and_then(|key| key.text()) else {
+            continue;
+        };
+        let Some(value_node) = child(string, "Value") else {
+            continue;
+        };
+        let is_protected = protected.contains_key(&value_node.id());
+        let value = protected
+            .get(&value_node.id())
+            .map(|value| value.as_str())
+            .or(value_node.text())
+            .unwrap_or_default();
+
+        if let Some((_, field)) = KNOWN_STRINGS.iter().find(|(known, _)| *known == key) {
+            builder.known_field(field, value);
+        } else if key == OTP_STRING {
+            builder.totp(value);
+        } else if let Some(setting) = key.strip_prefix(TIME_OTP_PREFIX) {
+            time_otp.push((setting.to_string(), value.to_string()));
+        } else {
+            let kind = if is_protected {
+                FieldKind::Hidden
+            } else {
+                FieldKind::Text
+            };
+            builder.field(key, kind, value);
+        }
+    }
+    if let Some(uri) = time_otp_uri(&time_otp) {
+        builder.totp(&uri);
+    }
+    if let Some(tags) = child(entry, "Tags").and_then(|tags| tags.text()) {
+        builder.tags(tags.split([';', ',']));
+    }
+    if let Some(folder) = folder {
+        builder.folder(folder);
+    }
+    builder.build()
+}
+
+/// An otpauth URI from the KeePass 2 `TimeOtp-*` settings.
+fn time_otp_uri(settings: &[(String, String)]) -> Option<String> {
+    let setting = |name: &str| {
+        settings
+            .iter()
+            .find(|(setting, _)| setting == name)
+            .map(|(_, value)| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let mut uri = format!(
+        "otpauth://totp/?secret={}",
+        setting("Secret-Base32")?.replace(' ', "")
+    );
+    if let Some(digits) = setting("Length") {
+        uri.push_str(&format!("&digits={digits}"));
+    }
+    if let Some(period) = setting("Period") {
+        uri.push_str(&format!("&period={period}"));
+    }
+    if let Some(algorithm) = setting("Algorithm") {
+        uri.push_str(&format!(
+            "&algorithm={}",
+            algorithm.replace("HMAC-", "").replace('-', "")
+        ));
+    }
+    Some(uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit, StreamCipher};
+    use aes::Aes256;
+    use base64::prelude::{Engine, BASE64_STANDARD};
+    use chacha20::ChaCha20;
+    use flate2::write::GzEncoder;
+    use ring::{digest, hmac};
+
+    use super::{
+        block_hmac_key, composite_key, import_kdbx, transform_key, CIPHER_AES256, CIPHER_CHACHA20,
+        KDF_AES, KDF_ARGON2D, SIGNATURE,
+    };
+    use crate::import::ImportError;
+    use crate::record::{
+        FieldKind, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+        RECORD_URL_FIELD,
+    };
+
+    const PASSWORD: &str = "correct horse battery staple";
+    const PROTECTED_VALUE: &str = "<Value Protected=\"True\">";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<KeePassFile>
+  <Meta><DatabaseName>Team</DatabaseName><RecycleBinUUID>cmVjeWNsZWJpbnV1aWQ=</RecycleBinUUID></Meta>
+  <Root>
+    <Group>
+      <UUID>cm9vdGdyb3VwdXVpZA==</UUID><Name>Team</Name>
+      <Entry>
+        <UUID>ZW50cnkx</UUID>
+        <Tags>ops;shared</Tags>
+        <String><Key>Title</Key><Value>Git</Value></String>
+        <String><Key>UserName</Key><Value>alice</Value></String>
+        <String><Key>Password</Key><Value Protected="True">hunter2</Value></String>
+        <String><Key>URL</Key><Value>https://git.example.com</Value></String>
+        <String><Key>Notes</Key><Value>deploy key</Value></String>
+        <String><Key>Recovery code</Key><Value Protected="True">42-42-42</Value></String>
+        <String><Key>otp</Key><Value Protected="True">otpauth://totp/Git:alice?secret=GEZDGNBVGY3TQOJQ</Value></String>
+        <History>
+          <Entry>
+            <String><Key>Title</Key><Value>Git (old)</Value></String>
+            <String><Key>Password</Key><Value Protected="True">old-password</Value></String>
+          </Entry>
+        </History>
+      </Entry>
+      <Group>
+        <UUID>d29ya2dyb3Vw</UUID><Name>Work</Name>
+        <Group>
+          <UUID>c2VydmVyc2dyb3Vw</UUID><Name>Servers</Name>
+          <Entry>
+            <String><Key>Title</Key><Value>Bastion</Value></String>
+            <String><Key>Password</Key><Value Protected="True">p@ss &amp; word</Value></String>
+            <String><Key>TimeOtp-Secret-Base32</Key><Value Protected="True">GEZDGNBVGY3TQOJQ</Value></String>
+            <String><Key>TimeOtp-Length</Key><Value>8</Value></String>
+            <String><Key>TimeOtp-Algorithm</Key><Value>HMAC-SHA-256</Value></String>
+          </Entry>
+        </Group>
+      </Group>
+      <Group>
+        <UUID>cmVjeWNsZWJpbnV1aWQ=</UUID><Name>Recycle Bin</Name>
+        <Entry><String><Key>Title</Key><Value>Deleted</Value></String></Entry>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>"#;
+
+    struct TestDatabase {
+        cipher: [u8; 16],
+        kdf: Vec<(u8, &'static str, Vec<u8>)>,
+        compressed: bool,
+    }
+
+    fn aes_kdf() -> Vec<(u8, &'static str, Vec<u8>)> {
+        vec![
+            (0x42, "$UUID", KDF_AES.to_vec()),
+            (0x05, "R", 100u64.to_le_bytes().to_vec()),
+            (0x42, "S", vec![7; 32]),
+        ]
+    }
+
+    fn argon2d_kdf() -> Vec<(u8, &'static str, Vec<u8>)> {
+        vec![
+            (0x42, "$UUID", KDF_ARGON2D.to_vec()),
+            (0x42, "S", vec![9; 32]),
+            (0x04, "P", 1u32.to_le_bytes().to_vec()),
+            (0x05, "M", (64u64 * 1024).to_le_bytes().to_vec()),
+            (0x05, "I", 2u64.to_le_bytes().to_vec()),
+            (0x04, "V", 0x13u32.to_le_bytes().to_vec()),
+        ]
+    }
+
+    fn header_field(out: &mut Vec<u8>, id: u8, value: &[u8]) {
+        out.push(id);
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        out.extend_from_slice(value);
+    }
+
+    /// Encrypts the protected values of `xml` with the inner stream, in document order.
+    fn protect_values(xml: &str, stream: &mut ChaCha20) -> String {
+        let mut protected = String::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find(PROTECTED_VALUE) {
+            let value_start = start + PROTECTED_VALUE.len();
+            let value_end = value_start + rest[value_start..].find("</Value>").unwrap();
+            let mut value = rest[value_start..value_end]
+                .replace("&amp;", "&")
+                .into_bytes();
+            stream.apply_keystream(&mut value);
+            protected.push_str(&rest[..value_start]);
+            protected.push_str(&BASE64_STANDARD.encode(value));
+            rest = &rest[value_end..];
+        }
+        protected.push_str(rest);
+        protected
+    }
+
+    impl TestDatabase {
+        fn write(&self, password: &str, xml: &str) -> Vec<u8> {
+            let inner_key = [5u8; 64];
+            let inner_hash = digest::digest(&digest::SHA512, &inner_key);
+            let mut inner_stream =
+                ChaCha20::new_from_slices(&inner_hash.as_ref()[..32], &inner_hash.as_ref()[32..44])
+                    .unwrap();
+            let mut payload = Vec::new();
+            header_field(&mut payload, 1, &3u32.to_le_bytes());
+            header_field(&mut payload, 2, &inner_key);
+            header_field(&mut payload, 0, &[]);
+            payload.extend_from_slice(protect_values(xml, &mut inner_stream).as_bytes());
+            if self.compressed {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&payload).unwrap();
+                payload = encoder.finish().unwrap();
+            }
+
+            let mut kdf = 0x0100u16.to_le_bytes().to_vec();
+            for (kind, name, value) in &self.kdf {
+                kdf.push(*kind);
+                kdf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+                kdf.extend_from_slice(name.as_bytes());
+                kdf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                kdf.extend_from_slice(value);
+            }
+            kdf.push(0);
+            let kdf_params: HashMap<String, Vec<u8>> = self
+                .kdf
+                .iter()
+                .map(|(_, name, value)| (name.to_string(), value.clone()))
+                .collect();
+
+            let master_seed = [3u8; 32];
+            let iv = if self.cipher == CIPHER_AES256 {
+                vec![4u8; 16]
+            } else {
+                vec![4u8; 12]
+            };
+            let mut header = SIGNATURE.to_vec();
+            header.extend_from_slice(&1u16.to_le_bytes());
+            header.extend_from_slice(&4u16.to_le_bytes());
+            header_field(&mut header, 2, &self.cipher);
+            header_field(&mut header, 3, &(self.compressed as u32).to_le_bytes());
+            header_field(&mut header, 4, &master_seed);
+            header_field(&mut header, 7, &iv);
+            header_field(&mut header, 11, &kdf);
+            header_field(&mut header, 0, b"\r\n\r\n");
+
+            let transformed = transform_key(&composite_key(password), &kdf_params).unwrap();
+            let mut seeded = master_seed.to_vec();
+            seeded.extend_from_slice(transformed.as_ref());
+            let cipher_key = digest::digest(&digest::SHA256, &seeded);
+            seeded.push(1);
+            let hmac_key = digest::digest(&digest::SHA512, &seeded);
+
+            let encrypted = if self.cipher == CIPHER_AES256 {
+                let len = payload.len();
+                payload.resize(len + 16, 0);
+                cbc::Encryptor::<Aes256>::new_from_slices(cipher_key.as_ref(), &iv)
+                    .unwrap()
+                    .encrypt_padded_mut::<Pkcs7>(&mut payload, len)
+                    .unwrap()
+                    .to_vec()
+            } else {
+                ChaCha20::new_from_slices(cipher_key.as_ref(), &iv)
+                    .unwrap()
+                    .apply_keystream(&mut payload);
+                payload
+            };
+
+            let mut file = header.clone();
+            file.extend_from_slice(digest::digest(&digest::SHA256, &header).as_ref());
+            file.extend_from_slice(
+                hmac::sign(&block_hmac_key(hmac_key.as_ref(), u64::MAX), &header).as_ref(),
+            );
+            for (index, block) in [encrypted.as_slice(), &[]].into_iter().enumerate() {
+                let mut signed = (index as u64).to_le_bytes().to_vec();
+                signed.extend_from_slice(&(block.len() as u32).to_le_bytes());
+                signed.extend_from_slice(block);
+                let tag = hmac::sign(&block_hmac_key(hmac_key.as_ref(), index as u64), &signed);
+                file.extend_from_slice(tag.as_ref());
+                file.extend_from_slice(&(block.len() as u32).to_le_bytes());
+                file.extend_from_slice(block);
+            }
+            file
+        }
+    }
+
+    #[test]
+    fn test_import_aes_database() {
+        let db = TestDatabase {
+            cipher: CIPHER_AES256,
+            kdf: aes_kdf(),
+            compressed: true,
+        };
+        let records = import_kdbx(&db.write(PASSWORD, XML), PASSWORD).unwrap();
+        let names: Vec<String> = records
+            .iter()
+            .map(|record| {
+                record
+                    .get_field_value(RECORD_NAME_FIELD)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(names, vec!["Git", "Bastion"]);
+
+        let git = &records[0];
+        assert_eq!(git.get_field_value(RECORD_LOGIN_FIELD).unwrap(), "alice");
+        assert_eq!(git.get_field_value(RECORD_PASSWD_FIELD).unwrap(), "hunter2");
+        assert_eq!(
+            git.get_field_value(RECORD_URL_FIELD).unwrap(),
+            "https://git.example.com"
+        );
+        assert_eq!(
+            git.get_field_value(RECORD_DESCR_FIELD).unwrap(),
+            "deploy key"
+        );
+        assert_eq!(git.get_field_value("Recovery code").unwrap(), "42-42-42");
+        assert_eq!(
+            git.get_field("Recovery code").unwrap().kind,
+            FieldKind::Hidden
+        );
+        assert_eq!(
+            git.totp().unwrap().unwrap().account.as_deref(),
+            Some("alice")
+        );
+        assert_eq!(git.tags(), ["ops".to_string(), "shared".to_string()]);
+        assert_eq!(git.folder(), None);
+
+        let bastion = &records[1];
+        assert_eq!(
+            bastion.get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "p@ss & word"
+        );
+        assert_eq!(bastion.folder(), Some("Work/Servers"));
+        let totp = bastion.totp().unwrap().unwrap();
+        assert_eq!(totp.digits(), 8);
+        assert_eq!(totp.algorithm, crate::totp::TotpAlgorithm::Sha256);
+        assert!(bastion.get_field("TimeOtp-Length").is_none());
+    }
+
+    #[test]
+    fn test_import_chacha20_argon2_database() {
+        let db = TestDatabase {
+            cipher: CIPHER_CHACHA20,
+            kdf: argon2d_kdf(),
+            compressed: false,
+        };
+        let data = db.write(PASSWORD, XML);
+        let records = import_kdbx(&data, PASSWORD).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "hunter2"
+        );
+
+        assert!(matches!(
+            import_kdbx(&data, "wrong password"),
+            Err(ImportError::WrongPassword)
+        ));
+        let mut corrupted = data.clone();
+        let last = corrupted.len() - 40;
+        corrupted[last] ^= 1;
+        assert!(matches!(
+            import_kdbx(&corrupted, PASSWORD),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_excessive_key_derivation_costs_are_refused() {
+        let params = |kdf: Vec<(u8, &'static str, Vec<u8>)>| -> HashMap<String, Vec<u8>> {
+            kdf.into_iter()
+                .map(|(_, name, value)| (name.to_string(), value))
+                .collect()
+        };
+        let composite = composite_key(PASSWORD);
+
+        let mut aes = params(aes_kdf());
+        aes.insert("R".into(), u64::MAX.to_le_bytes().to_vec());
+        assert!(matches!(
+            transform_key(&composite, &aes),
+            Err(ImportError::Unsupported(_))
+        ));
+        for (name, value) in [
+            ("M", 64u64 << 30),
+            ("I", u64::from(u32::MAX) + 1),
+            ("P", 1024),
+        ] {
+            let mut argon2 = params(argon2d_kdf());
+            argon2.insert(name.into(), value.to_le_bytes().to_vec());
+            assert!(
+                matches!(
+                    transform_key(&composite, &argon2),
+                    Err(ImportError::Unsupported(_))
+                ),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_older_versions_are_refused() {
+        let mut kdbx3 = SIGNATURE.to_vec();
+        kdbx3.extend_from_slice(&1u16.to_le_bytes());
+        kdbx3.extend_from_slice(&3u16.to_le_bytes());
+        assert!(matches!(
+            import_kdbx(&kdbx3, PASSWORD),
+            Err(ImportError::Unsupported(_))
+        ));
+        assert!(matches!(
+            import_kdbx(b"name,password\n", PASSWORD),
+            Err(ImportError::Unsupported(_))
+        ));
+    }
+}
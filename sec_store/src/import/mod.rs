@@ -0,0 +1,209 @@
+pub mod bitwarden;
+pub mod csv;
+pub mod kdbx;
+
+use thiserror::Error;
+
+use crate::record::{
+    FieldKind, Record, RecordField, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_TOTP_FIELD,
+    RECORD_URL_FIELD,
+};
+use crate::repository::RecordsRepository;
+use crate::totp::Totp;
+
+pub use self::csv::{CsvColumn, CsvMapping};
+
+pub type ImportResult<T> = Result<T, ImportError>;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("Wrong password or key file")]
+    WrongPassword,
+    #[error("Unsupported file: {0}")]
+    Unsupported(String),
+    #[error("Malformed file: {0}")]
+    Malformed(String),
+    #[error("Invalid column mapping '{0}', expected header=target")]
+    InvalidMapping(String),
+    #[error(transparent)]
+    Csv(#[from] ::csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Bitwarden,
+    Kdbx,
+    Csv,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the file contents, anything unrecognized is read as CSV.
+    pub fn detect(data: &[u8]) -> ImportFormat {
+        if kdbx::is_kdbx(data) {
+            ImportFormat::Kdbx
+        } else if data
+            .iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|byte| *byte == b'{')
+        {
+            ImportFormat::Bitwarden
+        } else {
+            ImportFormat::Csv
+        }
+    }
+
+    pub fn needs_password(&self) -> bool {
+        *self == ImportFormat::Kdbx
+    }
+}
+
+impl std::fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ImportFormat::Bitwarden => "Bitwarden JSON",
+            ImportFormat::Kdbx => "KeePass KDBX",
+            ImportFormat::Csv => "CSV",
+        })
+    }
+}
+
+/// Adds the imported records to the repository, returns how many were added.
+pub async fn import_records<R: RecordsRepository>(
+    repo: &mut R,
+    records: Vec<Record>,
+) -> anyhow::Result<usize> {
+    let count = records.len();
+    for record in records {
+        repo.add_record(record).await?;
+    }
+    Ok(count)
+}
+
+/// Collects the values of one imported entry, empty values are skipped.
+#[derive(Default)]
+struct RecordBuilder {
+    fields: Vec<RecordField>,
+    folder: Option<String>,
+    tags: Vec<String>,
+}
+
+impl RecordBuilder {
+    fn field(&mut self, name: &str, kind: FieldKind, value: &str) {
+        if value.trim().is_empty() {
+            return;
+        }
+        // Custom fields may repeat a name, the record needs them unique.
+        let mut unique_name = name.to_string();
+        let mut counter = 1;
+        while self.fields.iter().any(|field| field.name == unique_name) {
+            counter += 1;
+            unique_name = format!("{name} ({counter})");
+        }
+        self.fields
+            .push(RecordField::new(unique_name, kind, value.to_string()));
+    }
+
+    /// A field with the kind implied by its well-known name.
+    fn known_field(&mut self, name: &str, value: &str) {
+        self.field(name, FieldKind::for_name(name), value);
+    }
+
+    /// Secrets that aren't valid TOTP are kept as a hidden field instead of being lost.
+    fn totp(&mut self, value: &str) {
+        if Totp::parse(value).is_ok() {
+            self.known_field(RECORD_TOTP_FIELD, value.trim());
+        } else {
+            self.field("TOTP", FieldKind::Hidden, value);
+        }
+    }
+
+    fn folder(&mut self, folder: &str) {
+        if !folder.trim().is_empty() {
+            self.folder = Some(folder.to_string());
+        }
+    }
+
+    fn tags<S: AsRef<str>>(&mut self, tags: impl IntoIterator<Item = S>) {
+        self.tags
+            .extend(tags.into_iter().map(|tag| tag.as_ref().to_string()));
+    }
+
+    fn build(mut self) -> Record {
+        if !self
+            .fields
+            .iter()
+            .any(|field| field.name == RECORD_NAME_FIELD)
+        {
+            let name = [RECORD_URL_FIELD, RECORD_LOGIN_FIELD]
+                .iter()
+                .find_map(|name| self.fields.iter().find(|field| field.name == *name))
+                .map(|field| field.value.to_string())
+                .unwrap_or_else(|| "Imported record".to_string());
+            self.fields.insert(
+                0,
+                RecordField::new(RECORD_NAME_FIELD.to_string(), FieldKind::Text, name),
+            );
+        }
+        let mut record = Record::with_fields(self.fields);
+        record.set_tags(self.tags);
+        if let Some(folder) = self.folder {
+            record.set_folder(&folder);
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportFormat, RecordBuilder};
+    use crate::record::{
+        FieldKind, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_TOTP_FIELD, RECORD_URL_FIELD,
+    };
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            ImportFormat::detect(b"\x03\xd9\xa2\x9a\x67\xfb\x4b\xb5\x00\x00\x04\x00"),
+            ImportFormat::Kdbx
+        );
+        assert_eq!(
+            ImportFormat::detect(b"  {\"items\": []}"),
+            ImportFormat::Bitwarden
+        );
+        assert_eq!(
+            ImportFormat::detect(b"name,password\nmail,secret\n"),
+            ImportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn test_record_builder() {
+        let mut builder = RecordBuilder::default();
+        builder.known_field(RECORD_URL_FIELD, "https://example.com");
+        builder.known_field(RECORD_LOGIN_FIELD, "");
+        builder.field("PIN", FieldKind::Hidden, "1234");
+        builder.field("PIN", FieldKind::Hidden, "5678");
+        builder.totp("not a secret!");
+        builder.totp("GEZDGNBVGY3TQOJQ");
+        builder.folder("work/servers");
+        builder.tags(["ops", "ops", ""]);
+        let record = builder.build();
+
+        assert_eq!(
+            record.get_field_value(RECORD_NAME_FIELD).unwrap().as_str(),
+            "https://example.com"
+        );
+        assert!(record.get_field(RECORD_LOGIN_FIELD).is_none());
+        assert_eq!(record.get_field_value("PIN (2)").unwrap().as_str(), "5678");
+        assert_eq!(record.get_field("TOTP").unwrap().kind, FieldKind::Hidden);
+        assert!(record.totp().unwrap().is_ok());
+        assert_eq!(
+            record.get_field(RECORD_TOTP_FIELD).unwrap().kind,
+            FieldKind::Totp
+        );
+        assert_eq!(record.folder(), Some("work/servers"));
+        assert_eq!(record.tags(), ["ops".to_string()]);
+    }
+}
@@ -1,5 +1,12 @@
 // #![allow(dead_code)]
 
+pub mod audit;
+pub mod breach;
 pub mod cipher;
+pub mod export;
+pub mod generator;
+pub mod import;
 pub mod record;
 pub mod repository;
+pub mod secret;
+pub mod totp;
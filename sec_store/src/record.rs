@@ -1,15 +1,158 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
 
 use thiserror::Error;
 use uuid::Uuid;
+use zeroize::Zeroizing;
 
-use crate::cipher::{decrypt_string, encrypt_string, DecryptResult, EncryptedData};
+use crate::cipher::{
+    decrypt_string, encrypt_string, CipherKey, DecryptResult, DecryptionError, EncryptedData,
+};
+use crate::secret::SecretString;
+use crate::totp::{Totp, TotpResult};
 
 pub type RecordId = String;
 
 pub type FieldName = String;
-pub type FieldValue = String;
-pub type RecordField = (FieldName, FieldValue);
+pub type FieldValue = SecretString;
+
+// Well-known field names shared by every front-end.
+pub const RECORD_NAME_FIELD: &str = "RECORD_NAME";
+pub const RECORD_PASSWD_FIELD: &str = "RECORD_PASSWD";
+pub const RECORD_LOGIN_FIELD: &str = "RECORD_LOGIN";
+pub const RECORD_DESCR_FIELD: &str = "RECORD_DESCR";
+pub const RECORD_URL_FIELD: &str = "RECORD_URL";
+pub const RECORD_TOTP_FIELD: &str = "RECORD_TOTP";
+
+/// How many previous values are kept for every field.
+pub const MAX_FIELD_HISTORY: usize = 10;
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    Login,
+    Password,
+    Url,
+    Note,
+    Totp,
+    Hidden,
+    // Kinds written by a newer version are read as plain text.
+    #[default]
+    #[serde(other)]
+    Text,
+}
+
+impl FieldKind {
+    /// The kind used for fields added without one, based on the well-known names.
+    pub fn for_name(field_name: &str) -> FieldKind {
+        match field_name {
+            RECORD_PASSWD_FIELD => FieldKind::Password,
+            RECORD_LOGIN_FIELD => FieldKind::Login,
+            RECORD_DESCR_FIELD => FieldKind::Note,
+            RECORD_URL_FIELD => FieldKind::Url,
+            RECORD_TOTP_FIELD => FieldKind::Totp,
+            _ => FieldKind::Text,
+        }
+    }
+
+    /// Whether front-ends should mask the value until asked to show it.
+    pub fn is_concealed(&self) -> bool {
+        matches!(
+            self,
+            FieldKind::Password | FieldKind::Totp | FieldKind::Hidden
+        )
+    }
+}
+
+/// A value the field had before it was replaced at `replaced_at`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct FieldRevision {
+    pub value: FieldValue,
+    pub replaced_at: u64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(from = "StoredField")]
+pub struct RecordField {
+    pub name: FieldName,
+    pub kind: FieldKind,
+    pub value: FieldValue,
+    /// Previous values, the most recent first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<FieldRevision>,
+}
+
+impl RecordField {
+    pub fn new(name: FieldName, kind: FieldKind, value: impl Into<FieldValue>) -> RecordField {
+        RecordField {
+            name,
+            kind,
+            value: value.into(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns whether the value changed, an identical value is not recorded.
+    fn replace_value(&mut self, value: FieldValue, now: u64) -> bool {
+        if value == self.value {
+            return false;
+        }
+        let previous = std::mem::replace(&mut self.value, value);
+        self.history.insert(
+            0,
+            FieldRevision {
+                value: previous,
+                replaced_at: now,
+            },
+        );
+        self.history.truncate(MAX_FIELD_HISTORY);
+        true
+    }
+}
+
+// Records written before field kinds existed store fields as `[name, value]` pairs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredField {
+    Typed {
+        name: FieldName,
+        kind: FieldKind,
+        value: FieldValue,
+        #[serde(default)]
+        history: Vec<FieldRevision>,
+    },
+    Pair(FieldName, FieldValue),
+}
+
+impl From<StoredField> for RecordField {
+    fn from(field: StoredField) -> Self {
+        match field {
+            StoredField::Typed {
+                name,
+                kind,
+                value,
+                history,
+            } => RecordField {
+                name,
+                kind,
+                value,
+                history,
+            },
+            StoredField::Pair(name, value) => {
+                let kind = FieldKind::for_name(&name);
+                RecordField::new(name, kind, value)
+            }
+        }
+    }
+}
 
 pub type UpdateFieldResult<T> = anyhow::Result<T, FieldDoesntExist>;
 #[derive(Debug, Clone, PartialEq, Error)]
@@ -20,117 +163,343 @@ pub type AddFieldResult<T> = anyhow::Result<T, SameFieldAlreadyExist>;
 #[derive(Debug, Clone, PartialEq)]
 pub struct SameFieldAlreadyExist;
 
+pub type RestoreFieldResult<T> = anyhow::Result<T, RestoreFieldError>;
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RestoreFieldError {
+    #[error("Field {0} doesn't exist")]
+    FieldDoesntExist(String),
+    #[error("Field {0} has no revision {1}")]
+    RevisionDoesntExist(String, usize),
+}
+
 pub type EncryptedRecord = String;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Record {
     pub id: RecordId,
     fields: Vec<RecordField>,
+    // Records written before timestamps existed read as 0.
+    #[serde(default)]
+    created_at: u64,
+    #[serde(default)]
+    modified_at: u64,
+    /// Counts the changes made to the record, a copy with the same revision wasn't changed.
+    #[serde(default)]
+    revision: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Slash separated path such as `work/servers`, `None` for the top level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
 }
 
 impl Record {
-    pub fn new(fields: Vec<RecordField>) -> Record {
+    pub fn new<V: Into<FieldValue>>(fields: Vec<(FieldName, V)>) -> Record {
+        Record::with_fields(
+            fields
+                .into_iter()
+                .map(|(name, value)| {
+                    let kind = FieldKind::for_name(&name);
+                    RecordField::new(name, kind, value)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn with_fields(fields: Vec<RecordField>) -> Record {
+        let now = unix_now();
         Record {
             id: Uuid::new_v4().to_string(),
             fields,
+            created_at: now,
+            modified_at: now,
+            revision: 0,
+            tags: Vec::new(),
+            folder: None,
         }
     }
 
+    /// Unix time in seconds, 0 when unknown.
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// Unix time in seconds, 0 when unknown.
+    pub fn modified_at(&self) -> u64 {
+        self.modified_at
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn touch(&mut self, now: u64) {
+        self.modified_at = now;
+        self.revision += 1;
+    }
+
+    /// Adds a field with the kind implied by its name, see `FieldKind::for_name`.
     pub fn add_field(
         &mut self,
         field_name: FieldName,
-        field_value: FieldValue,
+        field_value: impl Into<FieldValue>,
     ) -> AddFieldResult<()> {
-        match self.fields.iter().find(|(name, _)| field_name.eq(name)) {
+        let kind = FieldKind::for_name(&field_name);
+        self.add_field_of_kind(field_name, kind, field_value)
+    }
+
+    pub fn add_field_of_kind(
+        &mut self,
+        field_name: FieldName,
+        kind: FieldKind,
+        field_value: impl Into<FieldValue>,
+    ) -> AddFieldResult<()> {
+        match self.get_field(&field_name) {
             Some(_) => Err(SameFieldAlreadyExist),
             None => {
-                self.fields.push((field_name, field_value));
+                self.fields
+                    .push(RecordField::new(field_name, kind, field_value));
+                self.touch(unix_now());
                 Ok(())
             }
         }
     }
 
-    pub fn get_fields(&self) -> Vec<&(String, String)> {
+    pub fn get_fields(&self) -> Vec<&RecordField> {
         self.fields.iter().collect()
     }
 
+    pub fn get_field(&self, field_name: &str) -> Option<&RecordField> {
+        self.fields.iter().find(|field| field.name == field_name)
+    }
+
     pub fn get_field_value(&self, field_name: &str) -> Option<FieldValue> {
-        self.fields
-            .iter()
-            .find(|(name, _)| name.eq(field_name))
-            .map(|(_, value)| value.clone())
+        self.get_field(field_name).map(|field| field.value.clone())
     }
 
+    /// Replaces the value and keeps the kind of the field.
+    /// A changed value is pushed to the field history.
     pub fn update_field(
         &mut self,
         field_name: FieldName,
-        field_value: FieldValue,
+        field_value: impl Into<FieldValue>,
     ) -> UpdateFieldResult<()> {
+        let now = unix_now();
         match self
             .fields
-            .iter()
-            .enumerate()
-            .find(|(_, (name, _))| field_name.eq(name))
-            .map(|(pos, _)| pos)
+            .iter_mut()
+            .find(|field| field.name == field_name)
         {
-            Some(idx) => {
-                self.fields[idx] = (field_name, field_value);
+            Some(field) => {
+                if field.replace_value(field_value.into(), now) {
+                    self.touch(now);
+                }
                 Ok(())
             }
             None => Err(FieldDoesntExist(field_name)),
         }
     }
 
-    pub fn encrypt(&self, passwd: &str) -> EncryptedRecord {
-        serde_json::to_string(&encrypt_string(
-            passwd,
-            &serde_json::to_string(self).unwrap(),
-        ))
-        .unwrap()
+    /// Brings back `history[revision]` of the field, the current value goes to the history.
+    pub fn restore_field(&mut self, field_name: &str, revision: usize) -> RestoreFieldResult<()> {
+        let now = unix_now();
+        let field = self
+            .fields
+            .iter_mut()
+            .find(|field| field.name == field_name)
+            .ok_or_else(|| RestoreFieldError::FieldDoesntExist(field_name.to_string()))?;
+        if revision >= field.history.len() {
+            return Err(RestoreFieldError::RevisionDoesntExist(
+                field_name.to_string(),
+                revision,
+            ));
+        }
+        let restored = field.history.remove(revision);
+        field.replace_value(restored.value, now);
+        self.touch(now);
+        Ok(())
+    }
+
+    // Used by the merge to take a field, history included, from another copy of the record.
+    pub(crate) fn put_field(&mut self, field: RecordField) {
+        match self.fields.iter_mut().find(|own| own.name == field.name) {
+            Some(own) => *own = field,
+            None => self.fields.push(field),
+        }
+        self.touch(unix_now());
+    }
+
+    pub(crate) fn remove_field(&mut self, field_name: &str) {
+        self.fields.retain(|field| field.name != field_name);
+        self.touch(unix_now());
+    }
+
+    /// Forgets the previous values of every field.
+    pub fn clear_history(&mut self) {
+        for field in &mut self.fields {
+            field.history.clear();
+        }
+    }
+
+    /// The one-time password generator of the first TOTP field.
+    pub fn totp(&self) -> Option<TotpResult<Totp>> {
+        self.fields
+            .iter()
+            .find(|field| field.kind == FieldKind::Totp)
+            .map(|field| Totp::parse(&field.value))
+    }
+
+    /// Validates `value` as an `otpauth://` URI or a base32 secret and stores it
+    /// in the `RECORD_TOTP_FIELD`.
+    pub fn set_totp(&mut self, value: &str) -> TotpResult<()> {
+        Totp::parse(value)?;
+        let value = value.trim();
+        if self.get_field(RECORD_TOTP_FIELD).is_some() {
+            let _ = self.update_field(RECORD_TOTP_FIELD.to_string(), value);
+        } else {
+            let _ = self.add_field_of_kind(RECORD_TOTP_FIELD.to_string(), FieldKind::Totp, value);
+        }
+        Ok(())
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Replaces the tags, blank and repeated ones are dropped.
+    pub fn set_tags<S: AsRef<str>>(&mut self, tags: impl IntoIterator<Item = S>) {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = tag.as_ref().trim();
+            if !tag.is_empty() && !normalized.iter().any(|t| t == tag) {
+                normalized.push(tag.to_string());
+            }
+        }
+        self.tags = normalized;
+        self.touch(unix_now());
+    }
+
+    pub fn folder(&self) -> Option<&str> {
+        self.folder.as_deref()
+    }
+
+    /// Moves the record to `folder`, an empty path moves it to the top level.
+    pub fn set_folder(&mut self, folder: &str) {
+        let path = folder
+            .split('/')
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<&str>>()
+            .join("/");
+        self.folder = (!path.is_empty()).then_some(path);
+        self.touch(unix_now());
     }
 
-    pub fn decrypt(passwd: &str, encrypted_record: &EncryptedRecord) -> DecryptResult<Record> {
-        Ok(serde_json::from_str::<Record>(&decrypt_string(
-            passwd,
-            serde_json::from_str::<EncryptedData>(encrypted_record).unwrap(),
-        )?)
-        .unwrap())
+    /// True for records placed in `folder` or any of its subfolders.
+    pub fn is_in_folder(&self, folder: &str) -> bool {
+        self.folder.as_deref().is_some_and(|own| {
+            own == folder || (own.starts_with(folder) && own[folder.len()..].starts_with('/'))
+        })
+    }
+
+    pub fn encrypt(&self, key: &CipherKey) -> EncryptedRecord {
+        let plaintext = Zeroizing::new(serde_json::to_string(self).unwrap());
+        serde_json::to_string(&encrypt_string(key, &plaintext)).unwrap()
+    }
+
+    pub fn decrypt(key: &CipherKey, encrypted_record: &EncryptedRecord) -> DecryptResult<Record> {
+        let encrypted_data = serde_json::from_str::<EncryptedData>(encrypted_record)
+            .map_err(|err| DecryptionError::Malformed(err.to_string()))?;
+        let plaintext = Zeroizing::new(decrypt_string(key, encrypted_data)?);
+        serde_json::from_str::<Record>(&plaintext)
+            .map_err(|err| DecryptionError::Malformed(err.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordsFilter {
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+impl RecordsFilter {
+    pub fn is_empty(&self) -> bool {
+        self.folder.is_none() && self.tag.is_none()
+    }
+
+    pub fn matches(&self, record: &Record) -> bool {
+        self.folder
+            .as_deref()
+            .is_none_or(|folder| record.is_in_folder(folder))
+            && self.tag.as_deref().is_none_or(|tag| record.has_tag(tag))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::record::Record;
+    use crate::cipher::test_key;
+    use crate::record::{
+        FieldKind, Record, RecordField, RecordsFilter, RestoreFieldError, MAX_FIELD_HISTORY,
+        RECORD_LOGIN_FIELD, RECORD_PASSWD_FIELD, RECORD_TOTP_FIELD,
+    };
 
     #[test]
     fn test_record_encryption() {
         let fields = vec![(String::from("First"), String::from("1"))];
-        super::Record::new(fields).encrypt("password");
+        super::Record::new(fields).encrypt(&test_key("password"));
     }
 
     #[test]
     fn test_record_decryption() {
-        let passwd = "password".to_string();
+        let key = test_key("password");
 
         let fields = vec![(String::from("First"), String::from("1"))];
         let original_record = super::Record::new(fields.clone());
         let decrypted_record =
-            super::Record::decrypt(&passwd, &original_record.encrypt(&passwd)).unwrap();
+            super::Record::decrypt(&key, &original_record.encrypt(&key)).unwrap();
 
         assert_eq!(original_record, decrypted_record);
-        assert_eq!(decrypted_record.fields, fields);
+        assert_eq!(
+            decrypted_record.fields,
+            vec![RecordField::new("First".to_string(), FieldKind::Text, "1")]
+        );
     }
 
     #[test]
     fn test_record_decryption_with_bad_passwd() {
         let fields = vec![(String::from("First"), String::from("1"))];
-        let result =
-            super::Record::decrypt("Second", &super::Record::new(fields.clone()).encrypt("One"));
+        let result = super::Record::decrypt(
+            &test_key("Second"),
+            &super::Record::new(fields.clone()).encrypt(&test_key("One")),
+        );
 
         let expected = crate::cipher::DecryptionError::WrongPassword;
         assert_eq!(result, Err(expected));
     }
 
+    #[test]
+    fn test_record_decryption_of_malformed_data() {
+        let key = test_key("password");
+        let not_encrypted = "{\"nonce\": []}".to_string();
+        let not_a_record =
+            serde_json::to_string(&crate::cipher::encrypt_string(&key, "[]")).unwrap();
+
+        assert!(matches!(
+            super::Record::decrypt(&key, &not_encrypted),
+            Err(crate::cipher::DecryptionError::Malformed(_))
+        ));
+        assert!(matches!(
+            super::Record::decrypt(&key, &not_a_record),
+            Err(crate::cipher::DecryptionError::Malformed(_))
+        ));
+    }
+
     #[test]
     fn test_field_add() {
         let fields = vec![("Field1".to_string(), "Value1".to_string())];
@@ -143,12 +512,22 @@ mod tests {
         assert_eq!(
             record.get_fields(),
             vec![
-                &("Field1".to_string(), "Value1".to_string()),
-                &("Field2".to_string(), "Value2".to_string()),
+                &RecordField::new("Field1".to_string(), FieldKind::Text, "Value1"),
+                &RecordField::new("Field2".to_string(), FieldKind::Text, "Value2"),
             ]
         )
     }
 
+    #[test]
+    fn test_debug_does_not_leak_values() {
+        let record = Record::new(vec![("Password".to_string(), "hunter2".to_string())]);
+
+        let debug = format!("{record:?}");
+
+        assert!(debug.contains("Password"));
+        assert!(!debug.contains("hunter2"));
+    }
+
     #[test]
     fn test_field_add_err() {
         let fields = vec![("Field1".to_string(), "Value1".to_string())];
@@ -167,10 +546,11 @@ mod tests {
             .update_field("Field1".to_string(), "Value2".to_string())
             .unwrap();
 
-        assert_eq!(
-            record.get_fields(),
-            vec![&("Field1".to_string(), "Value2".to_string()),]
-        )
+        let fields = record.get_fields();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].value, "Value2");
+        assert_eq!(fields[0].history.len(), 1);
+        assert_eq!(fields[0].history[0].value, "Value1");
     }
 
     #[test]
@@ -185,4 +565,220 @@ mod tests {
             Err(crate::record::FieldDoesntExist("Field2".to_string()))
         );
     }
+
+    #[test]
+    fn test_well_known_fields_get_kinds() {
+        let mut record = Record::new(vec![
+            (RECORD_PASSWD_FIELD.to_string(), "secret"),
+            ("Custom".to_string(), "value"),
+        ]);
+        record
+            .add_field_of_kind("PIN".to_string(), FieldKind::Hidden, "1234")
+            .unwrap();
+        record.update_field("PIN".to_string(), "4321").unwrap();
+
+        let kinds = record
+            .get_fields()
+            .iter()
+            .map(|field| field.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![FieldKind::Password, FieldKind::Text, FieldKind::Hidden]
+        );
+        assert!(record.get_field("PIN").unwrap().kind.is_concealed());
+        assert!(!record.get_field("Custom").unwrap().kind.is_concealed());
+    }
+
+    #[test]
+    fn test_legacy_fields_roundtrip() {
+        let legacy = format!(
+            r#"{{"id": "1", "fields": [["{RECORD_LOGIN_FIELD}", "user"], ["Custom", "value"]]}}"#
+        );
+
+        let record = serde_json::from_str::<Record>(&legacy).unwrap();
+
+        assert_eq!(
+            record.get_fields(),
+            vec![
+                &RecordField::new(RECORD_LOGIN_FIELD.to_string(), FieldKind::Login, "user"),
+                &RecordField::new("Custom".to_string(), FieldKind::Text, "value"),
+            ]
+        );
+        let reparsed =
+            serde_json::from_str::<Record>(&serde_json::to_string(&record).unwrap()).unwrap();
+        assert_eq!(reparsed, record);
+    }
+
+    #[test]
+    fn test_unknown_field_kind_is_read_as_text() {
+        let json = r#"{"id": "1", "fields": [{"name": "n", "kind": "passkey", "value": "v"}]}"#;
+
+        let record = serde_json::from_str::<Record>(json).unwrap();
+
+        assert_eq!(record.get_field("n").unwrap().kind, FieldKind::Text);
+    }
+
+    #[test]
+    fn test_update_keeps_bounded_history() {
+        let mut record = Record::new(vec![(RECORD_PASSWD_FIELD.to_string(), "0")]);
+        let created_at = record.created_at();
+
+        for idx in 1..=MAX_FIELD_HISTORY + 2 {
+            record
+                .update_field(RECORD_PASSWD_FIELD.to_string(), idx.to_string())
+                .unwrap();
+        }
+        record
+            .update_field(
+                RECORD_PASSWD_FIELD.to_string(),
+                (MAX_FIELD_HISTORY + 2).to_string(),
+            )
+            .unwrap();
+
+        let field = record.get_field(RECORD_PASSWD_FIELD).unwrap();
+        assert_eq!(field.history.len(), MAX_FIELD_HISTORY);
+        assert_eq!(
+            field.history[0].value,
+            (MAX_FIELD_HISTORY + 1).to_string().as_str()
+        );
+        assert_eq!(field.history[MAX_FIELD_HISTORY - 1].value, "2");
+        assert_eq!(record.created_at(), created_at);
+        assert!(record.modified_at() >= created_at);
+    }
+
+    #[test]
+    fn test_restore_field() {
+        let mut record = Record::new(vec![(RECORD_PASSWD_FIELD.to_string(), "old")]);
+        record
+            .update_field(RECORD_PASSWD_FIELD.to_string(), "new")
+            .unwrap();
+
+        record.restore_field(RECORD_PASSWD_FIELD, 0).unwrap();
+
+        let field = record.get_field(RECORD_PASSWD_FIELD).unwrap();
+        assert_eq!(field.value, "old");
+        assert_eq!(field.history.len(), 1);
+        assert_eq!(field.history[0].value, "new");
+        assert_eq!(
+            record.restore_field(RECORD_PASSWD_FIELD, 1),
+            Err(RestoreFieldError::RevisionDoesntExist(
+                RECORD_PASSWD_FIELD.to_string(),
+                1
+            ))
+        );
+        assert_eq!(
+            record.restore_field("Missing", 0),
+            Err(RestoreFieldError::FieldDoesntExist("Missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_legacy_record_has_unknown_timestamps() {
+        let record = serde_json::from_str::<Record>(r#"{"id": "1", "fields": []}"#).unwrap();
+
+        assert_eq!(record.created_at(), 0);
+        assert_eq!(record.modified_at(), 0);
+        assert_eq!(record.revision(), 0);
+    }
+
+    #[test]
+    fn test_changes_bump_revision() {
+        let mut record = Record::new(vec![(RECORD_PASSWD_FIELD.to_string(), "old")]);
+        assert_eq!(record.revision(), 0);
+        record
+            .update_field(RECORD_PASSWD_FIELD.to_string(), "new")
+            .unwrap();
+        record.set_tags(["work"]);
+        assert_eq!(record.revision(), 2);
+    }
+
+    #[test]
+    fn test_identical_update_keeps_revision() {
+        let mut record = Record::new(vec![(RECORD_PASSWD_FIELD.to_string(), "same")]);
+        let modified_at = record.modified_at();
+
+        record
+            .update_field(RECORD_PASSWD_FIELD.to_string(), "same")
+            .unwrap();
+
+        assert_eq!(record.revision(), 0);
+        assert_eq!(record.modified_at(), modified_at);
+        assert!(record
+            .get_field(RECORD_PASSWD_FIELD)
+            .unwrap()
+            .history
+            .is_empty());
+    }
+
+    #[test]
+    fn test_set_totp() {
+        let mut record = Record::new(vec![("Name".to_string(), "value")]);
+        assert!(record.totp().is_none());
+
+        assert!(record.set_totp("not a secret!").is_err());
+        assert!(record.get_field(RECORD_TOTP_FIELD).is_none());
+
+        record
+            .set_totp(" GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ ")
+            .unwrap();
+        let field = record.get_field(RECORD_TOTP_FIELD).unwrap();
+        assert_eq!(field.kind, FieldKind::Totp);
+        assert_eq!(field.value, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(record.totp().unwrap().unwrap().code_at(59), "287082");
+
+        record
+            .set_totp("otpauth://totp/x?secret=GEZDGNBV&digits=8")
+            .unwrap();
+        assert_eq!(record.totp().unwrap().unwrap().digits(), 8);
+        assert_eq!(
+            record.get_field(RECORD_TOTP_FIELD).unwrap().history.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_tags_are_normalized() {
+        let mut record = Record::new(vec![("Name".to_string(), "value")]);
+
+        record.set_tags(vec![" work ", "", "mail", "work"]);
+
+        assert_eq!(record.tags(), ["work", "mail"]);
+        assert!(record.has_tag("mail"));
+        assert!(!record.has_tag("home"));
+    }
+
+    #[test]
+    fn test_folder_matching() {
+        let mut record = Record::new(vec![("Name".to_string(), "value")]);
+        record.set_folder(" work// servers/");
+
+        assert_eq!(record.folder(), Some("work/servers"));
+        assert!(record.is_in_folder("work"));
+        assert!(record.is_in_folder("work/servers"));
+        assert!(!record.is_in_folder("wor"));
+        assert!(!record.is_in_folder("work/servers/db"));
+
+        record.set_folder("");
+        assert_eq!(record.folder(), None);
+    }
+
+    #[test]
+    fn test_records_filter() {
+        let mut record = Record::new(vec![("Name".to_string(), "value")]);
+        record.set_folder("work");
+        record.set_tags(vec!["mail"]);
+
+        assert!(RecordsFilter::default().matches(&record));
+        let filter = RecordsFilter {
+            folder: Some("work".to_string()),
+            tag: Some("mail".to_string()),
+        };
+        assert!(filter.matches(&record));
+        let filter = RecordsFilter {
+            folder: None,
+            tag: Some("home".to_string()),
+        };
+        assert!(!filter.matches(&record));
+    }
 }
@@ -1,3 +1,7 @@
+mod backup;
+mod blob;
+mod format;
+
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::sync::Arc;
@@ -7,16 +11,26 @@ use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use tempfile::NamedTempFile;
 use uuid::Uuid;
+use zeroize::Zeroizing;
 
-use super::{AddRecordError, UpdateRecordError};
-use crate::cipher::{decrypt_string, encrypt_string, DecryptionError, EncryptedData};
+use super::{AddRecordError, ChangePasswordError, ChangePasswordResult, UpdateRecordError};
+use crate::cipher::{
+    decrypt_bytes, decrypt_string, encrypt_bytes, encrypt_string, Argon2Cost, CipherKey,
+    DecryptionError, KdfParams,
+};
 use crate::record::EncryptedRecord;
-use crate::record::{Record, RecordId};
+use crate::record::{unix_now, Record, RecordId};
+use crate::repository::merge::{merge_records, MergeConflict};
 use crate::repository::{
-    AddResult, CreateRepositoryError, CreateRepositoryResult, OpenRepository, OpenResult,
-    RecordsRepository, RepositoriesSource, RepositoryOpenError, UpdateResult,
+    AddResult, Backup, CreateRepositoryError, CreateRepositoryResult, DamagedRecord,
+    ManageRepositoryError, ManageRepositoryResult, OpenRepository, OpenResult, RecordsRepository,
+    RepositoriesSource, RepositoryOpenError, UpdateResult,
 };
-use serde::{Deserialize, Serialize};
+use crate::secret::SecretString;
+pub use backup::{BackupPolicy, Backups, BACKUPS_DIR};
+pub use blob::Compression;
+use format::{read_repository, MigrationContext, RawRepository};
+pub use format::{BodyLayout, CipherSuite, RepositoryHeader, CURRENT_FORMAT_VERSION};
 use std::io::prelude::Read;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,33 +48,122 @@ impl RepositoryId {
 
 type RecordsMap = HashMap<RecordId, Record>;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct RecordsFileRepository {
     pub identifier: RepositoryId,
     file: PathBuf,
-    passwd: String,
+    passwd: SecretString,
+    header: RepositoryHeader,
+    key: CipherKey,
     records: RecordsMap,
     saved_records: RecordsMap,
+    quarantined: Vec<(DamagedRecord, EncryptedRecord)>,
+    backup_policy: BackupPolicy,
 }
 pub struct OpenRecordsFileRepository(pub PathBuf);
+/// Opens a repository skipping the records that can't be read instead of failing.
+/// Their ciphertext is moved to a `.damaged` file next to the repository on save.
+pub struct RecoverRecordsFileRepository(pub PathBuf);
+
+impl std::fmt::Debug for RecordsFileRepository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordsFileRepository")
+            .field("identifier", &self.identifier)
+            .field("file", &self.file)
+            .field("header", &self.header)
+            .field("records", &self.records.len())
+            .field("saved_records", &self.saved_records.len())
+            .field("quarantined", &self.quarantined.len())
+            .finish_non_exhaustive()
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct NamedFileRepositories {
     base_dir: PathBuf,
+    backup_policy: BackupPolicy,
 }
 
-#[derive(Serialize, Deserialize)]
-struct RawRepositoryJson(EncryptedData, Vec<EncryptedRecord>);
-
 impl RecordsFileRepository {
-    pub fn new(file: PathBuf, passwd: String) -> RecordsFileRepository {
-        RecordsFileRepository {
+    pub fn new(file: PathBuf, passwd: SecretString) -> RecordsFileRepository {
+        Self::new_with_kdf(file, passwd, KdfParams::argon2id(Argon2Cost::default()))
+            .expect("default Argon2id parameters must be valid")
+    }
+
+    pub fn new_with_kdf(
+        file: PathBuf,
+        passwd: SecretString,
+        kdf: KdfParams,
+    ) -> Result<RecordsFileRepository> {
+        let key = kdf.derive_key(&passwd)?;
+        Ok(RecordsFileRepository {
             file,
             records: HashMap::new(),
             passwd,
+            header: RepositoryHeader::new(kdf),
+            key,
             identifier: Uuid::new_v4().to_string().into(),
             saved_records: HashMap::new(),
+            quarantined: Vec::new(),
+            backup_policy: BackupPolicy::default(),
+        })
+    }
+
+    pub fn kdf(&self) -> &KdfParams {
+        &self.header.kdf
+    }
+
+    pub fn header(&self) -> &RepositoryHeader {
+        &self.header
+    }
+
+    pub fn body_layout(&self) -> BodyLayout {
+        self.header.body_layout
+    }
+
+    /// Takes effect on the next `save`.
+    pub fn set_body_layout(&mut self, body_layout: BodyLayout) {
+        self.header.body_layout = body_layout;
+    }
+
+    pub fn backup_policy(&self) -> BackupPolicy {
+        self.backup_policy
+    }
+
+    /// Every save keeps the replaced version according to `policy`.
+    pub fn set_backup_policy(&mut self, policy: BackupPolicy) {
+        self.backup_policy = policy;
+    }
+
+    pub fn backups(&self) -> Backups {
+        Backups::of(&self.file)
+    }
+
+    // Repositories opened with the legacy key derivation are re-keyed with
+    // a fresh salt before anything is written back to disk.
+    fn upgrade_legacy_kdf(&mut self) -> Result<()> {
+        if self.header.kdf.is_legacy() {
+            let kdf = KdfParams::argon2id(Argon2Cost::default());
+            self.key = kdf.derive_key(&self.passwd)?;
+            self.header.kdf = kdf;
         }
+        Ok(())
+    }
+
+    /// A standalone repository file holding `records`, encrypted with `passwd`
+    /// instead of the password of the open repository. Like a share, it leaves out the
+    /// field history, previous passwords included.
+    pub fn encrypted_dump(records: &[Record], passwd: SecretString) -> Result<Vec<u8>> {
+        let mut repository = RecordsFileRepository::new(PathBuf::new(), passwd);
+        repository.records = records
+            .iter()
+            .map(|record| {
+                let mut record = record.clone();
+                record.clear_history();
+                (record.id.clone(), record)
+            })
+            .collect();
+        repository.serialize_records(&repository.records)
     }
 
     pub async fn persisted_dump(&self) -> Result<Vec<u8>> {
@@ -71,16 +174,81 @@ impl RecordsFileRepository {
     }
 
     fn serialize_records(&self, records: &RecordsMap) -> Result<Vec<u8>> {
-        serde_json::to_vec(&RawRepositoryJson(
-            encrypt_string(&self.passwd, self.identifier.as_str()),
-            records
-                .values()
-                .map(|rec| rec.encrypt(&self.passwd))
-                .collect::<Vec<EncryptedRecord>>(),
-        ))
+        // Everything is re-encrypted below, so the output is always the latest format.
+        let header = RepositoryHeader {
+            format_version: CURRENT_FORMAT_VERSION,
+            cipher_suite: CipherSuite::Aes256Gcm,
+            ..self.header.clone()
+        };
+        let (records, body) = match self.header.body_layout {
+            BodyLayout::PerRecord => (
+                records
+                    .values()
+                    .map(|rec| rec.encrypt(&self.key))
+                    .collect::<Vec<EncryptedRecord>>(),
+                None,
+            ),
+            BodyLayout::SingleBlob { compression } => {
+                let mut records = records.values().collect::<Vec<&Record>>();
+                records.sort_by(|a, b| a.id.cmp(&b.id));
+                let packed = blob::pack(&records, compression)?;
+                (Vec::new(), Some(encrypt_bytes(&self.key, &packed)))
+            }
+        };
+        serde_json::to_vec(&RawRepository {
+            header,
+            identifier: encrypt_string(&self.key, self.identifier.as_str()),
+            records,
+            body,
+        })
         .with_context(|| format!("Failed json dump serialization {:?}", self.file))
     }
 
+    fn damaged_records_path(&self) -> PathBuf {
+        let mut path = self.file.clone().into_os_string();
+        path.push(".damaged");
+        path.into()
+    }
+
+    // Damaged records are dropped from the repository file, their ciphertext
+    // is kept aside so it can still be restored by hand.
+    fn preserve_damaged_records(&self) -> Result<()> {
+        if self.quarantined.is_empty() {
+            return Ok(());
+        }
+        let path = self.damaged_records_path();
+        let mut preserved: Vec<EncryptedRecord> = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Failed damaged records parsing {:?}", path))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed file open {:?}", path));
+            }
+        };
+        for (_, encrypted_record) in &self.quarantined {
+            if !preserved.contains(encrypted_record) {
+                preserved.push(encrypted_record.clone());
+            }
+        }
+        std::fs::write(&path, serde_json::to_vec(&preserved)?)
+            .with_context(|| format!("Failed damaged records save {:?}", path))
+    }
+
+    fn write_to_disk(&self, data: &[u8]) -> Result<()> {
+        self.preserve_damaged_records()?;
+        self.backups().back_up(&self.backup_policy)?;
+        let mut tmp_file = NamedTempFile::new_in(
+            self.file
+                .parent()
+                .with_context(|| format!("Failed get parent directory for {:?}", self.file))?,
+        )?;
+        tmp_file.write_all(data)?;
+
+        tmp_file.flush()?;
+        tmp_file.persist(self.file.as_path())?;
+        Ok(())
+    }
+
     fn create_new_on_disk(&self) -> Result<()> {
         let mut file = OpenOptions::new()
             .write(true)
@@ -93,73 +261,215 @@ impl RecordsFileRepository {
     }
 }
 
+/// Directory next to the named repositories where the deleted ones are moved.
+pub const TRASH_DIR: &str = ".trash";
+const REPOSITORY_EXTENSION: &str = "json";
+
+fn is_valid_repository_name(repository_name: &str) -> bool {
+    !repository_name.is_empty()
+        && repository_name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+}
+
 impl NamedFileRepositories {
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        Self {
+            base_dir,
+            backup_policy: BackupPolicy::default(),
+        }
+    }
+
+    /// Applied to the repositories created or opened from now on.
+    pub fn with_backup_policy(mut self, policy: BackupPolicy) -> Self {
+        self.backup_policy = policy;
+        self
+    }
+
+    /// Backs up the current version of a repository, pruning the older ones with the policy.
+    pub fn back_up(&self, repository_name: &str) -> ManageRepositoryResult<Backup> {
+        let path = self.named_path(repository_name)?;
+        Backups::of(&path).back_up_now(&self.backup_policy)
+    }
+
+    /// The file of a named repository, whether or not it exists.
+    pub fn named_path(&self, repository_name: &str) -> ManageRepositoryResult<PathBuf> {
+        self.repository_path(repository_name)
+            .map_err(|err| ManageRepositoryError::InvalidRepositoryName(err.to_string()))
+    }
+
+    // Fails with the same errors as opening, recovery mode accepts damaged repositories.
+    fn checked_path(
+        &self,
+        repository_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<PathBuf> {
+        let path = self.named_path(repository_name)?;
+        open_file(path.clone(), passwd, true, None)?;
+        Ok(path)
     }
 
     fn repository_path(&self, repository_name: &str) -> Result<PathBuf> {
-        if repository_name.is_empty()
-            || !repository_name
-                .chars()
-                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
-        {
+        if !is_valid_repository_name(repository_name) {
             return Err(anyhow!(repository_name.to_string()));
         }
 
-        Ok(self.base_dir.join(format!("{repository_name}.json")))
+        Ok(self
+            .base_dir
+            .join(format!("{repository_name}.{REPOSITORY_EXTENSION}")))
     }
 }
 
 #[async_trait]
 impl OpenRepository<RecordsFileRepository> for OpenRecordsFileRepository {
-    async fn open(self, passwd: String) -> OpenResult<RecordsFileRepository> {
-        if !self.0.exists() {
-            return Err(RepositoryOpenError::DoesntExist);
+    async fn open(self, passwd: SecretString) -> OpenResult<RecordsFileRepository> {
+        open_file(self.0, passwd, false, None)
+    }
+}
+
+#[async_trait]
+impl OpenRepository<RecordsFileRepository> for RecoverRecordsFileRepository {
+    async fn open(self, passwd: SecretString) -> OpenResult<RecordsFileRepository> {
+        open_file(self.0, passwd, true, None)
+    }
+}
+
+// `known_key` skips the key derivation when the file still uses the same parameters.
+fn open_file(
+    file: PathBuf,
+    passwd: SecretString,
+    recover: bool,
+    known_key: Option<(&KdfParams, &CipherKey)>,
+) -> OpenResult<RecordsFileRepository> {
+    if !file.exists() {
+        return Err(RepositoryOpenError::DoesntExist);
+    }
+
+    let data = std::fs::read(&file)
+        .with_context(|| format!("Failed file open {:?}", file.to_str()))
+        .map_err(RepositoryOpenError::OpenError)?;
+    let ctx = MigrationContext {
+        fallback_timestamp: file_modified_at(&file),
+    };
+    let raw_rep = read_repository(&data, &ctx)
+        .with_context(|| format!("Failed file {:?} deserealisation", file.to_str()))
+        .map_err(RepositoryOpenError::OpenError)?;
+    let key = match known_key {
+        Some((kdf, key)) if *kdf == raw_rep.header.kdf => key.clone(),
+        _ => raw_rep
+            .header
+            .kdf
+            .derive_key(&passwd)
+            .with_context(|| format!("Failed key derivation for {:?}", file.to_str()))
+            .map_err(RepositoryOpenError::OpenError)?,
+    };
+
+    let identifier = match decrypt_string(&key, raw_rep.identifier) {
+        Ok(identifier) => identifier,
+        Err(DecryptionError::WrongPassword) => return Err(RepositoryOpenError::WrongPassword),
+        Err(DecryptionError::Tampered) => {
+            return Err(RepositoryOpenError::OpenError(anyhow!(
+                "Repository file \"{:?}\" was modified or corrupted",
+                file.to_str()
+            )))
+        }
+        Err(DecryptionError::EncodingError(err)) => {
+            return Err(RepositoryOpenError::OpenError(anyhow!(
+                "Got encoding error \"{}\" for file \"{:?}\"",
+                err,
+                file.to_str()
+            )))
         }
+        Err(DecryptionError::Malformed(err)) => {
+            return Err(RepositoryOpenError::OpenError(anyhow!(
+                "Repository file \"{:?}\" identifier is malformed: {}",
+                file.to_str(),
+                err
+            )))
+        }
+    };
 
-        match File::open(self.0.clone())
-            .with_context(|| format!("Failed file open {:?}", self.0.to_str()))
-        {
-            Err(err) => Err(RepositoryOpenError::OpenError(err)),
-            Ok(file) => {
-                let raw_rep = serde_json::from_reader::<File, RawRepositoryJson>(file)
-                    .with_context(|| format!("Failed file {:?} deserealisation", self.0.to_str()))
-                    .map_err(RepositoryOpenError::OpenError)?;
-
-                match decrypt_string(&passwd, raw_rep.0) {
-                    Err(DecryptionError::WrongPassword) => Err(RepositoryOpenError::WrongPassword),
-                    Err(DecryptionError::EncodingError(err)) => {
-                        Err(RepositoryOpenError::OpenError(anyhow!(
-                            "Got encoding error \"{}\" for file \"{:?}\"",
-                            err,
-                            self.0.to_str()
-                        )))
-                    }
-                    Ok(identifier) => {
-                        let records = HashMap::from_iter(
-                            raw_rep
-                                .1
-                                .iter()
-                                .map(|encrypted_record| {
-                                    Record::decrypt(&passwd, encrypted_record).unwrap()
-                                })
-                                .map(|record| (record.id.clone(), record)),
-                        );
-                        Ok(RecordsFileRepository {
-                            file: self.0,
-                            identifier: identifier.into(),
-                            passwd,
-                            records: records.clone(),
-                            saved_records: records,
-                        })
-                    }
+    let mut quarantined = Vec::new();
+    let records = match raw_rep.header.body_layout {
+        BodyLayout::PerRecord => {
+            let mut records = Vec::with_capacity(raw_rep.records.len());
+            for (index, encrypted_record) in raw_rep.records.into_iter().enumerate() {
+                match Record::decrypt(&key, &encrypted_record) {
+                    Ok(record) => records.push(record),
+                    Err(err) => quarantined.push((
+                        DamagedRecord {
+                            index,
+                            reason: damage_reason(err),
+                        },
+                        encrypted_record,
+                    )),
                 }
             }
+            records
+        }
+        BodyLayout::SingleBlob { compression } => {
+            let body = raw_rep
+                .body
+                .ok_or_else(|| anyhow!("Repository body is missing"))
+                .map_err(RepositoryOpenError::OpenError)?;
+            let packed = decrypt_bytes(&key, body)
+                .map(Zeroizing::new)
+                .map_err(|err| {
+                    RepositoryOpenError::OpenError(anyhow!(
+                        "Repository file \"{:?}\" body can't be decrypted: {:?}",
+                        file.to_str(),
+                        err
+                    ))
+                })?;
+            blob::unpack(&packed, compression)
+                .with_context(|| format!("Failed file {:?} body", file.to_str()))
+                .map_err(RepositoryOpenError::OpenError)?
         }
+    };
+    if !recover && !quarantined.is_empty() {
+        return Err(RepositoryOpenError::DamagedRecords(
+            quarantined
+                .into_iter()
+                .map(|(damaged, _)| damaged)
+                .collect(),
+        ));
+    }
+
+    let records = HashMap::from_iter(
+        records
+            .into_iter()
+            .map(|record| (record.id.clone(), record)),
+    );
+    Ok(RecordsFileRepository {
+        file,
+        identifier: identifier.into(),
+        passwd,
+        header: raw_rep.header,
+        key,
+        records: records.clone(),
+        saved_records: records,
+        quarantined,
+        backup_policy: BackupPolicy::default(),
+    })
+}
+
+fn damage_reason(err: DecryptionError) -> String {
+    match err {
+        DecryptionError::WrongPassword => "encrypted with another key".to_string(),
+        DecryptionError::Tampered => "modified or corrupted".to_string(),
+        DecryptionError::EncodingError(err) => format!("invalid encoding: {err}"),
+        DecryptionError::Malformed(err) => format!("malformed: {err}"),
     }
 }
 
+fn file_modified_at(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or_else(unix_now, |duration| duration.as_secs())
+}
+
 #[async_trait]
 impl RecordsRepository for RecordsFileRepository {
     async fn cancel(&mut self) -> Result<()> {
@@ -168,16 +478,12 @@ impl RecordsRepository for RecordsFileRepository {
     }
 
     async fn save(&mut self) -> Result<()> {
-        let mut tmp_file = NamedTempFile::new_in(
-            self.file
-                .parent()
-                .with_context(|| format!("Failed get parent directory for {:?}", self.file))?,
-        )?;
-        tmp_file.write_all(&self.serialize_records(&self.records)?)?;
-
-        tmp_file.flush()?;
-        tmp_file.persist(self.file.as_path())?;
+        self.upgrade_legacy_kdf()?;
+        self.header.modified_at = unix_now();
+        self.write_to_disk(&self.serialize_records(&self.records)?)?;
 
+        self.header.format_version = CURRENT_FORMAT_VERSION;
+        self.header.cipher_suite = CipherSuite::Aes256Gcm;
         self.saved_records = self.records.clone();
 
         Ok(())
@@ -226,6 +532,96 @@ impl RecordsRepository for RecordsFileRepository {
     async fn dump(&self) -> Result<Vec<u8>> {
         self.serialize_records(&self.records)
     }
+
+    async fn merge_persisted(&mut self) -> Result<Vec<MergeConflict>> {
+        let persisted = match open_file(
+            self.file.clone(),
+            self.passwd.clone(),
+            true,
+            Some((&self.header.kdf, &self.key)),
+        ) {
+            Ok(repository) => repository.saved_records,
+            Err(RepositoryOpenError::DoesntExist) => return Ok(Vec::new()),
+            Err(err) => return Err(anyhow!("Failed to read the saved repository: {err}")),
+        };
+        if persisted == self.saved_records {
+            return Ok(Vec::new());
+        }
+
+        let merge = merge_records(
+            &self
+                .saved_records
+                .values()
+                .cloned()
+                .collect::<Vec<Record>>(),
+            &self.records.values().cloned().collect::<Vec<Record>>(),
+            &persisted.values().cloned().collect::<Vec<Record>>(),
+        );
+        self.records = merge
+            .records
+            .into_iter()
+            .map(|record| (record.id.clone(), record))
+            .collect();
+        self.saved_records = persisted;
+        Ok(merge.conflicts)
+    }
+
+    fn is_password(&self, passwd: &str) -> bool {
+        self.passwd == passwd
+    }
+
+    fn damaged_records(&self) -> Vec<DamagedRecord> {
+        self.quarantined
+            .iter()
+            .map(|(damaged, _)| damaged.clone())
+            .collect()
+    }
+
+    async fn change_password(
+        &mut self,
+        old_passwd: SecretString,
+        new_passwd: SecretString,
+    ) -> ChangePasswordResult<()> {
+        let old_key = self
+            .header
+            .kdf
+            .derive_key(&old_passwd)
+            .map_err(ChangePasswordError::UnexpectedError)?;
+        if old_key != self.key {
+            return Err(ChangePasswordError::WrongPassword);
+        }
+
+        let kdf = KdfParams::argon2id(Argon2Cost::default());
+        let key = kdf
+            .derive_key(&new_passwd)
+            .map_err(ChangePasswordError::UnexpectedError)?;
+
+        let previous = (
+            std::mem::replace(&mut self.passwd, new_passwd),
+            std::mem::replace(&mut self.header.kdf, kdf),
+            std::mem::replace(&mut self.key, key),
+            self.header.modified_at,
+        );
+        self.header.modified_at = unix_now();
+
+        // Only the persisted records are rewritten, the file never mixes keys.
+        let written = self
+            .serialize_records(&self.saved_records)
+            .and_then(|data| self.write_to_disk(&data));
+        if let Err(err) = written {
+            (
+                self.passwd,
+                self.header.kdf,
+                self.key,
+                self.header.modified_at,
+            ) = previous;
+            return Err(ChangePasswordError::UnexpectedError(err));
+        }
+
+        self.header.format_version = CURRENT_FORMAT_VERSION;
+        self.header.cipher_suite = CipherSuite::Aes256Gcm;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -233,13 +629,14 @@ impl RepositoriesSource<RecordsFileRepository> for NamedFileRepositories {
     async fn create_repository(
         &self,
         repository_name: &str,
-        passwd: String,
+        passwd: SecretString,
     ) -> CreateRepositoryResult<RecordsFileRepository> {
         let path = self
             .repository_path(repository_name)
             .map_err(|err| CreateRepositoryError::InvalidRepositoryName(err.to_string()))?;
 
         let mut repository = RecordsFileRepository::new(path, passwd);
+        repository.set_backup_policy(self.backup_policy);
         match repository.create_new_on_disk() {
             Ok(()) => repository.saved_records = repository.records.clone(),
             Err(err)
@@ -258,32 +655,137 @@ impl RepositoriesSource<RecordsFileRepository> for NamedFileRepositories {
     async fn open_repository(
         &self,
         repository_name: &str,
-        passwd: String,
+        passwd: SecretString,
     ) -> OpenResult<RecordsFileRepository> {
         let path = self
             .repository_path(repository_name)
             .map_err(|err| RepositoryOpenError::InvalidRepositoryName(err.to_string()))?;
-        OpenRecordsFileRepository(path).open(passwd).await
+        let mut repository = OpenRecordsFileRepository(path).open(passwd).await?;
+        repository.set_backup_policy(self.backup_policy);
+        Ok(repository)
+    }
+
+    async fn list_repositories(&self) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.base_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to list repositories in {:?}", self.base_dir))
+            }
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_file()
+                || path.extension().and_then(|ext| ext.to_str()) != Some(REPOSITORY_EXTENSION)
+            {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                if is_valid_repository_name(name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    async fn rename_repository(
+        &self,
+        repository_name: &str,
+        new_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()> {
+        let new_path = self.named_path(new_name)?;
+        let path = self.checked_path(repository_name, passwd)?;
+        // Unlike `rename`, linking never replaces an existing repository.
+        std::fs::hard_link(&path, &new_path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::AlreadyExists => ManageRepositoryError::RepositoryAlreadyExists,
+            _ => ManageRepositoryError::UnexpectedError(err.into()),
+        })?;
+        std::fs::remove_file(&path)
+            .map_err(|err| ManageRepositoryError::UnexpectedError(err.into()))?;
+        Backups::of(&path)
+            .move_to(&new_path)
+            .map_err(ManageRepositoryError::UnexpectedError)
+    }
+
+    async fn delete_repository(
+        &self,
+        repository_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()> {
+        let path = self.checked_path(repository_name, passwd)?;
+        move_to_trash(&path, &self.base_dir.join(TRASH_DIR), repository_name)
+            .with_context(|| format!("Failed to move {repository_name} to the trash"))
+            .map_err(ManageRepositoryError::UnexpectedError)
+    }
+
+    // Backups outlive a deleted repository, restoring one brings it back.
+    async fn list_backups(&self, repository_name: &str) -> ManageRepositoryResult<Vec<Backup>> {
+        let path = self.named_path(repository_name)?;
+        Backups::of(&path)
+            .list()
+            .map_err(ManageRepositoryError::UnexpectedError)
+    }
+
+    async fn restore_backup(
+        &self,
+        repository_name: &str,
+        backup_id: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()> {
+        let path = self.named_path(repository_name)?;
+        Backups::of(&path).restore(backup_id, passwd)
     }
 }
 
+// Deleting the same name twice within a second must not replace the first trashed file.
+fn move_to_trash(
+    path: &std::path::Path,
+    trash: &std::path::Path,
+    repository_name: &str,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(trash)?;
+    let deleted_at = unix_now();
+    for n in 0.. {
+        let trashed = match n {
+            0 => format!("{repository_name}.{deleted_at}.{REPOSITORY_EXTENSION}"),
+            n => format!("{repository_name}.{deleted_at}-{n}.{REPOSITORY_EXTENSION}"),
+        };
+        match std::fs::hard_link(path, trash.join(trashed)) {
+            Ok(()) => break,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    std::fs::remove_file(path)
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
     use std::io::prelude::Write;
     use tempfile::TempDir;
 
+    use crate::secret::SecretString;
     use crate::{
-        record::Record,
+        record::{Record, RecordsFilter},
         repository::{
-            file::{OpenRecordsFileRepository, RepositoryOpenError},
-            RepositoriesSource, UpdateRecordError,
+            file::{OpenRecordsFileRepository, RecoverRecordsFileRepository, RepositoryOpenError},
+            ChangePasswordError, ManageRepositoryError, RepositoriesSource, UpdateRecordError,
         },
         repository::{OpenRepository, RecordsRepository},
     };
     use anyhow::Result;
 
-    use super::{NamedFileRepositories, RecordsFileRepository};
+    use super::{
+        BackupPolicy, BodyLayout, CipherSuite, Compression, NamedFileRepositories,
+        RecordsFileRepository, CURRENT_FORMAT_VERSION, TRASH_DIR,
+    };
+    use crate::cipher::{encrypt_string, KdfParams};
 
     #[tokio::test]
     async fn test_repository_add_record() -> Result<()> {
@@ -295,7 +797,7 @@ mod tests {
             (String::from("Password"), String::from("2")),
         ];
 
-        let passwd = "Passwd".to_string();
+        let passwd = SecretString::from("Passwd");
         let record = Record::new(fields);
         let mut repo = RecordsFileRepository::new(file, passwd);
 
@@ -320,7 +822,7 @@ mod tests {
         ];
         let record = Record::new(fields);
 
-        let passwd = "Passwd".to_string();
+        let passwd = SecretString::from("Passwd");
         let mut repo = RecordsFileRepository::new(file.clone(), passwd.clone());
         repo.add_record(record).await.unwrap();
         repo.save().await.unwrap();
@@ -348,7 +850,7 @@ mod tests {
             (String::from("Password"), String::from("2")),
         ];
 
-        let passwd = "Passwd".to_string();
+        let passwd = SecretString::from("Passwd");
 
         let old_record = Record::new(fields);
         let mut repo = RecordsFileRepository::new(file, passwd);
@@ -356,9 +858,7 @@ mod tests {
         repo.add_record(old_record.clone()).await.unwrap();
 
         let mut new_record = repo.get(&old_record.id).await?.unwrap();
-        new_record
-            .add_field("Field3".to_string(), "3".to_string())
-            .unwrap();
+        new_record.add_field("Field3".to_string(), "3").unwrap();
 
         repo.update(new_record.clone()).await.unwrap();
 
@@ -382,7 +882,7 @@ mod tests {
             (String::from("Password"), String::from("2")),
         ];
 
-        let passwd = "Passwd".to_string();
+        let passwd = SecretString::from("Passwd");
 
         let record = Record::new(fields);
         let mut repo = RecordsFileRepository::new(file, passwd);
@@ -413,12 +913,12 @@ mod tests {
 
         let mut repo = RecordsFileRepository::new(
             tmp_dir.path().join("repo_file"),
-            "One password".to_string(),
+            SecretString::from("One password"),
         );
         repo.save().await.unwrap();
 
         let result = OpenRecordsFileRepository(repo.file)
-            .open("Wrong passwd".to_string())
+            .open(SecretString::from("Wrong passwd"))
             .await
             .unwrap_err();
 
@@ -430,7 +930,7 @@ mod tests {
     async fn test_repository_open_missed_file() {
         let tmp_dir = TempDir::new().unwrap();
         let result = OpenRecordsFileRepository(tmp_dir.path().join("any_file"))
-            .open("Wrong passwd".to_string())
+            .open(SecretString::from("Wrong passwd"))
             .await
             .unwrap_err();
 
@@ -441,7 +941,7 @@ mod tests {
     #[tokio::test]
     async fn test_repository_dump() {
         let tmp_dir = TempDir::new().unwrap();
-        let pass = "One password".to_string();
+        let pass = SecretString::from("One password");
 
         let mut repo = RecordsFileRepository::new(tmp_dir.path().join("repo_file"), pass);
         repo.save().await.unwrap();
@@ -455,7 +955,7 @@ mod tests {
     #[tokio::test]
     async fn test_repository_load_dumped() {
         let tmp_dir = TempDir::new().unwrap();
-        let pass = "One password".to_string();
+        let pass = SecretString::from("One password");
 
         let mut repo = RecordsFileRepository::new(tmp_dir.path().join("repo_file"), pass.clone());
 
@@ -504,11 +1004,11 @@ mod tests {
         let repositories = NamedFileRepositories::new(tmp_dir.path().to_path_buf());
 
         let first = repositories
-            .create_repository("demo", "One password".to_string())
+            .create_repository("demo", SecretString::from("One password"))
             .await
             .unwrap();
         let second = repositories
-            .create_repository("demo", "Another password".to_string())
+            .create_repository("demo", SecretString::from("Another password"))
             .await;
 
         assert!(matches!(
@@ -517,11 +1017,585 @@ mod tests {
         ));
 
         let reopened = OpenRecordsFileRepository(first.file.clone())
-            .open("One password".to_string())
+            .open(SecretString::from("One password"))
             .await
             .unwrap();
         assert_eq!(reopened.identifier, first.identifier);
 
         tmp_dir.close().unwrap();
     }
+
+    #[tokio::test]
+    async fn test_named_repositories_list_rename_and_delete() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repositories = NamedFileRepositories::new(tmp_dir.path().to_path_buf());
+        let passwd = SecretString::from("Passwd");
+        for name in ["work", "home", "bank"] {
+            repositories
+                .create_repository(name, passwd.clone())
+                .await
+                .unwrap();
+        }
+        File::create(tmp_dir.path().join("notes.txt")).unwrap();
+        assert_eq!(
+            repositories.list_repositories().await.unwrap(),
+            vec!["bank", "home", "work"]
+        );
+
+        assert!(matches!(
+            repositories
+                .rename_repository("home", "bank", passwd.clone())
+                .await,
+            Err(ManageRepositoryError::RepositoryAlreadyExists)
+        ));
+        assert!(matches!(
+            repositories
+                .rename_repository("home", "family", SecretString::from("Wrong"))
+                .await,
+            Err(ManageRepositoryError::WrongPassword)
+        ));
+        repositories
+            .rename_repository("home", "family", passwd.clone())
+            .await
+            .unwrap();
+        repositories
+            .open_repository("family", passwd.clone())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            repositories
+                .delete_repository("work", SecretString::from("Wrong"))
+                .await,
+            Err(ManageRepositoryError::WrongPassword)
+        ));
+        repositories
+            .delete_repository("work", passwd.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            repositories.list_repositories().await.unwrap(),
+            vec!["bank", "family"]
+        );
+        let trashed = std::fs::read_dir(tmp_dir.path().join(TRASH_DIR))
+            .unwrap()
+            .count();
+        assert_eq!(trashed, 1);
+        assert!(matches!(
+            repositories.delete_repository("work", passwd.clone()).await,
+            Err(ManageRepositoryError::DoesntExist)
+        ));
+
+        // Deleting a name again within the same second keeps both trashed files.
+        for _ in 0..2 {
+            repositories
+                .create_repository("work", passwd.clone())
+                .await
+                .unwrap();
+            repositories
+                .delete_repository("work", passwd.clone())
+                .await
+                .unwrap();
+        }
+        let trashed = std::fs::read_dir(tmp_dir.path().join(TRASH_DIR))
+            .unwrap()
+            .count();
+        assert_eq!(trashed, 3);
+    }
+
+    #[tokio::test]
+    async fn test_saves_are_backed_up_and_restored() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repositories = NamedFileRepositories::new(tmp_dir.path().to_path_buf());
+        let passwd = SecretString::from("Passwd");
+        let mut repository = repositories
+            .create_repository("work", passwd.clone())
+            .await
+            .unwrap();
+        let record = Record::new(vec![("name".to_string(), "mail".to_string())]);
+        repository.add_record(record.clone()).await.unwrap();
+        repository.save().await.unwrap();
+        repository.delete(&record.id).await.unwrap();
+        repository.save().await.unwrap();
+
+        let backups = repositories.list_backups("work").await.unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(matches!(
+            repositories
+                .restore_backup("work", &backups[0].id, SecretString::from("Wrong"))
+                .await,
+            Err(ManageRepositoryError::WrongPassword)
+        ));
+        assert!(matches!(
+            repositories
+                .restore_backup("work", "../work", passwd.clone())
+                .await,
+            Err(ManageRepositoryError::DoesntExist)
+        ));
+        repositories
+            .restore_backup("work", &backups[0].id, passwd.clone())
+            .await
+            .unwrap();
+        let restored = repositories
+            .open_repository("work", passwd.clone())
+            .await
+            .unwrap();
+        assert_eq!(restored.get_records().await.unwrap(), vec![record]);
+        assert_eq!(repositories.list_backups("work").await.unwrap().len(), 3);
+        assert_eq!(
+            repositories.list_repositories().await.unwrap(),
+            vec!["work"]
+        );
+
+        repositories
+            .rename_repository("work", "office", passwd.clone())
+            .await
+            .unwrap();
+        assert!(repositories.list_backups("work").await.unwrap().is_empty());
+        assert_eq!(repositories.list_backups("office").await.unwrap().len(), 3);
+
+        let repositories = repositories.with_backup_policy(BackupPolicy::disabled());
+        let mut unversioned = repositories
+            .create_repository("scratch", passwd)
+            .await
+            .unwrap();
+        unversioned.save().await.unwrap();
+        assert!(unversioned.backups().list().unwrap().is_empty());
+
+        let backup = repositories.back_up("scratch").unwrap();
+        assert_eq!(repositories.back_up("scratch").unwrap(), backup);
+        assert_eq!(unversioned.backups().list().unwrap(), vec![backup]);
+        assert!(matches!(
+            repositories.back_up("missing"),
+            Err(ManageRepositoryError::DoesntExist)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_repository_is_rekeyed_on_save() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("legacy_repo");
+        let passwd = SecretString::from("Legacy password");
+
+        let legacy_key = KdfParams::LegacySha256.derive_key(&passwd).unwrap();
+        let record = Record::new(vec![(String::from("Login"), String::from("1"))]);
+        let legacy = serde_json::json!([
+            encrypt_string(&legacy_key, "legacy-identifier"),
+            [record.encrypt(&legacy_key)],
+        ]);
+        std::fs::write(&file, serde_json::to_vec(&legacy).unwrap()).unwrap();
+
+        let mut repo = OpenRecordsFileRepository(file.clone())
+            .open(passwd.clone())
+            .await
+            .unwrap();
+        assert!(repo.kdf().is_legacy());
+        assert_eq!(repo.get_records().await.unwrap(), vec![record.clone()]);
+
+        repo.save().await.unwrap();
+        assert!(matches!(repo.kdf(), KdfParams::Argon2id { .. }));
+
+        let reopened = OpenRecordsFileRepository(file).open(passwd).await.unwrap();
+        assert!(matches!(reopened.kdf(), KdfParams::Argon2id { .. }));
+        assert_eq!(reopened.identifier.as_str(), "legacy-identifier");
+        assert_eq!(reopened.get_records().await.unwrap(), vec![record]);
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repositories_use_distinct_salts() {
+        let tmp_dir = TempDir::new().unwrap();
+        let passwd = SecretString::from("Same password");
+
+        let first = RecordsFileRepository::new(tmp_dir.path().join("first"), passwd.clone());
+        let second = RecordsFileRepository::new(tmp_dir.path().join("second"), passwd);
+
+        assert_ne!(first.kdf(), second.kdf());
+        assert_ne!(first.key, second.key);
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_writes_current_format_header() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let passwd = SecretString::from("Passwd");
+
+        let legacy_key = KdfParams::LegacySha256.derive_key(&passwd).unwrap();
+        let legacy = serde_json::json!([encrypt_string(&legacy_key, "legacy-identifier"), []]);
+        std::fs::write(&file, serde_json::to_vec(&legacy).unwrap()).unwrap();
+
+        let mut repo = OpenRecordsFileRepository(file.clone())
+            .open(passwd.clone())
+            .await
+            .unwrap();
+        assert_eq!(repo.header().cipher_suite, CipherSuite::Aes256CtrMd5);
+        let created_at = repo.header().created_at;
+
+        repo.save().await.unwrap();
+
+        let on_disk: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+        assert_eq!(on_disk["header"]["magic"], "PasswordsKeeper");
+        assert_eq!(on_disk["header"]["format_version"], CURRENT_FORMAT_VERSION);
+        assert_eq!(on_disk["header"]["cipher_suite"], "aes256-gcm");
+
+        let reopened = OpenRecordsFileRepository(file).open(passwd).await.unwrap();
+        assert_eq!(reopened.header().cipher_suite, CipherSuite::Aes256Gcm);
+        assert_eq!(reopened.header().created_at, created_at);
+        assert!(reopened.header().modified_at >= created_at);
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_merge_persisted_keeps_changes_saved_elsewhere() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let passwd = SecretString::from("Passwd");
+        let shared = Record::new(vec![
+            (String::from("Login"), String::from("alice")),
+            (String::from("Password"), String::from("1")),
+        ]);
+        let mut repo = RecordsFileRepository::new(file.clone(), passwd.clone());
+        repo.add_record(shared.clone()).await?;
+        repo.save().await?;
+
+        let mut other = OpenRecordsFileRepository(file.clone())
+            .open(passwd.clone())
+            .await
+            .unwrap();
+        let mut renamed = shared.clone();
+        renamed.update_field("Login".to_string(), "bob")?;
+        other.update(renamed).await?;
+        let added = Record::new(vec![(String::from("Login"), String::from("carol"))]);
+        other.add_record(added.clone()).await?;
+        other.save().await?;
+
+        let mut changed = shared.clone();
+        changed.update_field("Password".to_string(), "2")?;
+        repo.update(changed).await?;
+        assert!(repo.merge_persisted().await?.is_empty());
+        repo.save().await?;
+
+        let reopened = OpenRecordsFileRepository(file).open(passwd).await.unwrap();
+        let merged = reopened.get(&shared.id).await?.unwrap();
+        assert_eq!(merged.get_field_value("Login").unwrap(), "bob");
+        assert_eq!(merged.get_field_value("Password").unwrap(), "2");
+        assert!(reopened.get(&added.id).await?.is_some());
+
+        let mut conflicting = merged.clone();
+        conflicting.update_field("Password".to_string(), "3")?;
+        other.update(conflicting).await?;
+        assert_eq!(other.merge_persisted().await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_change_password_reencrypts_repository() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let record = Record::new(vec![(String::from("Login"), String::from("1"))]);
+
+        let mut repo = RecordsFileRepository::new(file.clone(), SecretString::from("Old password"));
+        repo.add_record(record.clone()).await.unwrap();
+        repo.save().await.unwrap();
+        let old_kdf = repo.kdf().clone();
+
+        repo.change_password(
+            SecretString::from("Old password"),
+            SecretString::from("New password"),
+        )
+        .await
+        .unwrap();
+        assert_ne!(repo.kdf(), &old_kdf);
+
+        assert!(matches!(
+            OpenRecordsFileRepository(file.clone())
+                .open(SecretString::from("Old password"))
+                .await
+                .unwrap_err(),
+            RepositoryOpenError::WrongPassword
+        ));
+        let reopened = OpenRecordsFileRepository(file)
+            .open(SecretString::from("New password"))
+            .await
+            .unwrap();
+        assert_eq!(reopened.identifier, repo.identifier);
+        assert_eq!(reopened.get_records().await.unwrap(), vec![record]);
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_change_password_keeps_unsaved_changes_pending() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let saved = Record::new(vec![(String::from("Login"), String::from("saved"))]);
+        let pending = Record::new(vec![(String::from("Login"), String::from("pending"))]);
+
+        let mut repo = RecordsFileRepository::new(file.clone(), SecretString::from("Old password"));
+        repo.add_record(saved.clone()).await.unwrap();
+        repo.save().await.unwrap();
+        repo.add_record(pending.clone()).await.unwrap();
+
+        repo.change_password(
+            SecretString::from("Old password"),
+            SecretString::from("New password"),
+        )
+        .await
+        .unwrap();
+
+        let reopened = OpenRecordsFileRepository(file.clone())
+            .open(SecretString::from("New password"))
+            .await
+            .unwrap();
+        assert_eq!(reopened.get_records().await.unwrap(), vec![saved]);
+        assert!(repo.get(&pending.id).await.unwrap().is_some());
+
+        repo.save().await.unwrap();
+        let reopened = OpenRecordsFileRepository(file)
+            .open(SecretString::from("New password"))
+            .await
+            .unwrap();
+        assert_eq!(reopened.get_records().await.unwrap().len(), 2);
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_change_password_with_wrong_old_password() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+
+        let mut repo = RecordsFileRepository::new(file.clone(), SecretString::from("Old password"));
+        repo.save().await.unwrap();
+
+        let result = repo
+            .change_password(
+                SecretString::from("Wrong password"),
+                SecretString::from("New password"),
+            )
+            .await;
+
+        assert!(matches!(result, Err(ChangePasswordError::WrongPassword)));
+        OpenRecordsFileRepository(file)
+            .open(SecretString::from("Old password"))
+            .await
+            .unwrap();
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_single_blob_layout_roundtrip() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let passwd = SecretString::from("Passwd");
+
+        let mut repo = RecordsFileRepository::new(file.clone(), passwd.clone());
+        repo.set_body_layout(BodyLayout::SingleBlob {
+            compression: Compression::Deflate,
+        });
+        for idx in 0..3 {
+            repo.add_record(Record::new(vec![(
+                String::from("Login"),
+                format!("user{idx}"),
+            )]))
+            .await
+            .unwrap();
+        }
+        repo.save().await.unwrap();
+
+        let on_disk: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+        assert!(on_disk.get("records").is_none());
+        assert!(on_disk.get("body").is_some());
+
+        let reopened = OpenRecordsFileRepository(file).open(passwd).await.unwrap();
+        let mut expected = repo.get_records().await.unwrap();
+        let mut actual = reopened.get_records().await.unwrap();
+        expected.sort_by(|a, b| a.id.cmp(&b.id));
+        actual.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(actual, expected);
+        assert_eq!(reopened.body_layout(), repo.body_layout());
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_single_blob_hides_record_count() {
+        let tmp_dir = TempDir::new().unwrap();
+        let passwd = SecretString::from("Passwd");
+        let layout = BodyLayout::SingleBlob {
+            compression: Compression::None,
+        };
+
+        let mut small = RecordsFileRepository::new(tmp_dir.path().join("small"), passwd.clone());
+        small.set_body_layout(layout);
+        small
+            .add_record(Record::new(vec![(
+                String::from("Login"),
+                String::from("1"),
+            )]))
+            .await
+            .unwrap();
+        let mut larger = RecordsFileRepository::new(tmp_dir.path().join("larger"), passwd);
+        larger.set_body_layout(layout);
+        for idx in 0..5 {
+            larger
+                .add_record(Record::new(vec![(String::from("Login"), idx.to_string())]))
+                .await
+                .unwrap();
+        }
+
+        let small_dump: serde_json::Value =
+            serde_json::from_slice(&small.dump().await.unwrap()).unwrap();
+        let larger_dump: serde_json::Value =
+            serde_json::from_slice(&larger.dump().await.unwrap()).unwrap();
+        assert_eq!(
+            small_dump["body"]["data"].as_array().unwrap().len(),
+            larger_dump["body"]["data"].as_array().unwrap().len()
+        );
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repository_debug_is_redacted() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut repo = RecordsFileRepository::new(
+            tmp_dir.path().join("repo_file"),
+            SecretString::from("Master password"),
+        );
+        repo.add_record(Record::new(vec![(
+            String::from("Password"),
+            String::from("Record secret"),
+        )]))
+        .await
+        .unwrap();
+
+        let debug = format!("{repo:?}");
+
+        assert!(!debug.contains("Master password"));
+        assert!(!debug.contains("Record secret"));
+
+        tmp_dir.close().unwrap();
+    }
+
+    // Saves three records and replaces the second one on disk with garbage.
+    async fn repository_with_damaged_record(file: &std::path::Path, passwd: &SecretString) {
+        let mut repo = RecordsFileRepository::new(file.to_path_buf(), passwd.clone());
+        for idx in 0..3 {
+            repo.add_record(Record::new(vec![(String::from("Login"), idx.to_string())]))
+                .await
+                .unwrap();
+        }
+        repo.save().await.unwrap();
+
+        let mut on_disk: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(file).unwrap()).unwrap();
+        on_disk["records"][1] = serde_json::Value::String("garbage".to_string());
+        std::fs::write(file, serde_json::to_vec(&on_disk).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_open_reports_damaged_records() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let passwd = SecretString::from("Passwd");
+        repository_with_damaged_record(&file, &passwd).await;
+
+        let result = OpenRecordsFileRepository(file).open(passwd).await;
+
+        match result {
+            Err(RepositoryOpenError::DamagedRecords(damaged)) => {
+                assert_eq!(damaged.len(), 1);
+                assert_eq!(damaged[0].index, 1);
+                assert!(damaged[0].reason.starts_with("malformed"));
+            }
+            other => panic!("expected damaged records, got {other:?}"),
+        }
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recover_quarantines_damaged_records() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let passwd = SecretString::from("Passwd");
+        repository_with_damaged_record(&file, &passwd).await;
+
+        let mut repo = RecoverRecordsFileRepository(file.clone())
+            .open(passwd.clone())
+            .await
+            .unwrap();
+        assert_eq!(repo.get_records().await.unwrap().len(), 2);
+        assert_eq!(repo.damaged_records().len(), 1);
+        assert_eq!(repo.damaged_records()[0].index, 1);
+
+        repo.save().await.unwrap();
+        let reopened = OpenRecordsFileRepository(file.clone())
+            .open(passwd)
+            .await
+            .unwrap();
+        assert_eq!(reopened.get_records().await.unwrap().len(), 2);
+        assert!(reopened.damaged_records().is_empty());
+
+        let preserved: Vec<String> = serde_json::from_slice(
+            &std::fs::read(tmp_dir.path().join("repo_file.damaged")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(preserved, vec!["garbage".to_string()]);
+
+        tmp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_records_by_folder_and_tag() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        let file = tmp_dir.path().join("repo_file");
+        let passwd = SecretString::from("Passwd");
+        let mut repo = RecordsFileRepository::new(file.clone(), passwd.clone());
+
+        let mut db = Record::new(vec![(String::from("Name"), "db")]);
+        db.set_folder("work/servers");
+        db.set_tags(vec!["prod"]);
+        let mut mail = Record::new(vec![(String::from("Name"), "mail")]);
+        mail.set_folder("work");
+        mail.set_tags(vec!["mail", "prod"]);
+        let home = Record::new(vec![(String::from("Name"), "home")]);
+        for record in [db.clone(), mail.clone(), home] {
+            repo.add_record(record).await.unwrap();
+        }
+        repo.save().await.unwrap();
+
+        let repo = OpenRecordsFileRepository(file).open(passwd).await.unwrap();
+        assert_eq!(repo.get_folders().await?, vec!["work", "work/servers"]);
+        assert_eq!(repo.get_tags().await?, vec!["mail", "prod"]);
+
+        let in_servers = repo
+            .find_records(&RecordsFilter {
+                folder: Some("work/servers".to_string()),
+                tag: None,
+            })
+            .await?;
+        assert_eq!(in_servers, vec![db]);
+
+        let mut mail_records = repo
+            .find_records(&RecordsFilter {
+                folder: Some("work".to_string()),
+                tag: Some("mail".to_string()),
+            })
+            .await?;
+        assert_eq!(mail_records.pop(), Some(mail));
+        assert!(mail_records.is_empty());
+
+        tmp_dir.close().unwrap();
+
+        Ok(())
+    }
 }
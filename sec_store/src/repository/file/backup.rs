@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tempfile::NamedTempFile;
+
+use super::{file_modified_at, open_file};
+use crate::repository::{Backup, ManageRepositoryError, ManageRepositoryResult};
+use crate::secret::SecretString;
+
+/// Directory next to the repository files where their backups are kept.
+pub const BACKUPS_DIR: &str = ".backups";
+const BACKUP_EXTENSION: &str = "json";
+const DAY: u64 = 24 * 60 * 60;
+const WEEK: u64 = 7 * DAY;
+
+/// Which previous versions of a repository are kept on save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupPolicy {
+    /// The most recent versions, whatever their age.
+    pub keep_last: usize,
+    /// The newest version of each of the last days having one.
+    pub keep_daily: usize,
+    /// The newest version of each of the last weeks having one.
+    pub keep_weekly: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: 10,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+impl BackupPolicy {
+    pub fn disabled() -> Self {
+        Self {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+        }
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        *self == Self::disabled()
+    }
+
+    // `backups` are sorted newest first.
+    fn kept(&self, backups: &[Backup]) -> HashSet<String> {
+        let mut kept: HashSet<String> = backups
+            .iter()
+            .take(self.keep_last)
+            .map(|backup| backup.id.clone())
+            .collect();
+        for (period, count) in [(DAY, self.keep_daily), (WEEK, self.keep_weekly)] {
+            let mut last_period = None;
+            let mut taken = 0;
+            for backup in backups {
+                if taken == count {
+                    break;
+                }
+                let backup_period = backup.created_at / period;
+                if last_period != Some(backup_period) {
+                    last_period = Some(backup_period);
+                    kept.insert(backup.id.clone());
+                    taken += 1;
+                }
+            }
+        }
+        kept
+    }
+}
+
+// Ids are `<created_at>` or `<created_at>-<n>` for the versions saved in the same second.
+fn parse_backup_id(id: &str) -> Option<(u64, u32)> {
+    let (created_at, n) = id.split_once('-').unwrap_or((id, "0"));
+    let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(created_at) || !all_digits(n) {
+        return None;
+    }
+    Some((created_at.parse().ok()?, n.parse().ok()?))
+}
+
+/// The backups of a repository file, kept in `.backups/<file name>/` next to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backups {
+    file: PathBuf,
+    dir: PathBuf,
+}
+
+impl Backups {
+    pub fn of(file: &Path) -> Self {
+        let parent = file.parent().unwrap_or(Path::new("."));
+        let name = file.file_name().unwrap_or_default();
+        Self {
+            file: file.to_path_buf(),
+            dir: parent.join(BACKUPS_DIR).join(name),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn backup_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.{BACKUP_EXTENSION}"))
+    }
+
+    /// Newest first.
+    pub fn list(&self) -> Result<Vec<Backup>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to list backups in {:?}", self.dir))
+            }
+        };
+        let mut backups = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(BACKUP_EXTENSION) {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|id| id.to_str()) else {
+                continue;
+            };
+            if let Some((created_at, _)) = parse_backup_id(id) {
+                backups.push(Backup {
+                    id: id.to_string(),
+                    created_at,
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+        backups.sort_by_key(|backup| std::cmp::Reverse(parse_backup_id(&backup.id)));
+        Ok(backups)
+    }
+
+    // Linking is enough, saves replace the repository file instead of writing into it.
+    // Returns the id of the backup, an existing one when the version was already kept.
+    fn keep_current(&self) -> Result<String> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create backups directory {:?}", self.dir))?;
+        let created_at = file_modified_at(&self.file);
+        let current =
+            std::fs::read(&self.file).with_context(|| format!("Failed to read {:?}", self.file))?;
+        for n in 0.. {
+            let id = match n {
+                0 => created_at.to_string(),
+                n => format!("{created_at}-{n}"),
+            };
+            let path = self.backup_path(&id);
+            match std::fs::hard_link(&self.file, &path) {
+                Ok(()) => return Ok(id),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if std::fs::read(&path).is_ok_and(|kept| kept == current) {
+                        return Ok(id);
+                    }
+                }
+                Err(_) => {
+                    std::fs::write(&path, &current)
+                        .with_context(|| format!("Failed to back up {:?}", self.file))?;
+                    return Ok(id);
+                }
+            }
+        }
+        unreachable!("backup ids are unbounded")
+    }
+
+    /// Keeps the current version of the repository file before it's replaced.
+    pub(super) fn back_up(&self, policy: &BackupPolicy) -> Result<()> {
+        if policy.is_disabled() || !self.file.exists() {
+            return Ok(());
+        }
+        self.keep_current()?;
+        self.prune(policy)?;
+        Ok(())
+    }
+
+    /// Keeps the current version now, whether or not the policy backs up on save.
+    pub fn back_up_now(&self, policy: &BackupPolicy) -> ManageRepositoryResult<Backup> {
+        if !self.file.is_file() {
+            return Err(ManageRepositoryError::DoesntExist);
+        }
+        let back_up = || -> Result<Backup> {
+            let id = self.keep_current()?;
+            if !policy.is_disabled() {
+                self.prune(policy)?;
+            }
+            self.list()?
+                .into_iter()
+                .find(|backup| backup.id == id)
+                .with_context(|| format!("Backup {id} was pruned right away"))
+        };
+        back_up()
+            .with_context(|| format!("Failed to back up {:?}", self.file))
+            .map_err(ManageRepositoryError::UnexpectedError)
+    }
+
+    /// Removes the backups the policy doesn't keep and returns them.
+    pub fn prune(&self, policy: &BackupPolicy) -> Result<Vec<Backup>> {
+        let backups = self.list()?;
+        let kept = policy.kept(&backups);
+        let mut removed = Vec::new();
+        for backup in backups {
+            if !kept.contains(&backup.id) {
+                std::fs::remove_file(self.backup_path(&backup.id))
+                    .with_context(|| format!("Failed to remove backup {}", backup.id))?;
+                removed.push(backup);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Replaces the repository file with the backup `id`, which must open with `passwd`.
+    /// The replaced version is always backed up first, so a restore can be undone.
+    pub fn restore(&self, id: &str, passwd: SecretString) -> ManageRepositoryResult<()> {
+        let path = self.backup_path(id);
+        if parse_backup_id(id).is_none() || !path.is_file() {
+            return Err(ManageRepositoryError::DoesntExist);
+        }
+        open_file(path.clone(), passwd, true, None)?;
+
+        let restore = || -> Result<()> {
+            if self.file.exists() {
+                self.keep_current()?;
+            }
+            let parent = self
+                .file
+                .parent()
+                .with_context(|| format!("Failed get parent directory for {:?}", self.file))?;
+            let mut tmp_file = NamedTempFile::new_in(parent)?;
+            tmp_file.write_all(&std::fs::read(&path)?)?;
+            tmp_file.flush()?;
+            tmp_file.persist(&self.file)?;
+            Ok(())
+        };
+        restore()
+            .with_context(|| format!("Failed to restore backup {id} of {:?}", self.file))
+            .map_err(ManageRepositoryError::UnexpectedError)
+    }
+
+    /// Moves the backups along with a renamed repository file, unless the new name
+    /// already has backups from a deleted repository.
+    pub(super) fn move_to(&self, file: &Path) -> Result<()> {
+        let target = Self::of(file);
+        if !self.dir.exists() || target.dir.exists() {
+            return Ok(());
+        }
+        std::fs::rename(&self.dir, &target.dir)
+            .with_context(|| format!("Failed to move backups {:?}", self.dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_backup_id, BackupPolicy, DAY, WEEK};
+    use crate::repository::Backup;
+
+    fn backup(created_at: u64) -> Backup {
+        Backup {
+            id: created_at.to_string(),
+            created_at,
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn test_backup_ids_are_validated() {
+        assert_eq!(parse_backup_id("1700000000"), Some((1_700_000_000, 0)));
+        assert_eq!(parse_backup_id("1700000000-2"), Some((1_700_000_000, 2)));
+        assert_eq!(parse_backup_id("../secret"), None);
+        assert_eq!(parse_backup_id("1700000000-"), None);
+        assert_eq!(parse_backup_id(""), None);
+    }
+
+    #[test]
+    fn test_policy_keeps_last_daily_and_weekly_versions() {
+        // Halfway through the fourth day of a week.
+        let now = 100 * WEEK + 3 * DAY + DAY / 2;
+        // Five versions today, then one a day for three weeks.
+        let mut backups: Vec<Backup> = (0..5).map(|i| backup(now - i * 60)).collect();
+        backups.extend((1..21).map(|day| backup(now - day * DAY)));
+        let policy = BackupPolicy {
+            keep_last: 3,
+            keep_daily: 4,
+            keep_weekly: 3,
+        };
+
+        let kept = policy.kept(&backups);
+
+        let mut expected: Vec<u64> = vec![now, now - 60, now - 120];
+        expected.extend([1, 2, 3].map(|day| now - day * DAY));
+        // The newest versions of the two previous weeks.
+        expected.extend([4, 11].map(|day| now - day * DAY));
+        let mut kept: Vec<u64> = kept.iter().map(|id| id.parse().unwrap()).collect();
+        kept.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(kept, expected);
+        assert!(BackupPolicy::disabled().kept(&backups).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::{Read, Write};
+
+use anyhow::{bail, Context, Result};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::record::Record;
+
+// The smallest size class, every padded body is a power of two above it.
+const MIN_BUCKET: usize = 4096;
+const LEN_PREFIX: usize = size_of::<u64>();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    None,
+    #[default]
+    Deflate,
+}
+
+/// Serializes, compresses and pads the records so that the encrypted body
+/// only reveals a power-of-two size class.
+pub(super) fn pack(records: &[&Record], compression: Compression) -> Result<Zeroizing<Vec<u8>>> {
+    let json = Zeroizing::new(serde_json::to_vec(records).context("Failed records serialization")?);
+    let payload = match compression {
+        Compression::None => json,
+        Compression::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+            encoder.write_all(&json)?;
+            Zeroizing::new(encoder.finish().context("Failed records compression")?)
+        }
+    };
+
+    let bucket = (LEN_PREFIX + payload.len())
+        .next_power_of_two()
+        .max(MIN_BUCKET);
+    let mut padded = Zeroizing::new(Vec::with_capacity(bucket));
+    padded.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    padded.extend_from_slice(&payload);
+    padded.resize(bucket, 0);
+    Ok(padded)
+}
+
+pub(super) fn unpack(padded: &[u8], compression: Compression) -> Result<Vec<Record>> {
+    let Some((len, rest)) = padded.split_first_chunk::<LEN_PREFIX>() else {
+        bail!("Repository body is too short");
+    };
+    let payload = usize::try_from(u64::from_le_bytes(*len))
+        .ok()
+        .and_then(|len| rest.get(..len))
+        .context("Repository body length is out of bounds")?;
+
+    let json = match compression {
+        Compression::None => Zeroizing::new(payload.to_vec()),
+        Compression::Deflate => {
+            let mut json = Zeroizing::new(Vec::new());
+            DeflateDecoder::new(payload)
+                .read_to_end(&mut json)
+                .context("Failed records decompression")?;
+            json
+        }
+    };
+    serde_json::from_slice(&json).context("Failed records deserialization")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, unpack, Compression, MIN_BUCKET};
+    use crate::record::Record;
+
+    fn records(count: usize) -> Vec<Record> {
+        (0..count)
+            .map(|idx| {
+                Record::new(vec![
+                    ("name".to_string(), format!("record {idx}")),
+                    ("password".to_string(), "x".repeat(idx % 50)),
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pack_roundtrip() {
+        for compression in [Compression::None, Compression::Deflate] {
+            let records = records(10);
+            let packed = pack(&records.iter().collect::<Vec<_>>(), compression).unwrap();
+
+            assert_eq!(unpack(&packed, compression).unwrap(), records);
+        }
+    }
+
+    #[test]
+    fn test_pack_pads_to_size_class() {
+        let one = pack(&records(1).iter().collect::<Vec<_>>(), Compression::None).unwrap();
+        let five = pack(&records(5).iter().collect::<Vec<_>>(), Compression::None).unwrap();
+        let many = pack(&records(200).iter().collect::<Vec<_>>(), Compression::None).unwrap();
+
+        assert_eq!(one.len(), MIN_BUCKET);
+        assert_eq!(five.len(), MIN_BUCKET);
+        assert!(many.len() > MIN_BUCKET);
+        assert!(many.len().is_power_of_two());
+    }
+
+    #[test]
+    fn test_unpack_rejects_bad_length() {
+        let mut packed = pack(&records(1).iter().collect::<Vec<_>>(), Compression::None).unwrap();
+        packed[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(unpack(&packed, Compression::None).is_err());
+        assert!(unpack(&[0; 4], Compression::None).is_err());
+    }
+}
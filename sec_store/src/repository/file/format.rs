@@ -0,0 +1,393 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::blob::Compression;
+use crate::cipher::{EncryptedData, KdfParams};
+use crate::record::{unix_now, EncryptedRecord};
+
+pub const MAGIC: &str = "PasswordsKeeper";
+pub const CURRENT_FORMAT_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CipherSuite {
+    Aes256Gcm,
+    // AES-CTR with an MD5 of the plaintext, see `cipher::EncryptedDataV1`.
+    Aes256CtrMd5,
+}
+
+/// How records are stored in the repository body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BodyLayout {
+    /// Every record is encrypted on its own, the number of records and
+    /// their sizes are visible in the file.
+    #[default]
+    PerRecord,
+    /// All records are encrypted as one padded payload.
+    SingleBlob { compression: Compression },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepositoryHeader {
+    pub magic: String,
+    pub format_version: u32,
+    pub cipher_suite: CipherSuite,
+    pub kdf: KdfParams,
+    pub body_layout: BodyLayout,
+    pub created_at: u64,
+    pub modified_at: u64,
+}
+
+impl RepositoryHeader {
+    pub fn new(kdf: KdfParams) -> RepositoryHeader {
+        let now = unix_now();
+        RepositoryHeader {
+            magic: MAGIC.to_string(),
+            format_version: CURRENT_FORMAT_VERSION,
+            cipher_suite: CipherSuite::Aes256Gcm,
+            kdf,
+            body_layout: BodyLayout::default(),
+            created_at: now,
+            modified_at: now,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct RawRepository {
+    pub header: RepositoryHeader,
+    pub identifier: EncryptedData,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<EncryptedRecord>,
+    // Only present for `BodyLayout::SingleBlob`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<EncryptedData>,
+}
+
+pub(super) struct MigrationContext {
+    // Used as created/modified time for files written before the header existed.
+    pub fallback_timestamp: u64,
+}
+
+type Migration = fn(Value, &MigrationContext) -> Result<Value>;
+
+// `MIGRATIONS[n]` upgrades a document of format version `n` to `n + 1`.
+//
+// Version history:
+// 0 - `[identifier, [records]]`, unsalted SHA-256 key, AES-CTR + MD5.
+// 1 - `{kdf, identifier, records}`, the key derivation is stored in the file.
+// 2 - `{header, identifier, records}`, explicit header with magic and version.
+// 3 - `{header, identifier, records | body}`, the header names the body layout.
+const MIGRATIONS: [Migration; CURRENT_FORMAT_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+pub(super) fn read_repository(data: &[u8], ctx: &MigrationContext) -> Result<RawRepository> {
+    let mut document: Value =
+        serde_json::from_slice(data).context("Repository file is not valid JSON")?;
+
+    let version = detect_version(&document)?;
+    for migration in &MIGRATIONS[version as usize..] {
+        document = migration(document, ctx)?;
+    }
+
+    let raw: RawRepository =
+        serde_json::from_value(document).context("Failed repository deserialization")?;
+    if raw.header.magic != MAGIC {
+        bail!("Unexpected repository magic {:?}", raw.header.magic);
+    }
+    raw.header
+        .kdf
+        .check_cost()
+        .context("Repository header is malformed")?;
+    match raw.header.body_layout {
+        BodyLayout::PerRecord if raw.body.is_some() => {
+            bail!("Per-record repository must not have a body")
+        }
+        BodyLayout::SingleBlob { .. } if raw.body.is_none() || !raw.records.is_empty() => {
+            bail!("Single blob repository must have a body and no records")
+        }
+        _ => Ok(raw),
+    }
+}
+
+pub(super) fn detect_version(document: &Value) -> Result<u32> {
+    match document {
+        Value::Array(_) => Ok(0),
+        Value::Object(object) => match object.get("header") {
+            Some(header) => {
+                let version = header
+                    .get("format_version")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| anyhow!("Repository header has no format version"))?;
+                let version = u32::try_from(version)
+                    .map_err(|_| anyhow!("Unsupported repository format version {version}"))?;
+                if version > CURRENT_FORMAT_VERSION {
+                    bail!(
+                        "Repository format version {version} is newer than supported {CURRENT_FORMAT_VERSION}"
+                    );
+                }
+                Ok(version)
+            }
+            None if object.contains_key("kdf") => Ok(1),
+            None => bail!("Unknown repository format"),
+        },
+        _ => bail!("Unknown repository format"),
+    }
+}
+
+fn migrate_v0_to_v1(document: Value, _ctx: &MigrationContext) -> Result<Value> {
+    let Value::Array(mut items) = document else {
+        bail!("Format version 0 repository must be a JSON array");
+    };
+    if items.len() != 2 {
+        bail!("Format version 0 repository must have exactly 2 items");
+    }
+    let records = items.pop().expect("length checked above");
+    let identifier = items.pop().expect("length checked above");
+
+    Ok(json!({
+        "kdf": serde_json::to_value(KdfParams::LegacySha256)?,
+        "identifier": identifier,
+        "records": records,
+    }))
+}
+
+fn migrate_v1_to_v2(document: Value, ctx: &MigrationContext) -> Result<Value> {
+    let Value::Object(mut object) = document else {
+        bail!("Format version 1 repository must be a JSON object");
+    };
+    let kdf = take_field(&mut object, "kdf")?;
+    let header = json!({
+        "magic": MAGIC,
+        "format_version": 2,
+        // Version 1 files could still contain AES-CTR records, the suite is
+        // switched to AES-GCM once everything is re-encrypted on save.
+        "cipher_suite": CipherSuite::Aes256CtrMd5,
+        "kdf": kdf,
+        "created_at": ctx.fallback_timestamp,
+        "modified_at": ctx.fallback_timestamp,
+    });
+
+    Ok(json!({
+        "header": header,
+        "identifier": take_field(&mut object, "identifier")?,
+        "records": take_field(&mut object, "records")?,
+    }))
+}
+
+fn migrate_v2_to_v3(document: Value, _ctx: &MigrationContext) -> Result<Value> {
+    let Value::Object(mut object) = document else {
+        bail!("Format version 2 repository must be a JSON object");
+    };
+    let Some(Value::Object(header)) = object.get_mut("header") else {
+        bail!("Format version 2 repository must have a header object");
+    };
+    header.insert("format_version".to_string(), json!(3));
+    header.insert(
+        "body_layout".to_string(),
+        serde_json::to_value(BodyLayout::PerRecord)?,
+    );
+    Ok(Value::Object(object))
+}
+
+fn take_field(object: &mut Map<String, Value>, name: &str) -> Result<Value> {
+    object
+        .remove(name)
+        .ok_or_else(|| anyhow!("Repository has no \"{name}\" field"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        detect_version, read_repository, BodyLayout, CipherSuite, MigrationContext,
+        CURRENT_FORMAT_VERSION, MAGIC,
+    };
+    use crate::cipher::{decrypt_string, encrypt_string, test_key, KdfParams};
+    use crate::repository::file::blob::Compression;
+
+    fn ctx() -> MigrationContext {
+        MigrationContext {
+            fallback_timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_reads_format_version_0() {
+        let key = KdfParams::LegacySha256.derive_key("password").unwrap();
+        let document = json!([encrypt_string(&key, "repo-id"), ["record"]]);
+        let data = serde_json::to_vec(&document).unwrap();
+
+        let raw = read_repository(&data, &ctx()).unwrap();
+
+        assert_eq!(raw.header.magic, MAGIC);
+        assert_eq!(raw.header.format_version, CURRENT_FORMAT_VERSION);
+        assert_eq!(raw.header.kdf, KdfParams::LegacySha256);
+        assert_eq!(raw.header.cipher_suite, CipherSuite::Aes256CtrMd5);
+        assert_eq!(raw.header.created_at, 1_700_000_000);
+        assert_eq!(raw.records, vec!["record".to_string()]);
+        assert_eq!(decrypt_string(&key, raw.identifier).unwrap(), "repo-id");
+    }
+
+    #[test]
+    fn test_reads_format_version_1() {
+        let kdf = KdfParams::argon2id(crate::cipher::Argon2Cost {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        });
+        let key = kdf.derive_key("password").unwrap();
+        let document = json!({
+            "kdf": kdf,
+            "identifier": encrypt_string(&key, "repo-id"),
+            "records": ["first", "second"],
+        });
+        let data = serde_json::to_vec(&document).unwrap();
+
+        let raw = read_repository(&data, &ctx()).unwrap();
+
+        assert_eq!(raw.header.format_version, CURRENT_FORMAT_VERSION);
+        assert_eq!(raw.header.kdf, kdf);
+        assert_eq!(raw.header.modified_at, 1_700_000_000);
+        assert_eq!(raw.records.len(), 2);
+        assert_eq!(decrypt_string(&key, raw.identifier).unwrap(), "repo-id");
+    }
+
+    #[test]
+    fn test_excessive_kdf_cost_is_refused() {
+        let key = test_key("password");
+        let mut kdf = serde_json::to_value(KdfParams::argon2id(crate::cipher::Argon2Cost {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        }))
+        .unwrap();
+        kdf["memory_kib"] = json!(u32::MAX);
+        let document = json!({
+            "kdf": kdf,
+            "identifier": encrypt_string(&key, "repo-id"),
+            "records": [],
+        });
+
+        let result = read_repository(&serde_json::to_vec(&document).unwrap(), &ctx());
+
+        assert!(matches!(result, Err(err) if err.to_string() == "Repository header is malformed"));
+    }
+
+    #[test]
+    fn test_reads_format_version_2() {
+        let key = test_key("password");
+        let document = json!({
+            "header": {
+                "magic": MAGIC,
+                "format_version": 2,
+                "cipher_suite": "aes256-gcm",
+                "kdf": {"algorithm": "legacy_sha256"},
+                "created_at": 10,
+                "modified_at": 20,
+            },
+            "identifier": encrypt_string(&key, "repo-id"),
+            "records": [],
+        });
+        let data = serde_json::to_vec(&document).unwrap();
+
+        let raw = read_repository(&data, &ctx()).unwrap();
+
+        assert_eq!(raw.header.format_version, CURRENT_FORMAT_VERSION);
+        assert_eq!(raw.header.cipher_suite, CipherSuite::Aes256Gcm);
+        assert_eq!(raw.header.body_layout, BodyLayout::PerRecord);
+        assert_eq!(raw.header.created_at, 10);
+        assert_eq!(raw.header.modified_at, 20);
+        assert_eq!(decrypt_string(&key, raw.identifier).unwrap(), "repo-id");
+    }
+
+    #[test]
+    fn test_reads_format_version_3() {
+        let key = test_key("password");
+        let document = json!({
+            "header": {
+                "magic": MAGIC,
+                "format_version": 3,
+                "cipher_suite": "aes256-gcm",
+                "kdf": {"algorithm": "legacy_sha256"},
+                "body_layout": {"kind": "single_blob", "compression": "deflate"},
+                "created_at": 10,
+                "modified_at": 20,
+            },
+            "identifier": encrypt_string(&key, "repo-id"),
+            "body": encrypt_string(&key, "body"),
+        });
+        let data = serde_json::to_vec(&document).unwrap();
+
+        let raw = read_repository(&data, &ctx()).unwrap();
+
+        assert_eq!(
+            raw.header.body_layout,
+            BodyLayout::SingleBlob {
+                compression: Compression::Deflate
+            }
+        );
+        assert!(raw.records.is_empty());
+        assert_eq!(decrypt_string(&key, raw.body.unwrap()).unwrap(), "body");
+    }
+
+    #[test]
+    fn test_rejects_body_not_matching_layout() {
+        let key = test_key("password");
+        let document = json!({
+            "header": {
+                "magic": MAGIC,
+                "format_version": 3,
+                "cipher_suite": "aes256-gcm",
+                "kdf": {"algorithm": "legacy_sha256"},
+                "body_layout": {"kind": "single_blob", "compression": "none"},
+                "created_at": 0,
+                "modified_at": 0,
+            },
+            "identifier": encrypt_string(&key, "repo-id"),
+            "records": ["record"],
+        });
+
+        assert!(read_repository(&serde_json::to_vec(&document).unwrap(), &ctx()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_newer_format_version() {
+        let document = json!({
+            "header": {"format_version": CURRENT_FORMAT_VERSION + 1},
+        });
+
+        let err = detect_version(&document).unwrap_err();
+
+        assert!(err.to_string().contains("newer than supported"));
+    }
+
+    #[test]
+    fn test_rejects_foreign_magic() {
+        let key = test_key("password");
+        let document = json!({
+            "header": {
+                "magic": "SomethingElse",
+                "format_version": 2,
+                "cipher_suite": "aes256-gcm",
+                "kdf": {"algorithm": "legacy_sha256"},
+                "created_at": 0,
+                "modified_at": 0,
+            },
+            "identifier": encrypt_string(&key, "repo-id"),
+            "records": [],
+        });
+
+        assert!(read_repository(&serde_json::to_vec(&document).unwrap(), &ctx()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_documents() {
+        assert!(read_repository(b"{}", &ctx()).is_err());
+        assert!(read_repository(b"42", &ctx()).is_err());
+        assert!(read_repository(b"not json", &ctx()).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::RecordsRepository;
+use crate::record::{FieldKind, FieldName, FieldValue, Record, RecordId};
+
+/// The part of a record changed differently by both sides.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPart {
+    Field(FieldName),
+    Tags,
+    Folder,
+    /// Deleted by one side and changed by the other.
+    Deletion,
+}
+
+impl Display for ConflictPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConflictPart::Field(name) => write!(f, "{name}"),
+            ConflictPart::Tags => write!(f, "tags"),
+            ConflictPart::Folder => write!(f, "folder"),
+            ConflictPart::Deletion => write!(f, "deletion"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeSide {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub record_id: RecordId,
+    pub part: ConflictPart,
+    /// `None` when the side deleted the record.
+    pub local: Option<Record>,
+    pub remote: Option<Record>,
+}
+
+impl MergeConflict {
+    fn side(&self, side: MergeSide) -> Option<&Record> {
+        match side {
+            MergeSide::Local => self.local.as_ref(),
+            MergeSide::Remote => self.remote.as_ref(),
+        }
+    }
+
+    /// Applies `side` of the conflict to the merged record, `None` means deleted.
+    pub fn resolve(&self, merged: Option<Record>, side: MergeSide) -> Option<Record> {
+        let chosen = self.side(side);
+        if self.part == ConflictPart::Deletion {
+            return chosen.cloned();
+        }
+        let (Some(mut record), Some(chosen)) = (merged.clone(), chosen) else {
+            return merged;
+        };
+        match &self.part {
+            ConflictPart::Field(name) => match chosen.get_field(name) {
+                Some(field) => record.put_field(field.clone()),
+                None => record.remove_field(name),
+            },
+            ConflictPart::Tags => record.set_tags(chosen.tags()),
+            ConflictPart::Folder => record.set_folder(chosen.folder().unwrap_or_default()),
+            ConflictPart::Deletion => {}
+        }
+        Some(record)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordsMerge {
+    /// The merged records, conflicting parts keep the local side.
+    pub records: Vec<Record>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+enum Pick {
+    Local,
+    Remote,
+    Conflict,
+}
+
+// `base` is `None` when the record didn't exist in the base.
+fn pick<T: PartialEq>(base: Option<T>, local: T, remote: T) -> Pick {
+    if local == remote || base.as_ref() == Some(&remote) {
+        Pick::Local
+    } else if base.as_ref() == Some(&local) {
+        Pick::Remote
+    } else {
+        Pick::Conflict
+    }
+}
+
+fn field_content<'a>(record: &'a Record, name: &str) -> Option<(FieldKind, &'a FieldValue)> {
+    record
+        .get_field(name)
+        .map(|field| (field.kind, &field.value))
+}
+
+// Timestamps and field history don't count as changes.
+fn same_content(a: &Record, b: &Record) -> bool {
+    a.get_fields().len() == b.get_fields().len()
+        && a.get_fields()
+            .iter()
+            .all(|field| field_content(b, &field.name) == Some((field.kind, &field.value)))
+        && a.tags() == b.tags()
+        && a.folder() == b.folder()
+}
+
+fn merge_record(
+    base: Option<&Record>,
+    local: &Record,
+    remote: &Record,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Record {
+    let mut merged = local.clone();
+    // Every change bumps the revision, the remote side didn't touch the record since `base`.
+    if same_content(local, remote) || base.is_some_and(|base| base.revision() == remote.revision())
+    {
+        return merged;
+    }
+    let mut conflict = |part| {
+        conflicts.push(MergeConflict {
+            record_id: local.id.clone(),
+            part,
+            local: Some(local.clone()),
+            remote: Some(remote.clone()),
+        })
+    };
+
+    let mut names: Vec<&FieldName> = local.get_fields().iter().map(|f| &f.name).collect();
+    for field in remote.get_fields() {
+        if !names.contains(&&field.name) {
+            names.push(&field.name);
+        }
+    }
+    for name in names {
+        let base_content = base.map(|base| field_content(base, name));
+        match pick(
+            base_content,
+            field_content(local, name),
+            field_content(remote, name),
+        ) {
+            Pick::Local => {}
+            Pick::Remote => match remote.get_field(name) {
+                Some(field) => merged.put_field(field.clone()),
+                None => merged.remove_field(name),
+            },
+            Pick::Conflict => conflict(ConflictPart::Field(name.clone())),
+        }
+    }
+    match pick(base.map(Record::tags), local.tags(), remote.tags()) {
+        Pick::Local => {}
+        Pick::Remote => merged.set_tags(remote.tags()),
+        Pick::Conflict => conflict(ConflictPart::Tags),
+    }
+    match pick(base.map(Record::folder), local.folder(), remote.folder()) {
+        Pick::Local => {}
+        Pick::Remote => merged.set_folder(remote.folder().unwrap_or_default()),
+        Pick::Conflict => conflict(ConflictPart::Folder),
+    }
+    merged
+}
+
+/// Three-way merges two copies of a repository that diverged from `base`.
+/// Records are matched by id, changes made by one side only are taken as they are
+/// and every part changed differently by both sides is reported as a conflict.
+pub fn merge_records(base: &[Record], local: &[Record], remote: &[Record]) -> RecordsMerge {
+    fn by_id(records: &[Record]) -> HashMap<&RecordId, &Record> {
+        records.iter().map(|record| (&record.id, record)).collect()
+    }
+    let (base_records, local_records, remote_records) = (by_id(base), by_id(local), by_id(remote));
+
+    let mut ids: Vec<&RecordId> = local.iter().map(|record| &record.id).collect();
+    ids.extend(
+        remote
+            .iter()
+            .map(|record| &record.id)
+            .filter(|id| !local_records.contains_key(id)),
+    );
+
+    let mut merge = RecordsMerge::default();
+    for id in ids {
+        let base = base_records.get(id).copied();
+        let (local, remote) = (
+            local_records.get(id).copied(),
+            remote_records.get(id).copied(),
+        );
+        let record = match (local, remote) {
+            (Some(local), Some(remote)) => {
+                Some(merge_record(base, local, remote, &mut merge.conflicts))
+            }
+            (Some(kept), None) | (None, Some(kept)) => match base {
+                None => Some(kept.clone()),
+                // The other side deleted it without knowing about the change.
+                Some(base) if !same_content(base, kept) => {
+                    merge.conflicts.push(MergeConflict {
+                        record_id: id.clone(),
+                        part: ConflictPart::Deletion,
+                        local: local.cloned(),
+                        remote: remote.cloned(),
+                    });
+                    local.cloned()
+                }
+                Some(_) => None,
+            },
+            (None, None) => None,
+        };
+        merge.records.extend(record);
+    }
+    merge
+}
+
+/// Applies `side` of `conflict` to the records of `repository`.
+pub async fn resolve_conflict<T>(
+    repository: &mut T,
+    conflict: &MergeConflict,
+    side: MergeSide,
+) -> Result<()>
+where
+    T: RecordsRepository,
+{
+    let merged = repository.get(&conflict.record_id).await?;
+    let exists = merged.is_some();
+    match (conflict.resolve(merged, side), exists) {
+        (Some(record), true) => repository.update(record).await?,
+        (Some(record), false) => repository.add_record(record).await?,
+        (None, true) => repository.delete(&conflict.record_id).await?,
+        (None, false) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_records, ConflictPart, MergeSide};
+    use crate::record::{Record, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+
+    fn record(name: &str) -> Record {
+        Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), name.to_string()),
+            (RECORD_LOGIN_FIELD.to_string(), "alice".to_string()),
+            (RECORD_PASSWD_FIELD.to_string(), "secret".to_string()),
+        ])
+    }
+
+    fn updated(record: &Record, field: &str, value: &str) -> Record {
+        let mut record = record.clone();
+        record.update_field(field.to_string(), value).unwrap();
+        record
+    }
+
+    #[test]
+    fn test_merge_takes_changes_of_both_sides() {
+        let (mail, bank, git) = (record("Mail"), record("Bank"), record("Git"));
+        let base = vec![mail.clone(), bank.clone(), git.clone()];
+        let added_locally = record("Wiki");
+        let added_remotely = record("Chat");
+        let mut tagged = mail.clone();
+        tagged.set_tags(["personal"]);
+        let local = vec![
+            updated(&mail, RECORD_PASSWD_FIELD, "local"),
+            bank.clone(),
+            added_locally.clone(),
+        ];
+        let remote = vec![
+            updated(&tagged, RECORD_LOGIN_FIELD, "bob"),
+            git.clone(),
+            added_remotely.clone(),
+        ];
+
+        let merge = merge_records(&base, &local, &remote);
+
+        assert!(merge.conflicts.is_empty());
+        let ids: Vec<&str> = merge.records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec![&mail.id, &added_locally.id, &added_remotely.id]);
+        let merged = &merge.records[0];
+        assert_eq!(
+            merged.get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "local"
+        );
+        assert_eq!(merged.get_field_value(RECORD_LOGIN_FIELD).unwrap(), "bob");
+        assert_eq!(merged.tags(), ["personal"]);
+    }
+
+    #[test]
+    fn test_merge_reports_conflicts() {
+        let (mail, bank) = (record("Mail"), record("Bank"));
+        let base = vec![mail.clone(), bank.clone()];
+        let local = vec![
+            updated(&mail, RECORD_PASSWD_FIELD, "local"),
+            updated(&bank, RECORD_LOGIN_FIELD, "carol"),
+        ];
+        let remote = vec![updated(&mail, RECORD_PASSWD_FIELD, "remote")];
+
+        let merge = merge_records(&base, &local, &remote);
+
+        let parts: Vec<&ConflictPart> = merge.conflicts.iter().map(|c| &c.part).collect();
+        assert_eq!(
+            parts,
+            vec![
+                &ConflictPart::Field(RECORD_PASSWD_FIELD.to_string()),
+                &ConflictPart::Deletion
+            ]
+        );
+        assert_eq!(merge.records, local);
+
+        let password = &merge.conflicts[0];
+        let resolved = password
+            .resolve(Some(merge.records[0].clone()), MergeSide::Remote)
+            .unwrap();
+        assert_eq!(
+            resolved.get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "remote"
+        );
+        let deletion = &merge.conflicts[1];
+        assert_eq!(
+            deletion.resolve(Some(merge.records[1].clone()), MergeSide::Remote),
+            None
+        );
+        assert_eq!(
+            deletion.resolve(Some(merge.records[1].clone()), MergeSide::Local),
+            Some(merge.records[1].clone())
+        );
+    }
+}
@@ -1,11 +1,16 @@
 pub mod file;
+pub mod merge;
 pub mod remote;
+pub mod share;
 
 use std::fmt::{Debug, Display};
 
-use crate::record::{Record, RecordId};
+use crate::record::{Record, RecordId, RecordsFilter};
+use crate::repository::merge::MergeConflict;
+use crate::secret::SecretString;
 use anyhow::{Error, Result};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -37,11 +42,75 @@ pub enum CreateRepositoryError {
 }
 pub type CreateRepositoryResult<T> = Result<T, CreateRepositoryError>;
 
+#[derive(Debug, Error)]
+pub enum ManageRepositoryError {
+    #[error("Wrong password")]
+    WrongPassword,
+    #[error("Repository doesn't exist")]
+    DoesntExist,
+    #[error("Repository already exists")]
+    RepositoryAlreadyExists,
+    /// The repository is open in a session that would keep writing to the old file.
+    #[error("Repository is in use")]
+    RepositoryInUse,
+    #[error("Invalid repository name: {0}")]
+    InvalidRepositoryName(String),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(Error),
+}
+pub type ManageRepositoryResult<T> = Result<T, ManageRepositoryError>;
+
+impl From<RepositoryOpenError> for ManageRepositoryError {
+    fn from(error: RepositoryOpenError) -> Self {
+        match error {
+            RepositoryOpenError::WrongPassword => ManageRepositoryError::WrongPassword,
+            RepositoryOpenError::DoesntExist => ManageRepositoryError::DoesntExist,
+            RepositoryOpenError::InvalidRepositoryName(name) => {
+                ManageRepositoryError::InvalidRepositoryName(name)
+            }
+            err => ManageRepositoryError::UnexpectedError(anyhow::anyhow!(err.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ChangePasswordError {
+    #[error("Wrong password")]
+    WrongPassword,
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(Error),
+}
+pub type ChangePasswordResult<T> = Result<T, ChangePasswordError>;
+
+/// A stored record that can't be decrypted or parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamagedRecord {
+    /// Position of the record in the repository file.
+    pub index: usize,
+    pub reason: String,
+}
+
+impl Display for DamagedRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} ({})", self.index, self.reason)
+    }
+}
+
+/// A previous version of a repository, kept when it was replaced by a save.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    pub id: String,
+    /// When the version was saved, unix time.
+    pub created_at: u64,
+    pub size: u64,
+}
+
 #[derive(Debug, Error)]
 pub enum RepositoryOpenError {
     WrongPassword,
     DoesntExist,
     InvalidRepositoryName(String),
+    DamagedRecords(Vec<DamagedRecord>),
     OpenError(anyhow::Error),
 }
 pub type OpenResult<T> = Result<T, RepositoryOpenError>;
@@ -54,6 +123,9 @@ impl Display for RepositoryOpenError {
             RepositoryOpenError::InvalidRepositoryName(name) => {
                 write!(f, "InvalidRepositoryName({name})")
             }
+            RepositoryOpenError::DamagedRecords(records) => {
+                write!(f, "DamagedRecords({})", records.len())
+            }
             RepositoryOpenError::OpenError(err) => {
                 write!(f, "OpenError({}, {})", err, err.root_cause())
             }
@@ -74,6 +146,63 @@ pub trait RecordsRepository: Debug + Clone + Sync + Send + 'static {
     async fn delete(&mut self, record_id: &RecordId) -> UpdateResult<()>;
     async fn add_record(&mut self, record: Record) -> AddResult<()>;
     async fn dump(&self) -> Result<Vec<u8>>;
+    async fn find_records(&self, filter: &RecordsFilter) -> Result<Vec<Record>> {
+        Ok(self
+            .get_records()
+            .await?
+            .into_iter()
+            .filter(|record| filter.matches(record))
+            .collect())
+    }
+    /// Every tag used by the records, sorted.
+    async fn get_tags(&self) -> Result<Vec<String>> {
+        let mut tags = self
+            .get_records()
+            .await?
+            .iter()
+            .flat_map(|record| record.tags().to_vec())
+            .collect::<Vec<String>>();
+        tags.sort();
+        tags.dedup();
+        Ok(tags)
+    }
+    /// Every folder holding records along with its parent folders, sorted.
+    async fn get_folders(&self) -> Result<Vec<String>> {
+        let mut folders = Vec::new();
+        for record in self.get_records().await? {
+            if let Some(folder) = record.folder() {
+                let mut end = folder.len();
+                loop {
+                    folders.push(folder[..end].to_string());
+                    match folder[..end].rfind('/') {
+                        Some(idx) => end = idx,
+                        None => break,
+                    }
+                }
+            }
+        }
+        folders.sort();
+        folders.dedup();
+        Ok(folders)
+    }
+    /// Whether the repository is encrypted with `passwd`, which exports must not reuse.
+    fn is_password(&self, passwd: &str) -> bool;
+    /// Records skipped when the repository was opened in recovery mode.
+    fn damaged_records(&self) -> Vec<DamagedRecord> {
+        Vec::new()
+    }
+    /// Three-way merges the changes saved elsewhere since the last `save` into the pending
+    /// ones. Conflicting parts keep the pending side and are returned for resolution.
+    async fn merge_persisted(&mut self) -> Result<Vec<MergeConflict>> {
+        Ok(Vec::new())
+    }
+    /// Re-encrypts the persisted repository with `new_passwd`.
+    /// Unsaved changes stay pending and are written with the new key on `save`.
+    async fn change_password(
+        &mut self,
+        old_passwd: SecretString,
+        new_passwd: SecretString,
+    ) -> ChangePasswordResult<()>;
 }
 
 #[async_trait]
@@ -81,7 +210,7 @@ pub trait OpenRepository<T>
 where
     T: RecordsRepository,
 {
-    async fn open(self, passwd: String) -> OpenResult<T>;
+    async fn open(self, passwd: SecretString) -> OpenResult<T>;
 }
 
 #[async_trait]
@@ -92,7 +221,31 @@ where
     async fn create_repository(
         &self,
         repository_name: &str,
-        passwd: String,
+        passwd: SecretString,
     ) -> CreateRepositoryResult<T>;
-    async fn open_repository(&self, repository_name: &str, passwd: String) -> OpenResult<T>;
+    async fn open_repository(&self, repository_name: &str, passwd: SecretString) -> OpenResult<T>;
+    /// Names of the existing repositories, sorted.
+    async fn list_repositories(&self) -> Result<Vec<String>>;
+    async fn rename_repository(
+        &self,
+        repository_name: &str,
+        new_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()>;
+    /// Moves the repository to the trash, where it can still be recovered by hand.
+    async fn delete_repository(
+        &self,
+        repository_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()>;
+    /// Backups of the repository, newest first.
+    async fn list_backups(&self, repository_name: &str) -> ManageRepositoryResult<Vec<Backup>>;
+    /// Replaces the repository with a backup opening with `passwd`,
+    /// the replaced version is backed up first.
+    async fn restore_backup(
+        &self,
+        repository_name: &str,
+        backup_id: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()>;
 }
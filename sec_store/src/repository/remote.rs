@@ -5,21 +5,54 @@ use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use reqwest::{Client, RequestBuilder, StatusCode};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use super::merge::MergeConflict;
+use super::share::RecordSelection;
 use super::{
-    AddRecordError, AddResult, CreateRepositoryError, CreateRepositoryResult, OpenResult,
-    RecordsRepository, RepositoriesSource, RepositoryOpenError, UpdateRecordError, UpdateResult,
+    AddRecordError, AddResult, Backup, ChangePasswordError, ChangePasswordResult,
+    CreateRepositoryError, CreateRepositoryResult, ManageRepositoryError, ManageRepositoryResult,
+    OpenResult, RecordsRepository, RepositoriesSource, RepositoryOpenError, UpdateRecordError,
+    UpdateResult,
 };
+use crate::export::ExportFormat;
 use crate::record::{Record, RecordId};
+use crate::secret::SecretString;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateRepositoryRequest {
-    pub password: String,
+    pub password: SecretString,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenRepositoryRequest {
-    pub password: String,
+    pub password: SecretString,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListRepositoriesResponse {
+    pub repositories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameRepositoryRequest {
+    pub new_name: String,
+    pub password: SecretString,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteRepositoryRequest {
+    pub password: SecretString,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListBackupsResponse {
+    pub backups: Vec<Backup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreBackupRequest {
+    pub password: SecretString,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,9 +70,54 @@ pub struct UpdateRecordRequest {
     pub record: Record,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub old_password: SecretString,
+    pub new_password: SecretString,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRequest {
+    pub format: ExportFormat,
+    /// Required by the encrypted export, must differ from the repository password.
+    #[serde(default)]
+    pub password: Option<SecretString>,
+    /// Exports every record when missing.
+    #[serde(default)]
+    pub record_ids: Option<Vec<RecordId>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareRecordsRequest {
+    pub repository_name: String,
+    pub password: SecretString,
+    pub selection: RecordSelection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareRecordsResponse {
+    pub records: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseResponse {
+    pub conflicts: Vec<MergeConflict>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
+    /// Records changed concurrently by another session, sent along with `409` on save.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// The session can't be saved, records were changed concurrently by another session.
+/// `RemoteRecordsRepository::rebase` brings in the other changes and returns the conflicts.
+#[derive(Debug, Error)]
+#[error("{} changes conflict with another session", .conflicts.len())]
+pub struct SaveConflictError {
+    pub conflicts: Vec<MergeConflict>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +138,8 @@ pub struct RemoteRecordsRepository {
     client: Client,
     base_url: String,
     pub session_id: String,
+    /// Kept to refuse exports reusing it, the server never returns it.
+    passwd: SecretString,
 }
 
 impl RemoteRepositoriesClient {
@@ -147,6 +227,19 @@ impl RemoteRecordsRepository {
         format!("{}{}", self.base_url, path)
     }
 
+    /// Merges the records saved by other sessions into this one, keeping its pending changes.
+    /// The returned conflicts keep the pending side until resolved, the next `save` writes them.
+    pub async fn rebase(&mut self) -> Result<Vec<MergeConflict>> {
+        let response = self
+            .request(reqwest::Method::POST, "/session/rebase")
+            .send()
+            .await
+            .context("Failed to rebase remote session")?;
+        expect_json::<RebaseResponse>(response)
+            .await
+            .map(|response| response.conflicts)
+    }
+
     fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
         self.client
             .request(method, self.url(path))
@@ -159,7 +252,7 @@ impl RepositoriesSource<RemoteRecordsRepository> for RemoteRepositoriesClient {
     async fn create_repository(
         &self,
         repository_name: &str,
-        passwd: String,
+        passwd: SecretString,
     ) -> CreateRepositoryResult<RemoteRecordsRepository> {
         let open_password = passwd.clone();
         let response = self
@@ -185,12 +278,14 @@ impl RepositoriesSource<RemoteRecordsRepository> for RemoteRepositoriesClient {
     async fn open_repository(
         &self,
         repository_name: &str,
-        passwd: String,
+        passwd: SecretString,
     ) -> OpenResult<RemoteRecordsRepository> {
         let response = self
             .client
             .post(self.url(&format!("/repositories/{repository_name}/sessions")))
-            .json(&OpenRepositoryRequest { password: passwd })
+            .json(&OpenRepositoryRequest {
+                password: passwd.clone(),
+            })
             .send()
             .await
             .map_err(|err| RepositoryOpenError::OpenError(err.into()))?;
@@ -205,6 +300,7 @@ impl RepositoriesSource<RemoteRecordsRepository> for RemoteRepositoriesClient {
                     client: self.client.clone(),
                     base_url: self.base_url.clone(),
                     session_id: response.session_id,
+                    passwd,
                 })
             }
             StatusCode::UNAUTHORIZED => Err(RepositoryOpenError::WrongPassword),
@@ -214,6 +310,87 @@ impl RepositoriesSource<RemoteRecordsRepository> for RemoteRepositoriesClient {
             ))),
         }
     }
+
+    async fn list_repositories(&self) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get(self.url("/repositories"))
+            .send()
+            .await
+            .context("Failed to list remote repositories")?;
+        expect_json::<ListRepositoriesResponse>(response)
+            .await
+            .map(|response| response.repositories)
+    }
+
+    async fn rename_repository(
+        &self,
+        repository_name: &str,
+        new_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()> {
+        let response = self
+            .client
+            .patch(self.url(&format!("/repositories/{repository_name}")))
+            .json(&RenameRepositoryRequest {
+                new_name: new_name.to_string(),
+                password: passwd,
+            })
+            .send()
+            .await
+            .map_err(|err| ManageRepositoryError::UnexpectedError(err.into()))?;
+        expect_manage_result(response).await
+    }
+
+    async fn delete_repository(
+        &self,
+        repository_name: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()> {
+        let response = self
+            .client
+            .delete(self.url(&format!("/repositories/{repository_name}")))
+            .json(&DeleteRepositoryRequest { password: passwd })
+            .send()
+            .await
+            .map_err(|err| ManageRepositoryError::UnexpectedError(err.into()))?;
+        expect_manage_result(response).await
+    }
+
+    async fn list_backups(&self, repository_name: &str) -> ManageRepositoryResult<Vec<Backup>> {
+        let response = self
+            .client
+            .get(self.url(&format!("/repositories/{repository_name}/backups")))
+            .send()
+            .await
+            .map_err(|err| ManageRepositoryError::UnexpectedError(err.into()))?;
+        if !response.status().is_success() {
+            return Err(manage_error(response).await);
+        }
+        response
+            .json::<ListBackupsResponse>()
+            .await
+            .map(|response| response.backups)
+            .map_err(|err| ManageRepositoryError::UnexpectedError(err.into()))
+    }
+
+    async fn restore_backup(
+        &self,
+        repository_name: &str,
+        backup_id: &str,
+        passwd: SecretString,
+    ) -> ManageRepositoryResult<()> {
+        let response = self
+            .client
+            .post(self.url(&format!(
+                "/repositories/{repository_name}/backups/{backup_id}/restore"
+            )))
+            .json(&RestoreBackupRequest { password: passwd })
+            .send()
+            .await
+            .map_err(|err| ManageRepositoryError::UnexpectedError(err.into()))?;
+        expect_manage_result(response).await
+    }
 }
 
 #[async_trait]
@@ -231,7 +408,34 @@ impl RecordsRepository for RemoteRecordsRepository {
     }
 
     async fn save(&mut self) -> Result<()> {
-        simple_post(self.request(reqwest::Method::POST, "/session/save")).await
+        let response = self
+            .request(reqwest::Method::POST, "/session/save")
+            .send()
+            .await
+            .context("Failed to save remote session")?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::CONFLICT => {
+                let status = response.status();
+                match response.json::<ErrorResponse>().await {
+                    Ok(err) if !err.conflicts.is_empty() => Err(SaveConflictError {
+                        conflicts: err.conflicts,
+                    }
+                    .into()),
+                    Ok(err) => Err(anyhow!("{status}: {}", err.error)),
+                    Err(_) => Err(anyhow!("{status}")),
+                }
+            }
+            _ => Err(anyhow!(read_error(response).await)),
+        }
+    }
+
+    async fn merge_persisted(&mut self) -> Result<Vec<MergeConflict>> {
+        self.rebase().await
+    }
+
+    fn is_password(&self, passwd: &str) -> bool {
+        self.passwd == passwd
     }
 
     async fn get_records(&self) -> Result<Vec<Record>> {
@@ -322,6 +526,33 @@ impl RecordsRepository for RemoteRecordsRepository {
             .map(|bytes| bytes.to_vec())
             .context("Failed to read remote repository export")
     }
+
+    async fn change_password(
+        &mut self,
+        old_passwd: SecretString,
+        new_passwd: SecretString,
+    ) -> ChangePasswordResult<()> {
+        let response = self
+            .request(reqwest::Method::POST, "/session/password")
+            .json(&ChangePasswordRequest {
+                old_password: old_passwd,
+                new_password: new_passwd.clone(),
+            })
+            .send()
+            .await
+            .map_err(|err| ChangePasswordError::UnexpectedError(err.into()))?;
+
+        match response.status() {
+            status if status.is_success() => {
+                self.passwd = new_passwd;
+                Ok(())
+            }
+            StatusCode::FORBIDDEN => Err(ChangePasswordError::WrongPassword),
+            _ => Err(ChangePasswordError::UnexpectedError(anyhow!(
+                read_error(response).await
+            ))),
+        }
+    }
 }
 
 async fn simple_post(request: RequestBuilder) -> Result<()> {
@@ -361,6 +592,26 @@ async fn expect_update_result(response: reqwest::Response) -> UpdateResult<()> {
     }
 }
 
+async fn expect_manage_result(response: reqwest::Response) -> ManageRepositoryResult<()> {
+    if response.status().is_success() {
+        return Ok(());
+    }
+    Err(manage_error(response).await)
+}
+
+async fn manage_error(response: reqwest::Response) -> ManageRepositoryError {
+    match response.status() {
+        StatusCode::UNAUTHORIZED => ManageRepositoryError::WrongPassword,
+        StatusCode::NOT_FOUND => ManageRepositoryError::DoesntExist,
+        StatusCode::CONFLICT => ManageRepositoryError::RepositoryAlreadyExists,
+        StatusCode::LOCKED => ManageRepositoryError::RepositoryInUse,
+        StatusCode::BAD_REQUEST => {
+            ManageRepositoryError::InvalidRepositoryName(read_error(response).await)
+        }
+        _ => ManageRepositoryError::UnexpectedError(anyhow!(read_error(response).await)),
+    }
+}
+
 async fn read_error(response: reqwest::Response) -> String {
     let status = response.status();
     match response.json::<ErrorResponse>().await {
@@ -0,0 +1,207 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{CreateRepositoryError, RecordsRepository, RepositoriesSource};
+use crate::record::{Record, RecordId, RecordsFilter, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD};
+use crate::secret::SecretString;
+
+/// The records of a repository to copy into a shared one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordSelection {
+    Ids(Vec<RecordId>),
+    Filter(RecordsFilter),
+    /// Case-insensitive match on the record name or login.
+    Search(String),
+}
+
+impl RecordSelection {
+    pub fn matches(&self, record: &Record) -> bool {
+        match self {
+            RecordSelection::Ids(ids) => ids.contains(&record.id),
+            RecordSelection::Filter(filter) => filter.matches(record),
+            RecordSelection::Search(query) => {
+                let query = query.to_lowercase();
+                [RECORD_NAME_FIELD, RECORD_LOGIN_FIELD].iter().any(|name| {
+                    record
+                        .get_field_value(name)
+                        .is_some_and(|value| value.to_lowercase().contains(&query))
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ShareRecordsError {
+    #[error("No records match the selection")]
+    NothingSelected,
+    #[error(transparent)]
+    CreateRepository(#[from] CreateRepositoryError),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(Error),
+}
+pub type ShareResult<T> = Result<T, ShareRecordsError>;
+
+/// Copies the selected records of `source` into a new repository protected by `passwd`
+/// and returns how many were copied. Field history is left out, the replaced values
+/// aren't meant for the recipient.
+pub async fn share_records<T, S, U>(
+    source: &T,
+    repositories: &S,
+    repository_name: &str,
+    passwd: SecretString,
+    selection: &RecordSelection,
+) -> ShareResult<usize>
+where
+    T: RecordsRepository,
+    U: RecordsRepository,
+    S: RepositoriesSource<U>,
+{
+    let records: Vec<Record> = source
+        .get_records()
+        .await
+        .map_err(ShareRecordsError::UnexpectedError)?
+        .into_iter()
+        .filter(|record| selection.matches(record))
+        .collect();
+    if records.is_empty() {
+        return Err(ShareRecordsError::NothingSelected);
+    }
+
+    let mut shared = repositories
+        .create_repository(repository_name, passwd)
+        .await?;
+    let count = records.len();
+    for mut record in records {
+        record.clear_history();
+        shared
+            .add_record(record)
+            .await
+            .map_err(|err| ShareRecordsError::UnexpectedError(err.into()))?;
+    }
+    shared
+        .save()
+        .await
+        .map_err(ShareRecordsError::UnexpectedError)?;
+    shared
+        .close()
+        .await
+        .map_err(ShareRecordsError::UnexpectedError)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::{share_records, RecordSelection, ShareRecordsError};
+    use crate::record::{
+        Record, RecordsFilter, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+    };
+    use crate::repository::file::NamedFileRepositories;
+    use crate::repository::{CreateRepositoryError, RecordsRepository, RepositoriesSource};
+
+    fn record(name: &str, login: &str, tag: &str) -> Record {
+        let mut record = Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), name.to_string()),
+            (RECORD_LOGIN_FIELD.to_string(), login.to_string()),
+            (RECORD_PASSWD_FIELD.to_string(), "old".to_string()),
+        ]);
+        record
+            .update_field(RECORD_PASSWD_FIELD.to_string(), "current")
+            .unwrap();
+        record.set_tags([tag]);
+        record
+    }
+
+    #[test]
+    fn test_selection_matches() {
+        let git = record("Git", "alice", "project-x");
+        let mail = record("Mail", "bob", "personal");
+
+        let by_ids = RecordSelection::Ids(vec![git.id.clone()]);
+        assert!(by_ids.matches(&git) && !by_ids.matches(&mail));
+        let by_tag = RecordSelection::Filter(RecordsFilter {
+            folder: None,
+            tag: Some("personal".into()),
+        });
+        assert!(!by_tag.matches(&git) && by_tag.matches(&mail));
+        let by_search = RecordSelection::Search("ALI".into());
+        assert!(by_search.matches(&git) && !by_search.matches(&mail));
+    }
+
+    #[tokio::test]
+    async fn test_share_records_to_new_repository() {
+        let tmp = TempDir::new().unwrap();
+        let repositories = NamedFileRepositories::new(tmp.path().to_path_buf());
+        let mut source = repositories
+            .create_repository("team", "master".into())
+            .await
+            .unwrap();
+        let git = record("Git", "alice", "project-x");
+        source.add_record(git.clone()).await.unwrap();
+        source
+            .add_record(record("Mail", "bob", "personal"))
+            .await
+            .unwrap();
+
+        let selection = RecordSelection::Filter(RecordsFilter {
+            folder: None,
+            tag: Some("project-x".into()),
+        });
+        let count = share_records(
+            &source,
+            &repositories,
+            "project-x",
+            "colleague".into(),
+            &selection,
+        )
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
+
+        let shared = repositories
+            .open_repository("project-x", "colleague".into())
+            .await
+            .unwrap();
+        let records = shared.get_records().await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, git.id);
+        assert_eq!(
+            records[0].get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "current"
+        );
+        assert!(records[0]
+            .get_field(RECORD_PASSWD_FIELD)
+            .unwrap()
+            .history
+            .is_empty());
+
+        assert!(matches!(
+            share_records(
+                &source,
+                &repositories,
+                "project-x",
+                "colleague".into(),
+                &selection,
+            )
+            .await,
+            Err(ShareRecordsError::CreateRepository(
+                CreateRepositoryError::RepositoryAlreadyExists
+            ))
+        ));
+        assert!(matches!(
+            share_records(
+                &source,
+                &repositories,
+                "nobody",
+                "colleague".into(),
+                &RecordSelection::Search("carol".into()),
+            )
+            .await,
+            Err(ShareRecordsError::NothingSelected)
+        ));
+    }
+}
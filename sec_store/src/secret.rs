@@ -0,0 +1,95 @@
+use std::fmt;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// A string holding a password or a decrypted value.
+///
+/// The buffer is wiped when dropped and `Debug` never prints the content.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: String) -> SecretString {
+        SecretString(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(<redacted>)")
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SecretString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        SecretString(value.to_string())
+    }
+}
+
+impl PartialEq<str> for SecretString {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for SecretString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecretString;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let secret = SecretString::from("hunter2");
+
+        assert!(!format!("{secret:?}").contains("hunter2"));
+        assert!(!format!("{:?}", vec![("name", secret)]).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_serde_is_transparent() {
+        let secret = SecretString::from("hunter2");
+
+        let json = serde_json::to_string(&secret).unwrap();
+
+        assert_eq!(json, "\"hunter2\"");
+        assert_eq!(serde_json::from_str::<SecretString>(&json).unwrap(), secret);
+    }
+}
@@ -0,0 +1,343 @@
+use ring::hmac;
+use thiserror::Error;
+use url::Url;
+use zeroize::Zeroizing;
+
+use crate::record::unix_now;
+
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+pub type TotpResult<T> = Result<T, TotpError>;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TotpError {
+    #[error("Invalid otpauth URI: {0}")]
+    InvalidUri(String),
+    #[error("Only totp URIs are supported, got '{0}'")]
+    UnsupportedType(String),
+    #[error("The secret is missing")]
+    MissingSecret,
+    #[error("The secret isn't valid base32")]
+    InvalidSecret,
+    #[error("Unsupported algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid number of digits '{0}'")]
+    InvalidDigits(String),
+    #[error("Invalid period '{0}'")]
+    InvalidPeriod(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    fn hmac_algorithm(&self) -> hmac::Algorithm {
+        match self {
+            TotpAlgorithm::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            TotpAlgorithm::Sha256 => hmac::HMAC_SHA256,
+            TotpAlgorithm::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// RFC 6238 time-based one-time password generator.
+#[derive(Clone)]
+pub struct Totp {
+    secret: Zeroizing<Vec<u8>>,
+    pub algorithm: TotpAlgorithm,
+    digits: u32,
+    period: u64,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+impl std::fmt::Debug for Totp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Totp")
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .field("period", &self.period)
+            .field("issuer", &self.issuer)
+            .field("account", &self.account)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Totp {
+    pub fn new(secret: Vec<u8>) -> Totp {
+        Totp {
+            secret: Zeroizing::new(secret),
+            algorithm: TotpAlgorithm::default(),
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD,
+            issuer: None,
+            account: None,
+        }
+    }
+
+    /// Accepts an `otpauth://totp/...` URI or a bare base32 secret.
+    pub fn parse(value: &str) -> TotpResult<Totp> {
+        let value = value.trim();
+        if value.starts_with("otpauth://") {
+            Totp::from_uri(value)
+        } else {
+            Ok(Totp::new(decode_base32(value)?))
+        }
+    }
+
+    fn from_uri(uri: &str) -> TotpResult<Totp> {
+        let url = Url::parse(uri).map_err(|err| TotpError::InvalidUri(err.to_string()))?;
+        match url.host_str() {
+            Some(kind) if kind.eq_ignore_ascii_case("totp") => {}
+            kind => return Err(TotpError::UnsupportedType(kind.unwrap_or_default().into())),
+        }
+
+        let mut totp = Totp::new(Vec::new());
+        let mut has_secret = false;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "secret" => {
+                    totp.secret = Zeroizing::new(decode_base32(&value)?);
+                    has_secret = true;
+                }
+                "algorithm" => {
+                    totp.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => TotpAlgorithm::Sha1,
+                        "SHA256" => TotpAlgorithm::Sha256,
+                        "SHA512" => TotpAlgorithm::Sha512,
+                        _ => return Err(TotpError::UnsupportedAlgorithm(value.into())),
+                    }
+                }
+                "digits" => totp.set_digits(
+                    value
+                        .parse()
+                        .map_err(|_| TotpError::InvalidDigits(value.to_string()))?,
+                )?,
+                "period" => totp.set_period(
+                    value
+                        .parse()
+                        .map_err(|_| TotpError::InvalidPeriod(value.to_string()))?,
+                )?,
+                "issuer" => totp.issuer = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        if !has_secret {
+            return Err(TotpError::MissingSecret);
+        }
+
+        let label = percent_encoding::percent_decode_str(url.path().trim_start_matches('/'))
+            .decode_utf8_lossy()
+            .into_owned();
+        match label.split_once(':') {
+            Some((issuer, account)) => {
+                totp.issuer.get_or_insert_with(|| issuer.trim().to_string());
+                totp.account = Some(account.trim().to_string());
+            }
+            None if !label.is_empty() => totp.account = Some(label),
+            None => {}
+        }
+        Ok(totp)
+    }
+
+    pub fn digits(&self) -> u32 {
+        self.digits
+    }
+
+    /// Authenticator apps only show 6 to 8 digits, more would overflow the code.
+    pub fn set_digits(&mut self, digits: u32) -> TotpResult<()> {
+        if !(6..=8).contains(&digits) {
+            return Err(TotpError::InvalidDigits(digits.to_string()));
+        }
+        self.digits = digits;
+        Ok(())
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn set_period(&mut self, period: u64) -> TotpResult<()> {
+        if period == 0 {
+            return Err(TotpError::InvalidPeriod(period.to_string()));
+        }
+        self.period = period;
+        Ok(())
+    }
+
+    /// The code for the given unix time, zero padded to `digits`.
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = unix_time / self.period;
+        let key = hmac::Key::new(self.algorithm.hmac_algorithm(), &self.secret);
+        let tag = hmac::sign(&key, &counter.to_be_bytes());
+        let hash = tag.as_ref();
+
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(self.digits),
+            width = self.digits as usize
+        )
+    }
+
+    pub fn code(&self) -> String {
+        self.code_at(unix_now())
+    }
+
+    /// Seconds until the code for `unix_time` expires.
+    pub fn seconds_remaining_at(&self, unix_time: u64) -> u64 {
+        self.period - unix_time % self.period
+    }
+
+    pub fn seconds_remaining(&self) -> u64 {
+        self.seconds_remaining_at(unix_now())
+    }
+}
+
+/// RFC 4648 base32 without padding requirements, spaces and case are ignored.
+fn decode_base32(value: &str) -> TotpResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in value.chars().filter(|c| !c.is_whitespace() && *c != '-') {
+        if c == '=' {
+            break;
+        }
+        let digit = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return Err(TotpError::InvalidSecret),
+        };
+        buffer = (buffer << 5) | digit;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bytes.is_empty() {
+        return Err(TotpError::InvalidSecret);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_base32, Totp, TotpAlgorithm, TotpError};
+
+    // RFC 6238 appendix B.
+    const RFC_VECTORS: [(u64, &str, &str, &str); 6] = [
+        (59, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    fn rfc_totp(secret: &[u8], algorithm: TotpAlgorithm) -> Totp {
+        let mut totp = Totp::new(secret.to_vec());
+        totp.algorithm = algorithm;
+        totp.set_digits(8).unwrap();
+        totp
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let sha1 = rfc_totp(b"12345678901234567890", TotpAlgorithm::Sha1);
+        let sha256 = rfc_totp(b"12345678901234567890123456789012", TotpAlgorithm::Sha256);
+        let sha512 = rfc_totp(
+            b"1234567890123456789012345678901234567890123456789012345678901234",
+            TotpAlgorithm::Sha512,
+        );
+
+        for (time, sha1_code, sha256_code, sha512_code) in RFC_VECTORS {
+            assert_eq!(sha1.code_at(time), sha1_code);
+            assert_eq!(sha256.code_at(time), sha256_code);
+            assert_eq!(sha512.code_at(time), sha512_code);
+        }
+    }
+
+    #[test]
+    fn test_base32_secret() {
+        // "12345678901234567890" in base32.
+        let totp = Totp::parse("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+
+        assert_eq!(totp.digits(), 6);
+        assert_eq!(totp.period(), 30);
+        assert_eq!(totp.code_at(59), "287082");
+        assert_eq!(totp.seconds_remaining_at(59), 1);
+        assert_eq!(totp.seconds_remaining_at(60), 30);
+        assert_eq!(
+            Totp::parse("not base32!").unwrap_err(),
+            TotpError::InvalidSecret
+        );
+        assert_eq!(decode_base32("MZXW6==="), Ok(b"foo".to_vec()));
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let totp = Totp::parse(
+            "otpauth://totp/Example%20Co:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+
+        assert_eq!(totp.algorithm, TotpAlgorithm::Sha256);
+        assert_eq!(totp.digits(), 8);
+        assert_eq!(totp.period(), 60);
+        assert_eq!(totp.issuer.as_deref(), Some("Example Co"));
+        assert_eq!(totp.account.as_deref(), Some("alice@example.com"));
+    }
+
+    #[test]
+    fn test_setters_refuse_values_that_would_break_codes() {
+        let mut totp = Totp::new(b"12345678901234567890".to_vec());
+
+        assert_eq!(
+            totp.set_digits(10),
+            Err(TotpError::InvalidDigits("10".to_string()))
+        );
+        assert_eq!(
+            totp.set_period(0),
+            Err(TotpError::InvalidPeriod("0".to_string()))
+        );
+        assert_eq!(totp.code_at(59), "287082");
+    }
+
+    #[test]
+    fn test_otpauth_uri_errors() {
+        assert_eq!(
+            Totp::parse("otpauth://hotp/x?secret=GEZDGNBV&counter=1").unwrap_err(),
+            TotpError::UnsupportedType("hotp".to_string())
+        );
+        assert_eq!(
+            Totp::parse("otpauth://totp/x?issuer=Example").unwrap_err(),
+            TotpError::MissingSecret
+        );
+        assert_eq!(
+            Totp::parse("otpauth://totp/x?secret=GEZDGNBV&digits=12").unwrap_err(),
+            TotpError::InvalidDigits("12".to_string())
+        );
+        assert_eq!(
+            Totp::parse("otpauth://totp/x?secret=GEZDGNBV&period=0").unwrap_err(),
+            TotpError::InvalidPeriod("0".to_string())
+        );
+        assert_eq!(
+            Totp::parse("otpauth://totp/x?secret=GEZDGNBV&algorithm=MD5").unwrap_err(),
+            TotpError::UnsupportedAlgorithm("MD5".to_string())
+        );
+    }
+}
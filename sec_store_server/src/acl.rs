@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::client_identity::{normalize_fingerprint, ClientIdentity};
+
+/// What a client may do with a repository, every level includes the previous ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    /// Open sessions, read and export the records.
+    Read,
+    /// Also change the records and save them.
+    ReadWrite,
+    /// Also change the password, rename and delete the repository.
+    Owner,
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Access::Read => write!(f, "read"),
+            Access::ReadWrite => write!(f, "read_write"),
+            Access::Owner => write!(f, "owner"),
+        }
+    }
+}
+
+/// A certificate SHA-256 fingerprint, or a subject common name prefixed with `cn:`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum ClientPattern {
+    Fingerprint(String),
+    CommonName(String),
+}
+
+impl From<String> for ClientPattern {
+    fn from(value: String) -> Self {
+        match value.strip_prefix("cn:") {
+            Some(common_name) => ClientPattern::CommonName(common_name.to_string()),
+            None => ClientPattern::Fingerprint(normalize_fingerprint(&value)),
+        }
+    }
+}
+
+impl ClientPattern {
+    pub fn matches(&self, client: &ClientIdentity) -> bool {
+        match self {
+            ClientPattern::Fingerprint(fingerprint) => client.fingerprint == *fingerprint,
+            ClientPattern::CommonName(name) => client.common_name.as_ref() == Some(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Grant {
+    pub client: ClientPattern,
+    /// A repository name, `*` for every repository.
+    pub repository: String,
+    pub access: Access,
+}
+
+/// Access granted by the operator on top of the repository owners, see `--acl-file`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Acl {
+    #[serde(default, rename = "grant")]
+    pub grants: Vec<Grant>,
+}
+
+impl Acl {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ACL file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse ACL file {}", path.display()))
+    }
+
+    /// The highest access granted to `client`.
+    pub fn access(&self, client: &ClientIdentity, repository_name: &str) -> Option<Access> {
+        self.grants
+            .iter()
+            .filter(|grant| grant.repository == "*" || grant.repository == repository_name)
+            .filter(|grant| grant.client.matches(client))
+            .map(|grant| grant.access)
+            .max()
+    }
+
+    /// Whether a grant names the repository, wildcards excluded.
+    pub fn mentions(&self, repository_name: &str) -> bool {
+        self.grants
+            .iter()
+            .any(|grant| grant.repository == repository_name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Owner {
+    pub fingerprint: String,
+    pub common_name: Option<String>,
+}
+
+impl From<&ClientIdentity> for Owner {
+    fn from(client: &ClientIdentity) -> Self {
+        Self {
+            fingerprint: client.fingerprint.clone(),
+            common_name: client.common_name.clone(),
+        }
+    }
+}
+
+/// Name of the owners file in the server state directory.
+pub const OWNERS_FILE: &str = "owners.json";
+
+/// The client owning each repository, persisted as JSON in the data directory.
+#[derive(Debug)]
+pub struct RepositoryOwners {
+    path: PathBuf,
+    owners: RwLock<HashMap<String, Owner>>,
+}
+
+impl RepositoryOwners {
+    pub async fn load(path: PathBuf) -> Result<Self> {
+        let owners = match tokio::fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse owners file {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to read owners file {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            owners: RwLock::new(owners),
+        })
+    }
+
+    pub async fn owner(&self, repository_name: &str) -> Option<Owner> {
+        self.owners.read().await.get(repository_name).cloned()
+    }
+
+    pub async fn set(&self, repository_name: &str, owner: Owner) -> Result<()> {
+        let mut owners = self.owners.write().await;
+        owners.insert(repository_name.to_string(), owner);
+        self.persist(&owners).await
+    }
+
+    /// Moves the owner to the new name, `fallback` owns it when the old name had none,
+    /// otherwise the renamed repository could be claimed by anyone knowing its password.
+    pub async fn rename(
+        &self,
+        repository_name: &str,
+        new_name: &str,
+        fallback: Owner,
+    ) -> Result<()> {
+        let mut owners = self.owners.write().await;
+        let owner = owners.remove(repository_name).unwrap_or(fallback);
+        owners.insert(new_name.to_string(), owner);
+        self.persist(&owners).await
+    }
+
+    pub async fn remove(&self, repository_name: &str) -> Result<()> {
+        let mut owners = self.owners.write().await;
+        owners.remove(repository_name);
+        self.persist(&owners).await
+    }
+
+    async fn persist(&self, owners: &HashMap<String, Owner>) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(owners)?).await?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .with_context(|| format!("Failed to write owners file {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sec_store::record::Record;
+    use sec_store::repository::remote::{
+        AddRecordRequest, ChangePasswordRequest, CreateRepositoryRequest, ListRepositoriesResponse,
+        OpenRepositoryRequest, OpenRepositoryResponse, RenameRepositoryRequest,
+    };
+    use sec_store::repository::RepositoriesSource;
+
+    use super::*;
+    use crate::test_support::{
+        spawn_plain_test_server, test_identity, test_password, PlainTestServer,
+    };
+
+    #[test]
+    fn grants_match_fingerprints_and_common_names() {
+        let acl: Acl = toml::from_str(
+            r#"
+[[grant]]
+client = "cn:bob"
+repository = "team"
+access = "read"
+
+[[grant]]
+client = "0000bob"
+repository = "team"
+access = "read_write"
+
+[[grant]]
+client = "cn:carol"
+repository = "*"
+access = "owner"
+"#,
+        )
+        .expect("acl");
+        let bob = ClientIdentity {
+            fingerprint: "0000BOB".to_string(),
+            common_name: Some("bob".to_string()),
+        };
+
+        assert_eq!(acl.access(&bob, "team"), Some(Access::ReadWrite));
+        assert_eq!(acl.access(&bob, "home"), None);
+        assert_eq!(
+            acl.access(&test_identity("carol"), "home"),
+            Some(Access::Owner)
+        );
+        assert_eq!(acl.access(&test_identity("dave"), "team"), None);
+        assert!(acl.mentions("team"));
+        assert!(!acl.mentions("home"));
+    }
+
+    async fn open(
+        server: &PlainTestServer,
+        client: &str,
+        name: &str,
+        password: &str,
+    ) -> Result<String, reqwest::StatusCode> {
+        let response = server
+            .request(
+                reqwest::Method::POST,
+                &format!("/repositories/{name}/sessions"),
+                client,
+            )
+            .json(&OpenRepositoryRequest {
+                password: password.into(),
+            })
+            .send()
+            .await
+            .expect("open response");
+        if !response.status().is_success() {
+            return Err(response.status());
+        }
+        Ok(response
+            .json::<OpenRepositoryResponse>()
+            .await
+            .expect("open json")
+            .session_id)
+    }
+
+    #[tokio::test]
+    async fn owners_and_grants_are_enforced() {
+        let acl: Acl = toml::from_str(
+            r#"
+[[grant]]
+client = "cn:alice"
+repository = "team"
+access = "owner"
+
+[[grant]]
+client = "cn:reader"
+repository = "team"
+access = "read"
+
+[[grant]]
+client = "cn:writer"
+repository = "team"
+access = "read_write"
+"#,
+        )
+        .expect("acl");
+        let server = spawn_plain_test_server(acl).await.expect("server");
+        let password = test_password();
+        let create = |client: &str| {
+            server
+                .request(reqwest::Method::POST, "/repositories/team", client)
+                .json(&CreateRepositoryRequest {
+                    password: password.clone().into(),
+                })
+                .send()
+        };
+        let status = create("mallory").await.expect("create").status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+        let status = create("alice").await.expect("create").status();
+        assert_eq!(status, reqwest::StatusCode::CREATED);
+
+        let listed = server
+            .request(reqwest::Method::GET, "/repositories", "mallory")
+            .send()
+            .await
+            .expect("list response")
+            .json::<ListRepositoriesResponse>()
+            .await
+            .expect("list json");
+        assert!(listed.repositories.is_empty());
+        assert_eq!(
+            open(&server, "mallory", "team", &password).await,
+            Err(reqwest::StatusCode::FORBIDDEN)
+        );
+
+        let reader = open(&server, "reader", "team", &password)
+            .await
+            .expect("reader session");
+        let record = Record::new(vec![("name".to_string(), "db".to_string())]);
+        let add = |client: &str, session: &str| {
+            server
+                .request(reqwest::Method::POST, "/session/records", client)
+                .bearer_auth(session)
+                .json(&AddRecordRequest {
+                    record: record.clone(),
+                })
+                .send()
+        };
+        let status = add("reader", &reader).await.expect("add").status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+
+        let writer = open(&server, "writer", "team", &password)
+            .await
+            .expect("writer session");
+        let status = add("reader", &writer).await.expect("add").status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+        let status = add("writer", &writer).await.expect("add").status();
+        assert_eq!(status, reqwest::StatusCode::CREATED);
+        let status = server
+            .request(reqwest::Method::POST, "/session/password", "writer")
+            .bearer_auth(&writer)
+            .json(&ChangePasswordRequest {
+                old_password: password.clone().into(),
+                new_password: test_password().into(),
+            })
+            .send()
+            .await
+            .expect("change password")
+            .status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+
+        for session in [&reader, &writer] {
+            let client = if session == &reader {
+                "reader"
+            } else {
+                "writer"
+            };
+            server
+                .request(reqwest::Method::DELETE, "/session", client)
+                .bearer_auth(session)
+                .send()
+                .await
+                .expect("close");
+        }
+        let rename = |client: &str| {
+            server
+                .request(reqwest::Method::PATCH, "/repositories/team", client)
+                .json(&RenameRepositoryRequest {
+                    new_name: "crew".to_string(),
+                    password: password.clone().into(),
+                })
+                .send()
+        };
+        let status = rename("writer").await.expect("rename").status();
+        assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+        // The grants name "team", they would no longer apply to "crew".
+        let status = rename("alice").await.expect("rename").status();
+        assert_eq!(status, reqwest::StatusCode::CONFLICT);
+        assert_eq!(
+            server.state.owners.owner("team").await,
+            Some(Owner::from(&test_identity("alice")))
+        );
+    }
+
+    #[tokio::test]
+    async fn renamed_repository_keeps_an_owner() {
+        let acl: Acl = toml::from_str(
+            r#"
+[[grant]]
+client = "cn:carol"
+repository = "*"
+access = "owner"
+"#,
+        )
+        .expect("acl");
+        let server = spawn_plain_test_server(acl).await.expect("server");
+        let password = test_password();
+        server
+            .state
+            .repositories
+            .create_repository("legacy", password.clone().into())
+            .await
+            .expect("create");
+
+        let status = server
+            .request(reqwest::Method::PATCH, "/repositories/legacy", "carol")
+            .json(&RenameRepositoryRequest {
+                new_name: "archive".to_string(),
+                password: password.clone().into(),
+            })
+            .send()
+            .await
+            .expect("rename")
+            .status();
+        assert_eq!(status, reqwest::StatusCode::OK);
+        assert_eq!(
+            server.state.owners.owner("archive").await,
+            Some(Owner::from(&test_identity("carol")))
+        );
+        assert_eq!(
+            open(&server, "mallory", "archive", &password).await,
+            Err(reqwest::StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[tokio::test]
+    async fn unowned_repository_is_claimed_by_first_client_with_password() {
+        let server = spawn_plain_test_server(Acl::default())
+            .await
+            .expect("server");
+        let password = test_password();
+        server
+            .state
+            .repositories
+            .create_repository("legacy", password.clone().into())
+            .await
+            .expect("create");
+
+        assert_eq!(
+            open(&server, "bob", "legacy", "wrong").await,
+            Err(reqwest::StatusCode::UNAUTHORIZED)
+        );
+        open(&server, "bob", "legacy", &password)
+            .await
+            .expect("bob claims");
+        assert_eq!(
+            open(&server, "carol", "legacy", &password).await,
+            Err(reqwest::StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            server.state.owners.owner("legacy").await,
+            Some(Owner::from(&test_identity("bob")))
+        );
+    }
+}
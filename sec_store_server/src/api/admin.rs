@@ -0,0 +1,207 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use sec_store::record::unix_now;
+use serde::{Deserialize, Serialize};
+
+use crate::acl::Access;
+use crate::audit_log::{AuditEntry, AuditQuery};
+use crate::client_identity::ClientIdentity;
+use crate::{ApiError, AppState};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/sessions", get(list_sessions))
+        .route("/admin/audit", get(query_audit_log))
+}
+
+fn require_admin(state: &AppState, client: &ClientIdentity) -> Result<(), ApiError> {
+    if !state.is_admin(client) {
+        return Err(ApiError::forbidden(
+            "Client certificate is not allowed to administer the server",
+        ));
+    }
+    Ok(())
+}
+
+/// An open session, without its id since that is the bearer token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub repository: String,
+    /// Fingerprint of the client certificate that opened the session.
+    pub client: String,
+    pub client_common_name: Option<String>,
+    pub access: Access,
+    pub opened_at: u64,
+    pub last_activity: u64,
+    pub expires_at: u64,
+}
+
+async fn list_sessions(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+) -> Result<Json<Vec<SessionInfo>>, ApiError> {
+    require_admin(&state, &client)?;
+    let now = unix_now();
+    let limits = state.session_limits();
+    let mut sessions: Vec<SessionInfo> = state
+        .sessions
+        .read()
+        .await
+        .values()
+        .filter(|session| session.expires_at(limits) > now)
+        .map(|session| SessionInfo {
+            repository: session.repository_name.clone(),
+            client: session.client.fingerprint.clone(),
+            client_common_name: session.client.common_name.clone(),
+            access: session.access,
+            opened_at: session.opened_at,
+            last_activity: session.last_activity(),
+            expires_at: session.expires_at(limits),
+        })
+        .collect();
+    sessions.sort_by(|a, b| (a.opened_at, &a.repository).cmp(&(b.opened_at, &b.repository)));
+    Ok(Json(sessions))
+}
+
+async fn query_audit_log(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, ApiError> {
+    require_admin(&state, &client)?;
+    let entries = state
+        .audit_log
+        .query(&query)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(Json(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::SessionInfo;
+    use crate::acl::Access;
+    use crate::audit_log::{AuditAction, AuditEntry};
+    use crate::test_support::{
+        build_client, create_repo, open_session, spawn_test_server, test_password,
+    };
+
+    #[tokio::test]
+    async fn admin_lists_open_sessions_without_their_ids() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+        create_repo(&client, &server, "demo", &password).await;
+        create_repo(&client, &server, "team", &password).await;
+        let demo = open_session(&client, &server, "demo", &password).await;
+        open_session(&client, &server, "team", &password).await;
+
+        let response = client
+            .get(format!("{}/admin/sessions", server.base_url))
+            .send()
+            .await
+            .expect("list response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.text().await.expect("list body");
+        assert!(!body.contains(&demo.session_id));
+        let sessions: Vec<SessionInfo> = serde_json::from_str(&body).expect("list json");
+        let mut repositories: Vec<&str> = sessions.iter().map(|s| s.repository.as_str()).collect();
+        repositories.sort();
+        assert_eq!(repositories, vec!["demo", "team"]);
+        assert!(sessions.iter().all(|session| {
+            session.client == server.client_fingerprint
+                && session.client_common_name.as_deref() == Some("allowed-client")
+                && session.access == Access::Owner
+                && session.last_activity >= session.opened_at
+                && session.expires_at > session.last_activity
+        }));
+
+        client
+            .delete(format!("{}/session", server.base_url))
+            .bearer_auth(&demo.session_id)
+            .send()
+            .await
+            .expect("close response");
+        let sessions: Vec<SessionInfo> = client
+            .get(format!("{}/admin/sessions", server.base_url))
+            .send()
+            .await
+            .expect("list response")
+            .json()
+            .await
+            .expect("list json");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].repository, "team");
+    }
+
+    #[tokio::test]
+    async fn audit_log_records_who_did_what_without_contents() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+        create_repo(&client, &server, "demo", &password).await;
+        let session = open_session(&client, &server, "demo", &password).await;
+        for path in ["/session/save", "/session/export"] {
+            let request = if path == "/session/save" {
+                client.post(format!("{}{path}", server.base_url))
+            } else {
+                client.get(format!("{}{path}", server.base_url))
+            };
+            let response = request
+                .bearer_auth(&session.session_id)
+                .send()
+                .await
+                .expect("session response");
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        client
+            .delete(format!("{}/session", server.base_url))
+            .bearer_auth(&session.session_id)
+            .send()
+            .await
+            .expect("close response");
+
+        let body = client
+            .get(format!(
+                "{}/admin/audit?repository=demo&limit=4",
+                server.base_url
+            ))
+            .send()
+            .await
+            .expect("audit response")
+            .text()
+            .await
+            .expect("audit body");
+        assert!(!body.contains(&password));
+        assert!(!body.contains(&session.session_id));
+        let entries: Vec<AuditEntry> = serde_json::from_str(&body).expect("audit json");
+        let actions: Vec<AuditAction> = entries.iter().map(|entry| entry.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::Open,
+                AuditAction::Save,
+                AuditAction::Export,
+                AuditAction::Close
+            ]
+        );
+        assert!(entries
+            .iter()
+            .all(|entry| entry.client == server.client_fingerprint));
+        assert_eq!(entries[0].seq, 2);
+        let saves: Vec<AuditEntry> = client
+            .get(format!("{}/admin/audit?action=save", server.base_url))
+            .send()
+            .await
+            .expect("audit response")
+            .json()
+            .await
+            .expect("audit json");
+        assert_eq!(saves.len(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod repositories;
 pub mod sessions;
 
@@ -9,6 +10,7 @@ use crate::{ApiError, AppState};
 
 pub fn router() -> Router<AppState> {
     Router::new()
+        .merge(admin::router())
         .merge(repositories::router())
         .merge(sessions::router())
 }
@@ -30,6 +32,7 @@ impl axum::response::IntoResponse for ApiError {
             self.status,
             Json(ErrorResponse {
                 error: self.message,
+                conflicts: self.conflicts,
             }),
         )
             .into_response()
@@ -2,66 +2,302 @@ use axum::{
     extract::{Path as AxumPath, State},
     http::StatusCode,
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use sec_store::repository::remote::{
-    CreateRepositoryRequest, OpenRepositoryRequest, OpenRepositoryResponse,
+    CreateRepositoryRequest, DeleteRepositoryRequest, ListBackupsResponse,
+    ListRepositoriesResponse, OpenRepositoryRequest, OpenRepositoryResponse,
+    RenameRepositoryRequest, RestoreBackupRequest,
 };
-use sec_store::repository::RepositoriesSource;
+use sec_store::repository::{ManageRepositoryError, RepositoriesSource};
 
 use super::SimpleStatus;
-use crate::{ApiError, AppState};
+use crate::acl::{Access, Owner};
+use crate::audit_log::AuditAction;
+use crate::client_identity::ClientIdentity;
+use crate::{run_blocking, ApiError, AppState, Authorization};
 
 pub fn router() -> Router<AppState> {
     Router::new()
-        .route("/repositories/{repository_name}", post(create_repository))
+        .route("/repositories", get(list_repositories))
+        .route(
+            "/repositories/{repository_name}",
+            post(create_repository)
+                .patch(rename_repository)
+                .delete(delete_repository),
+        )
         .route(
             "/repositories/{repository_name}/sessions",
             post(open_repository),
         )
+        .route("/repositories/{repository_name}/backups", get(list_backups))
+        .route(
+            "/repositories/{repository_name}/backups/{backup_id}/restore",
+            post(restore_backup),
+        )
 }
 
 async fn create_repository(
     State(state): State<AppState>,
+    client: ClientIdentity,
     AxumPath(repository_name): AxumPath<String>,
     Json(request): Json<CreateRepositoryRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let result = async {
+        // Names granted to others in the ACL can only be taken by their owners.
+        state
+            .authorize(&client, &repository_name, Access::Owner)
+            .await?;
+        let (repositories, name) = (state.repositories.clone(), repository_name.clone());
+        run_blocking(async move {
+            repositories
+                .create_repository(&name, request.password)
+                .await
+        })
+        .await?
+        .map_err(ApiError::from_create_error)?;
+        state.set_owner(&repository_name, &client).await
+    }
+    .await;
     state
+        .audit(&client, AuditAction::Create, &repository_name, None, result)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(SimpleStatus::new("created"))))
+}
+
+async fn list_repositories(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+) -> Result<Json<ListRepositoriesResponse>, ApiError> {
+    let mut repositories = Vec::new();
+    for name in state
         .repositories
-        .create_repository(&repository_name, request.password)
+        .list_repositories()
         .await
-        .map_err(ApiError::from_create_error)?;
+        .map_err(ApiError::internal)?
+    {
+        if state.authorize(&client, &name, Access::Read).await.is_ok() {
+            repositories.push(name);
+        }
+    }
+    Ok(Json(ListRepositoriesResponse { repositories }))
+}
 
-    Ok((StatusCode::CREATED, Json(SimpleStatus::new("created"))))
+async fn rename_repository(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    AxumPath(repository_name): AxumPath<String>,
+    Json(request): Json<RenameRepositoryRequest>,
+) -> Result<Json<SimpleStatus>, ApiError> {
+    let new_name = request.new_name.clone();
+    let result = async {
+        state
+            .authorize(&client, &repository_name, Access::Owner)
+            .await?;
+        state
+            .authorize(&client, &request.new_name, Access::Owner)
+            .await?;
+        if state.acl.mentions(&repository_name) {
+            return Err(ApiError::conflict(
+                "The ACL grants access to this repository by name, update it before renaming",
+            ));
+        }
+        let mut name_lock = state.lock_name(&repository_name).await;
+        if state.has_sessions(&repository_name).await {
+            return Err(ApiError::from_manage_error(
+                ManageRepositoryError::RepositoryInUse,
+            ));
+        }
+        let (repositories, name) = (state.repositories.clone(), repository_name.clone());
+        let new_name = request.new_name.clone();
+        run_blocking(async move {
+            repositories
+                .rename_repository(&name, &new_name, request.password)
+                .await
+        })
+        .await?
+        .map_err(ApiError::from_manage_error)?;
+        *name_lock += 1;
+        state
+            .owners
+            .rename(&repository_name, &request.new_name, Owner::from(&client))
+            .await
+            .map_err(ApiError::internal)
+    }
+    .await;
+    state
+        .audit(
+            &client,
+            AuditAction::Rename,
+            &repository_name,
+            Some(new_name),
+            result,
+        )
+        .await?;
+    Ok(Json(SimpleStatus::new("renamed")))
+}
+
+async fn delete_repository(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    AxumPath(repository_name): AxumPath<String>,
+    Json(request): Json<DeleteRepositoryRequest>,
+) -> Result<Json<SimpleStatus>, ApiError> {
+    let result = async {
+        state
+            .authorize(&client, &repository_name, Access::Owner)
+            .await?;
+        let mut name_lock = state.lock_name(&repository_name).await;
+        if state.has_sessions(&repository_name).await {
+            return Err(ApiError::from_manage_error(
+                ManageRepositoryError::RepositoryInUse,
+            ));
+        }
+        let (repositories, name) = (state.repositories.clone(), repository_name.clone());
+        run_blocking(async move {
+            repositories
+                .delete_repository(&name, request.password)
+                .await
+        })
+        .await?
+        .map_err(ApiError::from_manage_error)?;
+        *name_lock += 1;
+        state
+            .owners
+            .remove(&repository_name)
+            .await
+            .map_err(ApiError::internal)
+    }
+    .await;
+    state
+        .audit(&client, AuditAction::Delete, &repository_name, None, result)
+        .await?;
+    Ok(Json(SimpleStatus::new("deleted")))
 }
 
 async fn open_repository(
     State(state): State<AppState>,
+    client: ClientIdentity,
     AxumPath(repository_name): AxumPath<String>,
     Json(request): Json<OpenRepositoryRequest>,
 ) -> Result<Json<OpenRepositoryResponse>, ApiError> {
-    let repository = state
-        .repositories
-        .open_repository(&repository_name, request.password)
-        .await
-        .map_err(ApiError::from_open_error)?;
+    let result = async {
+        let authorization = state
+            .authorize(&client, &repository_name, Access::Read)
+            .await?;
+        // The key is derived without holding the name lock, a file replaced meanwhile
+        // shows up as a new generation.
+        let generation = *state.lock_name(&repository_name).await;
+        let (repositories, name) = (state.repositories.clone(), repository_name.clone());
+        let repository =
+            run_blocking(
+                async move { repositories.open_repository(&name, request.password).await },
+            )
+            .await?
+            .map_err(ApiError::from_open_error)?;
+        let name_lock = state.lock_name(&repository_name).await;
+        if *name_lock != generation {
+            return Err(ApiError::conflict(
+                "Repository was replaced while it was being opened, retry",
+            ));
+        }
+        let access = match authorization {
+            Authorization::Granted(access) => access,
+            Authorization::Unowned => {
+                state.set_owner(&repository_name, &client).await?;
+                Access::Owner
+            }
+        };
+        state
+            .insert_session(&repository_name, client.clone(), access, repository)
+            .await
+    }
+    .await;
     let session_id = state
-        .insert_session(repository)
-        .await
-        .map_err(ApiError::internal)?;
+        .audit(&client, AuditAction::Open, &repository_name, None, result)
+        .await?;
     Ok(Json(OpenRepositoryResponse { session_id }))
 }
 
+async fn list_backups(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    AxumPath(repository_name): AxumPath<String>,
+) -> Result<Json<ListBackupsResponse>, ApiError> {
+    state
+        .authorize(&client, &repository_name, Access::Read)
+        .await?;
+    let backups = state
+        .repositories
+        .list_backups(&repository_name)
+        .await
+        .map_err(ApiError::from_manage_error)?;
+    Ok(Json(ListBackupsResponse { backups }))
+}
+
+async fn restore_backup(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    AxumPath((repository_name, backup_id)): AxumPath<(String, String)>,
+    Json(request): Json<RestoreBackupRequest>,
+) -> Result<Json<SimpleStatus>, ApiError> {
+    let result = async {
+        let authorization = state
+            .authorize(&client, &repository_name, Access::Owner)
+            .await?;
+        // Open sessions would merge their changes into the restored version on save.
+        let mut name_lock = state.lock_name(&repository_name).await;
+        if state.has_sessions(&repository_name).await {
+            return Err(ApiError::from_manage_error(
+                ManageRepositoryError::RepositoryInUse,
+            ));
+        }
+        let (repositories, name) = (state.repositories.clone(), repository_name.clone());
+        let backup = backup_id.clone();
+        run_blocking(async move {
+            repositories
+                .restore_backup(&name, &backup, request.password)
+                .await
+        })
+        .await?
+        .map_err(ApiError::from_manage_error)?;
+        *name_lock += 1;
+        if authorization == Authorization::Unowned {
+            state.set_owner(&repository_name, &client).await?;
+        }
+        Ok(())
+    }
+    .await;
+    state
+        .audit(
+            &client,
+            AuditAction::Restore,
+            &repository_name,
+            Some(backup_id),
+            result,
+        )
+        .await?;
+    Ok(Json(SimpleStatus::new("restored")))
+}
+
 #[cfg(test)]
 mod tests {
     use axum::http::StatusCode;
     use sec_store::record::Record;
     use sec_store::repository::remote::{
-        AddRecordRequest, CreateRepositoryRequest, OpenRepositoryRequest, OpenRepositoryResponse,
+        AddRecordRequest, CreateRepositoryRequest, DeleteRepositoryRequest, OpenRepositoryRequest,
+        OpenRepositoryResponse, RemoteRepositoriesClient,
     };
+    use sec_store::repository::{ManageRepositoryError, RecordsRepository, RepositoriesSource};
 
-    use crate::test_support::{build_client, spawn_test_server, test_password};
+    use crate::acl::Acl;
+    use crate::audit_log::{AuditAction, AuditOutcome, AuditQuery};
+    use crate::test_support::{
+        build_client, create_repo, open_session, spawn_plain_test_server, spawn_test_server,
+        test_password,
+    };
 
     #[tokio::test]
     async fn mtls_server_rejects_unknown_client_and_persists_records() {
@@ -72,7 +308,7 @@ mod tests {
         let create_response = allowed_client
             .post(format!("{}/repositories/demo", server.base_url))
             .json(&CreateRepositoryRequest {
-                password: password.clone(),
+                password: password.clone().into(),
             })
             .send()
             .await
@@ -82,7 +318,7 @@ mod tests {
         let session = allowed_client
             .post(format!("{}/repositories/demo/sessions", server.base_url))
             .json(&OpenRepositoryRequest {
-                password: password.clone(),
+                password: password.clone().into(),
             })
             .send()
             .await
@@ -113,7 +349,9 @@ mod tests {
 
         let second_session = allowed_client
             .post(format!("{}/repositories/demo/sessions", server.base_url))
-            .json(&OpenRepositoryRequest { password })
+            .json(&OpenRepositoryRequest {
+                password: password.into(),
+            })
             .send()
             .await
             .expect("second open response")
@@ -150,7 +388,7 @@ mod tests {
         let create_response = client
             .post(format!("{}/repositories/invalid%3Aname", server.base_url))
             .json(&CreateRepositoryRequest {
-                password: password.clone(),
+                password: password.clone().into(),
             })
             .send()
             .await
@@ -162,10 +400,217 @@ mod tests {
                 "{}/repositories/invalid%3Aname/sessions",
                 server.base_url
             ))
-            .json(&OpenRepositoryRequest { password })
+            .json(&OpenRepositoryRequest {
+                password: password.into(),
+            })
             .send()
             .await
             .expect("open response");
         assert_eq!(open_response.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn repositories_are_listed_renamed_and_deleted() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+        create_repo(&client, &server, "home", &password).await;
+        create_repo(&client, &server, "work", &password).await;
+        let repositories = RemoteRepositoriesClient::from_pem_files(
+            &server.base_url,
+            server.certs_dir().join("client-identity.pem"),
+            server.certs_dir().join("ca.pem"),
+        )
+        .await
+        .expect("repositories client");
+        assert_eq!(
+            repositories.list_repositories().await.expect("list"),
+            vec!["home", "work"]
+        );
+
+        let session = open_session(&client, &server, "home", &password).await;
+        assert!(matches!(
+            repositories
+                .rename_repository("home", "family", password.clone().into())
+                .await,
+            Err(ManageRepositoryError::RepositoryInUse)
+        ));
+        client
+            .delete(format!("{}/session", server.base_url))
+            .bearer_auth(&session.session_id)
+            .send()
+            .await
+            .expect("close response");
+
+        assert!(matches!(
+            repositories
+                .rename_repository("home", "work", password.clone().into())
+                .await,
+            Err(ManageRepositoryError::RepositoryAlreadyExists)
+        ));
+        repositories
+            .rename_repository("home", "family", password.clone().into())
+            .await
+            .expect("rename");
+        assert!(matches!(
+            repositories
+                .delete_repository("work", test_password().into())
+                .await,
+            Err(ManageRepositoryError::WrongPassword)
+        ));
+        repositories
+            .delete_repository("work", password.clone().into())
+            .await
+            .expect("delete");
+        assert_eq!(
+            repositories.list_repositories().await.expect("list"),
+            vec!["family"]
+        );
+        open_session(&client, &server, "family", &password).await;
+    }
+
+    #[tokio::test]
+    async fn backups_are_listed_and_restored() {
+        let server = spawn_test_server().await.expect("server");
+        let password = test_password();
+        let repositories = RemoteRepositoriesClient::from_pem_files(
+            &server.base_url,
+            server.certs_dir().join("client-identity.pem"),
+            server.certs_dir().join("ca.pem"),
+        )
+        .await
+        .expect("repositories client");
+        let mut repository = repositories
+            .create_repository("home", password.clone().into())
+            .await
+            .expect("create");
+        repository
+            .add_record(Record::new(vec![("name".to_string(), "mail".to_string())]))
+            .await
+            .expect("add");
+        repository.save().await.expect("save");
+
+        let backups = repositories.list_backups("home").await.expect("backups");
+        assert_eq!(backups.len(), 1);
+        assert!(matches!(
+            repositories
+                .restore_backup("home", &backups[0].id, password.clone().into())
+                .await,
+            Err(ManageRepositoryError::RepositoryInUse)
+        ));
+        repository.close().await.expect("close");
+        assert!(matches!(
+            repositories
+                .restore_backup("home", "missing", password.clone().into())
+                .await,
+            Err(ManageRepositoryError::DoesntExist)
+        ));
+        repositories
+            .restore_backup("home", &backups[0].id, password.clone().into())
+            .await
+            .expect("restore");
+
+        let restored = repositories
+            .open_repository("home", password.into())
+            .await
+            .expect("open");
+        assert!(restored.get_records().await.expect("records").is_empty());
+        assert_eq!(
+            repositories
+                .list_backups("home")
+                .await
+                .expect("backups")
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn sessions_wait_while_the_repository_is_being_replaced() {
+        let server = spawn_plain_test_server(Acl::default())
+            .await
+            .expect("server");
+        let password = test_password();
+        let status = server
+            .request(reqwest::Method::POST, "/repositories/home", "alice")
+            .json(&CreateRepositoryRequest {
+                password: password.clone().into(),
+            })
+            .send()
+            .await
+            .expect("create")
+            .status();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let name_lock = server.state.lock_name("home").await;
+        let open = server
+            .request(
+                reqwest::Method::POST,
+                "/repositories/home/sessions",
+                "alice",
+            )
+            .json(&OpenRepositoryRequest {
+                password: password.into(),
+            })
+            .send();
+        tokio::pin!(open);
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(200), &mut open)
+                .await
+                .is_err()
+        );
+        assert!(!server.state.has_sessions("home").await);
+
+        drop(name_lock);
+        assert_eq!(open.await.expect("open").status(), StatusCode::OK);
+        assert!(server.state.has_sessions("home").await);
+    }
+
+    #[tokio::test]
+    async fn audit_log_records_the_outcome_of_each_request() {
+        let server = spawn_plain_test_server(Acl::default())
+            .await
+            .expect("server");
+        let password = test_password();
+        let delete = |client: &str, password: &str| {
+            server
+                .request(reqwest::Method::DELETE, "/repositories/home", client)
+                .json(&DeleteRepositoryRequest {
+                    password: password.into(),
+                })
+                .send()
+        };
+        let status = server
+            .request(reqwest::Method::POST, "/repositories/home", "alice")
+            .json(&CreateRepositoryRequest {
+                password: password.clone().into(),
+            })
+            .send()
+            .await
+            .expect("create")
+            .status();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let status = delete("mallory", &password).await.expect("delete").status();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let status = delete("alice", "Wrong").await.expect("delete").status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let status = delete("alice", &password).await.expect("delete").status();
+        assert_eq!(status, StatusCode::OK);
+
+        let deletes = server
+            .state
+            .audit_log
+            .query(&AuditQuery {
+                action: Some(AuditAction::Delete),
+                ..AuditQuery::default()
+            })
+            .await
+            .expect("query");
+        let outcomes: Vec<AuditOutcome> = deletes.iter().map(|entry| entry.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![AuditOutcome::Denied, AuditOutcome::Failed, AuditOutcome::Ok]
+        );
+    }
 }
@@ -5,12 +5,21 @@ use axum::{
     routing::{delete, get, post},
     Json, Router,
 };
+use sec_store::breach::{BreachedRecord, PwnedHashes};
+use sec_store::export::{export_records, ExportFormat};
 use sec_store::record::{Record, RecordId};
-use sec_store::repository::remote::{AddRecordRequest, UpdateRecordRequest};
+use sec_store::repository::remote::{
+    AddRecordRequest, ChangePasswordRequest, ExportRequest, RebaseResponse, ShareRecordsRequest,
+    ShareRecordsResponse, UpdateRecordRequest,
+};
+use sec_store::repository::share::share_records;
 use sec_store::repository::RecordsRepository;
 
 use super::SimpleStatus;
-use crate::{ApiError, AppState};
+use crate::acl::Access;
+use crate::audit_log::AuditAction;
+use crate::client_identity::ClientIdentity;
+use crate::{run_blocking, ApiError, AppState};
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -21,8 +30,15 @@ pub fn router() -> Router<AppState> {
             get(get_record).put(update_record).delete(delete_record),
         )
         .route("/session/save", post(save_session))
+        .route("/session/rebase", post(rebase_session))
         .route("/session/cancel", post(cancel_session))
-        .route("/session/export", get(export_repository))
+        .route(
+            "/session/export",
+            get(export_repository).post(export_in_format),
+        )
+        .route("/session/password", post(change_password))
+        .route("/session/share", post(share_selected_records))
+        .route("/session/breaches", get(check_breaches))
 }
 
 fn session_id_from_headers(headers: &HeaderMap) -> Result<&str, ApiError> {
@@ -40,29 +56,38 @@ fn session_id_from_headers(headers: &HeaderMap) -> Result<&str, ApiError> {
 
 async fn authorized_session(
     state: &AppState,
+    client: &ClientIdentity,
     headers: &HeaderMap,
+    required: Access,
 ) -> Result<tokio::sync::OwnedMutexGuard<crate::SessionState>, ApiError> {
-    let session = state.get_session(session_id_from_headers(headers)?).await?;
+    let session = state
+        .get_session(session_id_from_headers(headers)?, client, required)
+        .await?;
     Ok(session.lock_owned().await)
 }
 
-async fn close_session(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
-    let Ok(session_id) = session_id_from_headers(&headers) else {
-        return StatusCode::UNAUTHORIZED;
-    };
-    let removed = state.sessions.write().await.remove(session_id);
-    if removed.is_some() {
-        StatusCode::NO_CONTENT
-    } else {
-        StatusCode::NOT_FOUND
+async fn close_session(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let session_id = session_id_from_headers(&headers)?;
+    let session = state.get_session(session_id, &client, Access::Read).await?;
+    let repository_name = session.lock().await.repository_name.clone();
+    if state.remove_session(session_id).await {
+        state
+            .audit(&client, AuditAction::Close, &repository_name, None, Ok(()))
+            .await?;
     }
+    Ok(StatusCode::NO_CONTENT)
 }
 
 async fn list_records(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
 ) -> Result<Json<Vec<Record>>, ApiError> {
-    let session = authorized_session(&state, &headers).await?;
+    let session = authorized_session(&state, &client, &headers, Access::Read).await?;
     Ok(Json(
         session
             .repository
@@ -74,10 +99,11 @@ async fn list_records(
 
 async fn get_record(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
     AxumPath(record_id): AxumPath<String>,
 ) -> Result<Json<Record>, ApiError> {
-    let session = authorized_session(&state, &headers).await?;
+    let session = authorized_session(&state, &client, &headers, Access::Read).await?;
     let record = session
         .repository
         .get(&record_id)
@@ -89,10 +115,11 @@ async fn get_record(
 
 async fn add_record(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
     Json(request): Json<AddRecordRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let mut session = authorized_session(&state, &headers).await?;
+    let mut session = authorized_session(&state, &client, &headers, Access::ReadWrite).await?;
     session
         .repository
         .add_record(request.record)
@@ -103,6 +130,7 @@ async fn add_record(
 
 async fn update_record(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
     AxumPath(record_id): AxumPath<String>,
     Json(request): Json<UpdateRecordRequest>,
@@ -113,7 +141,7 @@ async fn update_record(
         ));
     }
 
-    let mut session = authorized_session(&state, &headers).await?;
+    let mut session = authorized_session(&state, &client, &headers, Access::ReadWrite).await?;
     session
         .repository
         .update(request.record)
@@ -124,10 +152,11 @@ async fn update_record(
 
 async fn delete_record(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
     AxumPath(record_id): AxumPath<RecordId>,
 ) -> Result<StatusCode, ApiError> {
-    let mut session = authorized_session(&state, &headers).await?;
+    let mut session = authorized_session(&state, &client, &headers, Access::ReadWrite).await?;
     session
         .repository
         .delete(&record_id)
@@ -138,9 +167,58 @@ async fn delete_record(
 
 async fn save_session(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
 ) -> Result<Json<SimpleStatus>, ApiError> {
-    let mut session = authorized_session(&state, &headers).await?;
+    let mut session = authorized_session(&state, &client, &headers, Access::ReadWrite).await?;
+    let repository_name = session.repository_name.clone();
+    let result = async {
+        let repository_lock = state
+            .repository_lock(session.repository.identifier.as_str())
+            .await;
+        let _repository_lock = repository_lock.lock().await;
+        let current_persisted = session
+            .repository
+            .persisted_dump()
+            .await
+            .map_err(ApiError::internal)?;
+        if current_persisted != session.persisted_snapshot {
+            // Merged on a copy so a rejected save leaves the session untouched.
+            let mut merged = session.repository.clone();
+            let conflicts = merged.merge_persisted().await.map_err(|_| {
+                ApiError::conflict("Repository changed in another session. Reopen and retry.")
+            })?;
+            if !conflicts.is_empty() {
+                return Err(ApiError::save_conflict(conflicts));
+            }
+            session.repository = merged;
+        }
+        // Saving a repository that still uses the legacy KDF derives a new key.
+        let mut repository = session.repository.clone();
+        session.repository =
+            run_blocking(async move { repository.save().await.map(|_| repository) })
+                .await?
+                .map_err(ApiError::internal)?;
+        session.persisted_snapshot = session
+            .repository
+            .persisted_dump()
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(())
+    }
+    .await;
+    state
+        .audit(&client, AuditAction::Save, &repository_name, None, result)
+        .await?;
+    Ok(Json(SimpleStatus::new("saved")))
+}
+
+async fn rebase_session(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    headers: HeaderMap,
+) -> Result<Json<RebaseResponse>, ApiError> {
+    let mut session = authorized_session(&state, &client, &headers, Access::Read).await?;
     let repository_lock = state
         .repository_lock(session.repository.identifier.as_str())
         .await;
@@ -150,29 +228,80 @@ async fn save_session(
         .persisted_dump()
         .await
         .map_err(ApiError::internal)?;
-    if current_persisted != session.persisted_snapshot {
-        return Err(ApiError::conflict(
-            "Repository changed in another session. Reopen and retry.",
-        ));
+    if current_persisted == session.persisted_snapshot {
+        return Ok(Json(RebaseResponse {
+            conflicts: Vec::new(),
+        }));
     }
-    session
-        .repository
-        .save()
-        .await
-        .map_err(ApiError::internal)?;
-    session.persisted_snapshot = session
-        .repository
-        .persisted_dump()
-        .await
-        .map_err(ApiError::internal)?;
-    Ok(Json(SimpleStatus::new("saved")))
+    let conflicts = session.repository.merge_persisted().await.map_err(|_| {
+        ApiError::conflict("Repository changed in another session. Reopen and retry.")
+    })?;
+    session.persisted_snapshot = current_persisted;
+    Ok(Json(RebaseResponse { conflicts }))
+}
+
+async fn change_password(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    headers: HeaderMap,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Json<SimpleStatus>, ApiError> {
+    if request.new_password.is_empty() {
+        return Err(ApiError::bad_request("New password cannot be empty"));
+    }
+
+    let mut session = authorized_session(&state, &client, &headers, Access::Owner).await?;
+    let repository_name = session.repository_name.clone();
+    let result = async {
+        let repository_lock = state
+            .repository_lock(session.repository.identifier.as_str())
+            .await;
+        let _repository_lock = repository_lock.lock().await;
+        let current_persisted = session
+            .repository
+            .persisted_dump()
+            .await
+            .map_err(ApiError::internal)?;
+        if current_persisted != session.persisted_snapshot {
+            return Err(ApiError::conflict(
+                "Repository changed in another session. Reopen and retry.",
+            ));
+        }
+        let mut repository = session.repository.clone();
+        session.repository = run_blocking(async move {
+            repository
+                .change_password(request.old_password, request.new_password)
+                .await
+                .map(|_| repository)
+        })
+        .await?
+        .map_err(ApiError::from_change_password_error)?;
+        session.persisted_snapshot = session
+            .repository
+            .persisted_dump()
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(())
+    }
+    .await;
+    state
+        .audit(
+            &client,
+            AuditAction::ChangePassword,
+            &repository_name,
+            None,
+            result,
+        )
+        .await?;
+    Ok(Json(SimpleStatus::new("password changed")))
 }
 
 async fn cancel_session(
     State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
 ) -> Result<Json<SimpleStatus>, ApiError> {
-    let mut session = authorized_session(&state, &headers).await?;
+    let mut session = authorized_session(&state, &client, &headers, Access::Read).await?;
     session
         .repository
         .cancel()
@@ -183,29 +312,168 @@ async fn cancel_session(
 
 async fn export_repository(
     State(state): State<AppState>,
+    client: ClientIdentity,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let session = authorized_session(&state, &client, &headers, Access::Read).await?;
+    let result = session.repository.dump().await.map_err(ApiError::internal);
+    let dump = state
+        .audit(
+            &client,
+            AuditAction::Export,
+            &session.repository_name,
+            Some(ExportFormat::Encrypted.to_string()),
+            result,
+        )
+        .await?;
+
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], dump))
+}
+
+async fn export_in_format(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    headers: HeaderMap,
+    Json(request): Json<ExportRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if request.format == ExportFormat::Encrypted
+        && request
+            .password
+            .as_ref()
+            .is_none_or(|password| password.is_empty())
+    {
+        return Err(ApiError::bad_request("Encrypted export needs a password"));
+    }
+    let session = authorized_session(&state, &client, &headers, Access::Read).await?;
+    let repository_name = session.repository_name.clone();
+    let format = request.format;
+    let result = async {
+        if request
+            .password
+            .as_ref()
+            .is_some_and(|password| session.repository.is_password(password))
+        {
+            return Err(ApiError::bad_request(
+                "The export password must differ from the repository password",
+            ));
+        }
+        let mut records = session
+            .repository
+            .get_records()
+            .await
+            .map_err(ApiError::internal)?;
+        drop(session);
+        if let Some(record_ids) = &request.record_ids {
+            records.retain(|record| record_ids.contains(&record.id));
+        }
+
+        tokio::task::spawn_blocking(move || export_records(&records, format, request.password))
+            .await
+            .map_err(ApiError::internal)?
+            .map_err(ApiError::internal)
+    }
+    .await;
+    let data = state
+        .audit(
+            &client,
+            AuditAction::Export,
+            &repository_name,
+            Some(format.to_string()),
+            result,
+        )
+        .await?;
+    Ok(([(header::CONTENT_TYPE, format.content_type())], data))
+}
+
+async fn share_selected_records(
+    State(state): State<AppState>,
+    client: ClientIdentity,
     headers: HeaderMap,
+    Json(request): Json<ShareRecordsRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let session = authorized_session(&state, &headers).await?;
-    let dump = session
+    if request.password.is_empty() {
+        return Err(ApiError::bad_request("Password cannot be empty"));
+    }
+    let session = authorized_session(&state, &client, &headers, Access::Read).await?;
+    let shared_name = request.repository_name.clone();
+    let result = async {
+        state
+            .authorize(&client, &request.repository_name, Access::Owner)
+            .await?;
+        let (repository, repositories) = (session.repository.clone(), state.repositories.clone());
+        let (target_name, selection) = (request.repository_name.clone(), request.selection);
+        let records = run_blocking(async move {
+            share_records(
+                &repository,
+                &repositories,
+                &target_name,
+                request.password,
+                &selection,
+            )
+            .await
+        })
+        .await?
+        .map_err(ApiError::from_share_error)?;
+        state.set_owner(&request.repository_name, &client).await?;
+        Ok(records)
+    }
+    .await;
+    let records = state
+        .audit(
+            &client,
+            AuditAction::Share,
+            &session.repository_name,
+            Some(shared_name),
+            result,
+        )
+        .await?;
+    Ok((StatusCode::CREATED, Json(ShareRecordsResponse { records })))
+}
+
+async fn check_breaches(
+    State(state): State<AppState>,
+    client: ClientIdentity,
+    headers: HeaderMap,
+) -> Result<Json<Vec<BreachedRecord>>, ApiError> {
+    let session = authorized_session(&state, &client, &headers, Access::Read).await?;
+    let hashes_file = state
+        .pwned_hashes_file
+        .clone()
+        .ok_or_else(|| ApiError::not_found("Breach check is not configured on this server"))?;
+    let records = session
         .repository
-        .dump()
+        .get_records()
         .await
         .map_err(ApiError::internal)?;
+    drop(session);
 
-    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], dump))
+    let breached = tokio::task::spawn_blocking(move || {
+        PwnedHashes::open(&hashes_file)?.check_records(&records)
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::internal)?;
+    Ok(Json(breached))
 }
 
 #[cfg(test)]
 mod tests {
     use axum::http::StatusCode;
-    use sec_store::record::Record;
+    use sec_store::breach::BreachedRecord;
+    use sec_store::export::ExportFormat;
+    use sec_store::record::{Record, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
     use sec_store::repository::file::OpenRecordsFileRepository;
-    use sec_store::repository::remote::AddRecordRequest;
+    use sec_store::repository::remote::{
+        AddRecordRequest, ChangePasswordRequest, ErrorResponse, ExportRequest, RebaseResponse,
+        ShareRecordsRequest, ShareRecordsResponse, UpdateRecordRequest,
+    };
+    use sec_store::repository::share::RecordSelection;
     use sec_store::repository::{OpenRepository, RecordsRepository};
     use tempfile::TempDir;
 
     use crate::test_support::{
-        build_client, create_repo, open_session, spawn_test_server, test_password,
+        build_client, create_repo, open_session, spawn_test_server, test_password, PWNED_PASSWORD,
+        PWNED_PASSWORD_COUNT,
     };
 
     #[tokio::test]
@@ -242,7 +510,7 @@ mod tests {
         let dump_path = temp_dir.path().join("repo.json");
         std::fs::write(&dump_path, dump).expect("write dump");
         let dumped_repo = OpenRecordsFileRepository(dump_path)
-            .open(password)
+            .open(password.into())
             .await
             .expect("open dumped repo");
         let records = dumped_repo.get_records().await.expect("records");
@@ -250,7 +518,161 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn concurrent_save_returns_conflict_instead_of_overwriting() {
+    async fn export_in_format_writes_selected_records() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+
+        create_repo(&client, &server, "demo", &password).await;
+        let session = open_session(&client, &server, "demo", &password).await;
+        let shared = Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), "shared".to_string()),
+            (RECORD_PASSWD_FIELD.to_string(), "team-secret".to_string()),
+        ]);
+        let private = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "private".to_string())]);
+        for record in [&shared, &private] {
+            client
+                .post(format!("{}/session/records", server.base_url))
+                .bearer_auth(&session.session_id)
+                .json(&AddRecordRequest {
+                    record: record.clone(),
+                })
+                .send()
+                .await
+                .expect("add response");
+        }
+        let export = |request: ExportRequest| {
+            client
+                .post(format!("{}/session/export", server.base_url))
+                .bearer_auth(&session.session_id)
+                .json(&request)
+                .send()
+        };
+
+        let csv_response = export(ExportRequest {
+            format: ExportFormat::Csv,
+            password: None,
+            record_ids: Some(vec![shared.id.clone()]),
+        })
+        .await
+        .expect("csv response");
+        assert_eq!(csv_response.status(), StatusCode::OK);
+        assert_eq!(
+            csv_response.headers()["content-type"].to_str().unwrap(),
+            "text/csv"
+        );
+        let csv = csv_response.text().await.expect("csv");
+        assert!(csv.contains("shared,,team-secret"));
+        assert!(!csv.contains("private"));
+
+        let missing_password = export(ExportRequest {
+            format: ExportFormat::Encrypted,
+            password: None,
+            record_ids: None,
+        })
+        .await
+        .expect("encrypted response");
+        assert_eq!(missing_password.status(), StatusCode::BAD_REQUEST);
+        let reused_password = export(ExportRequest {
+            format: ExportFormat::Encrypted,
+            password: Some(password.clone().into()),
+            record_ids: None,
+        })
+        .await
+        .expect("encrypted response");
+        assert_eq!(reused_password.status(), StatusCode::BAD_REQUEST);
+
+        let encrypted = export(ExportRequest {
+            format: ExportFormat::Encrypted,
+            password: Some("colleague".into()),
+            record_ids: Some(vec![shared.id.clone()]),
+        })
+        .await
+        .expect("encrypted response")
+        .bytes()
+        .await
+        .expect("encrypted bytes");
+        let temp_dir = TempDir::new().expect("temp dir");
+        let export_path = temp_dir.path().join("export.json");
+        std::fs::write(&export_path, encrypted).expect("write export");
+        let exported_repo = OpenRecordsFileRepository(export_path)
+            .open("colleague".into())
+            .await
+            .expect("open exported repo");
+        assert_eq!(
+            exported_repo.get_records().await.expect("records"),
+            vec![shared]
+        );
+    }
+
+    #[tokio::test]
+    async fn share_creates_repository_with_selected_records() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+
+        create_repo(&client, &server, "demo", &password).await;
+        let session = open_session(&client, &server, "demo", &password).await;
+        for name in ["project-x db", "personal mail"] {
+            client
+                .post(format!("{}/session/records", server.base_url))
+                .bearer_auth(&session.session_id)
+                .json(&AddRecordRequest {
+                    record: Record::new(vec![(RECORD_NAME_FIELD.to_string(), name.to_string())]),
+                })
+                .send()
+                .await
+                .expect("add response");
+        }
+        let share = |repository_name: &str, query: &str| {
+            client
+                .post(format!("{}/session/share", server.base_url))
+                .bearer_auth(&session.session_id)
+                .json(&ShareRecordsRequest {
+                    repository_name: repository_name.to_string(),
+                    password: "colleague".into(),
+                    selection: RecordSelection::Search(query.to_string()),
+                })
+                .send()
+        };
+
+        let response = share("project-x", "project-x")
+            .await
+            .expect("share response");
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = response
+            .json::<ShareRecordsResponse>()
+            .await
+            .expect("share body");
+        assert_eq!(response.records, 1);
+        assert_eq!(
+            share("project-x", "project-x").await.unwrap().status(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            share("nothing", "no such record").await.unwrap().status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let shared = open_session(&client, &server, "project-x", "colleague").await;
+        let records = client
+            .get(format!("{}/session/records", server.base_url))
+            .bearer_auth(&shared.session_id)
+            .send()
+            .await
+            .expect("records response")
+            .json::<Vec<Record>>()
+            .await
+            .expect("records");
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].get_field_value(RECORD_NAME_FIELD).unwrap(),
+            "project-x db"
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_saves_merge_independent_changes() {
         let server = spawn_test_server().await.expect("server");
         let client = build_client(&server, true).await.expect("client");
         let password = test_password();
@@ -277,7 +699,7 @@ mod tests {
             .post(format!("{}/session/records", server.base_url))
             .bearer_auth(&second.session_id)
             .json(&AddRecordRequest {
-                record: second_record,
+                record: second_record.clone(),
             })
             .send()
             .await
@@ -308,10 +730,177 @@ mod tests {
                 .status()
         };
         let (first_status, second_status) = tokio::join!(first_request, second_request);
-        let mut statuses = vec![first_status, second_status];
-        statuses.sort();
+        assert_eq!(
+            (first_status, second_status),
+            (StatusCode::OK, StatusCode::OK)
+        );
+
+        let verify = open_session(&client, &server, "demo", &password).await;
+        let mut records = client
+            .get(format!("{}/session/records", server.base_url))
+            .bearer_auth(&verify.session_id)
+            .send()
+            .await
+            .expect("records response")
+            .json::<Vec<Record>>()
+            .await
+            .expect("records json");
+        records.sort_by_key(|record| record.get_field_value("name").unwrap().to_string());
+        assert_eq!(records, vec![first_record, second_record]);
+    }
+
+    #[tokio::test]
+    async fn conflicting_saves_return_conflict_instead_of_overwriting() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+
+        create_repo(&client, &server, "demo", &password).await;
+        let setup = open_session(&client, &server, "demo", &password).await;
+        let record = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "db".to_string())]);
+        client
+            .post(format!("{}/session/records", server.base_url))
+            .bearer_auth(&setup.session_id)
+            .json(&AddRecordRequest {
+                record: record.clone(),
+            })
+            .send()
+            .await
+            .expect("add");
+        client
+            .post(format!("{}/session/save", server.base_url))
+            .bearer_auth(&setup.session_id)
+            .send()
+            .await
+            .expect("save");
+
+        let mut statuses = Vec::new();
+        let sessions = [
+            open_session(&client, &server, "demo", &password).await,
+            open_session(&client, &server, "demo", &password).await,
+        ];
+        for (session, name) in sessions.iter().zip(["first", "second"]) {
+            let mut renamed = record.clone();
+            renamed
+                .update_field(RECORD_NAME_FIELD.to_string(), name)
+                .unwrap();
+            client
+                .put(format!("{}/session/records/{}", server.base_url, record.id))
+                .bearer_auth(&session.session_id)
+                .json(&UpdateRecordRequest { record: renamed })
+                .send()
+                .await
+                .expect("update");
+        }
+        for session in &sessions {
+            statuses.push(
+                client
+                    .post(format!("{}/session/save", server.base_url))
+                    .bearer_auth(&session.session_id)
+                    .send()
+                    .await
+                    .expect("save")
+                    .status(),
+            );
+        }
         assert_eq!(statuses, vec![StatusCode::OK, StatusCode::CONFLICT]);
 
+        let verify = open_session(&client, &server, "demo", &password).await;
+        let saved = client
+            .get(format!("{}/session/records/{}", server.base_url, record.id))
+            .bearer_auth(&verify.session_id)
+            .send()
+            .await
+            .expect("record response")
+            .json::<Record>()
+            .await
+            .expect("record json");
+        assert_eq!(saved.get_field_value(RECORD_NAME_FIELD).unwrap(), "first");
+    }
+
+    #[tokio::test]
+    async fn save_conflict_lists_records_and_rebase_keeps_session_changes() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+
+        create_repo(&client, &server, "demo", &password).await;
+        let setup = open_session(&client, &server, "demo", &password).await;
+        let contested = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "db".to_string())]);
+        let untouched = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "mail".to_string())]);
+        for record in [&contested, &untouched] {
+            client
+                .post(format!("{}/session/records", server.base_url))
+                .bearer_auth(&setup.session_id)
+                .json(&AddRecordRequest {
+                    record: record.clone(),
+                })
+                .send()
+                .await
+                .expect("add");
+        }
+        client
+            .post(format!("{}/session/save", server.base_url))
+            .bearer_auth(&setup.session_id)
+            .send()
+            .await
+            .expect("save");
+
+        let first = open_session(&client, &server, "demo", &password).await;
+        let second = open_session(&client, &server, "demo", &password).await;
+        let edits = [
+            (&first, &contested, "first"),
+            (&first, &untouched, "mail (work)"),
+            (&second, &contested, "second"),
+        ];
+        for (session, record, name) in edits {
+            let mut renamed = record.clone();
+            renamed
+                .update_field(RECORD_NAME_FIELD.to_string(), name)
+                .unwrap();
+            client
+                .put(format!("{}/session/records/{}", server.base_url, record.id))
+                .bearer_auth(&session.session_id)
+                .json(&UpdateRecordRequest { record: renamed })
+                .send()
+                .await
+                .expect("update");
+        }
+        let save = |session_id: String| {
+            client
+                .post(format!("{}/session/save", server.base_url))
+                .bearer_auth(session_id)
+                .send()
+        };
+        assert_eq!(
+            save(first.session_id.clone()).await.expect("save").status(),
+            StatusCode::OK
+        );
+
+        let rejected = save(second.session_id.clone()).await.expect("save");
+        assert_eq!(rejected.status(), StatusCode::CONFLICT);
+        let body = rejected.json::<ErrorResponse>().await.expect("error json");
+        assert_eq!(body.conflicts.len(), 1);
+        assert_eq!(body.conflicts[0].record_id, contested.id);
+
+        let rebase = client
+            .post(format!("{}/session/rebase", server.base_url))
+            .bearer_auth(&second.session_id)
+            .send()
+            .await
+            .expect("rebase")
+            .json::<RebaseResponse>()
+            .await
+            .expect("rebase json");
+        assert_eq!(rebase.conflicts, body.conflicts);
+        assert_eq!(
+            save(second.session_id.clone())
+                .await
+                .expect("save")
+                .status(),
+            StatusCode::OK
+        );
+
         let verify = open_session(&client, &server, "demo", &password).await;
         let records = client
             .get(format!("{}/session/records", server.base_url))
@@ -322,7 +911,17 @@ mod tests {
             .json::<Vec<Record>>()
             .await
             .expect("records json");
-        assert_eq!(records, vec![first_record]);
+        let mut names: Vec<String> = records
+            .iter()
+            .map(|record| {
+                record
+                    .get_field_value(RECORD_NAME_FIELD)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["mail (work)", "second"]);
     }
 
     #[tokio::test]
@@ -350,4 +949,114 @@ mod tests {
             .expect("second save");
         assert_eq!(second_save.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn change_password_rekeys_repository() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+        let new_password = test_password();
+
+        create_repo(&client, &server, "demo", &password).await;
+        let session = open_session(&client, &server, "demo", &password).await;
+
+        let wrong = client
+            .post(format!("{}/session/password", server.base_url))
+            .bearer_auth(&session.session_id)
+            .json(&ChangePasswordRequest {
+                old_password: new_password.clone().into(),
+                new_password: new_password.clone().into(),
+            })
+            .send()
+            .await
+            .expect("wrong password response");
+        assert_eq!(wrong.status(), StatusCode::FORBIDDEN);
+
+        let changed = client
+            .post(format!("{}/session/password", server.base_url))
+            .bearer_auth(&session.session_id)
+            .json(&ChangePasswordRequest {
+                old_password: password.clone().into(),
+                new_password: new_password.clone().into(),
+            })
+            .send()
+            .await
+            .expect("change password response");
+        assert_eq!(changed.status(), StatusCode::OK);
+
+        let old_open = client
+            .post(format!("{}/repositories/demo/sessions", server.base_url))
+            .json(&sec_store::repository::remote::OpenRepositoryRequest {
+                password: password.into(),
+            })
+            .send()
+            .await
+            .expect("open with old password");
+        assert_eq!(old_open.status(), StatusCode::UNAUTHORIZED);
+        open_session(&client, &server, "demo", &new_password).await;
+
+        let save = client
+            .post(format!("{}/session/save", server.base_url))
+            .bearer_auth(&session.session_id)
+            .send()
+            .await
+            .expect("save after password change");
+        assert_eq!(save.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn breach_check_reports_session_records() {
+        let server = spawn_test_server().await.expect("server");
+        let client = build_client(&server, true).await.expect("client");
+        let password = test_password();
+
+        create_repo(&client, &server, "demo", &password).await;
+        let session = open_session(&client, &server, "demo", &password).await;
+
+        let breached = Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), "Forum"),
+            (RECORD_PASSWD_FIELD.to_string(), PWNED_PASSWORD),
+        ]);
+        let safe = Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), "Bank"),
+            (RECORD_PASSWD_FIELD.to_string(), "u7#Kp2$wQz9!mR4x"),
+        ]);
+        for record in [breached.clone(), safe] {
+            let response = client
+                .post(format!("{}/session/records", server.base_url))
+                .bearer_auth(&session.session_id)
+                .json(&AddRecordRequest { record })
+                .send()
+                .await
+                .expect("add response");
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let response = client
+            .get(format!("{}/session/breaches", server.base_url))
+            .bearer_auth(&session.session_id)
+            .send()
+            .await
+            .expect("breaches response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let report = response
+            .json::<Vec<BreachedRecord>>()
+            .await
+            .expect("breaches json");
+        assert_eq!(
+            report,
+            vec![BreachedRecord {
+                record_id: breached.id,
+                name: "Forum".to_string(),
+                occurrences: PWNED_PASSWORD_COUNT,
+            }]
+        );
+
+        let unauthorized = client
+            .get(format!("{}/session/breaches", server.base_url))
+            .send()
+            .await
+            .expect("unauthorized response");
+        assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+    }
 }
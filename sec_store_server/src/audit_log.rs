@@ -0,0 +1,331 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use ring::digest;
+use sec_store::record::unix_now;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::acl::ClientPattern;
+use crate::client_identity::ClientIdentity;
+
+/// Name of the audit log in the server state directory.
+pub const AUDIT_LOG_FILE: &str = "audit.log";
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Open,
+    Save,
+    Export,
+    Share,
+    ChangePassword,
+    Close,
+    /// The session was closed by the server after its idle timeout or lifetime.
+    Expire,
+    Rename,
+    Delete,
+    Restore,
+}
+
+/// How an audited request ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Ok,
+    /// Answered with 403 Forbidden, mostly for a client without the access the action needs.
+    Denied,
+    /// Refused or failed for any other reason, e.g. a wrong password or an existing name.
+    Failed,
+}
+
+/// One line of the audit log. Records never appear in it, only who did what to which repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, from 1.
+    pub seq: u64,
+    pub at: u64,
+    /// Fingerprint of the client certificate.
+    pub client: String,
+    pub client_common_name: Option<String>,
+    pub action: AuditAction,
+    pub repository: String,
+    pub outcome: AuditOutcome,
+    /// Free form context, e.g. the new name of a renamed repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub prev_hash: String,
+    /// SHA-256 of the entry serialized with an empty `hash`, chaining it to the previous one.
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let unhashed = AuditEntry {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let json = serde_json::to_vec(&unhashed).expect("audit entries always serialize");
+        digest::digest(&digest::SHA256, &json)
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// Filters of `GET /admin/audit`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub repository: Option<String>,
+    /// A fingerprint, or a common name prefixed with `cn:`.
+    pub client: Option<String>,
+    pub action: Option<AuditAction>,
+    pub outcome: Option<AuditOutcome>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Only the last `limit` matching entries.
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry, client: Option<&ClientPattern>) -> bool {
+        self.repository
+            .as_ref()
+            .is_none_or(|repository| *repository == entry.repository)
+            && client.is_none_or(|client| {
+                client.matches(&ClientIdentity {
+                    fingerprint: entry.client.clone(),
+                    common_name: entry.client_common_name.clone(),
+                })
+            })
+            && self.action.is_none_or(|action| action == entry.action)
+            && self.outcome.is_none_or(|outcome| outcome == entry.outcome)
+            && self.since.is_none_or(|since| entry.at >= since)
+            && self.until.is_none_or(|until| entry.at <= until)
+    }
+}
+
+/// The end of a valid audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditChainHead {
+    pub entries: u64,
+    /// Hash of the last entry, keep it elsewhere to detect a truncated log.
+    pub last_hash: String,
+}
+
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read audit log {}", path.display()))
+        }
+    };
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = line?;
+            serde_json::from_str(&line)
+                .with_context(|| format!("Line {} of the audit log is malformed", index + 1))
+        })
+        .collect()
+}
+
+/// Checks every entry of the log and its link to the previous one.
+pub fn verify(path: &Path) -> Result<AuditChainHead> {
+    let mut head = AuditChainHead {
+        entries: 0,
+        last_hash: GENESIS_HASH.to_string(),
+    };
+    for entry in read_entries(path)? {
+        let line = head.entries + 1;
+        if entry.seq != line {
+            bail!("Line {line} has sequence number {}", entry.seq);
+        }
+        if entry.prev_hash != head.last_hash {
+            bail!("Line {line} doesn't follow the previous entry");
+        }
+        if entry.hash != entry.compute_hash() {
+            bail!("Line {line} was modified");
+        }
+        head = AuditChainHead {
+            entries: line,
+            last_hash: entry.hash,
+        };
+    }
+    Ok(head)
+}
+
+#[derive(Debug)]
+struct AuditTail {
+    file: File,
+    head: AuditChainHead,
+}
+
+/// Append-only, hash-chained log of the repository operations, one JSON entry per line.
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    tail: Arc<Mutex<AuditTail>>,
+}
+
+impl AuditLog {
+    /// Refuses a log that doesn't verify, appending would hide where it was broken.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let head = verify(&path).with_context(|| {
+            format!(
+                "Audit log {} is broken, move it aside to start a new one",
+                path.display()
+            )
+        })?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+        Ok(Self {
+            path,
+            tail: Arc::new(Mutex::new(AuditTail { file, head })),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn record(
+        &self,
+        client: &ClientIdentity,
+        action: AuditAction,
+        repository: &str,
+        outcome: AuditOutcome,
+        detail: Option<String>,
+    ) -> Result<()> {
+        let mut tail = self.tail.clone().lock_owned().await;
+        let mut entry = AuditEntry {
+            seq: tail.head.entries + 1,
+            at: unix_now(),
+            client: client.fingerprint.clone(),
+            client_common_name: client.common_name.clone(),
+            action,
+            repository: repository.to_string(),
+            outcome,
+            detail,
+            prev_hash: tail.head.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let path = self.path.clone();
+        // The tail moves to the blocking thread, so the head is updated even when the caller
+        // stops waiting after the line was written.
+        tokio::task::spawn_blocking(move || {
+            tail.file
+                .write_all(&line)
+                .and_then(|()| tail.file.sync_data())
+                .with_context(|| format!("Failed to append to audit log {}", path.display()))?;
+            tail.head = AuditChainHead {
+                entries: entry.seq,
+                last_hash: entry.hash,
+            };
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        // Holding the tail keeps a half written line out of the results.
+        let _tail = self.tail.lock().await;
+        let client = query.client.clone().map(ClientPattern::from);
+        let mut entries: Vec<AuditEntry> = read_entries(&self.path)?
+            .into_iter()
+            .filter(|entry| query.matches(entry, client.as_ref()))
+            .collect();
+        if let Some(limit) = query.limit {
+            entries.drain(..entries.len().saturating_sub(limit));
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_support::test_identity;
+
+    #[tokio::test]
+    async fn log_is_chained_and_tampering_is_detected() {
+        let tmp = TempDir::new().expect("temp dir");
+        let path = tmp.path().join(AUDIT_LOG_FILE);
+        let log = AuditLog::open(path.clone()).expect("open");
+        let (alice, bob) = (test_identity("alice"), test_identity("bob"));
+        log.record(&alice, AuditAction::Open, "home", AuditOutcome::Ok, None)
+            .await
+            .expect("record");
+        log.record(&bob, AuditAction::Export, "team", AuditOutcome::Ok, None)
+            .await
+            .expect("record");
+        log.record(&alice, AuditAction::Close, "home", AuditOutcome::Ok, None)
+            .await
+            .expect("record");
+        drop(log);
+
+        let log = AuditLog::open(path.clone()).expect("reopen");
+        log.record(
+            &alice,
+            AuditAction::Rename,
+            "home",
+            AuditOutcome::Ok,
+            Some("family".to_string()),
+        )
+        .await
+        .expect("record");
+        let head = verify(&path).expect("verify");
+        assert_eq!(head.entries, 4);
+
+        let alice_entries = log
+            .query(&AuditQuery {
+                client: Some("cn:alice".to_string()),
+                limit: Some(2),
+                ..AuditQuery::default()
+            })
+            .await
+            .expect("query");
+        let actions: Vec<AuditAction> = alice_entries.iter().map(|entry| entry.action).collect();
+        assert_eq!(actions, vec![AuditAction::Close, AuditAction::Rename]);
+        let team = log
+            .query(&AuditQuery {
+                repository: Some("team".to_string()),
+                ..AuditQuery::default()
+            })
+            .await
+            .expect("query");
+        assert_eq!(team.len(), 1);
+        assert_eq!(team[0].client, bob.fingerprint);
+
+        let contents = std::fs::read_to_string(&path).expect("read");
+        std::fs::write(&path, contents.replacen("\"team\"", "\"home\"", 1)).expect("write");
+        let error = verify(&path).expect_err("modified entry");
+        assert_eq!(error.to_string(), "Line 2 was modified");
+        assert!(AuditLog::open(path.clone()).is_err());
+
+        let lines: Vec<&str> = contents.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).expect("write");
+        let error = verify(&path).expect_err("removed entry");
+        assert_eq!(error.to_string(), "Line 2 has sequence number 3");
+    }
+}
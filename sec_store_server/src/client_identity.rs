@@ -0,0 +1,197 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum_server::accept::Accept;
+use axum_server::tls_rustls::RustlsAcceptor;
+use ring::digest;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tower_http::add_extension::AddExtension;
+
+use crate::ApiError;
+
+/// The certificate the client authenticated with, available to handlers as an extension.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientIdentity {
+    /// Uppercase hex SHA-256 of the DER certificate.
+    pub fingerprint: String,
+    /// Common name of the certificate subject.
+    pub common_name: Option<String>,
+}
+
+impl ClientIdentity {
+    pub fn from_certificate(der: &[u8]) -> Self {
+        let fingerprint = digest::digest(&digest::SHA256, der)
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        Self {
+            fingerprint,
+            common_name: subject_common_name(der),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIdentity {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientIdentity>()
+            .cloned()
+            .ok_or_else(|| ApiError::unauthorized("Missing client certificate"))
+    }
+}
+
+// Splits the first DER element of `input` into its tag, contents and the bytes after it.
+fn der_element(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&length, mut input) = input.split_first()?;
+    let length = if length < 0x80 {
+        usize::from(length)
+    } else {
+        let size = usize::from(length & 0x7f);
+        if size == 0 || size > 4 || input.len() < size {
+            return None;
+        }
+        let (bytes, rest) = input.split_at(size);
+        input = rest;
+        bytes
+            .iter()
+            .fold(0, |length, byte| length << 8 | usize::from(*byte))
+    };
+    (input.len() >= length).then(|| {
+        let (contents, rest) = input.split_at(length);
+        (tag, contents, rest)
+    })
+}
+
+const COMMON_NAME_OID: &[u8] = &[0x55, 0x04, 0x03];
+const EXPLICIT_VERSION_TAG: u8 = 0xa0;
+
+/// The CN of the subject of a DER X.509 certificate.
+fn subject_common_name(der: &[u8]) -> Option<String> {
+    let (_, certificate, _) = der_element(der)?;
+    let (_, mut tbs, _) = der_element(certificate)?;
+    let mut fields = Vec::new();
+    while fields.len() < 7 && !tbs.is_empty() {
+        let (tag, contents, rest) = der_element(tbs)?;
+        fields.push((tag, contents));
+        tbs = rest;
+    }
+    // [version,] serial number, signature, issuer, validity, subject
+    let version = usize::from(fields.first()?.0 == EXPLICIT_VERSION_TAG);
+    let (_, mut names) = *fields.get(version + 4)?;
+    while !names.is_empty() {
+        let (_, mut attributes, rest) = der_element(names)?;
+        names = rest;
+        while !attributes.is_empty() {
+            let (_, attribute, rest) = der_element(attributes)?;
+            attributes = rest;
+            let (_, oid, value) = der_element(attribute)?;
+            if oid == COMMON_NAME_OID {
+                let (_, value, _) = der_element(value)?;
+                return String::from_utf8(value.to_vec()).ok();
+            }
+        }
+    }
+    None
+}
+
+/// Accepts `openssl x509 -fingerprint -sha256` output as well as plain hex.
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    let fingerprint = fingerprint.trim();
+    let fingerprint = fingerprint
+        .split_once('=')
+        .map_or(fingerprint, |(_, value)| value);
+    fingerprint
+        .chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+/// Runs the TLS handshake and adds the [`ClientIdentity`] of the peer to its requests.
+#[derive(Debug, Clone)]
+pub struct ClientIdentityAcceptor {
+    inner: RustlsAcceptor,
+}
+
+impl ClientIdentityAcceptor {
+    pub fn new(inner: RustlsAcceptor) -> Self {
+        Self { inner }
+    }
+}
+
+type AcceptFuture<I, S> = Pin<
+    Box<dyn Future<Output = io::Result<(TlsStream<I>, AddExtension<S, ClientIdentity>)>> + Send>,
+>;
+
+impl<I, S> Accept<I, S> for ClientIdentityAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = AddExtension<S, ClientIdentity>;
+    type Future = AcceptFuture<I, S>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let acceptor = self.inner.clone();
+        Box::pin(async move {
+            let (stream, service) = acceptor.accept(stream, service).await?;
+            let identity = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| ClientIdentity::from_certificate(cert))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "Missing client certificate",
+                    )
+                })?;
+            Ok((stream, AddExtension::new(service, identity)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{normalize_fingerprint, ClientIdentity};
+    use crate::load_certs;
+
+    #[test]
+    fn identity_has_fingerprint_and_common_name() {
+        let certs = load_certs(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/certs/client-identity.pem"),
+        )
+        .expect("fixture certificate");
+        let identity = ClientIdentity::from_certificate(&certs[0]);
+        assert_eq!(identity.common_name.as_deref(), Some("allowed-client"));
+        assert_eq!(
+            identity.fingerprint,
+            normalize_fingerprint(
+                "DC:28:2A:B0:76:4C:F6:8A:3D:07:F7:31:34:08:42:45:\
+                 B4:83:B6:77:54:8E:CF:96:F6:C5:38:CF:E5:1A:22:1F"
+            )
+        );
+    }
+
+    #[test]
+    fn fingerprints_are_normalized() {
+        assert_eq!(
+            normalize_fingerprint("sha256 Fingerprint=ab:0C:12\n"),
+            "AB0C12"
+        );
+        assert_eq!(normalize_fingerprint("ab0c12"), "AB0C12");
+    }
+}
@@ -0,0 +1,268 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use sec_store::repository::file::BackupPolicy;
+use serde::Deserialize;
+use tracing_subscriber::EnvFilter;
+
+use crate::{ServerConfigPaths, SessionLimits};
+
+/// The TOML server config file. Every value can also be passed on the command line,
+/// which wins over the file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addrs: Vec<SocketAddr>,
+    pub data_dir: Option<PathBuf>,
+    pub tls: TlsConfig,
+    /// Sorted Pwned Passwords SHA-1 list used by the breach check.
+    pub pwned_hashes_file: Option<PathBuf>,
+    pub acl_file: Option<PathBuf>,
+    pub admin_clients: Vec<String>,
+    pub sessions: SessionsConfig,
+    pub backups: BackupsConfig,
+    pub logging: LoggingConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addrs: vec![SocketAddr::from(([127, 0, 0, 1], 8443))],
+            data_dir: None,
+            tls: TlsConfig::default(),
+            pwned_hashes_file: None,
+            acl_file: None,
+            admin_clients: Vec::new(),
+            sessions: SessionsConfig::default(),
+            backups: BackupsConfig::default(),
+            logging: LoggingConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub server_cert_pem: Option<PathBuf>,
+    pub server_key_pem: Option<PathBuf>,
+    pub client_ca_cert_pem: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionsConfig {
+    pub idle_timeout_secs: u64,
+    pub max_lifetime_secs: u64,
+    pub max_per_repository: usize,
+    pub max_per_client: usize,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        let limits = SessionLimits::default();
+        Self {
+            idle_timeout_secs: limits.idle_timeout.as_secs(),
+            max_lifetime_secs: limits.max_lifetime.as_secs(),
+            max_per_repository: limits.max_per_repository,
+            max_per_client: limits.max_per_client,
+        }
+    }
+}
+
+impl From<&SessionsConfig> for SessionLimits {
+    fn from(config: &SessionsConfig) -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(config.idle_timeout_secs),
+            max_lifetime: Duration::from_secs(config.max_lifetime_secs),
+            max_per_repository: config.max_per_repository,
+            max_per_client: config.max_per_client,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupsConfig {
+    pub keep_last: usize,
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+}
+
+impl Default for BackupsConfig {
+    fn default() -> Self {
+        let policy = BackupPolicy::default();
+        Self {
+            keep_last: policy.keep_last,
+            keep_daily: policy.keep_daily,
+            keep_weekly: policy.keep_weekly,
+        }
+    }
+}
+
+impl From<&BackupsConfig> for BackupPolicy {
+    fn from(config: &BackupsConfig) -> Self {
+        Self {
+            keep_last: config.keep_last,
+            keep_daily: config.keep_daily,
+            keep_weekly: config.keep_weekly,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// `tracing` filter directives, `RUST_LOG` takes precedence when set.
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn env_filter(&self) -> Result<EnvFilter> {
+        if let Ok(filter) = EnvFilter::try_from_default_env() {
+            return Ok(filter);
+        }
+        EnvFilter::try_new(&self.filter)
+            .with_context(|| format!("Invalid logging filter \"{}\"", self.filter))
+    }
+}
+
+fn resolve(base_dir: &Path, path: &mut Option<PathBuf>) {
+    if let Some(path) = path.as_mut().filter(|path| path.is_relative()) {
+        *path = base_dir.join(&*path);
+    }
+}
+
+impl ServerConfig {
+    /// Relative paths in the file are relative to the directory of the file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut config: Self = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        for path in [
+            &mut config.data_dir,
+            &mut config.tls.server_cert_pem,
+            &mut config.tls.server_key_pem,
+            &mut config.tls.client_ca_cert_pem,
+            &mut config.pwned_hashes_file,
+            &mut config.acl_file,
+        ] {
+            resolve(base_dir, path);
+        }
+        Ok(config)
+    }
+
+    pub fn data_dir(&self) -> Result<&Path> {
+        match &self.data_dir {
+            Some(data_dir) => Ok(data_dir),
+            None => bail!("data_dir is missing, set it in the config file or pass --data-dir"),
+        }
+    }
+
+    pub fn server_config_paths(&self) -> Result<ServerConfigPaths> {
+        let required = |value: &Option<PathBuf>, name: &str| match value {
+            Some(path) => Ok(path.clone()),
+            None => Err(anyhow!(
+                "tls.{name} is missing, set it in the config file or pass --{}",
+                name.replace('_', "-")
+            )),
+        };
+        if self.bind_addrs.is_empty() {
+            bail!("bind_addrs is empty, the server would not listen anywhere");
+        }
+        Ok(ServerConfigPaths {
+            bind_addrs: self.bind_addrs.clone(),
+            data_dir: self.data_dir()?.to_path_buf(),
+            server_cert_pem: required(&self.tls.server_cert_pem, "server_cert_pem")?,
+            server_key_pem: required(&self.tls.server_key_pem, "server_key_pem")?,
+            client_ca_cert_pem: required(&self.tls.client_ca_cert_pem, "client_ca_cert_pem")?,
+            pwned_hashes_file: self.pwned_hashes_file.clone(),
+            session_limits: SessionLimits::from(&self.sessions),
+            backup_policy: BackupPolicy::from(&self.backups),
+            acl_file: self.acl_file.clone(),
+            admin_clients: self.admin_clients.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn config_file_is_parsed_and_completed_with_defaults() {
+        let tmp = TempDir::new().expect("temp dir");
+        let path = tmp.path().join("server.toml");
+        std::fs::write(
+            &path,
+            r#"
+bind_addrs = ["127.0.0.1:9443", "[::1]:9443"]
+data_dir = "data"
+acl_file = "/etc/sec_store/acl.toml"
+
+[tls]
+server_cert_pem = "certs/server.pem"
+server_key_pem = "certs/server-key.pem"
+client_ca_cert_pem = "certs/ca.pem"
+
+[sessions]
+idle_timeout_secs = 60
+
+[backups]
+keep_last = 0
+keep_daily = 0
+keep_weekly = 0
+"#,
+        )
+        .expect("write");
+
+        let paths = ServerConfig::load(&path)
+            .expect("load")
+            .server_config_paths()
+            .expect("paths");
+
+        assert_eq!(paths.bind_addrs.len(), 2);
+        assert_eq!(paths.data_dir, tmp.path().join("data"));
+        assert_eq!(paths.server_cert_pem, tmp.path().join("certs/server.pem"));
+        assert_eq!(
+            paths.acl_file,
+            Some(PathBuf::from("/etc/sec_store/acl.toml"))
+        );
+        assert_eq!(paths.session_limits.idle_timeout, Duration::from_secs(60));
+        assert_eq!(
+            paths.session_limits.max_per_client,
+            SessionLimits::default().max_per_client
+        );
+        assert!(paths.backup_policy.is_disabled());
+    }
+
+    #[test]
+    fn invalid_config_files_are_rejected() {
+        let tmp = TempDir::new().expect("temp dir");
+        let path = tmp.path().join("server.toml");
+
+        std::fs::write(&path, "data_dir = \"data\"\nbind_addr = \"127.0.0.1:1\"\n").expect("write");
+        assert!(ServerConfig::load(&path).is_err());
+
+        std::fs::write(&path, "data_dir = \"data\"\n").expect("write");
+        let error = ServerConfig::load(&path)
+            .expect("load")
+            .server_config_paths()
+            .expect_err("no certificates");
+        assert!(error.to_string().contains("--server-cert-pem"));
+        assert!(ServerConfig::default().data_dir().is_err());
+    }
+}
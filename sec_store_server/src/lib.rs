@@ -1,99 +1,473 @@
+pub mod acl;
 pub mod api;
+pub mod audit_log;
+pub mod client_identity;
+pub mod config;
+pub mod maintenance;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::io::{BufReader, Cursor};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use acl::{Access, Acl, Owner, RepositoryOwners, OWNERS_FILE};
 use anyhow::{anyhow, Context, Result};
+use audit_log::{AuditAction, AuditLog, AuditOutcome, AUDIT_LOG_FILE};
 use axum::{http::StatusCode, Router};
+use axum_server::tls_rustls::RustlsAcceptor;
+use client_identity::{normalize_fingerprint, ClientIdentity, ClientIdentityAcceptor};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use rustls::server::WebPkiClientVerifier;
 use rustls::{RootCertStore, ServerConfig};
-use sec_store::repository::file::{NamedFileRepositories, RecordsFileRepository};
-use sec_store::repository::{CreateRepositoryError, RepositoryOpenError, UpdateRecordError};
-use tokio::sync::{Mutex, RwLock};
+use sec_store::record::unix_now;
+use sec_store::repository::file::{BackupPolicy, NamedFileRepositories, RecordsFileRepository};
+use sec_store::repository::merge::MergeConflict;
+use sec_store::repository::share::ShareRecordsError;
+use sec_store::repository::{
+    ChangePasswordError, CreateRepositoryError, ManageRepositoryError, RepositoryOpenError,
+    UpdateRecordError,
+};
+use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
+use tokio::task::JoinSet;
 use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct ServerConfigPaths {
-    pub bind_addr: SocketAddr,
+    /// The server listens on every address.
+    pub bind_addrs: Vec<SocketAddr>,
     pub data_dir: PathBuf,
     pub server_cert_pem: PathBuf,
     pub server_key_pem: PathBuf,
     pub client_ca_cert_pem: PathBuf,
+    /// Sorted Pwned Passwords SHA-1 list used by the breach check.
+    pub pwned_hashes_file: Option<PathBuf>,
+    pub session_limits: SessionLimits,
+    /// Previous versions kept when a repository is saved.
+    pub backup_policy: BackupPolicy,
+    /// TOML grants of repository access to other clients than the owner.
+    pub acl_file: Option<PathBuf>,
+    /// SHA-256 fingerprints of the client certificates allowed to use `/admin`.
+    pub admin_clients: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// Sessions without requests for this long are closed.
+    pub idle_timeout: Duration,
+    /// Sessions are closed this long after being opened, even when in use.
+    pub max_lifetime: Duration,
+    pub max_per_repository: usize,
+    pub max_per_client: usize,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(15 * 60),
+            max_lifetime: Duration::from_secs(12 * 60 * 60),
+            max_per_repository: 16,
+            max_per_client: 32,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct AppState {
     repositories: NamedFileRepositories,
-    sessions: Arc<RwLock<HashMap<String, Arc<Mutex<SessionState>>>>>,
-    repository_locks: Arc<RwLock<HashMap<String, Arc<Mutex<()>>>>>,
+    pwned_hashes_file: Option<PathBuf>,
+    session_limits: SessionLimits,
+    admin_clients: Arc<HashSet<String>>,
+    acl: Arc<Acl>,
+    owners: Arc<RepositoryOwners>,
+    audit_log: Arc<AuditLog>,
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    repository_locks: Locks<()>,
+    /// Each lock guards the number of times the file behind the name was replaced.
+    name_locks: Locks<u64>,
 }
 
+type Locks<T> = Arc<RwLock<HashMap<String, Arc<Mutex<T>>>>>;
+
 #[derive(Debug)]
 pub(crate) struct SessionState {
+    pub(crate) repository_name: String,
     pub(crate) repository: RecordsFileRepository,
     pub(crate) persisted_snapshot: Vec<u8>,
 }
 
+/// An open session and what is known about it without locking its state.
+#[derive(Debug)]
+pub(crate) struct Session {
+    pub(crate) repository_name: String,
+    pub(crate) client: ClientIdentity,
+    pub(crate) access: Access,
+    pub(crate) opened_at: u64,
+    last_activity: AtomicU64,
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl Session {
+    pub(crate) fn last_activity(&self) -> u64 {
+        self.last_activity.load(Ordering::Relaxed)
+    }
+
+    /// When the session expires unless it is used again.
+    pub(crate) fn expires_at(&self, limits: &SessionLimits) -> u64 {
+        (self.opened_at + limits.max_lifetime.as_secs())
+            .min(self.last_activity() + limits.idle_timeout.as_secs())
+    }
+
+    fn is_expired(&self, limits: &SessionLimits, now: u64) -> bool {
+        self.expires_at(limits) <= now
+    }
+}
+
+fn take_expired(
+    sessions: &mut HashMap<String, Session>,
+    limits: &SessionLimits,
+    now: u64,
+) -> Vec<Session> {
+    sessions
+        .extract_if(|_, session| session.is_expired(limits, now))
+        .map(|(_, session)| session)
+        .collect()
+}
+
+/// Directory in the data directory holding the server's own files.
+pub const SERVER_STATE_DIR: &str = ".server";
+
+/// How a client may use a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Authorization {
+    Granted(Access),
+    /// Nobody owns the repository and no grant names it, the client becomes the owner
+    /// once it proves it knows the password.
+    Unowned,
+}
+
 impl AppState {
     pub async fn new(data_dir: PathBuf) -> Result<Self> {
         tokio::fs::create_dir_all(&data_dir)
             .await
             .with_context(|| format!("Failed to create data directory {}", data_dir.display()))?;
+        let state_dir = data_dir.join(SERVER_STATE_DIR);
+        let owners = RepositoryOwners::load(state_dir.join(OWNERS_FILE)).await?;
+        let audit_log = AuditLog::open(state_dir.join(AUDIT_LOG_FILE))?;
 
         Ok(Self {
             repositories: NamedFileRepositories::new(data_dir),
+            pwned_hashes_file: None,
+            session_limits: SessionLimits::default(),
+            admin_clients: Arc::new(HashSet::new()),
+            acl: Arc::new(Acl::default()),
+            owners: Arc::new(owners),
+            audit_log: Arc::new(audit_log),
             sessions: Arc::new(RwLock::new(HashMap::new())),
             repository_locks: Arc::new(RwLock::new(HashMap::new())),
+            name_locks: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
-    pub(crate) async fn insert_session(&self, repository: RecordsFileRepository) -> Result<String> {
+    pub fn with_pwned_hashes_file(mut self, path: Option<PathBuf>) -> Self {
+        self.pwned_hashes_file = path;
+        self
+    }
+
+    pub fn with_session_limits(mut self, limits: SessionLimits) -> Self {
+        self.session_limits = limits;
+        self
+    }
+
+    pub fn with_admin_clients(mut self, fingerprints: &[String]) -> Self {
+        self.admin_clients = Arc::new(
+            fingerprints
+                .iter()
+                .map(|fingerprint| normalize_fingerprint(fingerprint))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn with_backup_policy(mut self, policy: BackupPolicy) -> Self {
+        self.repositories = self.repositories.with_backup_policy(policy);
+        self
+    }
+
+    pub fn with_acl(mut self, acl: Acl) -> Self {
+        self.acl = Arc::new(acl);
+        self
+    }
+
+    pub(crate) fn session_limits(&self) -> &SessionLimits {
+        &self.session_limits
+    }
+
+    pub(crate) fn is_admin(&self, client: &ClientIdentity) -> bool {
+        self.admin_clients.contains(&client.fingerprint)
+    }
+
+    /// Checks that `client` has at least `required` access to the repository.
+    /// Admins own every repository.
+    pub(crate) async fn authorize(
+        &self,
+        client: &ClientIdentity,
+        repository_name: &str,
+        required: Access,
+    ) -> std::result::Result<Authorization, ApiError> {
+        if self.is_admin(client) {
+            return Ok(Authorization::Granted(Access::Owner));
+        }
+        let owner = self.owners.owner(repository_name).await;
+        let access = owner
+            .as_ref()
+            .filter(|owner| owner.fingerprint == client.fingerprint)
+            .map(|_| Access::Owner)
+            .max(self.acl.access(client, repository_name));
+        match access {
+            Some(access) if access >= required => Ok(Authorization::Granted(access)),
+            Some(access) => Err(ApiError::forbidden(format!(
+                "Client certificate has {access} access to this repository, {required} is needed"
+            ))),
+            None if owner.is_none() && !self.acl.mentions(repository_name) => {
+                Ok(Authorization::Unowned)
+            }
+            None => Err(ApiError::forbidden(
+                "Client certificate has no access to this repository",
+            )),
+        }
+    }
+
+    pub(crate) async fn set_owner(
+        &self,
+        repository_name: &str,
+        client: &ClientIdentity,
+    ) -> std::result::Result<(), ApiError> {
+        self.owners
+            .set(repository_name, Owner::from(client))
+            .await
+            .map_err(ApiError::internal)
+    }
+
+    /// Appends the outcome of an action that has run to the audit log and passes its result
+    /// on. The action can't be undone anymore, so a failed write is only logged.
+    pub(crate) async fn audit<T>(
+        &self,
+        client: &ClientIdentity,
+        action: AuditAction,
+        repository_name: &str,
+        detail: Option<String>,
+        result: std::result::Result<T, ApiError>,
+    ) -> std::result::Result<T, ApiError> {
+        let outcome = match &result {
+            Ok(_) => AuditOutcome::Ok,
+            Err(err) if err.status == StatusCode::FORBIDDEN => AuditOutcome::Denied,
+            Err(_) => AuditOutcome::Failed,
+        };
+        let recorded = self
+            .audit_log
+            .record(client, action, repository_name, outcome, detail)
+            .await;
+        if let Err(err) = recorded {
+            tracing::error!("Failed to record {action:?} of {repository_name}: {err:#}");
+        }
+        result
+    }
+
+    async fn audit_expired(&self, expired: Vec<Session>) {
+        for session in expired {
+            let recorded = self
+                .audit_log
+                .record(
+                    &session.client,
+                    AuditAction::Expire,
+                    &session.repository_name,
+                    AuditOutcome::Ok,
+                    None,
+                )
+                .await;
+            if let Err(err) = recorded {
+                tracing::error!("Failed to record an expired session: {err:#}");
+            }
+        }
+    }
+
+    pub(crate) async fn insert_session(
+        &self,
+        repository_name: &str,
+        client: ClientIdentity,
+        access: Access,
+        repository: RecordsFileRepository,
+    ) -> std::result::Result<String, ApiError> {
+        let persisted_snapshot = repository
+            .persisted_dump()
+            .await
+            .map_err(ApiError::internal)?;
+        let limits = &self.session_limits;
+        let now = unix_now();
+        let mut sessions = self.sessions.write().await;
+        let expired = take_expired(&mut sessions, limits, now);
+        if !expired.is_empty() {
+            // Recorded without the sessions lock, the audit log waits on the disk.
+            drop(sessions);
+            self.audit_expired(expired).await;
+            sessions = self.sessions.write().await;
+        }
+
+        let open_for_repository = sessions
+            .values()
+            .filter(|session| session.repository_name == repository_name)
+            .count();
+        if open_for_repository >= limits.max_per_repository {
+            return Err(ApiError::too_many_sessions(
+                "Too many open sessions for this repository",
+            ));
+        }
+        let open_for_client = sessions
+            .values()
+            .filter(|session| session.client == client)
+            .count();
+        if open_for_client >= limits.max_per_client {
+            return Err(ApiError::too_many_sessions(
+                "Too many open sessions for this client certificate",
+            ));
+        }
+
         let session_id = Uuid::new_v4().to_string();
-        let persisted_snapshot = repository.persisted_dump().await?;
-        self.sessions.write().await.insert(
+        sessions.insert(
             session_id.clone(),
-            Arc::new(Mutex::new(SessionState {
-                repository,
-                persisted_snapshot,
-            })),
+            Session {
+                repository_name: repository_name.to_string(),
+                client,
+                access,
+                opened_at: now,
+                last_activity: AtomicU64::new(now),
+                state: Arc::new(Mutex::new(SessionState {
+                    repository_name: repository_name.to_string(),
+                    repository,
+                    persisted_snapshot,
+                })),
+            },
         );
         Ok(session_id)
     }
 
+    /// Returns the state of a live session opened by `client` and marks it as used.
     pub(crate) async fn get_session(
         &self,
         session_id: &str,
+        client: &ClientIdentity,
+        required: Access,
     ) -> std::result::Result<Arc<Mutex<SessionState>>, ApiError> {
-        self.sessions
-            .read()
-            .await
-            .get(session_id)
-            .cloned()
-            .ok_or_else(|| ApiError::not_found("Session does not exist"))
+        let now = unix_now();
+        {
+            let sessions = self.sessions.read().await;
+            let session = sessions
+                .get(session_id)
+                .ok_or_else(|| ApiError::not_found("Session does not exist"))?;
+            if session.client != *client {
+                return Err(ApiError::forbidden(
+                    "Session was opened with another client certificate",
+                ));
+            }
+            if session.access < required {
+                return Err(ApiError::forbidden(format!(
+                    "Session has {} access to the repository, {required} is needed",
+                    session.access
+                )));
+            }
+            if !session.is_expired(&self.session_limits, now) {
+                session.last_activity.store(now, Ordering::Relaxed);
+                return Ok(session.state.clone());
+            }
+        }
+        let expired = self.sessions.write().await.remove(session_id);
+        self.audit_expired(expired.into_iter().collect()).await;
+        Err(ApiError::not_found("Session expired"))
+    }
+
+    /// Whether live sessions are open for the repository.
+    pub(crate) async fn has_sessions(&self, repository_name: &str) -> bool {
+        let now = unix_now();
+        self.sessions.read().await.values().any(|session| {
+            session.repository_name == repository_name
+                && !session.is_expired(&self.session_limits, now)
+        })
+    }
+
+    pub(crate) async fn remove_session(&self, session_id: &str) -> bool {
+        self.sessions.write().await.remove(session_id).is_some()
+    }
+
+    /// Closes the expired sessions, returns how many were closed.
+    pub async fn reap_expired_sessions(&self) -> usize {
+        let now = unix_now();
+        let expired = take_expired(&mut *self.sessions.write().await, &self.session_limits, now);
+        let closed = expired.len();
+        self.audit_expired(expired).await;
+        closed
+    }
+
+    /// Periodically closes the expired sessions in the background.
+    pub fn spawn_session_reaper(&self) -> tokio::task::JoinHandle<()> {
+        let state = self.clone();
+        let period = (self.session_limits.idle_timeout / 4)
+            .clamp(Duration::from_secs(1), Duration::from_secs(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let closed = state.reap_expired_sessions().await;
+                if closed > 0 {
+                    tracing::info!("Closed {closed} expired sessions");
+                }
+            }
+        })
     }
 
     pub(crate) async fn repository_lock(&self, repository_id: &str) -> Arc<Mutex<()>> {
-        if let Some(lock) = self
-            .repository_locks
-            .read()
+        lock_for(&self.repository_locks, repository_id).await
+    }
+
+    /// Held while a session is added for a name and while the file behind it is renamed,
+    /// deleted or restored. Those bump the guarded generation, so a session opened from a
+    /// file replaced in the meantime is noticed.
+    pub(crate) async fn lock_name(&self, repository_name: &str) -> OwnedMutexGuard<u64> {
+        lock_for(&self.name_locks, repository_name)
             .await
-            .get(repository_id)
-            .cloned()
-        {
-            return lock;
-        }
+            .lock_owned()
+            .await
+    }
+}
 
-        let mut locks = self.repository_locks.write().await;
-        locks
-            .entry(repository_id.to_string())
-            .or_insert_with(|| Arc::new(Mutex::new(())))
-            .clone()
+async fn lock_for<T: Default>(locks: &Locks<T>, key: &str) -> Arc<Mutex<T>> {
+    if let Some(lock) = locks.read().await.get(key).cloned() {
+        return lock;
     }
+
+    let mut locks = locks.write().await;
+    locks
+        .entry(key.to_string())
+        .or_insert_with(|| Arc::new(Mutex::new(T::default())))
+        .clone()
+}
+
+/// Runs `task` on a blocking thread. Opening, creating or re-keying a repository derives
+/// its key with Argon2id, which would otherwise stall the executor.
+pub(crate) async fn run_blocking<T>(
+    task: impl Future<Output = T> + Send + 'static,
+) -> std::result::Result<T, ApiError>
+where
+    T: Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || runtime.block_on(task))
+        .await
+        .map_err(ApiError::internal)
 }
 
 pub fn app(state: AppState) -> Router {
@@ -133,18 +507,44 @@ pub async fn rustls_config(
 
 pub async fn serve(config: ServerConfigPaths) -> Result<()> {
     install_crypto_provider();
-    let state = AppState::new(config.data_dir.clone()).await?;
+    let state = AppState::new(config.data_dir.clone())
+        .await?
+        .with_pwned_hashes_file(config.pwned_hashes_file.clone())
+        .with_session_limits(config.session_limits)
+        .with_backup_policy(config.backup_policy)
+        .with_admin_clients(&config.admin_clients)
+        .with_acl(match &config.acl_file {
+            Some(path) => Acl::load(path)?,
+            None => Acl::default(),
+        });
+    state.spawn_session_reaper();
     let tls_config = rustls_config(&config).await?;
-    axum_server::bind_rustls(config.bind_addr, tls_config)
-        .serve(app(state).into_make_service())
-        .await
-        .context("Server exited with error")
+    let acceptor = ClientIdentityAcceptor::new(RustlsAcceptor::new(tls_config));
+    let app = app(state);
+    let mut servers = JoinSet::new();
+    for addr in config.bind_addrs {
+        let server = axum_server::bind(addr)
+            .acceptor(acceptor.clone())
+            .serve(app.clone().into_make_service());
+        tracing::info!("Listening on {addr}");
+        servers.spawn(async move {
+            server
+                .await
+                .with_context(|| format!("Server on {addr} exited with error"))
+        });
+    }
+    // Stops serving on every address as soon as one of them fails.
+    match servers.join_next().await {
+        Some(result) => result.context("Server task panicked")?,
+        None => Err(anyhow!("No address to listen on")),
+    }
 }
 
 #[derive(Debug)]
 pub struct ApiError {
     status: StatusCode,
     message: String,
+    conflicts: Vec<MergeConflict>,
 }
 
 impl ApiError {
@@ -152,6 +552,7 @@ impl ApiError {
         Self {
             status: StatusCode::BAD_REQUEST,
             message: message.into(),
+            conflicts: Vec::new(),
         }
     }
 
@@ -159,6 +560,7 @@ impl ApiError {
         Self {
             status: StatusCode::NOT_FOUND,
             message: message.into(),
+            conflicts: Vec::new(),
         }
     }
 
@@ -166,6 +568,15 @@ impl ApiError {
         Self {
             status: StatusCode::UNAUTHORIZED,
             message: message.into(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            message: message.into(),
+            conflicts: Vec::new(),
         }
     }
 
@@ -173,6 +584,26 @@ impl ApiError {
         Self {
             status: StatusCode::CONFLICT,
             message: message.into(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    fn too_many_sessions(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            message: message.into(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    fn save_conflict(conflicts: Vec<MergeConflict>) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            message: format!(
+                "{} changes conflict with another session. Rebase and retry.",
+                conflicts.len()
+            ),
+            conflicts,
         }
     }
 
@@ -181,6 +612,7 @@ impl ApiError {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: "Internal server error".into(),
+            conflicts: Vec::new(),
         }
     }
 
@@ -189,6 +621,7 @@ impl ApiError {
             CreateRepositoryError::RepositoryAlreadyExists => Self {
                 status: StatusCode::CONFLICT,
                 message: "Repository already exists".into(),
+                conflicts: Vec::new(),
             },
             CreateRepositoryError::InvalidRepositoryName(name) => {
                 Self::bad_request(format!("Invalid repository name: {name}"))
@@ -197,30 +630,80 @@ impl ApiError {
         }
     }
 
+    fn from_share_error(error: ShareRecordsError) -> Self {
+        match error {
+            ShareRecordsError::NothingSelected => {
+                Self::bad_request("No records match the selection")
+            }
+            ShareRecordsError::CreateRepository(err) => Self::from_create_error(err),
+            ShareRecordsError::UnexpectedError(err) => Self::internal(err),
+        }
+    }
+
     fn from_open_error(error: RepositoryOpenError) -> Self {
         match error {
             RepositoryOpenError::WrongPassword => Self {
                 status: StatusCode::UNAUTHORIZED,
                 message: "Wrong password".into(),
+                conflicts: Vec::new(),
             },
             RepositoryOpenError::DoesntExist => Self::not_found("Repository does not exist"),
             RepositoryOpenError::InvalidRepositoryName(name) => {
                 Self::bad_request(format!("Invalid repository name: {name}"))
             }
+            RepositoryOpenError::DamagedRecords(records) => Self {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                message: format!(
+                    "Repository has damaged records: {}",
+                    records
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                conflicts: Vec::new(),
+            },
             RepositoryOpenError::OpenError(err) => Self::internal(err),
         }
     }
 
+    fn from_manage_error(error: ManageRepositoryError) -> Self {
+        match error {
+            ManageRepositoryError::WrongPassword => Self::unauthorized("Wrong password"),
+            ManageRepositoryError::DoesntExist => Self::not_found("Repository does not exist"),
+            ManageRepositoryError::RepositoryAlreadyExists => {
+                Self::conflict("Repository already exists")
+            }
+            ManageRepositoryError::RepositoryInUse => Self {
+                status: StatusCode::LOCKED,
+                message: "Repository has open sessions".into(),
+                conflicts: Vec::new(),
+            },
+            ManageRepositoryError::InvalidRepositoryName(name) => {
+                Self::bad_request(format!("Invalid repository name: {name}"))
+            }
+            ManageRepositoryError::UnexpectedError(err) => Self::internal(err),
+        }
+    }
+
     fn from_add_error(error: sec_store::repository::AddRecordError) -> Self {
         match error {
             sec_store::repository::AddRecordError::RecordDoesntExist => Self {
                 status: StatusCode::CONFLICT,
                 message: "Record already exists".into(),
+                conflicts: Vec::new(),
             },
             sec_store::repository::AddRecordError::UnxpectedError(err) => Self::internal(err),
         }
     }
 
+    fn from_change_password_error(error: ChangePasswordError) -> Self {
+        match error {
+            ChangePasswordError::WrongPassword => Self::forbidden("Wrong password"),
+            ChangePasswordError::UnexpectedError(err) => Self::internal(err),
+        }
+    }
+
     fn from_update_error(error: UpdateRecordError) -> Self {
         match error {
             UpdateRecordError::RecordDoesntExist => Self::not_found("Record does not exist"),
@@ -260,6 +743,7 @@ pub(crate) mod test_support {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     use reqwest::{Certificate, Identity};
+    use sec_store::breach::sha1_hex;
     use sec_store::repository::remote::{
         CreateRepositoryRequest, OpenRepositoryRequest, OpenRepositoryResponse,
     };
@@ -269,16 +753,22 @@ pub(crate) mod test_support {
 
     pub(crate) struct TestServer {
         pub(crate) base_url: String,
+        pub(crate) client_fingerprint: String,
         _tmp: TempDir,
     }
 
     impl TestServer {
-        fn certs_dir(&self) -> &Path {
+        pub(crate) fn certs_dir(&self) -> &Path {
             self._tmp.path()
         }
     }
 
-    const FIXTURE_CERTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/certs");
+    const PWNED_HASHES_FILE: &str = "pwned-passwords.txt";
+    pub(crate) const PWNED_PASSWORD: &str = "password";
+    pub(crate) const PWNED_PASSWORD_COUNT: u64 = 42;
+
+    pub(crate) const FIXTURE_CERTS_DIR: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/certs");
 
     pub(crate) fn test_password() -> String {
         let nanos = SystemTime::now()
@@ -297,7 +787,7 @@ pub(crate) mod test_support {
         let response = client
             .post(format!("{}/repositories/{}", server.base_url, name))
             .json(&CreateRepositoryRequest {
-                password: password.to_string(),
+                password: password.into(),
             })
             .send()
             .await
@@ -317,7 +807,7 @@ pub(crate) mod test_support {
                 server.base_url, name
             ))
             .json(&OpenRepositoryRequest {
-                password: password.to_string(),
+                password: password.into(),
             })
             .send()
             .await
@@ -330,17 +820,30 @@ pub(crate) mod test_support {
     pub(crate) async fn spawn_test_server() -> Result<TestServer> {
         let tmp = TempDir::new().context("temp dir")?;
         TestCertificates::copy_fixtures(tmp.path())?;
+        fs::write(
+            tmp.path().join(PWNED_HASHES_FILE),
+            format!("{}:{PWNED_PASSWORD_COUNT}\n", sha1_hex(PWNED_PASSWORD)),
+        )
+        .context("write pwned hashes")?;
 
         let listener = TcpListener::bind("127.0.0.1:0").context("bind listener")?;
         let addr = listener.local_addr().context("local addr")?;
         drop(listener);
 
+        let client_cert = load_certs(&tmp.path().join("client-identity.pem"))?;
+        let client_fingerprint = ClientIdentity::from_certificate(&client_cert[0]).fingerprint;
+
         let config = ServerConfigPaths {
-            bind_addr: addr,
+            bind_addrs: vec![addr],
             data_dir: tmp.path().join("data"),
             server_cert_pem: tmp.path().join("server.pem"),
             server_key_pem: tmp.path().join("server-key.pem"),
             client_ca_cert_pem: tmp.path().join("ca.pem"),
+            pwned_hashes_file: Some(tmp.path().join(PWNED_HASHES_FILE)),
+            session_limits: SessionLimits::default(),
+            backup_policy: BackupPolicy::default(),
+            acl_file: None,
+            admin_clients: vec![client_fingerprint.clone()],
         };
 
         tokio::spawn(async move {
@@ -351,6 +854,72 @@ pub(crate) mod test_support {
 
         Ok(TestServer {
             base_url: format!("https://{}", addr),
+            client_fingerprint,
+            _tmp: tmp,
+        })
+    }
+
+    /// Identity of the test client `name`, sent in the `x-test-client` header.
+    pub(crate) fn test_identity(name: &str) -> ClientIdentity {
+        ClientIdentity {
+            fingerprint: format!("{name:0>8}").to_ascii_uppercase(),
+            common_name: Some(name.to_string()),
+        }
+    }
+
+    /// Serves plain HTTP and takes the client identity from a header,
+    /// to test access control with several clients.
+    pub(crate) struct PlainTestServer {
+        base_url: String,
+        client: reqwest::Client,
+        pub(crate) state: AppState,
+        _tmp: TempDir,
+    }
+
+    impl PlainTestServer {
+        pub(crate) fn request(
+            &self,
+            method: reqwest::Method,
+            path: &str,
+            client: &str,
+        ) -> reqwest::RequestBuilder {
+            self.client
+                .request(method, format!("{}{path}", self.base_url))
+                .header("x-test-client", client)
+        }
+    }
+
+    async fn identity_from_header(
+        mut request: axum::extract::Request,
+        next: axum::middleware::Next,
+    ) -> axum::response::Response {
+        let client = request
+            .headers()
+            .get("x-test-client")
+            .and_then(|value| value.to_str().ok())
+            .map(test_identity);
+        if let Some(client) = client {
+            request.extensions_mut().insert(client);
+        }
+        next.run(request).await
+    }
+
+    pub(crate) async fn spawn_plain_test_server(acl: Acl) -> Result<PlainTestServer> {
+        let tmp = TempDir::new().context("temp dir")?;
+        let state = AppState::new(tmp.path().join("data")).await?.with_acl(acl);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("bind listener")?;
+        let addr = listener.local_addr().context("local addr")?;
+        let router = app(state.clone()).layer(axum::middleware::from_fn(identity_from_header));
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router).await;
+        });
+
+        Ok(PlainTestServer {
+            base_url: format!("http://{addr}"),
+            client: reqwest::Client::new(),
+            state,
             _tmp: tmp,
         })
     }
@@ -429,3 +998,98 @@ pub(crate) mod test_support {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sec_store::repository::RepositoriesSource;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_support::test_identity;
+
+    async fn state_with_repository(limits: SessionLimits) -> (TempDir, AppState) {
+        let tmp = TempDir::new().expect("temp dir");
+        let state = AppState::new(tmp.path().to_path_buf())
+            .await
+            .expect("state")
+            .with_session_limits(limits);
+        state
+            .repositories
+            .create_repository("demo", "password".into())
+            .await
+            .expect("create repository");
+        (tmp, state)
+    }
+
+    async fn open(state: &AppState, client: &str) -> std::result::Result<String, ApiError> {
+        let repository = state
+            .repositories
+            .open_repository("demo", "password".into())
+            .await
+            .expect("open repository");
+        state
+            .insert_session("demo", test_identity(client), Access::Owner, repository)
+            .await
+    }
+
+    #[tokio::test]
+    async fn sessions_are_capped_per_repository_and_client() {
+        let limits = SessionLimits {
+            max_per_repository: 3,
+            max_per_client: 2,
+            ..SessionLimits::default()
+        };
+        let (_tmp, state) = state_with_repository(limits).await;
+
+        let first = open(&state, "A").await.expect("first session");
+        open(&state, "A").await.expect("second session");
+        let error = open(&state, "A").await.expect_err("client cap");
+        assert_eq!(error.status, StatusCode::TOO_MANY_REQUESTS);
+        open(&state, "B").await.expect("other client");
+        let error = open(&state, "C").await.expect_err("repository cap");
+        assert_eq!(error.status, StatusCode::TOO_MANY_REQUESTS);
+
+        assert!(state.remove_session(&first).await);
+        open(&state, "C").await.expect("session after close");
+    }
+
+    #[tokio::test]
+    async fn idle_and_old_sessions_expire() {
+        let limits = SessionLimits {
+            idle_timeout: Duration::from_secs(60),
+            max_lifetime: Duration::from_secs(3600),
+            ..SessionLimits::default()
+        };
+        let (_tmp, state) = state_with_repository(limits).await;
+        let idle = open(&state, "A").await.expect("idle session");
+        let old = open(&state, "A").await.expect("old session");
+        let active = open(&state, "A").await.expect("active session");
+        {
+            let sessions = state.sessions.read().await;
+            sessions[&idle]
+                .last_activity
+                .fetch_sub(61, Ordering::Relaxed);
+        }
+        {
+            let mut sessions = state.sessions.write().await;
+            let old = sessions.get_mut(&old).expect("old session");
+            old.opened_at -= 3601;
+        }
+
+        let client = test_identity("A");
+        let error = state
+            .get_session(&idle, &client, Access::Read)
+            .await
+            .expect_err("idle expired");
+        assert_eq!(error.message, "Session expired");
+        assert_eq!(state.reap_expired_sessions().await, 1);
+        assert!(state
+            .get_session(&old, &client, Access::Read)
+            .await
+            .is_err());
+        state
+            .get_session(&active, &client, Access::Read)
+            .await
+            .expect("active session");
+    }
+}
@@ -1,39 +1,260 @@
+use std::io::{BufRead, IsTerminal, Write};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
-use anyhow::Result;
-use clap::Parser;
-use sec_store_server::{serve, ServerConfigPaths};
-use tracing_subscriber::EnvFilter;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use sec_store::repository::file::BackupPolicy;
+use sec_store_server::config::ServerConfig;
+use sec_store_server::{audit_log, maintenance, serve};
 
 #[derive(Parser, Debug)]
 #[command(name = "sec_store_server")]
 struct Cli {
-    #[arg(long, default_value = "127.0.0.1:8443")]
-    bind_addr: SocketAddr,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serves the repositories of the data directory.
+    Serve(Box<ServeArgs>),
+    /// Loads the certificates, ACL and data directory like `serve` does, without serving.
+    CheckConfig(Box<ServeArgs>),
+    /// Lists the repositories with their size, number of backups and owner.
+    ListRepos(ConfigArgs),
+    /// Decrypts every record of a repository, reading its password from stdin.
+    VerifyRepo {
+        #[command(flatten)]
+        config: ConfigArgs,
+        name: String,
+    },
+    /// Backs up the named repositories now, all of them without names.
+    Backup {
+        #[command(flatten)]
+        config: ConfigArgs,
+        names: Vec<String>,
+    },
+    /// Checks the hash chain of an audit log and prints its length and last hash.
+    VerifyAuditLog { path: PathBuf },
+}
+
+#[derive(Args, Debug)]
+struct ConfigArgs {
+    /// TOML config file, the options below override its values.
+    #[arg(long)]
+    config: Option<PathBuf>,
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+}
+
+impl ConfigArgs {
+    fn load(&self) -> Result<ServerConfig> {
+        let mut config = match &self.config {
+            Some(path) => ServerConfig::load(path)?,
+            None => ServerConfig::default(),
+        };
+        override_with(&mut config.data_dir, self.data_dir.clone().map(Some));
+        Ok(config)
+    }
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Address to listen on, repeatable [default: 127.0.0.1:8443]
+    #[arg(long = "bind-addr")]
+    bind_addrs: Vec<SocketAddr>,
+    #[arg(long)]
+    server_cert_pem: Option<PathBuf>,
+    #[arg(long)]
+    server_key_pem: Option<PathBuf>,
+    #[arg(long)]
+    client_ca_cert_pem: Option<PathBuf>,
+    /// Sorted Pwned Passwords SHA-1 list, enables the breach check endpoint.
+    #[arg(long)]
+    pwned_hashes_file: Option<PathBuf>,
+    /// Closes sessions without requests for this many seconds [default: 900]
     #[arg(long)]
-    data_dir: PathBuf,
+    session_idle_timeout_secs: Option<u64>,
+    /// Closes sessions this many seconds after they were opened [default: 43200]
     #[arg(long)]
-    server_cert_pem: PathBuf,
+    session_max_lifetime_secs: Option<u64>,
+    /// [default: 16]
     #[arg(long)]
-    server_key_pem: PathBuf,
+    max_sessions_per_repository: Option<usize>,
+    /// [default: 32]
     #[arg(long)]
-    client_ca_cert_pem: PathBuf,
+    max_sessions_per_client: Option<usize>,
+    /// Most recent versions kept when a repository is saved, 0 with the others disables
+    /// backups [default: 10]
+    #[arg(long)]
+    backup_keep_last: Option<usize>,
+    /// Days for which the newest version is kept [default: 7]
+    #[arg(long)]
+    backup_keep_daily: Option<usize>,
+    /// Weeks for which the newest version is kept [default: 4]
+    #[arg(long)]
+    backup_keep_weekly: Option<usize>,
+    /// TOML file granting repository access to other clients than the owner.
+    #[arg(long)]
+    acl_file: Option<PathBuf>,
+    /// SHA-256 fingerprint of a client certificate allowed to use `/admin`, repeatable.
+    #[arg(long = "admin-client-fingerprint")]
+    admin_clients: Vec<String>,
+    /// `tracing` filter directives, `RUST_LOG` wins when set [default: info]
+    #[arg(long)]
+    log_filter: Option<String>,
+}
+
+fn override_with<T>(value: &mut T, from_cli: Option<T>) {
+    if let Some(from_cli) = from_cli {
+        *value = from_cli;
+    }
+}
+
+// Repeatable options replace the list of the config file when given.
+fn non_empty<T>(values: Vec<T>) -> Option<Vec<T>> {
+    (!values.is_empty()).then_some(values)
+}
+
+impl ServeArgs {
+    fn load(self) -> Result<ServerConfig> {
+        let mut config = self.config.load()?;
+        override_with(&mut config.bind_addrs, non_empty(self.bind_addrs));
+        let tls = &mut config.tls;
+        override_with(&mut tls.server_cert_pem, self.server_cert_pem.map(Some));
+        override_with(&mut tls.server_key_pem, self.server_key_pem.map(Some));
+        override_with(
+            &mut tls.client_ca_cert_pem,
+            self.client_ca_cert_pem.map(Some),
+        );
+        override_with(
+            &mut config.pwned_hashes_file,
+            self.pwned_hashes_file.map(Some),
+        );
+        let sessions = &mut config.sessions;
+        override_with(
+            &mut sessions.idle_timeout_secs,
+            self.session_idle_timeout_secs,
+        );
+        override_with(
+            &mut sessions.max_lifetime_secs,
+            self.session_max_lifetime_secs,
+        );
+        override_with(
+            &mut sessions.max_per_repository,
+            self.max_sessions_per_repository,
+        );
+        override_with(&mut sessions.max_per_client, self.max_sessions_per_client);
+        let backups = &mut config.backups;
+        override_with(&mut backups.keep_last, self.backup_keep_last);
+        override_with(&mut backups.keep_daily, self.backup_keep_daily);
+        override_with(&mut backups.keep_weekly, self.backup_keep_weekly);
+        override_with(&mut config.acl_file, self.acl_file.map(Some));
+        override_with(&mut config.admin_clients, non_empty(self.admin_clients));
+        override_with(&mut config.logging.filter, self.log_filter);
+        Ok(config)
+    }
+}
+
+// Typed passwords are not echoed, piped ones are read as the first line.
+fn read_password(name: &str) -> Result<String> {
+    let prompt = format!("Password of {name}: ");
+    let password = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(prompt)
+            .context("Failed to read the password from the terminal")?
+    } else {
+        eprint!("{prompt}");
+        std::io::stderr().flush()?;
+        let mut password = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut password)
+            .context("Failed to read the password from stdin")?;
+        password
+    };
+    let password = password.trim_end_matches(['\n', '\r']);
+    if password.is_empty() {
+        bail!("No password given");
+    }
+    Ok(password.to_string())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
-
     let cli = Cli::parse();
-    serve(ServerConfigPaths {
-        bind_addr: cli.bind_addr,
-        data_dir: cli.data_dir,
-        server_cert_pem: cli.server_cert_pem,
-        server_key_pem: cli.server_key_pem,
-        client_ca_cert_pem: cli.client_ca_cert_pem,
-    })
-    .await
+    match cli.command {
+        Command::Serve(args) => {
+            let config = args.load()?;
+            tracing_subscriber::fmt()
+                .with_env_filter(config.logging.env_filter()?)
+                .init();
+            serve(config.server_config_paths()?).await
+        }
+        Command::CheckConfig(args) => {
+            for checked in maintenance::check_config(&args.load()?).await? {
+                println!("{checked}");
+            }
+            println!("Config is valid");
+            Ok(())
+        }
+        Command::ListRepos(config) => {
+            let config = config.load()?;
+            let repositories = maintenance::list_repositories(config.data_dir()?).await?;
+            let width = repositories
+                .iter()
+                .map(|repository| repository.name.len())
+                .max()
+                .unwrap_or(0);
+            for repository in repositories {
+                let owner = match &repository.owner {
+                    Some(owner) => owner
+                        .common_name
+                        .clone()
+                        .map_or_else(|| owner.fingerprint.clone(), |name| format!("cn:{name}")),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{:width$}  {:>10} bytes  {:>3} backups  {owner}",
+                    repository.name, repository.size, repository.backups
+                );
+            }
+            Ok(())
+        }
+        Command::VerifyRepo { config, name } => {
+            let config = config.load()?;
+            let password = read_password(&name)?;
+            let check =
+                maintenance::verify_repository(config.data_dir()?, &name, password.into()).await?;
+            println!(
+                "{name}: format version {}, {} records",
+                check.format_version, check.records
+            );
+            for damaged in &check.damaged {
+                println!("Damaged record {damaged}");
+            }
+            if !check.damaged.is_empty() {
+                bail!("{} records can't be decrypted", check.damaged.len());
+            }
+            Ok(())
+        }
+        Command::Backup { config, names } => {
+            let config = config.load()?;
+            let policy = BackupPolicy::from(&config.backups);
+            for (name, backup) in maintenance::back_up(config.data_dir()?, policy, &names).await? {
+                println!("{name}: backup {}", backup.id);
+            }
+            Ok(())
+        }
+        Command::VerifyAuditLog { path } => {
+            if !path.is_file() {
+                bail!("No audit log at {}", path.display());
+            }
+            let head = audit_log::verify(&path)?;
+            println!("{} entries, last hash {}", head.entries, head.last_hash);
+            Ok(())
+        }
+    }
 }
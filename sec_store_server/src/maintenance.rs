@@ -0,0 +1,219 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use sec_store::repository::file::{
+    BackupPolicy, NamedFileRepositories, RecoverRecordsFileRepository,
+};
+use sec_store::repository::{
+    Backup, DamagedRecord, ManageRepositoryError, OpenRepository, RecordsRepository,
+    RepositoriesSource, RepositoryOpenError,
+};
+use sec_store::secret::SecretString;
+
+use crate::acl::{Acl, Owner, RepositoryOwners, OWNERS_FILE};
+use crate::audit_log::{self, AUDIT_LOG_FILE};
+use crate::config::ServerConfig;
+use crate::{rustls_config, SERVER_STATE_DIR};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepositorySummary {
+    pub name: String,
+    pub owner: Option<Owner>,
+    pub size: u64,
+    pub backups: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryCheck {
+    pub format_version: u32,
+    pub records: usize,
+    /// Records that can't be decrypted, the server refuses to open the repository.
+    pub damaged: Vec<DamagedRecord>,
+}
+
+pub async fn list_repositories(data_dir: &Path) -> Result<Vec<RepositorySummary>> {
+    let repositories = NamedFileRepositories::new(data_dir.to_path_buf());
+    let owners = RepositoryOwners::load(data_dir.join(SERVER_STATE_DIR).join(OWNERS_FILE)).await?;
+    let mut summaries = Vec::new();
+    for name in repositories.list_repositories().await? {
+        let path = repositories.named_path(&name)?;
+        let size = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        summaries.push(RepositorySummary {
+            owner: owners.owner(&name).await,
+            size,
+            backups: repositories.list_backups(&name).await?.len(),
+            name,
+        });
+    }
+    Ok(summaries)
+}
+
+/// Decrypts every record of the repository.
+pub async fn verify_repository(
+    data_dir: &Path,
+    name: &str,
+    passwd: SecretString,
+) -> Result<RepositoryCheck> {
+    let path = NamedFileRepositories::new(data_dir.to_path_buf()).named_path(name)?;
+    let repository = RecoverRecordsFileRepository(path)
+        .open(passwd)
+        .await
+        .map_err(|err| match err {
+            RepositoryOpenError::WrongPassword => anyhow!("Wrong password"),
+            RepositoryOpenError::DoesntExist => anyhow!("Repository {name} doesn't exist"),
+            RepositoryOpenError::OpenError(err) => err,
+            err => anyhow!("Failed to open repository {name}: {err}"),
+        })?;
+    Ok(RepositoryCheck {
+        format_version: repository.header().format_version,
+        records: repository.get_records().await?.len(),
+        damaged: repository.damaged_records(),
+    })
+}
+
+/// Backs up the named repositories, or all of them, and prunes their backups with `policy`.
+pub async fn back_up(
+    data_dir: &Path,
+    policy: BackupPolicy,
+    names: &[String],
+) -> Result<Vec<(String, Backup)>> {
+    let repositories =
+        NamedFileRepositories::new(data_dir.to_path_buf()).with_backup_policy(policy);
+    let names = match names {
+        [] => repositories.list_repositories().await?,
+        names => names.to_vec(),
+    };
+    let mut backups = Vec::new();
+    for name in names {
+        let backup = repositories.back_up(&name).map_err(|err| match err {
+            ManageRepositoryError::DoesntExist => anyhow!("Repository {name} doesn't exist"),
+            ManageRepositoryError::UnexpectedError(err) => err,
+            err => anyhow!("Failed to back up {name}: {err}"),
+        })?;
+        backups.push((name, backup));
+    }
+    Ok(backups)
+}
+
+/// Loads everything the server would load on start, returns what was checked.
+pub async fn check_config(config: &ServerConfig) -> Result<Vec<String>> {
+    let paths = config.server_config_paths()?;
+    config.logging.env_filter()?;
+    let mut checked = Vec::new();
+
+    rustls_config(&paths).await?;
+    checked.push("TLS certificates and key load".to_string());
+    if let Some(path) = &paths.acl_file {
+        let acl = Acl::load(path)?;
+        checked.push(format!("ACL has {} grants", acl.grants.len()));
+    }
+    if let Some(path) = &paths.pwned_hashes_file {
+        std::fs::metadata(path)
+            .with_context(|| format!("Failed to read pwned hashes file {}", path.display()))?;
+        checked.push("Pwned hashes file exists".to_string());
+    }
+
+    if !paths.data_dir.exists() {
+        checked.push(format!(
+            "Data directory {} will be created",
+            paths.data_dir.display()
+        ));
+        return Ok(checked);
+    }
+    let repositories = list_repositories(&paths.data_dir).await?;
+    checked.push(format!(
+        "Data directory holds {} repositories",
+        repositories.len()
+    ));
+    let head = audit_log::verify(&paths.data_dir.join(SERVER_STATE_DIR).join(AUDIT_LOG_FILE))?;
+    checked.push(format!("Audit log has {} valid entries", head.entries));
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use sec_store::record::Record;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::TlsConfig;
+    use crate::test_support::{test_identity, FIXTURE_CERTS_DIR};
+
+    #[tokio::test]
+    async fn config_check_loads_what_the_server_needs() {
+        let tmp = TempDir::new().expect("temp dir");
+        let certs = Path::new(FIXTURE_CERTS_DIR);
+        let mut config = ServerConfig {
+            data_dir: Some(tmp.path().join("data")),
+            tls: TlsConfig {
+                server_cert_pem: Some(certs.join("server.pem")),
+                server_key_pem: Some(certs.join("server-key.pem")),
+                client_ca_cert_pem: Some(certs.join("ca.pem")),
+            },
+            ..ServerConfig::default()
+        };
+        let checked = check_config(&config).await.expect("valid config");
+        assert!(checked.last().expect("checks").ends_with("will be created"));
+
+        config.tls.server_key_pem = Some(certs.join("ca.pem"));
+        assert!(check_config(&config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn repositories_are_listed_verified_and_backed_up() {
+        let tmp = TempDir::new().expect("temp dir");
+        let repositories = NamedFileRepositories::new(tmp.path().to_path_buf());
+        let passwd = SecretString::from("Passwd");
+        for name in ["home", "work"] {
+            let mut repository = repositories
+                .create_repository(name, passwd.clone())
+                .await
+                .expect("create");
+            repository
+                .add_record(Record::new(vec![("name".to_string(), name.to_string())]))
+                .await
+                .expect("add");
+            repository.save().await.expect("save");
+        }
+        let alice = test_identity("alice");
+        RepositoryOwners::load(tmp.path().join(SERVER_STATE_DIR).join(OWNERS_FILE))
+            .await
+            .expect("owners")
+            .set("work", Owner::from(&alice))
+            .await
+            .expect("set owner");
+
+        let summaries = list_repositories(tmp.path()).await.expect("list");
+        let names: Vec<&str> = summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["home", "work"]);
+        assert_eq!(summaries[0].owner, None);
+        assert_eq!(summaries[1].owner, Some(Owner::from(&alice)));
+        assert_eq!(summaries[1].backups, 1);
+
+        let check = verify_repository(tmp.path(), "work", passwd.clone())
+            .await
+            .expect("verify");
+        assert_eq!(check.records, 1);
+        assert!(check.damaged.is_empty());
+        let error = verify_repository(tmp.path(), "work", SecretString::from("Wrong"))
+            .await
+            .expect_err("wrong password");
+        assert_eq!(error.to_string(), "Wrong password");
+
+        let backups = back_up(tmp.path(), BackupPolicy::default(), &[])
+            .await
+            .expect("back up");
+        assert_eq!(backups.len(), 2);
+        assert_eq!(
+            list_repositories(tmp.path()).await.expect("list")[1].backups,
+            2
+        );
+        assert!(
+            back_up(tmp.path(), BackupPolicy::default(), &["gone".to_string()])
+                .await
+                .is_err()
+        );
+    }
+}
@@ -9,11 +9,18 @@ use ratatui::{
     widgets::{Block, Clear, ListState, Paragraph, Wrap},
     DefaultTerminal, Frame,
 };
+use sec_store::generator::{
+    generate_passphrase, generate_password, PassphrasePolicy, PasswordPolicy,
+};
+use sec_store::repository::remote::RemoteRecordsRepository;
 use sec_store::repository::RecordsRepository;
 
 use crate::dialogues::Dialogue;
 use crate::input::InputState;
-use crate::repo::{default_repo_path, resolve_data_dir, FileRepositoryFactory, RepositoryFactory};
+use crate::repo::{
+    default_repo_path, resolve_data_dir, FileRepositoryFactory, RemoteRepositoryFactory,
+    RepositoryFactory,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AppConfig {
@@ -46,6 +53,16 @@ impl Default for App<FileRepositoryFactory, sec_store::repository::file::Records
     }
 }
 
+impl App<RemoteRepositoryFactory, RemoteRecordsRepository> {
+    /// Starts with the list of the repositories on the server.
+    pub fn new_remote(config: AppConfig, factory: RemoteRepositoryFactory) -> Self {
+        use crate::dialogues::PickRepoDialogue;
+        let mut app = Self::new(config, factory.clone());
+        app.screen = Box::new(PickRepoDialogue::new(factory));
+        app
+    }
+}
+
 impl<F, R> App<F, R>
 where
     F: RepositoryFactory<R>,
@@ -105,10 +122,11 @@ where
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Length(1), Constraint::Min(1)])
                 .split(inner);
-            frame.render_widget(
-                Paragraph::new(inp.prompt.as_str()).style(Style::new().dim()),
-                chunks[0],
-            );
+            let prompt = match &inp.note {
+                Some(note) => format!("{} ({note})", inp.prompt),
+                None => inp.prompt.clone(),
+            };
+            frame.render_widget(Paragraph::new(prompt).style(Style::new().dim()), chunks[0]);
             let mut p = Paragraph::new(inp.display())
                 .style(Style::new().white())
                 .block(Block::default());
@@ -118,15 +136,26 @@ where
             frame.render_widget(p, chunks[1]);
 
             let instructions = if inp.password_mode {
-                Line::from(vec![
+                let mut spans = vec![
                     Span::raw(" "),
                     Span::styled("Enter", Style::new().cyan()),
                     Span::raw(" submit, "),
                     Span::styled("Ctrl+v", Style::new().cyan()),
                     Span::raw(" toggle visibility, "),
+                ];
+                if self.screen.offers_password_generator() {
+                    spans.extend([
+                        Span::styled("Ctrl+g", Style::new().cyan()),
+                        Span::raw(" generate, "),
+                        Span::styled("Ctrl+p", Style::new().cyan()),
+                        Span::raw(" passphrase, "),
+                    ]);
+                }
+                spans.extend([
                     Span::styled("Esc", Style::new().cyan()),
                     Span::raw(" cancel"),
-                ])
+                ]);
+                Line::from(spans)
             } else {
                 Line::from(vec![
                     Span::raw(" "),
@@ -177,6 +206,11 @@ where
     }
 
     fn handle_events(&mut self) -> std::io::Result<()> {
+        if let Some(interval) = self.screen.refresh_interval() {
+            if !event::poll(interval)? {
+                return Ok(());
+            }
+        }
         match event::read()? {
             Event::Key(k) if k.kind == KeyEventKind::Press => self.handle_key(k),
             _ => {}
@@ -217,6 +251,24 @@ where
                 {
                     inp.password_visible = !inp.password_visible;
                 }
+                KeyCode::Char(c @ ('g' | 'p'))
+                    if inp.password_mode
+                        && k.modifiers.contains(KeyModifiers::CONTROL)
+                        && self.screen.offers_password_generator() =>
+                {
+                    let generated = if c == 'g' {
+                        generate_password(&PasswordPolicy::default())
+                    } else {
+                        generate_passphrase(&PassphrasePolicy::default())
+                    };
+                    match generated {
+                        Ok(generated) => inp.fill_generated(&generated),
+                        Err(err) => {
+                            self.error = Some(err.to_string());
+                            self.success = None;
+                        }
+                    }
+                }
                 KeyCode::Char(c) => inp.push_char(c),
                 KeyCode::Backspace => inp.backspace(),
                 _ => {}
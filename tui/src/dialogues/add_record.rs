@@ -2,7 +2,7 @@ use ratatui::symbols::border;
 use ratatui::{layout::Rect, widgets::Block, Frame};
 use sec_store::repository::RecordsRepository;
 
-use crate::dialogues::{Dialogue, DialogueResult};
+use crate::dialogues::{save_or_merge, Dialogue, DialogueResult};
 use crate::record_fields::RecordFields;
 use crate::repo::{self, RepositoryFactory};
 
@@ -40,11 +40,11 @@ where
     }
 
     fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
-        use crate::fields::{
-            RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
-        };
         use crate::record_fields::AddRecordStep;
         use sec_store::record::Record;
+        use sec_store::record::{
+            RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+        };
         let step = self.record_fields.get_current_step();
         match step {
             AddRecordStep::Password => {
@@ -98,8 +98,8 @@ where
                 if let Err(e) = repo::add_record(&mut self.repo, record) {
                     return DialogueResult::Error(e.to_string());
                 }
-                if let Err(e) = repo::save(&mut self.repo) {
-                    return DialogueResult::Error(e.to_string());
+                if let Err(res) = save_or_merge(&self.factory, &mut self.repo) {
+                    return res;
                 }
                 let repo = self.repo.clone();
                 DialogueResult::ChangeScreen(Box::new(
@@ -124,6 +124,10 @@ where
     fn on_exit(&mut self) {
         let _ = repo::close_connection(&self.repo);
     }
+
+    fn offers_password_generator(&self) -> bool {
+        self.record_fields.get_current_step() == crate::record_fields::AddRecordStep::Password
+    }
 }
 
 #[cfg(test)]
@@ -131,10 +135,10 @@ mod tests {
     use tempfile::TempDir;
 
     use crate::dialogues::{Dialogue, DialogueResult};
-    use crate::fields::RECORD_LOGIN_FIELD;
     use crate::repo::FileRepositoryFactory;
     use crate::runtime::block_on;
     use crate::test_helpers::test_password;
+    use sec_store::record::RECORD_LOGIN_FIELD;
     use sec_store::repository::file::{OpenRecordsFileRepository, RecordsFileRepository};
     use sec_store::repository::{OpenRepository, RecordsRepository};
 
@@ -149,7 +153,7 @@ mod tests {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
         let repo_password = test_password();
-        let mut repo = RecordsFileRepository::new(path, repo_password.clone());
+        let mut repo = RecordsFileRepository::new(path, repo_password.clone().into());
         crate::runtime::block_on(repo.save()).expect("save repo");
         let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
         (tmp, factory, repo, repo_password)
@@ -182,6 +186,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_generator_offered_only_for_password() {
+        let (_tmp, factory, repo, _repo_password) = make_repo();
+        let mut dialogue = AddRecordDialogue::new(factory, repo);
+        assert!(dialogue.offers_password_generator());
+
+        let _ = dialogue.on_input_submit("pw".to_string());
+        assert!(!dialogue.offers_password_generator());
+    }
+
     #[test]
     fn test_empty_name_returns_error() {
         let (_tmp, factory, repo, _repo_password) = make_repo();
@@ -240,7 +254,7 @@ mod tests {
         let res = dialogue.on_input_submit("desc".to_string());
         assert!(matches!(res, DialogueResult::ChangeScreen(_)));
 
-        let opened = OpenRecordsFileRepository(path).open(repo_password);
+        let opened = OpenRecordsFileRepository(path).open(repo_password.into());
         let opened = block_on(opened).expect("open saved repo");
         assert_eq!(block_on(opened.get_records()).expect("records").len(), 1);
     }
@@ -277,7 +291,7 @@ mod tests {
         let res = dialogue.on_input_submit(String::new());
         assert!(matches!(res, DialogueResult::ChangeScreen(_)));
 
-        let opened = OpenRecordsFileRepository(path).open(repo_password);
+        let opened = OpenRecordsFileRepository(path).open(repo_password.into());
         let opened = block_on(opened).expect("open saved repo");
         let record = block_on(opened.get_records())
             .expect("records")
@@ -0,0 +1,313 @@
+use std::path::Path;
+
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use sec_store::audit::AuditReport;
+use sec_store::repository::RecordsRepository;
+
+use crate::dialogues::{Dialogue, DialogueResult, ViewRecordDialogue};
+use crate::repo::{self, RepositoryFactory};
+
+#[derive(Debug)]
+pub struct AuditDialogue<F, R> {
+    factory: F,
+    repo: R,
+    report: AuditReport,
+    list_state: ListState,
+}
+
+impl<F, R> AuditDialogue<F, R>
+where
+    R: RecordsRepository,
+{
+    pub fn new(factory: F, repo: R) -> anyhow::Result<Self> {
+        let report = repo::audit(&repo)?;
+        Ok(Self {
+            factory,
+            repo,
+            report,
+            list_state: ListState::default().with_selected(Some(0)),
+        })
+    }
+
+    fn summary(&self) -> String {
+        if self.report.is_clean() {
+            format!(
+                "Checked {} records, no issues found",
+                self.report.checked_records
+            )
+        } else {
+            format!(
+                "Checked {} records, {} issues in {} records",
+                self.report.checked_records,
+                self.report.issues_count(),
+                self.report.records.len()
+            )
+        }
+    }
+}
+
+impl<F, R> Dialogue<F, R> for AuditDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Audit ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().magenta());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(1)])
+            .split(inner);
+        frame.render_widget(Paragraph::new(self.summary()), chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .report
+            .records
+            .iter()
+            .map(|record| {
+                let mut lines = vec![Line::styled(
+                    record.name.clone(),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )];
+                lines.extend(
+                    record
+                        .issues
+                        .iter()
+                        .map(|issue| Line::styled(format!("  {issue}"), Style::new().yellow())),
+                );
+                ListItem::new(Text::from(lines))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        frame.render_stateful_widget(list, chunks[1], &mut self.list_state);
+
+        let instructions = Line::from(vec![
+            Span::styled("↑/↓", Style::new().cyan()),
+            Span::raw(" navigate "),
+            Span::styled("Enter", Style::new().cyan()),
+            Span::raw(" view record "),
+            Span::styled("p", Style::new().cyan()),
+            Span::raw(" check breaches "),
+            Span::styled("Esc", Style::new().cyan()),
+            Span::raw(" back "),
+            Span::styled("q", Style::new().cyan()),
+            Span::raw(" quit"),
+        ]);
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        let n = self.report.records.len();
+        let sel = self
+            .list_state
+            .selected()
+            .unwrap_or(0)
+            .min(n.saturating_sub(1));
+        match k.code {
+            KeyCode::Up | KeyCode::Char('k') if n > 0 => {
+                self.list_state
+                    .select(Some(if sel == 0 { n - 1 } else { sel - 1 }));
+                DialogueResult::NoOp
+            }
+            KeyCode::Down | KeyCode::Char('j') if n > 0 => {
+                self.list_state.select(Some((sel + 1) % n));
+                DialogueResult::NoOp
+            }
+            KeyCode::Enter if n > 0 => {
+                DialogueResult::ChangeScreen(Box::new(ViewRecordDialogue::new(
+                    self.factory.clone(),
+                    self.repo.clone(),
+                    self.report.records[sel].record_id.clone(),
+                    false,
+                )))
+            }
+            KeyCode::Char('p') => DialogueResult::StartInput {
+                prompt: "Path to the Pwned Passwords SHA-1 file".to_string(),
+                password: false,
+            },
+            KeyCode::Esc | KeyCode::Char('b') => DialogueResult::ChangeScreen(Box::new(
+                crate::dialogues::view_repo::ViewRepoDialogue::new(
+                    self.factory.clone(),
+                    self.repo.clone(),
+                    Some(0),
+                ),
+            )),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
+        let path = value.trim();
+        if path.is_empty() {
+            return DialogueResult::NoOp;
+        }
+        match repo::check_breaches(&self.repo, Path::new(path)) {
+            Ok(breached) => {
+                self.report.add_breaches(&breached);
+                DialogueResult::Success(format!(
+                    "{} records have breached passwords",
+                    breached.len()
+                ))
+            }
+            Err(err) => DialogueResult::Error(format!("{err:#}")),
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        DialogueResult::NoOp
+    }
+
+    fn on_exit(&mut self) {
+        let _ = repo::close_connection(&self.repo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::repo::FileRepositoryFactory;
+    use crate::runtime::block_on;
+    use crate::test_helpers::test_password;
+    use sec_store::audit::AuditIssue;
+    use sec_store::breach::sha1_hex;
+    use sec_store::record::{Record, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+    use sec_store::repository::file::RecordsFileRepository;
+    use sec_store::repository::RecordsRepository;
+
+    use super::AuditDialogue;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn make_repo(
+        passwords: &[(&str, &str)],
+    ) -> (TempDir, FileRepositoryFactory, RecordsFileRepository) {
+        let tmp = TempDir::new().expect("temp dir");
+        let path = tmp.path().join("repo");
+        let mut repo = RecordsFileRepository::new(path, test_password().into());
+        for (name, password) in passwords {
+            let rec = Record::new(vec![
+                (RECORD_NAME_FIELD.to_string(), name.to_string()),
+                (RECORD_PASSWD_FIELD.to_string(), password.to_string()),
+            ]);
+            block_on(repo.add_record(rec)).expect("add");
+        }
+        block_on(repo.save()).expect("save");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        (tmp, factory, repo)
+    }
+
+    #[test]
+    fn test_reports_reused_passwords() {
+        let (_tmp, factory, repo) =
+            make_repo(&[("Mail", "u7#Kp2$wQz9!mR4x"), ("Bank", "u7#Kp2$wQz9!mR4x")]);
+        let dialogue = AuditDialogue::new(factory, repo).expect("audit");
+
+        assert_eq!(dialogue.report.records.len(), 2);
+        assert_eq!(
+            dialogue.report.records[0].issues,
+            vec![AuditIssue::ReusedPassword(vec!["Mail".to_string()])]
+        );
+        assert_eq!(
+            dialogue.summary(),
+            "Checked 2 records, 2 issues in 2 records"
+        );
+    }
+
+    #[test]
+    fn test_clean_repository() {
+        let (_tmp, factory, repo) = make_repo(&[("Mail", "u7#Kp2$wQz9!mR4x")]);
+        let mut dialogue = AuditDialogue::new(factory, repo).expect("audit");
+
+        assert_eq!(dialogue.summary(), "Checked 1 records, no issues found");
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Enter)),
+            DialogueResult::NoOp
+        ));
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Esc)),
+            DialogueResult::ChangeScreen(_)
+        ));
+    }
+
+    #[test]
+    fn test_enter_opens_record_and_navigation_wraps() {
+        let (_tmp, factory, repo) = make_repo(&[("Mail", "short"), ("Bank", "weak")]);
+        let mut dialogue = AuditDialogue::new(factory, repo).expect("audit");
+
+        let _ = dialogue.handle_key(key(KeyCode::Up));
+        assert_eq!(dialogue.list_state.selected(), Some(1));
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Enter)),
+            DialogueResult::ChangeScreen(_)
+        ));
+    }
+
+    #[test]
+    fn test_breach_check_adds_issues() {
+        let (_tmp, factory, repo) =
+            make_repo(&[("Mail", "u7#Kp2$wQz9!mR4x"), ("Bank", "Xk8$vL2@pQ7#nM5w")]);
+        let mut dialogue = AuditDialogue::new(factory, repo).expect("audit");
+        assert!(dialogue.report.is_clean());
+
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Char('p'))),
+            DialogueResult::StartInput {
+                password: false,
+                ..
+            }
+        ));
+        let mut hashes = NamedTempFile::new().expect("hashes file");
+        writeln!(hashes, "{}:12", sha1_hex("Xk8$vL2@pQ7#nM5w")).expect("write");
+        let res = dialogue.on_input_submit(hashes.path().display().to_string());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        assert_eq!(dialogue.report.records.len(), 1);
+        assert_eq!(dialogue.report.records[0].name, "Bank");
+        assert_eq!(
+            dialogue.report.records[0].issues,
+            vec![AuditIssue::BreachedPassword(12)]
+        );
+    }
+
+    #[test]
+    fn test_breach_check_reports_bad_path() {
+        let (tmp, factory, repo) = make_repo(&[("Mail", "u7#Kp2$wQz9!mR4x")]);
+        let mut dialogue = AuditDialogue::new(factory, repo).expect("audit");
+
+        let missing = tmp.path().join("missing.txt");
+        assert!(matches!(
+            dialogue.on_input_submit(missing.display().to_string()),
+            DialogueResult::Error(_)
+        ));
+        assert!(dialogue.report.is_clean());
+    }
+}
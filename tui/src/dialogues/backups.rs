@@ -0,0 +1,248 @@
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::widgets::Block;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use sec_store::repository::{Backup, RecordsRepository};
+
+use crate::dialogues::view_record::format_timestamp;
+use crate::dialogues::view_repo::ViewRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult, WelcomeDialogue};
+use crate::repo::RepositoryFactory;
+
+/// Lists the previous versions of the repository to restore one.
+#[derive(Debug)]
+pub struct BackupsDialogue<F> {
+    factory: F,
+    backups: Vec<Backup>,
+    list_state: ListState,
+    load_error: Option<String>,
+    /// The backup waiting for its password.
+    restoring: Option<Backup>,
+}
+
+impl<F> BackupsDialogue<F> {
+    pub fn new<R>(factory: F) -> Self
+    where
+        F: RepositoryFactory<R>,
+        R: RecordsRepository,
+    {
+        let mut dialogue = Self {
+            factory,
+            backups: Vec::new(),
+            list_state: ListState::default(),
+            load_error: None,
+            restoring: None,
+        };
+        dialogue.reload();
+        dialogue
+    }
+
+    fn reload<R>(&mut self)
+    where
+        F: RepositoryFactory<R>,
+        R: RecordsRepository,
+    {
+        match self.factory.list_backups() {
+            Ok(backups) => {
+                self.backups = backups;
+                self.load_error = None;
+            }
+            Err(err) => {
+                self.backups.clear();
+                self.load_error = Some(format!("Can't list backups: {err}"));
+            }
+        }
+        self.list_state
+            .select((!self.backups.is_empty()).then_some(0));
+    }
+
+    fn selected(&self) -> Option<&Backup> {
+        self.list_state.selected().and_then(|i| self.backups.get(i))
+    }
+}
+
+impl<F, R> Dialogue<F, R> for BackupsDialogue<F>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Backups ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().cyan());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if let Some(error) = &self.load_error {
+            frame.render_widget(
+                Paragraph::new(error.as_str()).style(Style::new().red()),
+                inner,
+            );
+        } else if self.backups.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No backups yet, one is kept on every save.")
+                    .style(Style::new().dark_gray()),
+                inner,
+            );
+        } else {
+            let items: Vec<ListItem> = self
+                .backups
+                .iter()
+                .map(|backup| {
+                    ListItem::new(format!(
+                        "{}  {} bytes",
+                        format_timestamp(backup.created_at),
+                        backup.size
+                    ))
+                })
+                .collect();
+            let list = List::new(items)
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                .highlight_symbol(">> ");
+            frame.render_stateful_widget(list, inner, &mut self.list_state);
+        }
+
+        let instructions = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Enter", Style::new().cyan()),
+            Span::raw(" restore "),
+            Span::styled("u", Style::new().cyan()),
+            Span::raw(" reload "),
+            Span::styled("q", Style::new().cyan()),
+            Span::raw(" back"),
+        ]);
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        let n = self.backups.len();
+        let sel = self.list_state.selected().unwrap_or(0);
+        match k.code {
+            KeyCode::Up | KeyCode::Char('k') if n > 0 => {
+                self.list_state
+                    .select(Some(if sel == 0 { n - 1 } else { sel - 1 }));
+                DialogueResult::NoOp
+            }
+            KeyCode::Down | KeyCode::Char('j') if n > 0 => {
+                self.list_state.select(Some((sel + 1) % n));
+                DialogueResult::NoOp
+            }
+            KeyCode::Enter => match self.selected().cloned() {
+                Some(backup) => {
+                    let prompt = format!(
+                        "Password of the version saved {}",
+                        format_timestamp(backup.created_at)
+                    );
+                    self.restoring = Some(backup);
+                    DialogueResult::StartInput {
+                        prompt,
+                        password: true,
+                    }
+                }
+                None => DialogueResult::NoOp,
+            },
+            KeyCode::Char('u') => {
+                self.reload();
+                DialogueResult::NoOp
+            }
+            KeyCode::Char('q') | KeyCode::Esc => DialogueResult::ChangeScreen(Box::new(
+                WelcomeDialogue::new(self.factory.clone(), Some(2)),
+            )),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
+        let Some(backup) = self.restoring.take() else {
+            return DialogueResult::NoOp;
+        };
+        match self.factory.restore_backup(&backup.id, value.into()) {
+            Ok(repo) => DialogueResult::ChangeScreen(Box::new(ViewRepoDialogue::new(
+                self.factory.clone(),
+                repo,
+                Some(0),
+            ))),
+            Err(err) => DialogueResult::Error(format!("Restore failed: {err}")),
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        self.restoring = None;
+        DialogueResult::NoOp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use sec_store::record::Record;
+    use sec_store::repository::RecordsRepository;
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::repo::{FileRepositoryFactory, RepositoryFactory};
+    use crate::runtime::block_on;
+    use crate::test_helpers::{test_password, ScopedTuiDataDir};
+
+    use super::BackupsDialogue;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_without_backups_enter_is_noop() {
+        let scope = ScopedTuiDataDir::new();
+        let factory = FileRepositoryFactory::new(scope.temp_dir.path().join("repo"));
+        let mut dialogue = BackupsDialogue::new(factory);
+        assert!(dialogue.backups.is_empty());
+        assert!(dialogue.load_error.is_none());
+        let res: DialogueResult<FileRepositoryFactory, _> =
+            dialogue.handle_key(key(KeyCode::Enter));
+        assert!(matches!(res, DialogueResult::NoOp));
+    }
+
+    #[test]
+    fn test_restore_asks_password_and_opens_repository() {
+        let scope = ScopedTuiDataDir::new();
+        let password = test_password();
+        let factory = FileRepositoryFactory::new(scope.temp_dir.path().join("repo"));
+        let mut repo = factory
+            .create_repo(password.clone().into())
+            .expect("repo creation failed");
+        block_on(repo.add_record(Record::new(vec![("name".to_string(), "mail".to_string())])))
+            .expect("add");
+        block_on(repo.save()).expect("save");
+
+        let mut dialogue = BackupsDialogue::new(factory);
+        assert_eq!(dialogue.backups.len(), 1);
+        let res = dialogue.handle_key(key(KeyCode::Enter));
+        assert!(matches!(
+            res,
+            DialogueResult::StartInput { password: true, .. }
+        ));
+        assert!(matches!(
+            dialogue.on_input_submit("wrong".to_string()),
+            DialogueResult::Error(_)
+        ));
+
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        assert!(matches!(
+            dialogue.on_input_submit(password),
+            DialogueResult::ChangeScreen(_)
+        ));
+    }
+}
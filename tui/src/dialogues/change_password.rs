@@ -0,0 +1,276 @@
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+use sec_store::repository::RecordsRepository;
+use sec_store::secret::SecretString;
+
+use crate::dialogues::view_repo::ViewRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult};
+use crate::repo::{self, RepositoryFactory};
+
+#[derive(Debug)]
+pub struct ChangePasswordDialogue<F, R> {
+    factory: F,
+    repo: R,
+    step: ChangePasswordStep,
+    changed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChangePasswordStep {
+    Old,
+    New {
+        old: SecretString,
+    },
+    Repeat {
+        old: SecretString,
+        new: SecretString,
+    },
+}
+
+impl<F, R> ChangePasswordDialogue<F, R> {
+    pub fn new(factory: F, repo: R) -> Self {
+        Self {
+            factory,
+            repo,
+            step: ChangePasswordStep::Old,
+            changed: false,
+        }
+    }
+
+    pub fn first_prompt() -> String {
+        "Enter the current password".to_string()
+    }
+}
+
+impl<F, R> ChangePasswordDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn back_to_repo(&self) -> DialogueResult<F, R> {
+        DialogueResult::ChangeScreen(Box::new(ViewRepoDialogue::new(
+            self.factory.clone(),
+            self.repo.clone(),
+            Some(0),
+        )))
+    }
+}
+
+impl<F, R> Dialogue<F, R> for ChangePasswordDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Change master password ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().yellow());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = if self.changed {
+            "Master password changed."
+        } else {
+            "Master password was not changed."
+        };
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+
+        let instructions = if self.changed {
+            Line::from(vec![
+                Span::styled("Enter", Style::new().cyan()),
+                Span::raw(" back "),
+            ])
+        } else {
+            Line::from(vec![
+                Span::styled("Enter", Style::new().cyan()),
+                Span::raw(" retry "),
+                Span::styled("Esc", Style::new().cyan()),
+                Span::raw(" back "),
+            ])
+        };
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        match k.code {
+            KeyCode::Enter if !self.changed => {
+                self.step = ChangePasswordStep::Old;
+                DialogueResult::StartInput {
+                    prompt: Self::first_prompt(),
+                    password: true,
+                }
+            }
+            KeyCode::Enter | KeyCode::Esc => self.back_to_repo(),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
+        match self.step.clone() {
+            ChangePasswordStep::Old => {
+                self.step = ChangePasswordStep::New { old: value.into() };
+                DialogueResult::StartInput {
+                    prompt: "Enter a new password".to_string(),
+                    password: true,
+                }
+            }
+            ChangePasswordStep::New { old } => {
+                if value.is_empty() {
+                    return DialogueResult::StartInput {
+                        prompt: "Enter a new password (password cannot be empty)".to_string(),
+                        password: true,
+                    };
+                }
+                self.step = ChangePasswordStep::Repeat {
+                    old,
+                    new: value.into(),
+                };
+                DialogueResult::StartInput {
+                    prompt: "Repeat the new password".to_string(),
+                    password: true,
+                }
+            }
+            ChangePasswordStep::Repeat { old, new } => {
+                if new != value.as_str() {
+                    return DialogueResult::StartInput {
+                        prompt: "Repeat the new password (passwords don't match)".to_string(),
+                        password: true,
+                    };
+                }
+                self.step = ChangePasswordStep::Old;
+                match repo::change_password(&mut self.repo, old, new) {
+                    Ok(()) => {
+                        self.changed = true;
+                        DialogueResult::Success("Master password changed".to_string())
+                    }
+                    Err(err) => DialogueResult::Error(err.to_string()),
+                }
+            }
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        self.back_to_repo()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tempfile::TempDir;
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::repo::{FileRepositoryFactory, RepositoryFactory};
+    use crate::test_helpers::test_password;
+
+    use super::ChangePasswordDialogue;
+
+    fn created_repo() -> (
+        TempDir,
+        FileRepositoryFactory,
+        sec_store::repository::file::RecordsFileRepository,
+        String,
+    ) {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let password = test_password();
+        let repo = factory
+            .create_repo(password.clone().into())
+            .expect("repo should be created");
+        (tmp, factory, repo, password)
+    }
+
+    #[test]
+    fn test_change_password_flow() {
+        let (_tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ChangePasswordDialogue::new(factory.clone(), repo);
+        let new_password = test_password();
+
+        assert!(matches!(
+            dialogue.on_input_submit(password.clone()),
+            DialogueResult::StartInput { password: true, .. }
+        ));
+        assert!(matches!(
+            dialogue.on_input_submit(new_password.clone()),
+            DialogueResult::StartInput { password: true, .. }
+        ));
+        let res = dialogue.on_input_submit(new_password.clone());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        assert!(factory.open_repo(password.into()).is_err());
+        factory
+            .open_repo(new_password.into())
+            .expect("repo should open");
+        assert!(matches!(
+            dialogue.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)),
+            DialogueResult::ChangeScreen(_)
+        ));
+    }
+
+    #[test]
+    fn test_wrong_current_password_is_reported() {
+        let (_tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ChangePasswordDialogue::new(factory.clone(), repo);
+        let new_password = test_password();
+
+        let _ = dialogue.on_input_submit(test_password());
+        let _ = dialogue.on_input_submit(new_password.clone());
+        let res = dialogue.on_input_submit(new_password);
+
+        match res {
+            DialogueResult::Error(msg) => assert!(msg.contains("Wrong password")),
+            _ => panic!("expected error"),
+        }
+        factory
+            .open_repo(password.into())
+            .expect("repo should still open");
+    }
+
+    #[test]
+    fn test_mismatched_repeat_asks_again() {
+        let (_tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ChangePasswordDialogue::new(factory, repo);
+
+        let _ = dialogue.on_input_submit(password);
+        let _ = dialogue.on_input_submit(test_password());
+        let res = dialogue.on_input_submit(test_password());
+
+        match res {
+            DialogueResult::StartInput { prompt, .. } => {
+                assert!(prompt.contains("passwords don't match"))
+            }
+            _ => panic!("expected repeated input"),
+        }
+    }
+
+    #[test]
+    fn test_empty_new_password_is_rejected() {
+        let (_tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ChangePasswordDialogue::new(factory, repo);
+
+        let _ = dialogue.on_input_submit(password);
+        let res = dialogue.on_input_submit(String::new());
+
+        match res {
+            DialogueResult::StartInput { prompt, .. } => {
+                assert!(prompt.contains("cannot be empty"))
+            }
+            _ => panic!("expected repeated input"),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use ratatui::symbols::border;
 use ratatui::{layout::Rect, widgets::Block, Frame};
 use sec_store::repository::RecordsRepository;
+use sec_store::secret::SecretString;
 
 use crate::dialogues::{Dialogue, DialogueResult};
 use crate::repo::RepositoryFactory;
@@ -9,7 +10,7 @@ use crate::repo::RepositoryFactory;
 pub struct CreateRepoDialogue<F> {
     factory: F,
     step: CreateRepoStep,
-    first_password: String,
+    first_password: SecretString,
 }
 
 #[derive(Clone, Debug)]
@@ -23,11 +24,11 @@ impl<F> CreateRepoDialogue<F> {
         Self {
             factory,
             step: CreateRepoStep::Password1,
-            first_password: String::new(),
+            first_password: SecretString::default(),
         }
     }
 
-    pub fn set_first_password(&mut self, pwd: String) {
+    pub fn set_first_password(&mut self, pwd: SecretString) {
         self.first_password = pwd;
         self.step = CreateRepoStep::Password2;
     }
@@ -55,14 +56,14 @@ where
                 if value.is_empty() {
                     return DialogueResult::Error("Password cannot be empty".to_string());
                 }
-                self.set_first_password(value);
+                self.set_first_password(value.into());
                 DialogueResult::StartInput {
                     prompt: "Repeat the password".to_string(),
                     password: true,
                 }
             }
             CreateRepoStep::Password2 => {
-                if value != self.first_password {
+                if self.first_password != value.as_str() {
                     return DialogueResult::StartInput {
                         prompt: "Repeat the password (wrong password)".to_string(),
                         password: true,
@@ -1,7 +1,4 @@
-use crate::dialogues::{Dialogue, DialogueResult};
-use crate::fields::{
-    RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
-};
+use crate::dialogues::{save_or_merge, Dialogue, DialogueResult};
 use crate::repo::{self, RepositoryFactory};
 use ratatui::symbols::border;
 use ratatui::{
@@ -11,10 +8,43 @@ use ratatui::{
     widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
+use sec_store::record::{
+    Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+    RECORD_TOTP_FIELD,
+};
 use sec_store::repository::RecordsRepository;
 
 type RecordId = String;
 
+// List entries that are record attributes rather than fields.
+const FOLDER_ENTRY: &str = "FOLDER";
+const TAGS_ENTRY: &str = "TAGS";
+
+fn editable_entries(rec: &Record) -> Vec<&'static str> {
+    let mut entries = vec![RECORD_NAME_FIELD, RECORD_PASSWD_FIELD];
+    if rec.get_field_value(RECORD_LOGIN_FIELD).is_some() {
+        entries.push(RECORD_LOGIN_FIELD);
+    }
+    if rec.get_field_value(RECORD_DESCR_FIELD).is_some() {
+        entries.push(RECORD_DESCR_FIELD);
+    }
+    entries.extend([FOLDER_ENTRY, TAGS_ENTRY, RECORD_TOTP_FIELD]);
+    entries
+}
+
+pub(crate) fn entry_label(entry: &str) -> &str {
+    match entry {
+        RECORD_NAME_FIELD => "Name",
+        RECORD_LOGIN_FIELD => "Login",
+        RECORD_DESCR_FIELD => "Description",
+        RECORD_PASSWD_FIELD => "Password",
+        FOLDER_ENTRY => "Folder",
+        TAGS_ENTRY => "Tags",
+        RECORD_TOTP_FIELD => "2FA secret",
+        _ => entry,
+    }
+}
+
 #[derive(Debug)]
 pub struct EditRecordDialogue<F, R> {
     factory: F,
@@ -53,15 +83,12 @@ where
 
         if let Some(ref field) = self.editing_field {
             if let Ok(Some(rec)) = repo::get_record(&self.repo, &self.record_id) {
-                let val = rec.get_field_value(field).unwrap_or_default();
-                let label = match field.as_str() {
-                    RECORD_NAME_FIELD => "Name",
-                    RECORD_LOGIN_FIELD => "Login",
-                    RECORD_DESCR_FIELD => "Description",
-                    RECORD_PASSWD_FIELD => "Password",
-                    _ => field,
+                let val = match field.as_str() {
+                    FOLDER_ENTRY => rec.folder().unwrap_or_default().to_string(),
+                    TAGS_ENTRY => rec.tags().join(", "),
+                    _ => rec.get_field_value(field).unwrap_or_default().to_string(),
                 };
-                let text = format!("Editing {label}:\n{val}");
+                let text = format!("Editing {}:\n{val}", entry_label(field));
                 frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
             }
         } else {
@@ -69,13 +96,10 @@ where
                 Ok(Some(x)) => x,
                 _ => return,
             };
-            let mut items = vec![ListItem::new("Name"), ListItem::new("Password")];
-            if rec.get_field_value(RECORD_LOGIN_FIELD).is_some() {
-                items.push(ListItem::new("Login"));
-            }
-            if rec.get_field_value(RECORD_DESCR_FIELD).is_some() {
-                items.push(ListItem::new("Description"));
-            }
+            let items: Vec<ListItem> = editable_entries(&rec)
+                .into_iter()
+                .map(|entry| ListItem::new(entry_label(entry)))
+                .collect();
             let list = List::new(items.clone())
                 .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
                 .highlight_symbol(">> ");
@@ -115,13 +139,7 @@ where
             Ok(Some(x)) => x,
             _ => return DialogueResult::NoOp,
         };
-        let mut fields = vec![RECORD_NAME_FIELD, RECORD_PASSWD_FIELD];
-        if rec.get_field_value(RECORD_LOGIN_FIELD).is_some() {
-            fields.push(RECORD_LOGIN_FIELD);
-        }
-        if rec.get_field_value(RECORD_DESCR_FIELD).is_some() {
-            fields.push(RECORD_DESCR_FIELD);
-        }
+        let fields = editable_entries(&rec);
         let n = fields.len();
         let sel = self
             .list_state
@@ -142,9 +160,15 @@ where
             KeyCode::Enter => {
                 let f = fields[sel].to_string();
                 self.editing_field = Some(f.clone());
+                let prompt = match f.as_str() {
+                    FOLDER_ENTRY => "Enter folder path, empty for none",
+                    TAGS_ENTRY => "Enter tags separated by commas",
+                    RECORD_TOTP_FIELD => "Enter otpauth:// URI or base32 secret",
+                    _ => "Enter new value",
+                };
                 DialogueResult::StartInput {
-                    prompt: "Enter new value".to_string(),
-                    password: f == RECORD_PASSWD_FIELD,
+                    prompt: prompt.to_string(),
+                    password: f == RECORD_PASSWD_FIELD || f == RECORD_TOTP_FIELD,
                 }
             }
             KeyCode::Esc => DialogueResult::ChangeScreen(Box::new(
@@ -163,10 +187,27 @@ where
         if let Some(field) = self.editing_field.clone() {
             let rid = self.record_id.clone();
             if let Ok(Some(mut rec)) = repo::get_record(&self.repo, &rid) {
-                if rec.update_field(field.clone(), value).is_ok()
-                    && repo::update_record(&mut self.repo, rec).is_ok()
-                    && repo::save(&mut self.repo).is_ok()
-                {
+                let updated = match field.as_str() {
+                    FOLDER_ENTRY => {
+                        rec.set_folder(&value);
+                        true
+                    }
+                    TAGS_ENTRY => {
+                        rec.set_tags(value.split(','));
+                        true
+                    }
+                    RECORD_TOTP_FIELD => {
+                        if let Err(err) = rec.set_totp(&value) {
+                            return DialogueResult::Error(format!("Invalid 2FA secret: {err}"));
+                        }
+                        true
+                    }
+                    _ => rec.update_field(field.clone(), value).is_ok(),
+                };
+                if updated && repo::update_record(&mut self.repo, rec).is_ok() {
+                    if let Err(res) = save_or_merge(&self.factory, &mut self.repo) {
+                        return res;
+                    }
                     return DialogueResult::ChangeScreen(Box::new(
                         crate::dialogues::view_record::ViewRecordDialogue::new(
                             self.factory.clone(),
@@ -192,6 +233,10 @@ where
     fn on_exit(&mut self) {
         let _ = repo::close_connection(&self.repo);
     }
+
+    fn offers_password_generator(&self) -> bool {
+        self.editing_field.as_deref() == Some(RECORD_PASSWD_FIELD)
+    }
 }
 
 #[cfg(test)]
@@ -200,13 +245,13 @@ mod tests {
     use tempfile::TempDir;
 
     use crate::dialogues::{Dialogue, DialogueResult};
-    use crate::fields::{
-        RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
-    };
     use crate::repo::FileRepositoryFactory;
     use crate::runtime::block_on;
     use crate::test_helpers::test_password;
     use sec_store::record::Record;
+    use sec_store::record::{
+        RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+    };
     use sec_store::repository::file::{OpenRecordsFileRepository, RecordsFileRepository};
     use sec_store::repository::{OpenRepository, RecordsRepository};
 
@@ -226,7 +271,7 @@ mod tests {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
         let repo_password = test_password();
-        let mut repo = RecordsFileRepository::new(path, repo_password.clone());
+        let mut repo = RecordsFileRepository::new(path, repo_password.clone().into());
         let rec = Record::new(vec![
             (RECORD_NAME_FIELD.to_string(), "Mail".to_string()),
             (RECORD_PASSWD_FIELD.to_string(), "pw".to_string()),
@@ -247,13 +292,13 @@ mod tests {
 
         let res = dialogue.handle_key(key(KeyCode::Up));
         assert!(matches!(res, DialogueResult::NoOp));
-        assert_eq!(dialogue.list_state.selected(), Some(3));
+        assert_eq!(dialogue.list_state.selected(), Some(6));
     }
 
     #[test]
     fn test_navigation_wraps_down() {
         let (_tmp, factory, repo, id, _repo_password) = make_repo_with_full_record();
-        let mut dialogue = EditRecordDialogue::new(factory, repo, id, Some(3));
+        let mut dialogue = EditRecordDialogue::new(factory, repo, id, Some(6));
 
         let res = dialogue.handle_key(key(KeyCode::Down));
         assert!(matches!(res, DialogueResult::NoOp));
@@ -290,6 +335,7 @@ mod tests {
             _ => panic!("expected StartInput"),
         }
         assert_eq!(dialogue.editing_field.as_deref(), Some(RECORD_PASSWD_FIELD));
+        assert!(dialogue.offers_password_generator());
     }
 
     #[test]
@@ -310,7 +356,8 @@ mod tests {
         let res = dialogue.on_input_submit("New Mail".to_string());
         assert!(matches!(res, DialogueResult::ChangeScreen(_)));
 
-        let repo_after = OpenRecordsFileRepository(tmp.path().join("repo")).open(repo_password);
+        let repo_after =
+            OpenRecordsFileRepository(tmp.path().join("repo")).open(repo_password.into());
         let repo_after = block_on(repo_after).expect("open repo");
         let rec = block_on(repo_after.get(&id))
             .expect("get")
@@ -321,6 +368,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_input_submit_sets_folder_and_tags() {
+        let (tmp, factory, repo, id, repo_password) = make_repo_with_full_record();
+        let mut dialogue = EditRecordDialogue::new(factory, repo, id.clone(), Some(4));
+        match dialogue.handle_key(key(KeyCode::Enter)) {
+            DialogueResult::StartInput { prompt, password } => {
+                assert_eq!(prompt, "Enter folder path, empty for none");
+                assert!(!password);
+            }
+            _ => panic!("expected StartInput"),
+        }
+        let res = dialogue.on_input_submit("work/mail".to_string());
+        assert!(matches!(res, DialogueResult::ChangeScreen(_)));
+
+        let mut dialogue =
+            EditRecordDialogue::new(dialogue.factory, dialogue.repo, id.clone(), Some(5));
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        let res = dialogue.on_input_submit("mail, personal".to_string());
+        assert!(matches!(res, DialogueResult::ChangeScreen(_)));
+
+        let repo_after =
+            OpenRecordsFileRepository(tmp.path().join("repo")).open(repo_password.into());
+        let repo_after = block_on(repo_after).expect("open repo");
+        let rec = block_on(repo_after.get(&id))
+            .expect("get")
+            .expect("record must exist");
+        assert_eq!(rec.folder(), Some("work/mail"));
+        assert_eq!(rec.tags(), ["mail", "personal"]);
+    }
+
+    #[test]
+    fn test_input_submit_validates_totp_secret() {
+        let (_tmp, factory, repo, id, _repo_password) = make_repo_with_full_record();
+        let mut dialogue = EditRecordDialogue::new(factory, repo, id.clone(), Some(6));
+        match dialogue.handle_key(key(KeyCode::Enter)) {
+            DialogueResult::StartInput { prompt, password } => {
+                assert_eq!(prompt, "Enter otpauth:// URI or base32 secret");
+                assert!(password);
+            }
+            _ => panic!("expected StartInput"),
+        }
+
+        match dialogue.on_input_submit("not a secret!".to_string()) {
+            DialogueResult::Error(msg) => assert!(msg.starts_with("Invalid 2FA secret")),
+            _ => panic!("expected error"),
+        }
+
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        let res = dialogue.on_input_submit("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string());
+        assert!(matches!(res, DialogueResult::ChangeScreen(_)));
+        let rec = block_on(dialogue.repo.get(&id))
+            .expect("get")
+            .expect("record must exist");
+        assert!(rec.totp().is_some_and(|totp| totp.is_ok()));
+    }
+
     #[test]
     fn test_input_cancel_exits_edit_mode() {
         let (_tmp, factory, repo, id, _repo_password) = make_repo_with_full_record();
@@ -337,7 +440,7 @@ mod tests {
     fn test_missing_record_returns_noop() {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
-        let mut repo = RecordsFileRepository::new(path, test_password());
+        let mut repo = RecordsFileRepository::new(path, test_password().into());
         block_on(repo.save()).expect("save");
 
         let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
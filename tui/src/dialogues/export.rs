@@ -0,0 +1,385 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use sec_store::export::ExportFormat;
+use sec_store::record::RecordId;
+use sec_store::repository::RecordsRepository;
+use sec_store::secret::SecretString;
+
+use crate::dialogues::view_repo::ViewRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult};
+use crate::repo::{self, RepositoryFactory};
+
+// Typed to confirm a plain-text export, a single key press is too easy to hit.
+const CONFIRMATION: &str = "EXPORT";
+
+#[derive(Debug)]
+pub struct ExportDialogue<F, R> {
+    factory: F,
+    repo: R,
+    record_ids: Vec<RecordId>,
+    list_state: ListState,
+    step: ExportStep,
+}
+
+#[derive(Debug)]
+enum ExportStep {
+    Choose,
+    Confirm,
+    Password,
+    Repeat(SecretString),
+    Path(Option<SecretString>),
+}
+
+impl<F, R> ExportDialogue<F, R> {
+    /// Exports the records with `record_ids`, the ones shown in the repository view.
+    pub fn new(factory: F, repo: R, record_ids: Vec<RecordId>) -> Self {
+        Self {
+            factory,
+            repo,
+            record_ids,
+            list_state: ListState::default().with_selected(Some(0)),
+            step: ExportStep::Choose,
+        }
+    }
+
+    fn format(&self) -> ExportFormat {
+        ExportFormat::ALL[self.list_state.selected().unwrap_or(0) % ExportFormat::ALL.len()]
+    }
+
+    fn path_prompt(&self) -> String {
+        format!("Path of the new {} file", self.format())
+    }
+}
+
+impl<F, R> ExportDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn write(&self, path: &Path, password: Option<SecretString>) -> anyhow::Result<()> {
+        let data = repo::export_records(&self.repo, &self.record_ids, self.format(), password)?;
+        // Never replace an existing file, it may be the repository itself.
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+        file.write_all(&data)?;
+        Ok(())
+    }
+}
+
+impl<F, R> Dialogue<F, R> for ExportDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Export ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().yellow());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Length(4),
+                Constraint::Min(1),
+            ])
+            .split(inner);
+        frame.render_widget(
+            Paragraph::new(format!("Export {} records", self.record_ids.len())),
+            chunks[0],
+        );
+
+        let (warning, style) = if self.format().is_plain_text() {
+            (
+                "WARNING: the file will hold every password in PLAIN TEXT. Anyone who can \
+                 read it can read your passwords. Delete it as soon as it has been imported.",
+                Style::new().red().add_modifier(Modifier::BOLD),
+            )
+        } else {
+            (
+                "The file is a repository encrypted with its own password, \
+                 open it with PasswordsKeeper.",
+                Style::new().green(),
+            )
+        };
+        frame.render_widget(
+            Paragraph::new(warning)
+                .style(style)
+                .wrap(Wrap { trim: true }),
+            chunks[1],
+        );
+
+        let items: Vec<ListItem> = ExportFormat::ALL
+            .iter()
+            .map(|format| {
+                let (note, style) = if format.is_plain_text() {
+                    ("  unencrypted", Style::new().red())
+                } else {
+                    ("  new password", Style::new().green())
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format.to_string()),
+                    Span::styled(note, style),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        frame.render_stateful_widget(list, chunks[2], &mut self.list_state);
+
+        let instructions = Line::from(vec![
+            Span::styled("↑/↓", Style::new().cyan()),
+            Span::raw(" format "),
+            Span::styled("Enter", Style::new().cyan()),
+            Span::raw(" export "),
+            Span::styled("Esc", Style::new().cyan()),
+            Span::raw(" back "),
+        ]);
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        let n = ExportFormat::ALL.len();
+        let sel = self.list_state.selected().unwrap_or(0) % n;
+        match k.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.list_state
+                    .select(Some(if sel == 0 { n - 1 } else { sel - 1 }));
+                DialogueResult::NoOp
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.list_state.select(Some((sel + 1) % n));
+                DialogueResult::NoOp
+            }
+            KeyCode::Enter if self.format().is_plain_text() => {
+                self.step = ExportStep::Confirm;
+                DialogueResult::StartInput {
+                    prompt: format!("Type {CONFIRMATION} to write the passwords unencrypted"),
+                    password: false,
+                }
+            }
+            KeyCode::Enter => {
+                self.step = ExportStep::Password;
+                DialogueResult::StartInput {
+                    prompt: "Password for the exported file".to_string(),
+                    password: true,
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('b') => DialogueResult::ChangeScreen(Box::new(
+                ViewRepoDialogue::new(self.factory.clone(), self.repo.clone(), Some(0)),
+            )),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
+        match std::mem::replace(&mut self.step, ExportStep::Choose) {
+            ExportStep::Choose => DialogueResult::NoOp,
+            ExportStep::Confirm if value.trim() == CONFIRMATION => {
+                self.step = ExportStep::Path(None);
+                DialogueResult::StartInput {
+                    prompt: self.path_prompt(),
+                    password: false,
+                }
+            }
+            ExportStep::Confirm => DialogueResult::Error("Export cancelled".to_string()),
+            ExportStep::Password if value.is_empty() => {
+                DialogueResult::Error("The password can't be empty".to_string())
+            }
+            ExportStep::Password if self.repo.is_password(&value) => DialogueResult::Error(
+                "The export password must differ from the repository password".to_string(),
+            ),
+            ExportStep::Password => {
+                self.step = ExportStep::Repeat(value.into());
+                DialogueResult::StartInput {
+                    prompt: "Repeat the password".to_string(),
+                    password: true,
+                }
+            }
+            ExportStep::Repeat(password) if password.as_str() != value => {
+                DialogueResult::Error("Passwords don't match".to_string())
+            }
+            ExportStep::Repeat(password) => {
+                self.step = ExportStep::Path(Some(password));
+                DialogueResult::StartInput {
+                    prompt: self.path_prompt(),
+                    password: false,
+                }
+            }
+            ExportStep::Path(password) => {
+                let path = Path::new(value.trim());
+                match self.write(path, password) {
+                    Ok(()) => DialogueResult::Success(format!(
+                        "Exported {} records to {}",
+                        self.record_ids.len(),
+                        path.display()
+                    )),
+                    Err(err) => DialogueResult::Error(format!("Export failed: {err}")),
+                }
+            }
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        self.step = ExportStep::Choose;
+        DialogueResult::NoOp
+    }
+
+    fn on_exit(&mut self) {
+        let _ = repo::close_connection(&self.repo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tempfile::TempDir;
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::repo::{FileRepositoryFactory, RepositoryFactory};
+    use crate::runtime::block_on;
+    use crate::test_helpers::test_password;
+    use sec_store::record::{Record, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+    use sec_store::repository::file::{OpenRecordsFileRepository, RecordsFileRepository};
+    use sec_store::repository::{OpenRepository, RecordsRepository};
+
+    use super::ExportDialogue;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn repo_with_records() -> (
+        TempDir,
+        FileRepositoryFactory,
+        RecordsFileRepository,
+        Vec<Record>,
+    ) {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let mut repo = factory
+            .create_repo(test_password().into())
+            .expect("repo should be created");
+        let records: Vec<Record> = ["Mail", "Bank"]
+            .into_iter()
+            .map(|name| {
+                Record::new(vec![
+                    (RECORD_NAME_FIELD.to_string(), name.to_string()),
+                    (RECORD_PASSWD_FIELD.to_string(), format!("{name}-secret")),
+                ])
+            })
+            .collect();
+        for record in &records {
+            block_on(repo.add_record(record.clone())).expect("add record");
+        }
+        (tmp, factory, repo, records)
+    }
+
+    #[test]
+    fn test_plain_text_export_needs_confirmation() {
+        let (tmp, factory, repo, records) = repo_with_records();
+        let mut dialogue = ExportDialogue::new(factory, repo, vec![records[0].id.clone()]);
+        let path = tmp.path().join("export.csv");
+
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Enter)),
+            DialogueResult::StartInput {
+                password: false,
+                ..
+            }
+        ));
+        assert!(matches!(
+            dialogue.on_input_submit("yes".to_string()),
+            DialogueResult::Error(_)
+        ));
+        assert!(matches!(
+            dialogue.on_input_submit(path.display().to_string()),
+            DialogueResult::NoOp
+        ));
+        assert!(!path.exists());
+
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        let _ = dialogue.on_input_submit("EXPORT".to_string());
+        let res = dialogue.on_input_submit(path.display().to_string());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        let csv = std::fs::read_to_string(&path).expect("export file");
+        assert!(csv.contains("Mail,,Mail-secret"));
+        assert!(!csv.contains("Bank"));
+
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        let _ = dialogue.on_input_submit("EXPORT".to_string());
+        assert!(matches!(
+            dialogue.on_input_submit(path.display().to_string()),
+            DialogueResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_export_uses_new_password() {
+        let (tmp, factory, repo, records) = repo_with_records();
+        let ids = records.iter().map(|record| record.id.clone()).collect();
+        let mut dialogue = ExportDialogue::new(factory, repo, ids);
+        let path = tmp.path().join("export.json");
+
+        let _ = dialogue.handle_key(key(KeyCode::Up));
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Enter)),
+            DialogueResult::StartInput { password: true, .. }
+        ));
+        let _ = dialogue.on_input_submit("colleague".to_string());
+        assert!(matches!(
+            dialogue.on_input_submit("colleage".to_string()),
+            DialogueResult::Error(_)
+        ));
+
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        let _ = dialogue.on_input_submit("colleague".to_string());
+        let _ = dialogue.on_input_submit("colleague".to_string());
+        let res = dialogue.on_input_submit(path.display().to_string());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        let exported = block_on(OpenRecordsFileRepository(path).open("colleague".into()))
+            .expect("export should open with the new password");
+        assert_eq!(block_on(exported.get_records()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_encrypted_export_refuses_the_repository_password() {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let password = test_password();
+        let repo = factory
+            .create_repo(password.clone().into())
+            .expect("repo should be created");
+        let mut dialogue = ExportDialogue::new(factory, repo, Vec::new());
+
+        let _ = dialogue.handle_key(key(KeyCode::Up));
+        let _ = dialogue.handle_key(key(KeyCode::Enter));
+        assert!(matches!(
+            dialogue.on_input_submit(password),
+            DialogueResult::Error(_)
+        ));
+    }
+}
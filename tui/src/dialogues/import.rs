@@ -0,0 +1,305 @@
+use std::path::Path;
+
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+use sec_store::import::{
+    bitwarden::import_bitwarden, csv::import_csv, kdbx::import_kdbx, CsvMapping, ImportFormat,
+    ImportResult,
+};
+use sec_store::record::Record;
+use sec_store::repository::RecordsRepository;
+
+use crate::dialogues::view_repo::ViewRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult};
+use crate::repo::{self, RepositoryFactory};
+
+#[derive(Debug)]
+pub struct ImportDialogue<F, R> {
+    factory: F,
+    repo: R,
+    step: ImportStep,
+    imported: Option<usize>,
+}
+
+#[derive(Debug)]
+enum ImportStep {
+    Path,
+    Password(Vec<u8>),
+    Mapping(Vec<u8>),
+}
+
+impl<F, R> ImportDialogue<F, R> {
+    pub fn new(factory: F, repo: R) -> Self {
+        Self {
+            factory,
+            repo,
+            step: ImportStep::Path,
+            imported: None,
+        }
+    }
+
+    pub fn first_prompt() -> String {
+        "Path to a KeePass, Bitwarden JSON or CSV file".to_string()
+    }
+}
+
+impl<F, R> ImportDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn back_to_repo(&self) -> DialogueResult<F, R> {
+        DialogueResult::ChangeScreen(Box::new(ViewRepoDialogue::new(
+            self.factory.clone(),
+            self.repo.clone(),
+            Some(0),
+        )))
+    }
+
+    fn finish(&mut self, records: ImportResult<Vec<Record>>) -> DialogueResult<F, R> {
+        self.step = ImportStep::Path;
+        let records = match records {
+            Ok(records) => records,
+            Err(err) => return DialogueResult::Error(format!("Import failed: {err}")),
+        };
+        match repo::import_records(&mut self.repo, records) {
+            Ok(count) => {
+                self.imported = Some(count);
+                DialogueResult::Success(format!("Imported {count} records"))
+            }
+            Err(err) => DialogueResult::Error(format!("Import failed: {err}")),
+        }
+    }
+}
+
+impl<F, R> Dialogue<F, R> for ImportDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Import ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().yellow());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = match self.imported {
+            Some(count) => format!("Imported {count} records."),
+            None => "Nothing was imported.".to_string(),
+        };
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+
+        let instructions = Line::from(vec![
+            Span::styled("Enter", Style::new().cyan()),
+            Span::raw(" import another file "),
+            Span::styled("Esc", Style::new().cyan()),
+            Span::raw(" back "),
+        ]);
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        match k.code {
+            KeyCode::Enter => {
+                self.step = ImportStep::Path;
+                DialogueResult::StartInput {
+                    prompt: Self::first_prompt(),
+                    password: false,
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('b') => self.back_to_repo(),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
+        match std::mem::replace(&mut self.step, ImportStep::Path) {
+            ImportStep::Path => {
+                let path = Path::new(value.trim());
+                let data = match std::fs::read(path) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        return DialogueResult::Error(format!(
+                            "Failed to read {}: {err}",
+                            path.display()
+                        ))
+                    }
+                };
+                match ImportFormat::detect(&data) {
+                    ImportFormat::Kdbx => {
+                        self.step = ImportStep::Password(data);
+                        DialogueResult::StartInput {
+                            prompt: "Enter the KeePass database password".to_string(),
+                            password: true,
+                        }
+                    }
+                    ImportFormat::Bitwarden => self.finish(import_bitwarden(&data)),
+                    ImportFormat::Csv => {
+                        self.step = ImportStep::Mapping(data);
+                        DialogueResult::StartInput {
+                            prompt: "Column mapping such as Site=name,Secret=password (empty for the defaults)"
+                                .to_string(),
+                            password: false,
+                        }
+                    }
+                }
+            }
+            ImportStep::Password(data) => self.finish(import_kdbx(&data, &value)),
+            ImportStep::Mapping(data) => {
+                let records =
+                    CsvMapping::parse(&value).and_then(|mapping| import_csv(&data, &mapping));
+                self.finish(records)
+            }
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        self.step = ImportStep::Path;
+        self.back_to_repo()
+    }
+
+    fn on_exit(&mut self) {
+        let _ = repo::close_connection(&self.repo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::repo::{FileRepositoryFactory, RepositoryFactory};
+    use crate::test_helpers::test_password;
+    use sec_store::record::{RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+    use sec_store::repository::file::RecordsFileRepository;
+
+    use super::ImportDialogue;
+
+    fn created_repo() -> (
+        TempDir,
+        FileRepositoryFactory,
+        RecordsFileRepository,
+        String,
+    ) {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let password = test_password();
+        let repo = factory
+            .create_repo(password.clone().into())
+            .expect("repo should be created");
+        (tmp, factory, repo, password)
+    }
+
+    fn file_with(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("temp file");
+        file.write_all(content.as_bytes()).expect("write");
+        file
+    }
+
+    fn saved_names(factory: &FileRepositoryFactory, password: String) -> Vec<String> {
+        let repo = factory
+            .open_repo(password.into())
+            .expect("repo should open");
+        let mut names: Vec<String> =
+            crate::runtime::block_on(sec_store::repository::RecordsRepository::get_records(&repo))
+                .expect("records")
+                .iter()
+                .map(|record| {
+                    record
+                        .get_field_value(RECORD_NAME_FIELD)
+                        .unwrap()
+                        .to_string()
+                })
+                .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_import_csv_with_mapping() {
+        let (_tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ImportDialogue::new(factory.clone(), repo);
+        let csv = file_with("Site;Secret\nMail;p1\nBank;p2\n");
+
+        assert!(matches!(
+            dialogue.on_input_submit(csv.path().display().to_string()),
+            DialogueResult::StartInput {
+                password: false,
+                ..
+            }
+        ));
+        let res = dialogue.on_input_submit("Site=name,Secret=password".to_string());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        assert_eq!(dialogue.imported, Some(2));
+        assert_eq!(saved_names(&factory, password), vec!["Bank", "Mail"]);
+    }
+
+    #[test]
+    fn test_import_bitwarden_json() {
+        let (_tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ImportDialogue::new(factory.clone(), repo);
+        let json = file_with(
+            r#"{"encrypted": false, "items": [{"type": 1, "name": "Git", "login": {"password": "hunter2"}}]}"#,
+        );
+
+        let res = dialogue.on_input_submit(json.path().display().to_string());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        let repo = factory
+            .open_repo(password.into())
+            .expect("repo should open");
+        let records =
+            crate::runtime::block_on(sec_store::repository::RecordsRepository::get_records(&repo))
+                .expect("records");
+        assert_eq!(
+            records[0].get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "hunter2"
+        );
+    }
+
+    #[test]
+    fn test_import_errors_are_reported() {
+        let (tmp, factory, repo, password) = created_repo();
+        let mut dialogue = ImportDialogue::new(factory.clone(), repo);
+
+        let missing = tmp.path().join("missing.csv");
+        assert!(matches!(
+            dialogue.on_input_submit(missing.display().to_string()),
+            DialogueResult::Error(_)
+        ));
+
+        let csv = file_with("name,password\nMail,p1\n");
+        let _ = dialogue.on_input_submit(csv.path().display().to_string());
+        assert!(matches!(
+            dialogue.on_input_submit("Site".to_string()),
+            DialogueResult::Error(_)
+        ));
+        assert_eq!(dialogue.imported, None);
+        assert!(saved_names(&factory, password).is_empty());
+
+        assert!(matches!(
+            dialogue.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            DialogueResult::ChangeScreen(_)
+        ));
+    }
+}
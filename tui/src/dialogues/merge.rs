@@ -0,0 +1,353 @@
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use sec_store::record::{Record, RECORD_NAME_FIELD};
+use sec_store::repository::merge::{ConflictPart, MergeConflict, MergeSide};
+use sec_store::repository::RecordsRepository;
+
+use crate::dialogues::edit_record::entry_label;
+use crate::dialogues::view_repo::ViewRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult};
+use crate::repo::{self, RepositoryFactory, SaveConflicts};
+
+/// Saves `repo`. Changes conflicting with the ones saved elsewhere open the merge screen,
+/// other failures become an error result.
+pub fn save_or_merge<F, R>(factory: &F, repo: &mut R) -> Result<(), DialogueResult<F, R>>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    match repo::save(repo) {
+        Ok(()) => Ok(()),
+        Err(err) => match err.downcast::<SaveConflicts<R>>() {
+            Ok(SaveConflicts { merged, conflicts }) => Err(DialogueResult::ChangeScreen(Box::new(
+                MergeDialogue::new(factory.clone(), repo.clone(), merged, conflicts),
+            ))),
+            Err(err) => Err(DialogueResult::Error(err.to_string())),
+        },
+    }
+}
+
+#[derive(Debug)]
+pub struct MergeDialogue<F, R> {
+    factory: F,
+    // Kept to go back without saving.
+    repo: R,
+    merged: R,
+    conflicts: Vec<MergeConflict>,
+    choices: Vec<MergeSide>,
+    list_state: ListState,
+    values_visible: bool,
+}
+
+impl<F, R> MergeDialogue<F, R> {
+    pub fn new(factory: F, repo: R, merged: R, conflicts: Vec<MergeConflict>) -> Self {
+        let choices = vec![MergeSide::Local; conflicts.len()];
+        Self {
+            factory,
+            repo,
+            merged,
+            conflicts,
+            choices,
+            list_state: ListState::default().with_selected(Some(0)),
+            values_visible: false,
+        }
+    }
+
+    fn side_value(&self, conflict: &MergeConflict, record: Option<&Record>) -> String {
+        let Some(record) = record else {
+            return "deleted".to_string();
+        };
+        match &conflict.part {
+            ConflictPart::Field(name) => match record.get_field(name) {
+                Some(field) if field.kind.is_concealed() && !self.values_visible => {
+                    "*".repeat(field.value.len())
+                }
+                Some(field) => field.value.to_string(),
+                None => "-".to_string(),
+            },
+            ConflictPart::Tags => record.tags().join(", "),
+            ConflictPart::Folder => record.folder().unwrap_or("-").to_string(),
+            ConflictPart::Deletion => "changed".to_string(),
+        }
+    }
+
+    fn conflict_line(&self, conflict: &MergeConflict, side: MergeSide) -> Line<'static> {
+        let name = conflict
+            .local
+            .as_ref()
+            .or(conflict.remote.as_ref())
+            .and_then(|record| record.get_field_value(RECORD_NAME_FIELD))
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let part = match &conflict.part {
+            ConflictPart::Field(field) => entry_label(field).to_string(),
+            part => part.to_string(),
+        };
+        let style = |shown: MergeSide| {
+            if shown == side {
+                Style::new().green().add_modifier(Modifier::BOLD)
+            } else {
+                Style::new().dim()
+            }
+        };
+        Line::from(vec![
+            Span::raw(format!("{name} · {part}  ")),
+            Span::styled(
+                format!(
+                    "mine: {}",
+                    self.side_value(conflict, conflict.local.as_ref())
+                ),
+                style(MergeSide::Local),
+            ),
+            Span::raw("  "),
+            Span::styled(
+                format!(
+                    "saved: {}",
+                    self.side_value(conflict, conflict.remote.as_ref())
+                ),
+                style(MergeSide::Remote),
+            ),
+        ])
+    }
+}
+
+impl<F, R> MergeDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn apply(&mut self) -> DialogueResult<F, R> {
+        let mut merged = self.merged.clone();
+        for (conflict, side) in self.conflicts.iter().zip(&self.choices) {
+            if let Err(err) = repo::resolve_conflict(&mut merged, conflict, *side) {
+                return DialogueResult::Error(format!("Merge failed: {err}"));
+            }
+        }
+        match save_or_merge(&self.factory, &mut merged) {
+            Ok(()) => DialogueResult::ChangeScreen(Box::new(ViewRepoDialogue::new(
+                self.factory.clone(),
+                merged,
+                Some(0),
+            ))),
+            Err(res) => res,
+        }
+    }
+}
+
+impl<F, R> Dialogue<F, R> for MergeDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Merge conflicts ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().yellow());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(1)])
+            .split(inner);
+        frame.render_widget(
+            Paragraph::new(format!(
+                "The repository was saved elsewhere while you edited it. Other changes were \
+                 merged, pick the version to keep for each of the {} conflicts below.",
+                self.conflicts.len()
+            ))
+            .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+
+        let items: Vec<ListItem> = self
+            .conflicts
+            .iter()
+            .zip(&self.choices)
+            .map(|(conflict, side)| ListItem::new(self.conflict_line(conflict, *side)))
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        frame.render_stateful_widget(list, chunks[1], &mut self.list_state);
+
+        let instructions = Line::from(vec![
+            Span::styled("↑/↓", Style::new().cyan()),
+            Span::raw(" navigate "),
+            Span::styled("←/→", Style::new().cyan()),
+            Span::raw(" mine/saved "),
+            Span::styled("v", Style::new().cyan()),
+            Span::raw(" show values "),
+            Span::styled("Enter", Style::new().cyan()),
+            Span::raw(" save "),
+            Span::styled("Esc", Style::new().cyan()),
+            Span::raw(" cancel "),
+        ]);
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        let n = self.conflicts.len().max(1);
+        let sel = self.list_state.selected().unwrap_or(0) % n;
+        match k.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.list_state
+                    .select(Some(if sel == 0 { n - 1 } else { sel - 1 }));
+                DialogueResult::NoOp
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.list_state.select(Some((sel + 1) % n));
+                DialogueResult::NoOp
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                if let Some(side) = self.choices.get_mut(sel) {
+                    *side = MergeSide::Local;
+                }
+                DialogueResult::NoOp
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(side) = self.choices.get_mut(sel) {
+                    *side = MergeSide::Remote;
+                }
+                DialogueResult::NoOp
+            }
+            KeyCode::Char('v') => {
+                self.values_visible = !self.values_visible;
+                DialogueResult::NoOp
+            }
+            KeyCode::Enter => self.apply(),
+            KeyCode::Esc => DialogueResult::ChangeScreen(Box::new(ViewRepoDialogue::new(
+                self.factory.clone(),
+                self.repo.clone(),
+                Some(0),
+            ))),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, _value: String) -> DialogueResult<F, R> {
+        DialogueResult::NoOp
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        DialogueResult::NoOp
+    }
+
+    fn on_exit(&mut self) {
+        let _ = repo::close_connection(&self.repo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tempfile::TempDir;
+
+    use crate::dialogues::DialogueResult;
+    use crate::repo::{self, FileRepositoryFactory, RepositoryFactory};
+    use crate::runtime::block_on;
+    use crate::test_helpers::test_password;
+    use sec_store::record::{Record, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
+    use sec_store::repository::RecordsRepository;
+
+    use super::save_or_merge;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn updated(record: &Record, field: &str, value: &str) -> Record {
+        let mut record = record.clone();
+        record.update_field(field.to_string(), value).unwrap();
+        record
+    }
+
+    #[test]
+    fn test_conflicting_save_opens_merge_screen() {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let password = test_password();
+        let mut repo = factory
+            .create_repo(password.clone().into())
+            .expect("repo should be created");
+        let record = Record::new(vec![
+            (RECORD_NAME_FIELD.to_string(), "Mail".to_string()),
+            (RECORD_LOGIN_FIELD.to_string(), "alice".to_string()),
+            (RECORD_PASSWD_FIELD.to_string(), "base".to_string()),
+        ]);
+        block_on(repo.add_record(record.clone())).expect("add record");
+        repo::save(&mut repo).expect("save");
+
+        let mut other = factory
+            .open_repo(password.clone().into())
+            .expect("repo should open");
+        let saved_elsewhere = updated(&record, RECORD_PASSWD_FIELD, "saved");
+        let saved_elsewhere = updated(&saved_elsewhere, RECORD_LOGIN_FIELD, "bob");
+        repo::update_record(&mut other, saved_elsewhere).expect("update");
+        repo::save(&mut other).expect("save");
+
+        repo::update_record(&mut repo, updated(&record, RECORD_PASSWD_FIELD, "mine"))
+            .expect("update");
+        let Err(DialogueResult::ChangeScreen(mut dialogue)) = save_or_merge(&factory, &mut repo)
+        else {
+            panic!("expected the merge screen");
+        };
+        let _ = dialogue.handle_key(key(KeyCode::Right));
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Enter)),
+            DialogueResult::ChangeScreen(_)
+        ));
+
+        let reopened = factory
+            .open_repo(password.into())
+            .expect("repo should open");
+        let merged = repo::get_record(&reopened, &record.id)
+            .expect("get record")
+            .expect("record");
+        assert_eq!(
+            merged.get_field_value(RECORD_PASSWD_FIELD).unwrap(),
+            "saved"
+        );
+        assert_eq!(merged.get_field_value(RECORD_LOGIN_FIELD).unwrap(), "bob");
+    }
+
+    #[test]
+    fn test_independent_changes_are_merged_on_save() {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let password = test_password();
+        let mut repo = factory
+            .create_repo(password.clone().into())
+            .expect("repo should be created");
+        let mut other = factory
+            .open_repo(password.clone().into())
+            .expect("repo should open");
+        let mail = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "Mail".to_string())]);
+        let bank = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "Bank".to_string())]);
+        block_on(other.add_record(mail.clone())).expect("add record");
+        repo::save(&mut other).expect("save");
+        block_on(repo.add_record(bank.clone())).expect("add record");
+
+        assert!(save_or_merge(&factory, &mut repo).is_ok());
+
+        let reopened = factory
+            .open_repo(password.into())
+            .expect("repo should open");
+        assert_eq!(block_on(reopened.get_records()).expect("records").len(), 2);
+    }
+}
@@ -1,12 +1,27 @@
 pub mod add_record;
+pub mod audit;
+pub mod backups;
+pub mod change_password;
 pub mod create_repo;
 pub mod edit_record;
+pub mod export;
+pub mod import;
+pub mod merge;
 pub mod open_repo;
+pub mod pick_repo;
+pub mod share;
 pub mod view_record;
 pub mod view_repo;
 pub mod welcome;
 
 pub use add_record::AddRecordDialogue;
+pub use audit::AuditDialogue;
+pub use change_password::ChangePasswordDialogue;
+pub use export::ExportDialogue;
+pub use import::ImportDialogue;
+pub use merge::save_or_merge;
+pub use pick_repo::PickRepoDialogue;
+pub use share::ShareDialogue;
 pub use view_record::ViewRecordDialogue;
 pub use welcome::WelcomeDialogue;
 
@@ -24,6 +39,14 @@ where
     fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R>;
     fn on_input_cancel(&mut self) -> DialogueResult<F, R>;
     fn on_exit(&mut self) {}
+    /// Whether the active input may be filled by the password generator.
+    fn offers_password_generator(&self) -> bool {
+        false
+    }
+    /// Redraw period for screens showing time dependent content, `None` redraws on input only.
+    fn refresh_interval(&self) -> Option<std::time::Duration> {
+        None
+    }
 }
 
 #[derive(Debug)]
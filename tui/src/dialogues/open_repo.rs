@@ -33,7 +33,7 @@ where
     }
 
     fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
-        match self.factory.open_repo(value) {
+        match self.factory.open_repo(value.into()) {
             Ok(repo) => DialogueResult::ChangeScreen(Box::new(
                 crate::dialogues::view_repo::ViewRepoDialogue::new(
                     self.factory.clone(),
@@ -73,7 +73,7 @@ mod tests {
         let password = test_password();
         let factory = FileRepositoryFactory::new(_scope.temp_dir.path().join("repo"));
         factory
-            .create_repo(password.clone())
+            .create_repo(password.clone().into())
             .expect("repo creation failed");
 
         let mut dialogue = OpenRepoDialogue::new(factory);
@@ -87,7 +87,7 @@ mod tests {
         let _scope = ScopedTuiDataDir::new();
         let factory = FileRepositoryFactory::new(_scope.temp_dir.path().join("repo"));
         factory
-            .create_repo(test_password())
+            .create_repo(test_password().into())
             .expect("repo creation failed");
         let wrong_password = test_password();
 
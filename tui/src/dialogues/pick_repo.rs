@@ -0,0 +1,312 @@
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::widgets::Block;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use sec_store::repository::remote::RemoteRecordsRepository;
+
+use crate::dialogues::create_repo::CreateRepoDialogue;
+use crate::dialogues::open_repo::OpenRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult};
+use crate::repo::RemoteRepositoryFactory;
+
+type PickResult = DialogueResult<RemoteRepositoryFactory, RemoteRecordsRepository>;
+
+/// Lists the repositories of the server to open, create, rename or delete one.
+#[derive(Debug)]
+pub struct PickRepoDialogue {
+    factory: RemoteRepositoryFactory,
+    names: Vec<String>,
+    list_state: ListState,
+    load_error: Option<String>,
+    step: Option<PickStep>,
+}
+
+#[derive(Debug)]
+enum PickStep {
+    NewName,
+    RenameTo { name: String },
+    RenamePassword { name: String, new_name: String },
+    DeletePassword { name: String },
+}
+
+impl PickRepoDialogue {
+    pub fn new(factory: RemoteRepositoryFactory) -> Self {
+        let mut dialogue = Self {
+            factory,
+            names: Vec::new(),
+            list_state: ListState::default(),
+            load_error: None,
+            step: None,
+        };
+        dialogue.reload();
+        dialogue
+    }
+
+    fn reload(&mut self) {
+        match self.factory.list_repos() {
+            Ok(names) => {
+                self.names = names;
+                self.load_error = None;
+            }
+            Err(err) => {
+                self.names.clear();
+                self.load_error = Some(format!("Can't list repositories: {err}"));
+            }
+        }
+        self.select_name(self.factory.repository_name().map(str::to_string));
+    }
+
+    fn select_name(&mut self, name: Option<String>) {
+        let index = name
+            .and_then(|name| self.names.iter().position(|n| *n == name))
+            .unwrap_or(0);
+        self.list_state
+            .select((!self.names.is_empty()).then_some(index));
+    }
+
+    fn selected(&self) -> Option<&String> {
+        self.list_state.selected().and_then(|i| self.names.get(i))
+    }
+
+    fn input(prompt: &str, password: bool) -> PickResult {
+        DialogueResult::StartInput {
+            prompt: prompt.to_string(),
+            password,
+        }
+    }
+}
+
+impl Dialogue<RemoteRepositoryFactory, RemoteRecordsRepository> for PickRepoDialogue {
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Repositories on the server ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().cyan());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if let Some(error) = &self.load_error {
+            frame.render_widget(
+                Paragraph::new(error.as_str()).style(Style::new().red()),
+                inner,
+            );
+        } else if self.names.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No repositories yet, press n to create one.")
+                    .style(Style::new().dark_gray()),
+                inner,
+            );
+        } else {
+            let items: Vec<ListItem> = self
+                .names
+                .iter()
+                .map(|name| ListItem::new(name.as_str()))
+                .collect();
+            let list = List::new(items)
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                .highlight_symbol(">> ");
+            frame.render_stateful_widget(list, inner, &mut self.list_state);
+        }
+
+        let instructions = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Enter", Style::new().cyan()),
+            Span::raw(" open "),
+            Span::styled("n", Style::new().cyan()),
+            Span::raw(" new "),
+            Span::styled("r", Style::new().cyan()),
+            Span::raw(" rename "),
+            Span::styled("d", Style::new().cyan()),
+            Span::raw(" delete "),
+            Span::styled("u", Style::new().cyan()),
+            Span::raw(" reload "),
+            Span::styled("q", Style::new().cyan()),
+            Span::raw(" quit"),
+        ]);
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> PickResult {
+        let n = self.names.len();
+        let sel = self.list_state.selected().unwrap_or(0);
+        match k.code {
+            KeyCode::Up | KeyCode::Char('k') if n > 0 => {
+                self.list_state
+                    .select(Some(if sel == 0 { n - 1 } else { sel - 1 }));
+                DialogueResult::NoOp
+            }
+            KeyCode::Down | KeyCode::Char('j') if n > 0 => {
+                self.list_state.select(Some((sel + 1) % n));
+                DialogueResult::NoOp
+            }
+            KeyCode::Enter => match self.selected() {
+                Some(name) => DialogueResult::ChangeScreenAndStartInput {
+                    dialogue: Box::new(OpenRepoDialogue::new(self.factory.with_repository(name))),
+                    prompt: "Enter password".to_string(),
+                    password: true,
+                },
+                None => DialogueResult::NoOp,
+            },
+            KeyCode::Char('n') => {
+                self.step = Some(PickStep::NewName);
+                Self::input(
+                    "Name of the new repository (letters, digits, '-', '_' and '.')",
+                    false,
+                )
+            }
+            KeyCode::Char('r') => match self.selected().cloned() {
+                Some(name) => {
+                    let prompt = format!("New name of \"{name}\"");
+                    self.step = Some(PickStep::RenameTo { name });
+                    Self::input(&prompt, false)
+                }
+                None => DialogueResult::NoOp,
+            },
+            KeyCode::Char('d') => match self.selected().cloned() {
+                Some(name) => {
+                    let prompt = format!("Password of \"{name}\" to move it to the trash");
+                    self.step = Some(PickStep::DeletePassword { name });
+                    Self::input(&prompt, true)
+                }
+                None => DialogueResult::NoOp,
+            },
+            KeyCode::Char('u') => {
+                self.reload();
+                DialogueResult::NoOp
+            }
+            KeyCode::Char('q') | KeyCode::Esc => DialogueResult::Exit,
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> PickResult {
+        match self.step.take() {
+            Some(PickStep::NewName) if value.trim().is_empty() => DialogueResult::NoOp,
+            Some(PickStep::NewName) => DialogueResult::ChangeScreenAndStartInput {
+                dialogue: Box::new(CreateRepoDialogue::new(
+                    self.factory.with_repository(value.trim()),
+                )),
+                prompt: "Choose a password".to_string(),
+                password: true,
+            },
+            Some(PickStep::RenameTo { .. }) if value.trim().is_empty() => DialogueResult::NoOp,
+            Some(PickStep::RenameTo { name }) => {
+                let prompt = format!("Password of \"{name}\"");
+                self.step = Some(PickStep::RenamePassword {
+                    name,
+                    new_name: value.trim().to_string(),
+                });
+                Self::input(&prompt, true)
+            }
+            Some(PickStep::RenamePassword { name, new_name }) => {
+                match self.factory.rename_repo(&name, &new_name, value.into()) {
+                    Ok(()) => {
+                        self.reload();
+                        self.select_name(Some(new_name.clone()));
+                        DialogueResult::Success(format!("Renamed \"{name}\" to \"{new_name}\""))
+                    }
+                    Err(err) => DialogueResult::Error(format!("Rename failed: {err}")),
+                }
+            }
+            Some(PickStep::DeletePassword { name }) => {
+                match self.factory.delete_repo(&name, value.into()) {
+                    Ok(()) => {
+                        self.reload();
+                        DialogueResult::Success(format!("Moved \"{name}\" to the trash"))
+                    }
+                    Err(err) => DialogueResult::Error(format!("Delete failed: {err}")),
+                }
+            }
+            None => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> PickResult {
+        self.step = None;
+        DialogueResult::NoOp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::test_helpers::offline_remote_factory;
+
+    use super::{PickRepoDialogue, PickStep};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_unreachable_server_shows_error() {
+        let mut dialogue = PickRepoDialogue::new(offline_remote_factory());
+        assert!(dialogue.load_error.is_some());
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Enter)),
+            DialogueResult::NoOp
+        ));
+    }
+
+    #[test]
+    fn test_rename_asks_new_name_then_password() {
+        let mut dialogue = PickRepoDialogue::new(offline_remote_factory());
+        dialogue.names = vec!["home".to_string(), "work".to_string()];
+        dialogue.select_name(Some("work".to_string()));
+        assert_eq!(dialogue.list_state.selected(), Some(1));
+        let _ = dialogue.handle_key(key(KeyCode::Down));
+        assert_eq!(dialogue.selected().map(String::as_str), Some("home"));
+
+        let res = dialogue.handle_key(key(KeyCode::Char('r')));
+        assert!(matches!(
+            res,
+            DialogueResult::StartInput {
+                password: false,
+                ..
+            }
+        ));
+        let res = dialogue.on_input_submit("family".to_string());
+        assert!(matches!(
+            res,
+            DialogueResult::StartInput { password: true, .. }
+        ));
+        assert!(matches!(
+            &dialogue.step,
+            Some(PickStep::RenamePassword { name, new_name }) if name == "home" && new_name == "family"
+        ));
+        assert!(matches!(
+            dialogue.on_input_submit("password".to_string()),
+            DialogueResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_new_repository_goes_to_creation() {
+        let mut dialogue = PickRepoDialogue::new(offline_remote_factory());
+        let _ = dialogue.handle_key(key(KeyCode::Char('n')));
+        match dialogue.on_input_submit(" team ".to_string()) {
+            DialogueResult::ChangeScreenAndStartInput {
+                prompt, password, ..
+            } => {
+                assert_eq!(prompt, "Choose a password");
+                assert!(password);
+            }
+            _ => panic!("expected ChangeScreenAndStartInput"),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crossterm::event::KeyCode;
+use ratatui::symbols::border;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+use sec_store::record::RecordId;
+use sec_store::repository::share::RecordSelection;
+use sec_store::repository::RecordsRepository;
+use sec_store::secret::SecretString;
+
+use crate::dialogues::view_repo::ViewRepoDialogue;
+use crate::dialogues::{Dialogue, DialogueResult};
+use crate::repo::RepositoryFactory;
+
+#[derive(Debug)]
+pub struct ShareDialogue<F, R> {
+    factory: F,
+    repo: R,
+    record_ids: Vec<RecordId>,
+    step: ShareStep,
+    shared: Option<(String, usize)>,
+}
+
+#[derive(Debug)]
+enum ShareStep {
+    Name,
+    Password {
+        name: String,
+    },
+    Repeat {
+        name: String,
+        password: SecretString,
+    },
+}
+
+impl<F, R> ShareDialogue<F, R> {
+    /// Shares the records with `record_ids` into a new repository.
+    pub fn new(factory: F, repo: R, record_ids: Vec<RecordId>) -> Self {
+        Self {
+            factory,
+            repo,
+            record_ids,
+            step: ShareStep::Name,
+            shared: None,
+        }
+    }
+
+    pub fn first_prompt() -> String {
+        "Name of the new repository (letters, digits, '-', '_' and '.')".to_string()
+    }
+}
+
+impl<F, R> ShareDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn back_to_repo(&self) -> DialogueResult<F, R> {
+        DialogueResult::ChangeScreen(Box::new(ViewRepoDialogue::new(
+            self.factory.clone(),
+            self.repo.clone(),
+            Some(0),
+        )))
+    }
+}
+
+impl<F, R> Dialogue<F, R> for ShareDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Share records ")
+            .border_set(border::ROUNDED)
+            .border_style(Style::new().yellow());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = match &self.shared {
+            Some((name, count)) => format!(
+                "Copied {count} records to the new repository \"{name}\". \
+                 Hand it over along with its password."
+            ),
+            None => format!(
+                "{} records selected for sharing. Their field history is never copied.",
+                self.record_ids.len()
+            ),
+        };
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+
+        let instructions = if self.shared.is_some() {
+            Line::from(vec![
+                Span::styled("Enter", Style::new().cyan()),
+                Span::raw(" back "),
+            ])
+        } else {
+            Line::from(vec![
+                Span::styled("Enter", Style::new().cyan()),
+                Span::raw(" retry "),
+                Span::styled("Esc", Style::new().cyan()),
+                Span::raw(" back "),
+            ])
+        };
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
+        };
+        frame.render_widget(
+            Paragraph::new(instructions).style(Style::new().dim()),
+            bottom,
+        );
+    }
+
+    fn handle_key(&mut self, k: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
+        match k.code {
+            KeyCode::Enter if self.shared.is_none() => {
+                self.step = ShareStep::Name;
+                DialogueResult::StartInput {
+                    prompt: Self::first_prompt(),
+                    password: false,
+                }
+            }
+            KeyCode::Enter | KeyCode::Esc => self.back_to_repo(),
+            _ => DialogueResult::NoOp,
+        }
+    }
+
+    fn on_input_submit(&mut self, value: String) -> DialogueResult<F, R> {
+        match std::mem::replace(&mut self.step, ShareStep::Name) {
+            ShareStep::Name if value.trim().is_empty() => DialogueResult::StartInput {
+                prompt: Self::first_prompt(),
+                password: false,
+            },
+            ShareStep::Name => {
+                self.step = ShareStep::Password {
+                    name: value.trim().to_string(),
+                };
+                DialogueResult::StartInput {
+                    prompt: "Password of the new repository".to_string(),
+                    password: true,
+                }
+            }
+            ShareStep::Password { name } if value.is_empty() => {
+                self.step = ShareStep::Password { name };
+                DialogueResult::StartInput {
+                    prompt: "Password of the new repository (password cannot be empty)".to_string(),
+                    password: true,
+                }
+            }
+            ShareStep::Password { name } => {
+                self.step = ShareStep::Repeat {
+                    name,
+                    password: value.into(),
+                };
+                DialogueResult::StartInput {
+                    prompt: "Repeat the password".to_string(),
+                    password: true,
+                }
+            }
+            ShareStep::Repeat { name, password } if password != value.as_str() => {
+                self.step = ShareStep::Repeat { name, password };
+                DialogueResult::StartInput {
+                    prompt: "Repeat the password (passwords don't match)".to_string(),
+                    password: true,
+                }
+            }
+            ShareStep::Repeat { name, password } => {
+                let selection = RecordSelection::Ids(self.record_ids.clone());
+                match self
+                    .factory
+                    .share_records(&self.repo, &name, password, &selection)
+                {
+                    Ok(count) => {
+                        let message = format!("Shared {count} records to \"{name}\"");
+                        self.shared = Some((name, count));
+                        DialogueResult::Success(message)
+                    }
+                    Err(err) => DialogueResult::Error(format!("Sharing failed: {err}")),
+                }
+            }
+        }
+    }
+
+    fn on_input_cancel(&mut self) -> DialogueResult<F, R> {
+        self.back_to_repo()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use crate::dialogues::{Dialogue, DialogueResult};
+    use crate::repo::{FileRepositoryFactory, RepositoryFactory};
+    use crate::runtime::block_on;
+    use crate::test_helpers::test_password;
+    use sec_store::record::{Record, RECORD_NAME_FIELD};
+    use sec_store::repository::file::NamedFileRepositories;
+    use sec_store::repository::{RecordsRepository, RepositoriesSource};
+
+    use super::ShareDialogue;
+
+    #[test]
+    fn test_share_selected_records() {
+        let tmp = TempDir::new().expect("temp dir");
+        let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
+        let mut repo = factory
+            .create_repo(test_password().into())
+            .expect("repo should be created");
+        let shared = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "db".to_string())]);
+        let private = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "mail".to_string())]);
+        block_on(repo.add_record(shared.clone())).expect("add record");
+        block_on(repo.add_record(private)).expect("add record");
+        let mut dialogue = ShareDialogue::new(factory, repo, vec![shared.id.clone()]);
+
+        let _ = dialogue.on_input_submit("project-x".to_string());
+        let _ = dialogue.on_input_submit("colleague".to_string());
+        assert!(matches!(
+            dialogue.on_input_submit("colleage".to_string()),
+            DialogueResult::StartInput { password: true, .. }
+        ));
+        let res = dialogue.on_input_submit("colleague".to_string());
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        assert_eq!(dialogue.shared, Some(("project-x".to_string(), 1)));
+        let repositories = NamedFileRepositories::new(tmp.path().to_path_buf());
+        let repo = block_on(repositories.open_repository("project-x", "colleague".into()))
+            .expect("shared repository should open");
+        assert_eq!(
+            block_on(repo.get_records()).expect("records")[0].id,
+            shared.id
+        );
+
+        let _ = dialogue.on_input_submit("project-x".to_string());
+        let _ = dialogue.on_input_submit("colleague".to_string());
+        assert!(matches!(
+            dialogue.on_input_submit("colleague".to_string()),
+            DialogueResult::Error(_)
+        ));
+    }
+}
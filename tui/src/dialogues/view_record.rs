@@ -1,3 +1,5 @@
+use std::time::{Duration, UNIX_EPOCH};
+
 use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::{
     layout::Rect,
@@ -7,12 +9,13 @@ use ratatui::{
     Frame,
 };
 
-use crate::dialogues::{Dialogue, DialogueResult};
-use crate::fields::{
-    RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
-};
+use crate::dialogues::{save_or_merge, Dialogue, DialogueResult};
 use crate::repo::{self, RepositoryFactory};
 use ratatui::symbols::border;
+use sec_store::record::{
+    Record, RECORD_DESCR_FIELD, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD,
+    RECORD_TOTP_FIELD,
+};
 use sec_store::repository::RecordsRepository;
 
 type RecordId = String;
@@ -24,6 +27,10 @@ pub struct ViewRecordDialogue<F, R> {
     record_id: RecordId,
     confirm_delete: bool,
     password_visible: bool,
+    // Selected entry while the password history is shown.
+    history_selected: Option<usize>,
+    // Set by `draw`, the one-time code countdown needs a redraw every second.
+    has_totp: bool,
 }
 
 impl<F, R> ViewRecordDialogue<F, R> {
@@ -34,6 +41,95 @@ impl<F, R> ViewRecordDialogue<F, R> {
             record_id,
             confirm_delete,
             password_visible: false,
+            history_selected: None,
+            has_totp: false,
+        }
+    }
+
+    fn mask(&self, value: &str) -> String {
+        if self.password_visible {
+            value.to_string()
+        } else {
+            "*".repeat(value.len())
+        }
+    }
+
+    fn history_lines(&self, rec: &Record, selected: usize) -> Vec<String> {
+        let mut lines = vec!["Password history:".to_string()];
+        let history = rec
+            .get_field(RECORD_PASSWD_FIELD)
+            .map(|field| field.history.as_slice())
+            .unwrap_or_default();
+        for (idx, revision) in history.iter().enumerate() {
+            lines.push(format!(
+                "{} {}  replaced {}",
+                if idx == selected { ">>" } else { "  " },
+                self.mask(&revision.value),
+                format_timestamp(revision.replaced_at)
+            ));
+        }
+        lines
+    }
+}
+
+fn totp_line(rec: &Record) -> Option<String> {
+    Some(match rec.totp()? {
+        Ok(totp) => format!(
+            "2FA code: {} ({}s left)",
+            totp.code(),
+            totp.seconds_remaining()
+        ),
+        Err(err) => format!("2FA code: unavailable, {err}"),
+    })
+}
+
+pub(crate) fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "unknown".to_string();
+    }
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(timestamp)).to_string()
+}
+
+impl<F, R> ViewRecordDialogue<F, R>
+where
+    F: RepositoryFactory<R>,
+    R: RecordsRepository,
+{
+    fn handle_history_key(&mut self, code: KeyCode, selected: usize) -> DialogueResult<F, R> {
+        let mut rec = match repo::get_record(&self.repo, &self.record_id) {
+            Ok(Some(rec)) => rec,
+            _ => return DialogueResult::Error("Record not found".to_string()),
+        };
+        let history_len = rec
+            .get_field(RECORD_PASSWD_FIELD)
+            .map_or(0, |field| field.history.len());
+        match code {
+            KeyCode::Up => {
+                self.history_selected = Some(selected.saturating_sub(1));
+                DialogueResult::NoOp
+            }
+            KeyCode::Down => {
+                self.history_selected = Some((selected + 1).min(history_len.saturating_sub(1)));
+                DialogueResult::NoOp
+            }
+            KeyCode::Char('r') => {
+                if let Err(err) = rec.restore_field(RECORD_PASSWD_FIELD, selected) {
+                    return DialogueResult::Error(err.to_string());
+                }
+                self.history_selected = None;
+                if let Err(err) = repo::update_record(&mut self.repo, rec) {
+                    return DialogueResult::Error(err.to_string());
+                }
+                match save_or_merge(&self.factory, &mut self.repo) {
+                    Ok(()) => DialogueResult::Success("Password restored".to_string()),
+                    Err(res) => res,
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('h') => {
+                self.history_selected = None;
+                DialogueResult::NoOp
+            }
+            _ => DialogueResult::NoOp,
         }
     }
 }
@@ -60,29 +156,78 @@ where
             }
         };
 
-        let password_display = if let Some(pwd) = rec.get_field_value(RECORD_PASSWD_FIELD) {
-            if self.password_visible {
-                pwd
-            } else {
-                "*".repeat(pwd.len())
-            }
-        } else {
-            String::new()
+        let bottom = Rect {
+            y: area.y + area.height.saturating_sub(1),
+            ..area
         };
+        if let Some(selected) = self.history_selected {
+            let text = self.history_lines(&rec, selected).join("\n");
+            frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+            let instructions = Line::from(vec![
+                Span::styled("↑/↓", Style::new().cyan()),
+                Span::raw(" select "),
+                Span::styled("r", Style::new().cyan()),
+                Span::raw(" restore "),
+                Span::styled("Ctrl+v", Style::new().cyan()),
+                Span::raw(" toggle password "),
+                Span::styled("Esc", Style::new().cyan()),
+                Span::raw(" back"),
+            ]);
+            frame.render_widget(
+                Paragraph::new(instructions).style(Style::new().dim()),
+                bottom,
+            );
+            return;
+        }
+
+        let password_display = rec
+            .get_field_value(RECORD_PASSWD_FIELD)
+            .map(|pwd| self.mask(&pwd))
+            .unwrap_or_default();
 
         let mut lines = vec![
             format!(
                 "Name: {}",
-                rec.get_field_value(RECORD_NAME_FIELD).unwrap_or_default()
+                rec.get_field_value(RECORD_NAME_FIELD)
+                    .as_deref()
+                    .unwrap_or_default()
             ),
             format!("Password: {}", password_display),
         ];
         if let Some(l) = rec.get_field_value(RECORD_LOGIN_FIELD) {
-            lines.push(format!("Login: {l}"));
+            lines.push(format!("Login: {}", l.as_str()));
         }
         if let Some(d) = rec.get_field_value(RECORD_DESCR_FIELD) {
-            lines.push(format!("Description: {d}"));
+            lines.push(format!("Description: {}", d.as_str()));
+        }
+        for field in rec.get_fields().into_iter().filter(|field| {
+            ![
+                RECORD_NAME_FIELD,
+                RECORD_PASSWD_FIELD,
+                RECORD_LOGIN_FIELD,
+                RECORD_DESCR_FIELD,
+                RECORD_TOTP_FIELD,
+            ]
+            .contains(&field.name.as_str())
+        }) {
+            let value = if field.kind.is_concealed() {
+                self.mask(&field.value)
+            } else {
+                field.value.to_string()
+            };
+            lines.push(format!("{}: {value}", field.name));
         }
+        let totp = totp_line(&rec);
+        self.has_totp = totp.is_some();
+        lines.extend(totp);
+        if let Some(folder) = rec.folder() {
+            lines.push(format!("Folder: {folder}"));
+        }
+        if !rec.tags().is_empty() {
+            lines.push(format!("Tags: {}", rec.tags().join(", ")));
+        }
+        lines.push(format!("Created: {}", format_timestamp(rec.created_at())));
+        lines.push(format!("Modified: {}", format_timestamp(rec.modified_at())));
         if self.confirm_delete {
             lines.push(String::new());
             lines.push("Do you really want to remove this record? (Y/N)".to_string());
@@ -109,6 +254,8 @@ where
                 Span::raw(" copy "),
                 Span::styled("Ctrl+v", Style::new().cyan()),
                 Span::raw(" toggle password "),
+                Span::styled("h", Style::new().cyan()),
+                Span::raw(" history "),
                 Span::styled("d", Style::new().cyan()),
                 Span::raw(" delete "),
                 Span::styled("b", Style::new().cyan()),
@@ -117,10 +264,6 @@ where
                 Span::raw(" quit"),
             ])
         };
-        let bottom = Rect {
-            y: area.y + area.height.saturating_sub(1),
-            ..area
-        };
         frame.render_widget(
             Paragraph::new(instructions).style(Style::new().dim()),
             bottom,
@@ -138,21 +281,24 @@ where
             self.password_visible = !self.password_visible;
             return DialogueResult::NoOp;
         }
+        if let Some(selected) = self.history_selected {
+            return self.handle_history_key(k.code, selected);
+        }
 
         match k.code {
             KeyCode::Char('y') | KeyCode::Char('Y') if self.confirm_delete => {
-                if repo::delete_record(&mut self.repo, &rid).is_ok()
-                    && repo::save(&mut self.repo).is_ok()
-                {
-                    DialogueResult::ChangeScreen(Box::new(
+                if repo::delete_record(&mut self.repo, &rid).is_err() {
+                    return DialogueResult::Error("Delete failed".to_string());
+                }
+                match save_or_merge(&self.factory, &mut self.repo) {
+                    Ok(()) => DialogueResult::ChangeScreen(Box::new(
                         crate::dialogues::view_repo::ViewRepoDialogue::new(
                             self.factory.clone(),
                             self.repo.clone(),
                             Some(0),
                         ),
-                    ))
-                } else {
-                    DialogueResult::Error("Delete failed".to_string())
+                    )),
+                    Err(res) => res,
                 }
             }
             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc if self.confirm_delete => {
@@ -226,6 +372,20 @@ where
                     Some(0),
                 ),
             )),
+            KeyCode::Char('h') if !self.confirm_delete => {
+                match repo::get_record(&self.repo, &rid) {
+                    Ok(Some(rec))
+                        if rec
+                            .get_field(RECORD_PASSWD_FIELD)
+                            .is_some_and(|field| !field.history.is_empty()) =>
+                    {
+                        self.history_selected = Some(0);
+                        DialogueResult::NoOp
+                    }
+                    Ok(Some(_)) => DialogueResult::Error("Password has no history".to_string()),
+                    _ => DialogueResult::Error("Record not found".to_string()),
+                }
+            }
             KeyCode::Char('d') if !self.confirm_delete => {
                 self.confirm_delete = true;
                 DialogueResult::NoOp
@@ -252,6 +412,10 @@ where
     fn on_exit(&mut self) {
         let _ = repo::close_connection(&self.repo);
     }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        self.has_totp.then(|| Duration::from_secs(1))
+    }
 }
 
 #[cfg(test)]
@@ -260,15 +424,15 @@ mod tests {
     use tempfile::TempDir;
 
     use crate::dialogues::{Dialogue, DialogueResult};
-    use crate::fields::{RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
     use crate::repo::FileRepositoryFactory;
     use crate::runtime::block_on;
     use crate::test_helpers::test_password;
     use sec_store::record::Record;
+    use sec_store::record::{RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
     use sec_store::repository::file::{OpenRecordsFileRepository, RecordsFileRepository};
     use sec_store::repository::{OpenRepository, RecordsRepository};
 
-    use super::ViewRecordDialogue;
+    use super::{totp_line, ViewRecordDialogue};
 
     fn key(code: KeyCode) -> KeyEvent {
         KeyEvent::new(code, KeyModifiers::NONE)
@@ -288,7 +452,7 @@ mod tests {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
         let repo_password = test_password();
-        let mut repo = RecordsFileRepository::new(path, repo_password.clone());
+        let mut repo = RecordsFileRepository::new(path, repo_password.clone().into());
         let rec = Record::new(vec![
             (RECORD_NAME_FIELD.to_string(), "Mail".to_string()),
             (RECORD_PASSWD_FIELD.to_string(), "pw".to_string()),
@@ -308,7 +472,7 @@ mod tests {
     ) {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
-        let mut repo = RecordsFileRepository::new(path, test_password());
+        let mut repo = RecordsFileRepository::new(path, test_password().into());
         let rec = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "Mail".to_string())]);
         let id = rec.id.clone();
         crate::runtime::block_on(repo.add_record(rec)).expect("add");
@@ -366,7 +530,8 @@ mod tests {
         let res = dialogue.handle_key(key(KeyCode::Char('y')));
         assert!(matches!(res, DialogueResult::ChangeScreen(_)));
 
-        let repo_after = OpenRecordsFileRepository(tmp.path().join("repo")).open(repo_password);
+        let repo_after =
+            OpenRecordsFileRepository(tmp.path().join("repo")).open(repo_password.into());
         let repo_after = block_on(repo_after).expect("open repo");
         let found = block_on(repo_after.get(&id)).expect("get");
         assert!(found.is_none());
@@ -392,7 +557,7 @@ mod tests {
     fn test_copy_with_missing_record_returns_error() {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
-        let mut repo = RecordsFileRepository::new(path, test_password());
+        let mut repo = RecordsFileRepository::new(path, test_password().into());
         block_on(repo.save()).expect("save");
 
         let factory = FileRepositoryFactory::new(tmp.path().join("repo"));
@@ -415,4 +580,57 @@ mod tests {
             _ => panic!("expected error"),
         }
     }
+
+    #[test]
+    fn test_history_without_previous_passwords_returns_error() {
+        let (_tmp, factory, repo, id, _repo_password) = make_repo_with_password_record();
+        let mut dialogue = ViewRecordDialogue::new(factory, repo, id, false);
+
+        match dialogue.handle_key(key(KeyCode::Char('h'))) {
+            DialogueResult::Error(msg) => assert_eq!(msg, "Password has no history"),
+            _ => panic!("expected error"),
+        }
+        assert!(dialogue.history_selected.is_none());
+    }
+
+    #[test]
+    fn test_restore_password_from_history() {
+        let (_tmp, factory, mut repo, id, _repo_password) = make_repo_with_password_record();
+        let mut rec = block_on(repo.get(&id)).expect("get").expect("record");
+        rec.update_field(RECORD_PASSWD_FIELD.to_string(), "new-pw")
+            .expect("update field");
+        block_on(repo.update(rec)).expect("update");
+        let mut dialogue = ViewRecordDialogue::new(factory, repo, id.clone(), false);
+
+        assert!(matches!(
+            dialogue.handle_key(key(KeyCode::Char('h'))),
+            DialogueResult::NoOp
+        ));
+        assert_eq!(dialogue.history_selected, Some(0));
+        let res = dialogue.handle_key(key(KeyCode::Char('r')));
+
+        assert!(matches!(res, DialogueResult::Success(_)));
+        assert!(dialogue.history_selected.is_none());
+        let rec = block_on(dialogue.repo.get(&id))
+            .expect("get")
+            .expect("record");
+        assert_eq!(rec.get_field_value(RECORD_PASSWD_FIELD).unwrap(), "pw");
+    }
+
+    #[test]
+    fn test_totp_code_line() {
+        let mut rec = Record::new(vec![(RECORD_NAME_FIELD.to_string(), "Mail".to_string())]);
+        assert_eq!(totp_line(&rec), None);
+
+        rec.set_totp("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")
+            .expect("valid secret");
+        let line = totp_line(&rec).expect("totp line");
+        let code = line
+            .strip_prefix("2FA code: ")
+            .and_then(|rest| rest.split(' ').next())
+            .expect("code");
+        assert_eq!(code.len(), 6);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+        assert!(line.ends_with("s left)"));
+    }
 }
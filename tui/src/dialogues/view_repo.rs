@@ -1,6 +1,8 @@
-use crate::dialogues::{AddRecordDialogue, ViewRecordDialogue, WelcomeDialogue};
+use crate::dialogues::{
+    AddRecordDialogue, AuditDialogue, ChangePasswordDialogue, ExportDialogue, ImportDialogue,
+    ShareDialogue, ViewRecordDialogue, WelcomeDialogue,
+};
 use crate::dialogues::{Dialogue, DialogueResult};
-use crate::fields::{RECORD_LOGIN_FIELD, RECORD_NAME_FIELD};
 use crate::repo::{self, RepositoryFactory};
 use crossterm::event::KeyCode;
 use ratatui::symbols::border;
@@ -11,7 +13,9 @@ use ratatui::{
     widgets::{Block, List, ListItem, ListState, Paragraph},
     Frame,
 };
+use sec_store::record::{RecordsFilter, RECORD_LOGIN_FIELD, RECORD_NAME_FIELD};
 use sec_store::repository::RecordsRepository;
+use std::collections::HashSet;
 
 type RecordId = String;
 
@@ -22,7 +26,9 @@ pub struct ViewRepoDialogue<F, R> {
     list_state: ListState,
     search_query: String,
     is_searching: bool,
+    filter: RecordsFilter,
     records_error: Option<String>,
+    marked: HashSet<RecordId>,
 }
 
 impl<F, R> ViewRepoDialogue<F, R>
@@ -38,12 +44,34 @@ where
             list_state: state,
             search_query: String::new(),
             is_searching: false,
+            filter: RecordsFilter::default(),
             records_error: None,
+            marked: HashSet::new(),
+        }
+    }
+
+    /// Marked records if any, otherwise the rows shown.
+    fn target_ids(&self, rows: &[(RecordId, String)]) -> Vec<RecordId> {
+        if self.marked.is_empty() {
+            rows.iter().map(|(id, _)| id.clone()).collect()
+        } else {
+            self.marked.iter().cloned().collect()
+        }
+    }
+
+    fn filter_title(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(folder) = &self.filter.folder {
+            parts.push(format!("folder: {folder}"));
+        }
+        if let Some(tag) = &self.filter.tag {
+            parts.push(format!("tag: {tag}"));
         }
+        (!parts.is_empty()).then(|| format!(" {} ", parts.join(", ")))
     }
 
     fn get_filtered_records(&mut self) -> Vec<(RecordId, String)> {
-        let records = match repo::get_records(&self.repo) {
+        let records = match repo::find_records(&self.repo, &self.filter) {
             Ok(records) => {
                 self.records_error = None;
                 records
@@ -59,8 +87,11 @@ where
             .map(|r| {
                 let name = r
                     .get_field_value(RECORD_NAME_FIELD)
+                    .map(|name| name.to_string())
                     .unwrap_or_else(|| "-".to_string());
-                let login = r.get_field_value(RECORD_LOGIN_FIELD);
+                let login = r
+                    .get_field_value(RECORD_LOGIN_FIELD)
+                    .map(|login| login.to_string());
                 (r.id.clone(), name, login)
             })
             .collect();
@@ -90,10 +121,29 @@ where
     R: RecordsRepository,
 {
     fn draw(&mut self, frame: &mut Frame, area: Rect) {
-        let block = Block::bordered()
+        let mut block = Block::bordered()
             .title(" Repository ")
             .border_set(border::ROUNDED)
             .border_style(Style::new().green());
+        let damaged = self.repo.damaged_records().len();
+        if damaged > 0 {
+            block = block.title(
+                Line::styled(
+                    format!(" {damaged} damaged records skipped "),
+                    Style::new().red(),
+                )
+                .right_aligned(),
+            );
+        }
+        if let Some(filter) = self.filter_title() {
+            block = block.title(Line::styled(filter, Style::new().cyan()));
+        }
+        if !self.marked.is_empty() {
+            block = block.title(Line::styled(
+                format!(" {} selected ", self.marked.len()),
+                Style::new().green(),
+            ));
+        }
         let inner = block.inner(area);
         frame.render_widget(block, area);
 
@@ -110,7 +160,15 @@ where
 
         let mut items: Vec<ListItem> = rows
             .iter()
-            .map(|(_, name)| ListItem::new(name.as_str()))
+            .map(|(id, name)| {
+                if self.marked.contains(id) {
+                    ListItem::new(format!("[x] {name}")).style(Style::new().green())
+                } else if self.marked.is_empty() {
+                    ListItem::new(name.as_str())
+                } else {
+                    ListItem::new(format!("[ ] {name}"))
+                }
+            })
             .collect();
         items.push(ListItem::new("─── Add record"));
         items.push(ListItem::new("─── Close repository"));
@@ -157,8 +215,24 @@ where
                 Span::raw(" search "),
                 Span::styled("Enter", Style::new().cyan()),
                 Span::raw(" view "),
+                Span::styled("f", Style::new().cyan()),
+                Span::raw(" folder "),
+                Span::styled("t", Style::new().cyan()),
+                Span::raw(" tag "),
                 Span::styled("a", Style::new().cyan()),
                 Span::raw(" add "),
+                Span::styled("p", Style::new().cyan()),
+                Span::raw(" change password "),
+                Span::styled("u", Style::new().cyan()),
+                Span::raw(" audit "),
+                Span::styled("i", Style::new().cyan()),
+                Span::raw(" import "),
+                Span::styled("Space", Style::new().cyan()),
+                Span::raw(" select "),
+                Span::styled("e", Style::new().cyan()),
+                Span::raw(" export "),
+                Span::styled("s", Style::new().cyan()),
+                Span::raw(" share "),
                 Span::styled("c", Style::new().cyan()),
                 Span::raw(" close "),
                 Span::styled("q", Style::new().cyan()),
@@ -243,6 +317,71 @@ where
                 prompt: "Enter password".to_string(),
                 password: true,
             },
+            KeyCode::Char('p') if !self.is_searching => DialogueResult::ChangeScreenAndStartInput {
+                dialogue: Box::new(ChangePasswordDialogue::new(
+                    self.factory.clone(),
+                    self.repo.clone(),
+                )),
+                prompt: ChangePasswordDialogue::<F, R>::first_prompt(),
+                password: true,
+            },
+            KeyCode::Char('f') if !self.is_searching => match repo::get_folders(&self.repo) {
+                Ok(folders) => {
+                    self.filter.folder = next_choice(&folders, self.filter.folder.as_deref());
+                    self.list_state.select(Some(0));
+                    DialogueResult::NoOp
+                }
+                Err(err) => DialogueResult::Error(format!("Failed to load folders: {err}")),
+            },
+            KeyCode::Char('t') if !self.is_searching => match repo::get_tags(&self.repo) {
+                Ok(tags) => {
+                    self.filter.tag = next_choice(&tags, self.filter.tag.as_deref());
+                    self.list_state.select(Some(0));
+                    DialogueResult::NoOp
+                }
+                Err(err) => DialogueResult::Error(format!("Failed to load tags: {err}")),
+            },
+            KeyCode::Char('u') if !self.is_searching => {
+                match AuditDialogue::new(self.factory.clone(), self.repo.clone()) {
+                    Ok(dialogue) => DialogueResult::ChangeScreen(Box::new(dialogue)),
+                    Err(err) => DialogueResult::Error(format!("Audit failed: {err}")),
+                }
+            }
+            KeyCode::Char('i') if !self.is_searching => DialogueResult::ChangeScreenAndStartInput {
+                dialogue: Box::new(ImportDialogue::new(self.factory.clone(), self.repo.clone())),
+                prompt: ImportDialogue::<F, R>::first_prompt(),
+                password: false,
+            },
+            KeyCode::Char(' ') if !self.is_searching => {
+                if let Some((id, _)) = rows.get(sel) {
+                    if !self.marked.remove(id) {
+                        self.marked.insert(id.clone());
+                    }
+                }
+                DialogueResult::NoOp
+            }
+            KeyCode::Char('e') if !self.is_searching => {
+                DialogueResult::ChangeScreen(Box::new(ExportDialogue::new(
+                    self.factory.clone(),
+                    self.repo.clone(),
+                    self.target_ids(&rows),
+                )))
+            }
+            KeyCode::Char('s') if !self.is_searching => {
+                let record_ids = self.target_ids(&rows);
+                if record_ids.is_empty() {
+                    return DialogueResult::Error("No records to share".to_string());
+                }
+                DialogueResult::ChangeScreenAndStartInput {
+                    dialogue: Box::new(ShareDialogue::new(
+                        self.factory.clone(),
+                        self.repo.clone(),
+                        record_ids,
+                    )),
+                    prompt: ShareDialogue::<F, R>::first_prompt(),
+                    password: false,
+                }
+            }
             KeyCode::Char('c') if !self.is_searching => {
                 let _ = repo::close_connection(&self.repo);
                 DialogueResult::ChangeScreen(Box::new(
@@ -292,6 +431,18 @@ where
     }
 }
 
+/// Cycles through `choices` and back to no choice after the last one.
+fn next_choice(choices: &[String], current: Option<&str>) -> Option<String> {
+    let next = match current {
+        None => 0,
+        Some(current) => match choices.iter().position(|choice| choice == current) {
+            Some(idx) => idx + 1,
+            None => 0,
+        },
+    };
+    choices.get(next).cloned()
+}
+
 #[cfg(test)]
 mod tests {
     use std::fmt;
@@ -302,12 +453,14 @@ mod tests {
     use tempfile::TempDir;
 
     use crate::dialogues::{Dialogue, DialogueResult};
-    use crate::fields::{RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
     use crate::repo::{FileRepositoryFactory, RepositoryFactory};
     use crate::test_helpers::test_password;
     use sec_store::record::Record;
+    use sec_store::record::{RECORD_LOGIN_FIELD, RECORD_NAME_FIELD, RECORD_PASSWD_FIELD};
     use sec_store::repository::file::RecordsFileRepository;
+    use sec_store::repository::share::RecordSelection;
     use sec_store::repository::RecordsRepository;
+    use sec_store::secret::SecretString;
 
     use super::ViewRepoDialogue;
 
@@ -318,9 +471,9 @@ mod tests {
     fn repo_with_records() -> (TempDir, FileRepositoryFactory, RecordsFileRepository) {
         let tmp = TempDir::new().expect("temp dir");
         let path = tmp.path().join("repo");
-        let mut repo = RecordsFileRepository::new(path, test_password());
+        let mut repo = RecordsFileRepository::new(path, test_password().into());
 
-        let rec1 = Record::new(vec![
+        let mut rec1 = Record::new(vec![
             (RECORD_NAME_FIELD.to_string(), "Mail".to_string()),
             (RECORD_PASSWD_FIELD.to_string(), "pw1".to_string()),
             (
@@ -334,6 +487,9 @@ mod tests {
             (RECORD_LOGIN_FIELD.to_string(), "octocat".to_string()),
         ]);
 
+        rec1.set_folder("personal/mail");
+        rec1.set_tags(vec!["mail"]);
+
         crate::runtime::block_on(repo.add_record(rec1)).expect("add rec1");
         crate::runtime::block_on(repo.add_record(rec2)).expect("add rec2");
         crate::runtime::block_on(repo.save()).expect("save repo");
@@ -358,11 +514,33 @@ mod tests {
             true
         }
 
-        fn create_repo(&self, _password: String) -> anyhow::Result<FailingRepo> {
+        fn create_repo(&self, _password: SecretString) -> anyhow::Result<FailingRepo> {
             Ok(FailingRepo)
         }
 
-        fn open_repo(&self, _password: String) -> anyhow::Result<FailingRepo> {
+        fn open_repo(&self, _password: SecretString) -> anyhow::Result<FailingRepo> {
+            Ok(FailingRepo)
+        }
+
+        fn share_records(
+            &self,
+            _repo: &FailingRepo,
+            _repository_name: &str,
+            _password: SecretString,
+            _selection: &RecordSelection,
+        ) -> anyhow::Result<usize> {
+            Err(anyhow!("session expired"))
+        }
+
+        fn list_backups(&self) -> anyhow::Result<Vec<sec_store::repository::Backup>> {
+            Ok(Vec::new())
+        }
+
+        fn restore_backup(
+            &self,
+            _backup_id: &str,
+            _password: SecretString,
+        ) -> anyhow::Result<FailingRepo> {
             Ok(FailingRepo)
         }
     }
@@ -406,6 +584,18 @@ mod tests {
         async fn dump(&self) -> anyhow::Result<Vec<u8>> {
             Ok(Vec::new())
         }
+
+        fn is_password(&self, _passwd: &str) -> bool {
+            false
+        }
+
+        async fn change_password(
+            &mut self,
+            _old_passwd: SecretString,
+            _new_passwd: SecretString,
+        ) -> sec_store::repository::ChangePasswordResult<()> {
+            Ok(())
+        }
     }
 
     #[test]
@@ -537,6 +727,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_char_p_starts_change_password_input() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+
+        let res = dialogue.handle_key(key(KeyCode::Char('p')));
+        match res {
+            DialogueResult::ChangeScreenAndStartInput {
+                prompt, password, ..
+            } => {
+                assert_eq!(prompt, "Enter the current password");
+                assert!(password);
+            }
+            _ => panic!("expected ChangeScreenAndStartInput"),
+        }
+    }
+
+    #[test]
+    fn test_char_u_opens_audit() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+        let res = dialogue.handle_key(key(KeyCode::Char('u')));
+        assert!(matches!(res, DialogueResult::ChangeScreen(_)));
+    }
+
+    #[test]
+    fn test_char_i_starts_import_input() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+        let res = dialogue.handle_key(key(KeyCode::Char('i')));
+        assert!(matches!(
+            res,
+            DialogueResult::ChangeScreenAndStartInput {
+                password: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_char_e_opens_export() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+        let res = dialogue.handle_key(key(KeyCode::Char('e')));
+        assert!(matches!(res, DialogueResult::ChangeScreen(_)));
+    }
+
+    #[test]
+    fn test_space_marks_records_for_sharing() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+        let rows = dialogue.get_filtered_records();
+        assert_eq!(dialogue.target_ids(&rows).len(), rows.len());
+
+        let _ = dialogue.handle_key(key(KeyCode::Char(' ')));
+        let _ = dialogue.handle_key(key(KeyCode::Down));
+        let _ = dialogue.handle_key(key(KeyCode::Char(' ')));
+        let _ = dialogue.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(dialogue.target_ids(&rows), vec![rows[0].0.clone()]);
+
+        let res = dialogue.handle_key(key(KeyCode::Char('s')));
+        assert!(matches!(
+            res,
+            DialogueResult::ChangeScreenAndStartInput {
+                password: false,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_char_c_closes_repository() {
         let (_tmp, factory, repo) = repo_with_records();
@@ -576,4 +836,53 @@ mod tests {
             _ => panic!("expected error"),
         }
     }
+
+    #[test]
+    fn test_char_f_cycles_folders() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(1));
+
+        let res = dialogue.handle_key(key(KeyCode::Char('f')));
+        assert!(matches!(res, DialogueResult::NoOp));
+        assert_eq!(dialogue.filter.folder.as_deref(), Some("personal"));
+        assert_eq!(dialogue.list_state.selected(), Some(0));
+        let rows = dialogue.get_filtered_records();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1, "Mail");
+
+        let _ = dialogue.handle_key(key(KeyCode::Char('f')));
+        assert_eq!(dialogue.filter.folder.as_deref(), Some("personal/mail"));
+
+        let _ = dialogue.handle_key(key(KeyCode::Char('f')));
+        assert_eq!(dialogue.filter.folder, None);
+        assert_eq!(dialogue.get_filtered_records().len(), 2);
+    }
+
+    #[test]
+    fn test_char_t_cycles_tags() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+
+        let _ = dialogue.handle_key(key(KeyCode::Char('t')));
+        assert_eq!(dialogue.filter.tag.as_deref(), Some("mail"));
+        assert_eq!(dialogue.filter_title().as_deref(), Some(" tag: mail "));
+        let rows = dialogue.get_filtered_records();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1, "Mail");
+
+        let _ = dialogue.handle_key(key(KeyCode::Char('t')));
+        assert_eq!(dialogue.filter.tag, None);
+        assert_eq!(dialogue.filter_title(), None);
+    }
+
+    #[test]
+    fn test_search_accepts_filter_keys_as_text() {
+        let (_tmp, factory, repo) = repo_with_records();
+        let mut dialogue = ViewRepoDialogue::new(factory, repo, Some(0));
+        let _ = dialogue.handle_key(key(KeyCode::Char('/')));
+        let _ = dialogue.handle_key(key(KeyCode::Char('f')));
+
+        assert_eq!(dialogue.search_query, "f");
+        assert_eq!(dialogue.filter.folder, None);
+    }
 }
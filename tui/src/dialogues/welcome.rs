@@ -10,6 +10,7 @@ use ratatui::{
 };
 use sec_store::repository::RecordsRepository;
 
+use crate::dialogues::backups::BackupsDialogue;
 use crate::dialogues::create_repo::CreateRepoDialogue;
 use crate::dialogues::open_repo::OpenRepoDialogue;
 use crate::dialogues::{Dialogue, DialogueResult};
@@ -48,6 +49,7 @@ where
         let mut items = vec![
             ListItem::new("Create repository"),
             ListItem::new("Open repository"),
+            ListItem::new("Restore from backup"),
             ListItem::new("Quit"),
         ];
         if !self.factory.has_repo() {
@@ -81,7 +83,7 @@ where
     }
 
     fn handle_key(&mut self, key_event: crossterm::event::KeyEvent) -> DialogueResult<F, R> {
-        let n = 3;
+        let n = 4;
         let sel = self.list_state.selected().unwrap_or(0);
         match key_event.code {
             KeyCode::Up | KeyCode::Char('k') => {
@@ -110,7 +112,10 @@ where
                         DialogueResult::NoOp
                     }
                 }
-                2 => DialogueResult::Exit,
+                2 => DialogueResult::ChangeScreen(Box::new(BackupsDialogue::new(
+                    self.factory.clone(),
+                ))),
+                3 => DialogueResult::Exit,
                 _ => DialogueResult::NoOp,
             },
             _ => DialogueResult::NoOp,
@@ -147,14 +152,14 @@ mod tests {
         let mut dialogue = WelcomeDialogue::new(factory, Some(0));
         let res = dialogue.handle_key(key(KeyCode::Up));
         assert!(matches!(res, DialogueResult::NoOp));
-        assert_eq!(dialogue.list_state.selected(), Some(2));
+        assert_eq!(dialogue.list_state.selected(), Some(3));
     }
 
     #[test]
     fn test_navigation_wraps_down() {
         let scope = ScopedTuiDataDir::new();
         let factory = FileRepositoryFactory::new(scope.temp_dir.path().join("repo"));
-        let mut dialogue = WelcomeDialogue::new(factory, Some(2));
+        let mut dialogue = WelcomeDialogue::new(factory, Some(3));
         let res = dialogue.handle_key(key(KeyCode::Down));
         assert!(matches!(res, DialogueResult::NoOp));
         assert_eq!(dialogue.list_state.selected(), Some(0));
@@ -194,7 +199,7 @@ mod tests {
         let repo_path = scope.temp_dir.path().join("repo");
         let factory = FileRepositoryFactory::new(repo_path);
         factory
-            .create_repo(test_password())
+            .create_repo(test_password().into())
             .expect("repo creation failed");
         let mut dialogue = WelcomeDialogue::new(factory, Some(1));
 
@@ -211,11 +216,20 @@ mod tests {
     }
 
     #[test]
-    fn test_enter_quit_returns_exit() {
+    fn test_enter_backups_changes_screen() {
         let scope = ScopedTuiDataDir::new();
         let factory = FileRepositoryFactory::new(scope.temp_dir.path().join("repo"));
         let mut dialogue = WelcomeDialogue::new(factory, Some(2));
         let res = dialogue.handle_key(key(KeyCode::Enter));
+        assert!(matches!(res, DialogueResult::ChangeScreen(_)));
+    }
+
+    #[test]
+    fn test_enter_quit_returns_exit() {
+        let scope = ScopedTuiDataDir::new();
+        let factory = FileRepositoryFactory::new(scope.temp_dir.path().join("repo"));
+        let mut dialogue = WelcomeDialogue::new(factory, Some(3));
+        let res = dialogue.handle_key(key(KeyCode::Enter));
         assert!(matches!(res, DialogueResult::Exit));
     }
 }
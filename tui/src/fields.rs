@@ -1,5 +0,0 @@
-// Record field names; must match sec_store / bot for compatibility.
-pub const RECORD_NAME_FIELD: &str = "RECORD_NAME";
-pub const RECORD_PASSWD_FIELD: &str = "RECORD_PASSWD";
-pub const RECORD_LOGIN_FIELD: &str = "RECORD_LOGIN";
-pub const RECORD_DESCR_FIELD: &str = "RECORD_DESCR";
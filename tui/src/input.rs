@@ -1,9 +1,12 @@
+use sec_store::generator::GeneratedPassword;
+
 #[derive(Clone, Debug)]
 pub struct InputState {
     pub prompt: String,
     pub buffer: String,
     pub password_mode: bool,
     pub password_visible: bool,
+    pub note: Option<String>,
 }
 
 impl InputState {
@@ -13,9 +16,17 @@ impl InputState {
             buffer: String::new(),
             password_mode,
             password_visible: false,
+            note: None,
         }
     }
 
+    /// Replaces the buffer with a generated password and shows it.
+    pub fn fill_generated(&mut self, generated: &GeneratedPassword) {
+        self.buffer = generated.value.to_string();
+        self.password_visible = true;
+        self.note = Some(format!("{:.0} bits of entropy", generated.entropy_bits));
+    }
+
     pub fn push_char(&mut self, c: char) {
         self.buffer.push(c);
     }
@@ -39,6 +50,8 @@ impl InputState {
 
 #[cfg(test)]
 mod tests {
+    use sec_store::generator::GeneratedPassword;
+
     use super::InputState;
 
     #[test]
@@ -103,6 +116,19 @@ mod tests {
         assert_eq!(input.display(), "***");
     }
 
+    #[test]
+    fn test_fill_generated_shows_value_and_entropy() {
+        let mut input = InputState::new("Prompt", true);
+        input.push_char('a');
+        input.fill_generated(&GeneratedPassword {
+            value: "generated".into(),
+            entropy_bits: 64.4,
+        });
+
+        assert_eq!(input.display(), "generated");
+        assert_eq!(input.note.as_deref(), Some("64 bits of entropy"));
+    }
+
     #[test]
     fn test_display_password_mode_visible() {
         let mut input = InputState::new("Prompt", true);
@@ -1,7 +1,6 @@
 mod app;
 pub mod cli;
 mod dialogues;
-mod fields;
 mod input;
 mod record_fields;
 mod repo;
@@ -30,7 +30,7 @@ fn main() -> io::Result<()> {
             let data_dir = std::env::current_dir().unwrap_or_default();
 
             ratatui::run(|terminal| {
-                let mut app = App::new(AppConfig { data_dir }, factory);
+                let mut app = App::new_remote(AppConfig { data_dir }, factory);
                 app.run(terminal)
             })
         }
@@ -4,15 +4,23 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use clap::ValueEnum;
-use sec_store::record::{Record, RecordId};
-use sec_store::repository::file::{OpenRecordsFileRepository, RecordsFileRepository};
+use sec_store::audit::{audit_repository, AuditPolicy, AuditReport};
+use sec_store::breach::{BreachedRecord, PwnedHashes};
+use sec_store::export::ExportFormat;
+use sec_store::record::{Record, RecordId, RecordsFilter};
+use sec_store::repository::file::{
+    Backups, NamedFileRepositories, RecordsFileRepository, RecoverRecordsFileRepository,
+};
+use sec_store::repository::merge::{MergeConflict, MergeSide};
 use sec_store::repository::remote::{
     RemoteClientConfig, RemoteRecordsRepository, RemoteRepositoriesClient,
 };
+use sec_store::repository::share::{share_records, RecordSelection};
 use sec_store::repository::{
-    CreateRepositoryError, OpenRepository, RecordsRepository, RepositoriesSource,
-    RepositoryOpenError,
+    Backup, ChangePasswordError, CreateRepositoryError, OpenRepository, RecordsRepository,
+    RepositoriesSource, RepositoryOpenError,
 };
+use sec_store::secret::SecretString;
 use serde::Deserialize;
 
 use crate::runtime::block_on;
@@ -28,7 +36,8 @@ pub struct RemoteRepositoryConfig {
     pub base_url: String,
     pub client_identity_pem_path: PathBuf,
     pub ca_cert_pem_path: PathBuf,
-    pub repository_name: String,
+    /// Preselected in the repository picker.
+    pub repository_name: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,8 +51,21 @@ where
     R: RecordsRepository,
 {
     fn has_repo(&self) -> bool;
-    fn create_repo(&self, password: String) -> Result<R>;
-    fn open_repo(&self, password: String) -> Result<R>;
+    fn create_repo(&self, password: SecretString) -> Result<R>;
+    fn open_repo(&self, password: SecretString) -> Result<R>;
+    /// Copies the selected records of `repo` into a new repository next to it,
+    /// returns how many were copied.
+    fn share_records(
+        &self,
+        repo: &R,
+        repository_name: &str,
+        password: SecretString,
+        selection: &RecordSelection,
+    ) -> Result<usize>;
+    /// Previous versions of the repository, newest first.
+    fn list_backups(&self) -> Result<Vec<Backup>>;
+    /// Replaces the repository with a backup opening with `password`, then opens it.
+    fn restore_backup(&self, backup_id: &str, password: SecretString) -> Result<R>;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -62,7 +84,7 @@ struct RawRemoteRepositoryTomlConfig {
     base_url: String,
     client_identity_pem_path: PathBuf,
     ca_cert_pem_path: PathBuf,
-    repository_name: String,
+    repository_name: Option<String>,
 }
 
 impl FileRepositoryFactory {
@@ -88,6 +110,44 @@ impl RemoteRepositoryFactory {
 
         Ok(Self { config, client })
     }
+
+    pub fn repository_name(&self) -> Option<&str> {
+        self.config.repository_name.as_deref()
+    }
+
+    /// The factory for `repository_name` on the same server.
+    pub fn with_repository(&self, repository_name: &str) -> Self {
+        let mut factory = self.clone();
+        factory.config.repository_name = Some(repository_name.to_string());
+        factory
+    }
+
+    fn selected_repository(&self) -> Result<&str> {
+        self.repository_name()
+            .ok_or_else(|| anyhow::anyhow!("Pick a repository first"))
+    }
+
+    pub fn list_repos(&self) -> Result<Vec<String>> {
+        block_on(self.client.list_repositories())
+    }
+
+    pub fn rename_repo(
+        &self,
+        repository_name: &str,
+        new_name: &str,
+        password: SecretString,
+    ) -> Result<()> {
+        block_on(
+            self.client
+                .rename_repository(repository_name, new_name, password),
+        )
+        .map_err(Into::into)
+    }
+
+    /// Moves the repository to the server's trash.
+    pub fn delete_repo(&self, repository_name: &str, password: SecretString) -> Result<()> {
+        block_on(self.client.delete_repository(repository_name, password)).map_err(Into::into)
+    }
 }
 
 fn resolve_from_config_dir(base_dir: &Path, path: PathBuf) -> PathBuf {
@@ -127,6 +187,9 @@ fn map_open_error(error: RepositoryOpenError) -> anyhow::Error {
         RepositoryOpenError::InvalidRepositoryName(name) => {
             anyhow::anyhow!("Invalid repository name: {name}")
         }
+        RepositoryOpenError::DamagedRecords(records) => {
+            anyhow::anyhow!("Repository has {} damaged records", records.len())
+        }
         RepositoryOpenError::OpenError(err) => err,
     }
 }
@@ -136,18 +199,48 @@ impl RepositoryFactory<RecordsFileRepository> for FileRepositoryFactory {
         self.repo_path.exists()
     }
 
-    fn create_repo(&self, password: String) -> Result<RecordsFileRepository> {
+    fn create_repo(&self, password: SecretString) -> Result<RecordsFileRepository> {
         ensure_data_dir_for_path(&self.repo_path)?;
         let mut repo = RecordsFileRepository::new(self.repo_path.clone(), password);
         block_on(repo.save())?;
         Ok(repo)
     }
 
-    fn open_repo(&self, password: String) -> Result<RecordsFileRepository> {
-        let repo = block_on(OpenRecordsFileRepository(self.repo_path.clone()).open(password))
+    fn open_repo(&self, password: SecretString) -> Result<RecordsFileRepository> {
+        let repo = block_on(RecoverRecordsFileRepository(self.repo_path.clone()).open(password))
             .map_err(map_open_error)?;
         Ok(repo)
     }
+
+    fn share_records(
+        &self,
+        repo: &RecordsFileRepository,
+        repository_name: &str,
+        password: SecretString,
+        selection: &RecordSelection,
+    ) -> Result<usize> {
+        let repositories = NamedFileRepositories::new(resolve_data_dir(&self.repo_path));
+        block_on(share_records(
+            repo,
+            &repositories,
+            repository_name,
+            password,
+            selection,
+        ))
+        .map_err(Into::into)
+    }
+    fn list_backups(&self) -> Result<Vec<Backup>> {
+        Backups::of(&self.repo_path).list()
+    }
+
+    fn restore_backup(
+        &self,
+        backup_id: &str,
+        password: SecretString,
+    ) -> Result<RecordsFileRepository> {
+        Backups::of(&self.repo_path).restore(backup_id, password.clone())?;
+        self.open_repo(password)
+    }
 }
 
 impl RepositoryFactory<RemoteRecordsRepository> for RemoteRepositoryFactory {
@@ -155,21 +248,54 @@ impl RepositoryFactory<RemoteRecordsRepository> for RemoteRepositoryFactory {
         true
     }
 
-    fn create_repo(&self, password: String) -> Result<RemoteRecordsRepository> {
+    fn create_repo(&self, password: SecretString) -> Result<RemoteRecordsRepository> {
         block_on(
             self.client
-                .create_repository(&self.config.repository_name, password),
+                .create_repository(self.selected_repository()?, password),
         )
         .map_err(map_create_error)
     }
 
-    fn open_repo(&self, password: String) -> Result<RemoteRecordsRepository> {
+    fn open_repo(&self, password: SecretString) -> Result<RemoteRecordsRepository> {
         block_on(
             self.client
-                .open_repository(&self.config.repository_name, password),
+                .open_repository(self.selected_repository()?, password),
         )
         .map_err(map_open_error)
     }
+
+    fn share_records(
+        &self,
+        repo: &RemoteRecordsRepository,
+        repository_name: &str,
+        password: SecretString,
+        selection: &RecordSelection,
+    ) -> Result<usize> {
+        block_on(share_records(
+            repo,
+            &self.client,
+            repository_name,
+            password,
+            selection,
+        ))
+        .map_err(Into::into)
+    }
+    fn list_backups(&self) -> Result<Vec<Backup>> {
+        block_on(self.client.list_backups(self.selected_repository()?)).map_err(Into::into)
+    }
+
+    fn restore_backup(
+        &self,
+        backup_id: &str,
+        password: SecretString,
+    ) -> Result<RemoteRecordsRepository> {
+        block_on(self.client.restore_backup(
+            self.selected_repository()?,
+            backup_id,
+            password.clone(),
+        ))?;
+        self.open_repo(password)
+    }
 }
 
 pub fn close_connection<R>(repo: &R) -> Result<()>
@@ -179,11 +305,42 @@ where
     block_on(repo.close())
 }
 
-pub fn get_records<R>(repo: &R) -> Result<Vec<Record>>
+pub fn find_records<R>(repo: &R, filter: &RecordsFilter) -> Result<Vec<Record>>
+where
+    R: RecordsRepository,
+{
+    block_on(repo.find_records(filter))
+}
+
+pub fn get_tags<R>(repo: &R) -> Result<Vec<String>>
+where
+    R: RecordsRepository,
+{
+    block_on(repo.get_tags())
+}
+
+pub fn get_folders<R>(repo: &R) -> Result<Vec<String>>
+where
+    R: RecordsRepository,
+{
+    block_on(repo.get_folders())
+}
+
+pub fn audit<R>(repo: &R) -> Result<AuditReport>
+where
+    R: RecordsRepository,
+{
+    block_on(audit_repository(repo, &AuditPolicy::default()))
+}
+
+pub fn check_breaches<R>(repo: &R, hashes_path: &Path) -> Result<Vec<BreachedRecord>>
 where
     R: RecordsRepository,
 {
-    block_on(repo.get_records())
+    let mut hashes = PwnedHashes::open(hashes_path)
+        .with_context(|| format!("open hash list {}", hashes_path.display()))?;
+    let records = block_on(repo.get_records())?;
+    Ok(hashes.check_records(&records)?)
 }
 
 pub fn get_record<R>(repo: &R, record_id: &RecordId) -> Result<Option<Record>>
@@ -200,6 +357,30 @@ where
     block_on(repo.add_record(record)).map_err(Into::into)
 }
 
+pub fn import_records<R>(repo: &mut R, records: Vec<Record>) -> Result<usize>
+where
+    R: RecordsRepository,
+{
+    let count = block_on(sec_store::import::import_records(repo, records))?;
+    block_on(repo.save())?;
+    Ok(count)
+}
+
+/// Writes the records with the given ids in `format`, see `sec_store::export`.
+pub fn export_records<R>(
+    repo: &R,
+    record_ids: &[RecordId],
+    format: ExportFormat,
+    password: Option<SecretString>,
+) -> Result<Vec<u8>>
+where
+    R: RecordsRepository,
+{
+    let mut records = block_on(repo.get_records())?;
+    records.retain(|record| record_ids.contains(&record.id));
+    sec_store::export::export_records(&records, format, password)
+}
+
 pub fn update_record<R>(repo: &mut R, record: Record) -> Result<(), anyhow::Error>
 where
     R: RecordsRepository,
@@ -214,13 +395,64 @@ where
     block_on(repo.delete(record_id)).map_err(Into::into)
 }
 
+/// Changes saved elsewhere that conflict with the pending ones, returned by `save`.
+#[derive(Debug)]
+pub struct SaveConflicts<R> {
+    /// `repo` with every change merged in, the conflicting parts keep the pending side.
+    pub merged: R,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl<R> std::fmt::Display for SaveConflicts<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} changes conflict with the ones saved elsewhere",
+            self.conflicts.len()
+        )
+    }
+}
+
+impl<R: Debug> std::error::Error for SaveConflicts<R> {}
+
+/// Merges the changes saved elsewhere before saving. On conflicts nothing is saved
+/// and a `SaveConflicts` error is returned, `repo` is left untouched.
 pub fn save<R>(repo: &mut R) -> Result<()>
 where
     R: RecordsRepository,
 {
+    let mut merged = repo.clone();
+    let conflicts = block_on(merged.merge_persisted())?;
+    if !conflicts.is_empty() {
+        return Err(SaveConflicts { merged, conflicts }.into());
+    }
+    *repo = merged;
     block_on(repo.save())
 }
 
+pub fn resolve_conflict<R>(repo: &mut R, conflict: &MergeConflict, side: MergeSide) -> Result<()>
+where
+    R: RecordsRepository,
+{
+    block_on(sec_store::repository::merge::resolve_conflict(
+        repo, conflict, side,
+    ))
+}
+
+pub fn change_password<R>(
+    repo: &mut R,
+    old_password: SecretString,
+    new_password: SecretString,
+) -> Result<()>
+where
+    R: RecordsRepository,
+{
+    block_on(repo.change_password(old_password, new_password)).map_err(|err| match err {
+        ChangePasswordError::WrongPassword => anyhow::anyhow!("Wrong password"),
+        ChangePasswordError::UnexpectedError(err) => err,
+    })
+}
+
 pub fn default_repo_path() -> PathBuf {
     std::env::current_dir()
         .unwrap_or_default()
@@ -327,7 +559,7 @@ mod tests {
         let repo_path = temp_dir.path().join("repo");
         let factory = FileRepositoryFactory::new(repo_path.clone());
         factory
-            .create_repo(test_password())
+            .create_repo(test_password().into())
             .expect("repo should be created");
 
         assert!(repo_path.exists());
@@ -340,7 +572,7 @@ mod tests {
         let factory = FileRepositoryFactory::new(repo_file.clone());
 
         factory
-            .create_repo(test_password())
+            .create_repo(test_password().into())
             .expect("repo should be created");
         assert!(repo_file.exists());
     }
@@ -350,7 +582,7 @@ mod tests {
         let temp_dir = TempDir::new().expect("temp dir");
         let factory = FileRepositoryFactory::new(temp_dir.path().join("repo"));
         factory
-            .create_repo(test_password())
+            .create_repo(test_password().into())
             .expect("repo should be created");
         assert!(factory.has_repo());
     }
@@ -361,9 +593,11 @@ mod tests {
         let factory = FileRepositoryFactory::new(temp_dir.path().join("repo"));
         let password = test_password();
         factory
-            .create_repo(password.clone())
+            .create_repo(password.clone().into())
             .expect("repo should be created");
-        factory.open_repo(password).expect("repo should open");
+        factory
+            .open_repo(password.into())
+            .expect("repo should open");
     }
 
     #[test]
@@ -371,11 +605,11 @@ mod tests {
         let temp_dir = TempDir::new().expect("temp dir");
         let factory = FileRepositoryFactory::new(temp_dir.path().join("repo"));
         factory
-            .create_repo(test_password())
+            .create_repo(test_password().into())
             .expect("repo should be created");
         let wrong_password = test_password();
         let err = factory
-            .open_repo(wrong_password)
+            .open_repo(wrong_password.into())
             .expect_err("open should fail");
         assert!(err.to_string().contains("Wrong password"));
     }
@@ -385,7 +619,7 @@ mod tests {
         let temp_dir = TempDir::new().expect("temp dir");
         let factory = FileRepositoryFactory::new(temp_dir.path().join("repo"));
         let err = factory
-            .open_repo(test_password())
+            .open_repo(test_password().into())
             .expect_err("open should fail");
         assert!(err
             .to_string()
@@ -415,8 +649,27 @@ repository_name = "demo"
                 base_url: "https://127.0.0.1:8443".to_string(),
                 client_identity_pem_path: temp_dir.path().join("client.pem"),
                 ca_cert_pem_path: temp_dir.path().join("ca.pem"),
-                repository_name: "demo".to_string(),
+                repository_name: Some("demo".to_string()),
             }
         );
     }
+
+    #[test]
+    fn test_load_remote_repository_config_without_repository_name() {
+        let temp_dir = TempDir::new().expect("temp dir");
+        let config_path = temp_dir.path().join("remote.toml");
+        fs::write(
+            &config_path,
+            r#"
+base_url = "https://127.0.0.1:8443"
+client_identity_pem_path = "client.pem"
+ca_cert_pem_path = "ca.pem"
+"#,
+        )
+        .expect("write config");
+
+        let config = load_remote_repository_config(&config_path).expect("config should parse");
+
+        assert_eq!(config.repository_name, None);
+    }
 }
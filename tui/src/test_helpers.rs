@@ -37,6 +37,23 @@ pub(crate) fn test_password() -> String {
     format!("tui-secret-{}-{nanos}", std::process::id())
 }
 
+const SERVER_FIXTURE_CERTS_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../sec_store_server/tests/fixtures/certs"
+);
+
+/// A remote factory with valid certificates and no server listening behind `base_url`.
+pub(crate) fn offline_remote_factory() -> crate::repo::RemoteRepositoryFactory {
+    let certs = std::path::Path::new(SERVER_FIXTURE_CERTS_DIR);
+    crate::repo::RemoteRepositoryFactory::new(crate::repo::RemoteRepositoryConfig {
+        base_url: "https://127.0.0.1:9".to_string(),
+        client_identity_pem_path: certs.join("client-identity.pem"),
+        ca_cert_pem_path: certs.join("ca.pem"),
+        repository_name: None,
+    })
+    .expect("remote factory")
+}
+
 #[cfg(test)]
 mod tests {
     use super::{test_password, ScopedTuiDataDir};